@@ -1,13 +1,14 @@
 use cosmwasm_std::{
-    to_json_binary, DepsMut, Empty, Env, MessageInfo, QueryRequest, Response, WasmQuery,
+    to_json_binary, DepsMut, Empty, Env, MessageInfo, QueryRequest, Response, WasmMsg, WasmQuery,
 };
-use cw721::Cw721Execute;
+use cw721::{Cw721Execute, Cw721Query};
 use cw721_base::{
     ContractError::Ownership,
     OwnershipError::{NoOwner, NotOwner},
 };
 use mars_types::{
     account_nft::NftConfigUpdates,
+    credit_manager::ExecuteMsg as CreditManagerExecuteMsg,
     health::{HealthValuesResponse, QueryMsg::HealthValues},
     oracle::ActionKind,
 };
@@ -29,6 +30,9 @@ pub fn mint(deps: DepsMut, info: MessageInfo, user: &str) -> Result<Response, Co
 /// A few checks to ensure accounts are not accidentally deleted:
 /// - Cannot burn if debt balance
 /// - Cannot burn if collateral exceeding config set amount
+///
+/// The trigger orders of the account are purged in the Credit Manager,
+/// keeper fees are refunded to the account owner.
 pub fn burn(
     deps: DepsMut,
     env: Env,
@@ -64,7 +68,22 @@ pub fn burn(
         });
     }
 
-    Parent::default().burn(deps, env, info, token_id).map_err(Into::into)
+    let owner =
+        Parent::default().owner_of(deps.as_ref(), env.clone(), token_id.clone(), false)?.owner;
+    let mut response = Parent::default().burn(deps, env, info, token_id.clone())?;
+
+    if let Some(credit_manager_addr) = config.credit_manager_contract_addr {
+        response = response.add_message(WasmMsg::Execute {
+            contract_addr: credit_manager_addr.into(),
+            msg: to_json_binary(&CreditManagerExecuteMsg::PurgeAccountTriggerOrders {
+                account_id: token_id,
+                refund_recipient: owner,
+            })?,
+            funds: vec![],
+        });
+    }
+
+    Ok(response)
 }
 
 pub fn update_config(
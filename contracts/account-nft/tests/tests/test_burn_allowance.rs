@@ -123,3 +123,22 @@ fn burn_allowance_when_under_max() {
     );
     res.unwrap_err();
 }
+
+#[test]
+fn burn_purges_trigger_orders_in_credit_manager() {
+    let mut mock = MockEnv::new().build().unwrap();
+
+    let user = Addr::unchecked("user");
+    let token_id = mock.mint(&user).unwrap();
+    mock.set_health_response(&user, &token_id, &below_max_for_burn());
+
+    let res = mock.burn(&user, &token_id).unwrap();
+
+    // The credit manager is called to purge the trigger orders of the account
+    assert!(res.events.iter().any(|event| {
+        event.ty == "execute"
+            && event.attributes.iter().any(|attr| {
+                attr.key == "_contract_address" && attr.value == mock.cm_contract.as_str()
+            })
+    }));
+}
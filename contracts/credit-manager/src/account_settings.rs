@@ -0,0 +1,179 @@
+use std::{cmp::min, collections::BTreeSet};
+
+use cosmwasm_std::{Coin, Deps, DepsMut, MessageInfo, Order, Response, StdResult};
+use mars_types::credit_manager::{
+    AccountSettings, Action, ActionAmount, ActionCoin, CallbackMsg, PerpSettlementPolicy,
+};
+
+use crate::{
+    error::ContractResult,
+    operator::{assert_is_account_owner, spent_coins},
+    state::{ACCOUNT_SETTINGS, COIN_BALANCES, PARAMS, PERPS, RED_BANK},
+    utils::{assert_coin_is_whitelisted, decrement_coin_balance},
+};
+
+pub fn update_account_settings(
+    mut deps: DepsMut,
+    info: MessageInfo,
+    account_id: &str,
+    settings: AccountSettings,
+) -> ContractResult<Response> {
+    assert_is_account_owner(deps.as_ref(), &info.sender, account_id)?;
+
+    if let PerpSettlementPolicy::SwapFrom(denoms) = &settings.perp_settlement {
+        for denom in denoms {
+            assert_coin_is_whitelisted(&mut deps, denom)?;
+        }
+    }
+
+    ACCOUNT_SETTINGS.save(deps.storage, account_id, &settings)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_account_settings")
+        .add_attribute("account_id", account_id)
+        .add_attribute("auto_lend", settings.auto_lend.to_string()))
+}
+
+pub fn query_account_settings(deps: Deps, account_id: &str) -> StdResult<AccountSettings> {
+    Ok(ACCOUNT_SETTINGS.may_load(deps.storage, account_id)?.unwrap_or_default())
+}
+
+/// Reclaim the lent coins which may be spent by the actions, so they are available in the
+/// coin balances when the actions are executed. Idle balances are lent again at the end.
+pub fn reclaim_for_actions(
+    deps: Deps,
+    account_id: &str,
+    settings: &AccountSettings,
+    actions: &[Action],
+) -> ContractResult<Vec<CallbackMsg>> {
+    let red_bank = RED_BANK.load(deps.storage)?;
+
+    // Refunding or closing the account moves out every coin balance, so all lends are reclaimed
+    let unwinds_account = actions.iter().any(|action| {
+        matches!(action, Action::RefundAllCoinBalances {} | Action::CloseAccount { .. })
+    });
+    let lent_denoms = if unwinds_account {
+        red_bank
+            .query_all_lent(&deps.querier, account_id)?
+            .into_iter()
+            .map(|coin| coin.denom)
+            .collect()
+    } else {
+        let mut denoms = BTreeSet::new();
+        for action in actions {
+            denoms.extend(reclaimed_denoms(deps, action, settings)?);
+        }
+        let mut lent_denoms = BTreeSet::new();
+        for denom in denoms {
+            if !red_bank.query_lent(&deps.querier, account_id, &denom)?.is_zero() {
+                lent_denoms.insert(denom);
+            }
+        }
+        lent_denoms
+    };
+
+    Ok(lent_denoms
+        .into_iter()
+        .map(|denom| CallbackMsg::Reclaim {
+            account_id: account_id.to_string(),
+            coin: ActionCoin {
+                denom,
+                amount: ActionAmount::AccountBalance,
+            },
+        })
+        .collect())
+}
+
+/// Denoms the action may draw from the coin balances of the account
+fn reclaimed_denoms(
+    deps: Deps,
+    action: &Action,
+    settings: &AccountSettings,
+) -> ContractResult<Vec<String>> {
+    let denoms = match action {
+        // Perp losses are paid with the base denom (reclaimed when paying) and, depending on the
+        // settlement policy, with the coins swapped into it. The margin of isolated positions is
+        // moved out of the base denom balance.
+        Action::ExecutePerpOrder {
+            margin,
+            ..
+        } => {
+            let mut denoms = match &settings.perp_settlement {
+                PerpSettlementPolicy::SwapFrom(denoms) => denoms.clone(),
+                _ => vec![],
+            };
+            if margin.is_some() {
+                let perps = PERPS.load(deps.storage)?;
+                denoms.push(perps.query_config(&deps.querier)?.base_denom);
+            }
+            denoms
+        }
+        Action::TransferToAccount {
+            coin,
+            ..
+        } => vec![coin.denom.clone()],
+        // The swapped coins of the composite actions are drawn from the coin balances,
+        // while the coins listed for their spend limits are borrowed
+        Action::SwapCollateral {
+            from: coin,
+            ..
+        } => vec![coin.denom.clone()],
+        Action::Deleverage {
+            collateral_denom,
+            ..
+        } => vec![collateral_denom.clone()],
+        Action::SwapDebt {
+            ..
+        }
+        | Action::Leverage {
+            ..
+        } => vec![],
+        _ => spent_coins(action).into_iter().map(|coin| coin.denom).collect(),
+    };
+    Ok(denoms)
+}
+
+/// Lend every coin balance of the account which can be deposited to the Red Bank.
+/// The lent amount is limited by the room left under the deposit cap, so idle balances
+/// never make the account update fail.
+pub fn auto_lend(deps: DepsMut, account_id: &str) -> ContractResult<Response> {
+    let params = PARAMS.load(deps.storage)?;
+    let red_bank = RED_BANK.load(deps.storage)?;
+
+    let balances = COIN_BALANCES
+        .prefix(account_id)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut res = Response::new();
+    let mut coins_lent = vec![];
+    for (denom, amount) in balances {
+        let lendable = match params.query_asset_params(&deps.querier, &denom)? {
+            Some(p) => p.credit_manager.whitelisted && p.red_bank.deposit_enabled,
+            None => false,
+        };
+        if !lendable {
+            continue;
+        }
+
+        // Coin balances are already included in the total deposit
+        let total_deposit = params.query_total_deposit(&deps.querier, &denom)?;
+        let available = total_deposit.cap.saturating_sub(total_deposit.amount);
+        let to_lend = Coin {
+            denom,
+            amount: min(amount, available),
+        };
+        if to_lend.amount.is_zero() {
+            continue;
+        }
+
+        decrement_coin_balance(deps.storage, account_id, &to_lend)?;
+        res = res.add_message(red_bank.lend_msg(&to_lend, account_id)?);
+        coins_lent.push(to_lend.to_string());
+    }
+
+    Ok(res
+        .add_attribute("action", "auto_lend")
+        .add_attribute("account_id", account_id)
+        .add_attribute("coins_lent", coins_lent.join(",")))
+}
@@ -0,0 +1,253 @@
+use std::cmp::min;
+
+use cosmwasm_std::{Addr, Coin, Decimal, Deps, DepsMut, Env, Order, Response, Uint128};
+use mars_types::{
+    credit_manager::{ActionAmount, ActionCoin, CallbackMsg},
+    oracle::ActionKind,
+};
+
+use crate::{
+    error::{ContractError, ContractResult},
+    perp::{close_all_perps, close_isolated_perps},
+    query::query_positions,
+    refund::withdraw_coin_balances,
+    state::{DEBT_SHARES, ORACLE, ZAPPER},
+    utils::{assert_slippage, callback_msgs},
+};
+
+/// Close all positions of the account which don't depend on the coin balances:
+/// perps are closed right away, trigger and recurring orders are deleted (refunding their keeper
+/// fees), vault and Astro LP positions are exited, LP tokens are withdrawn and lends are reclaimed.
+/// Debts are repaid afterwards with the resulting balances (see `repay_all_debts`).
+pub fn close_account(
+    mut deps: DepsMut,
+    env: Env,
+    account_id: &str,
+    recipient: Addr,
+    slippage: Decimal,
+) -> ContractResult<Response> {
+    assert_slippage(deps.storage, slippage)?;
+
+    let positions = query_positions(deps.as_ref(), account_id, ActionKind::Default)?;
+
+    let mut callbacks = vec![CallbackMsg::DeleteAllTriggerOrders {
+        account_id: account_id.to_string(),
+    }];
+    for position in positions.vaults {
+        if !position.amount.locked().is_zero() || !position.amount.unlocking().is_empty() {
+            return Err(ContractError::CannotCloseAccount {
+                account_id: account_id.to_string(),
+                reason: format!(
+                    "vault {} has locked or unlocking positions",
+                    position.vault.address
+                ),
+            });
+        }
+        let amount = position.amount.unlocked();
+        if !amount.is_zero() {
+            callbacks.push(CallbackMsg::ExitVault {
+                account_id: account_id.to_string(),
+                vault: position.vault,
+                amount,
+            });
+        }
+    }
+    // LP tokens held in the coin balances are withdrawn along with the unstaked ones.
+    // Denoms which aren't LP tokens of a pool supported by the zapper are kept as they are.
+    let zapper = ZAPPER.load(deps.storage)?;
+    for coin in &positions.deposits {
+        let staked = positions.staked_astro_lps.iter().any(|lp_coin| lp_coin.denom == coin.denom);
+        if staked || !zapper.is_lp_token(&deps.querier, &coin.denom)? {
+            continue;
+        }
+        callbacks.push(CallbackMsg::WithdrawLiquidity {
+            account_id: account_id.to_string(),
+            lp_token: ActionCoin {
+                denom: coin.denom.clone(),
+                amount: ActionAmount::AccountBalance,
+            },
+            slippage,
+        });
+    }
+    for lp_coin in positions.staked_astro_lps {
+        callbacks.push(CallbackMsg::UnstakeAstroLp {
+            account_id: account_id.to_string(),
+            lp_token: ActionCoin::from(&lp_coin),
+        });
+        callbacks.push(CallbackMsg::WithdrawLiquidity {
+            account_id: account_id.to_string(),
+            lp_token: ActionCoin {
+                denom: lp_coin.denom,
+                amount: ActionAmount::AccountBalance,
+            },
+            slippage,
+        });
+    }
+    for lend in positions.lends {
+        callbacks.push(CallbackMsg::Reclaim {
+            account_id: account_id.to_string(),
+            coin: ActionCoin {
+                denom: lend.denom,
+                amount: ActionAmount::AccountBalance,
+            },
+        });
+    }
+    callbacks.push(CallbackMsg::RepayAllDebts {
+        account_id: account_id.to_string(),
+        slippage,
+    });
+    callbacks.push(CallbackMsg::FinalizeCloseAccount {
+        account_id: account_id.to_string(),
+        recipient: recipient.clone(),
+    });
+
+    // Closing the perps settles the PnL in the coin balances (borrowing if needed),
+    // so it has to happen before the debts are repaid. The loss of isolated positions is capped
+    // at their margin.
+    let res = if positions.perps.is_empty() {
+        Response::new()
+    } else {
+        let isolated_res = close_isolated_perps(deps.branch(), account_id, ActionKind::Default)?;
+        close_all_perps(deps, &env, account_id, ActionKind::Default)?
+            .add_submessages(isolated_res.messages)
+            .add_attributes(isolated_res.attributes)
+    };
+
+    Ok(res
+        .add_messages(callback_msgs(&env, &callbacks)?)
+        .add_attribute("action", "close_account")
+        .add_attribute("account_id", account_id)
+        .add_attribute("recipient", recipient))
+}
+
+/// Repay all debts of the account. Where the coin balance of a debt denom doesn't cover the debt,
+/// other collateral (including the balance of other debt denoms exceeding their debt) is swapped
+/// into it. The amount to swap is the oracle value of the shortfall grossed up by the slippage.
+/// Collateral without an oracle price can't be valued, it isn't swapped.
+pub fn repay_all_debts(
+    deps: Deps,
+    env: Env,
+    account_id: &str,
+    slippage: Decimal,
+) -> ContractResult<Response> {
+    let positions = query_positions(deps, account_id, ActionKind::Default)?;
+    let oracle = ORACLE.load(deps.storage)?;
+    let out_ratio = Decimal::one().checked_sub(slippage)?;
+
+    // Only collateral that isn't needed to repay its own debt is swapped
+    let mut collateral = positions
+        .deposits
+        .iter()
+        .map(|coin| {
+            let debt = positions
+                .debts
+                .iter()
+                .find(|debt| debt.denom == coin.denom)
+                .map(|debt| debt.amount)
+                .unwrap_or_default();
+            Coin {
+                denom: coin.denom.clone(),
+                amount: coin.amount.saturating_sub(debt),
+            }
+        })
+        .filter(|coin| !coin.amount.is_zero())
+        .collect::<Vec<_>>();
+
+    let mut callbacks = vec![];
+    for debt in &positions.debts {
+        let balance = positions
+            .deposits
+            .iter()
+            .find(|coin| coin.denom == debt.denom)
+            .map(|coin| coin.amount)
+            .unwrap_or_default();
+        let mut shortfall = debt.amount.saturating_sub(balance);
+        if shortfall.is_zero() {
+            continue;
+        }
+
+        let debt_price = oracle.query_price(&deps.querier, &debt.denom, ActionKind::Default)?.price;
+        for coin in collateral.iter_mut().filter(|coin| !coin.amount.is_zero()) {
+            let Ok(coin_price) = oracle
+                .query_price(&deps.querier, &coin.denom, ActionKind::Default)
+                .map(|res| res.price)
+            else {
+                continue;
+            };
+            let needed = shortfall
+                .checked_mul_ceil(debt_price.checked_div(coin_price)?)?
+                .checked_div_ceil(out_ratio)?;
+            let swap_amount = min(needed, coin.amount);
+            let received = if swap_amount == needed {
+                shortfall
+            } else {
+                swap_amount
+                    .checked_mul_floor(coin_price.checked_div(debt_price)?)?
+                    .checked_mul_floor(out_ratio)?
+            };
+
+            callbacks.push(CallbackMsg::SwapExactIn {
+                account_id: account_id.to_string(),
+                coin_in: ActionCoin {
+                    denom: coin.denom.clone(),
+                    amount: ActionAmount::Exact(swap_amount),
+                },
+                denom_out: debt.denom.clone(),
+                min_receive: Uint128::zero(),
+                route: None,
+                oracle_slippage: Some(slippage),
+            });
+
+            coin.amount -= swap_amount;
+            shortfall = shortfall.saturating_sub(received);
+            if shortfall.is_zero() {
+                break;
+            }
+        }
+
+        if !shortfall.is_zero() {
+            return Err(ContractError::CannotCloseAccount {
+                account_id: account_id.to_string(),
+                reason: format!("not enough collateral to repay the {} debt", debt.denom),
+            });
+        }
+    }
+
+    for debt in &positions.debts {
+        callbacks.push(CallbackMsg::Repay {
+            account_id: account_id.to_string(),
+            coin: ActionCoin {
+                denom: debt.denom.clone(),
+                amount: ActionAmount::AccountBalance,
+            },
+        });
+    }
+
+    Ok(Response::new()
+        .add_messages(callback_msgs(&env, &callbacks)?)
+        .add_attribute("action", "repay_all_debts")
+        .add_attribute("account_id", account_id)
+        .add_attribute("debts_repaid", positions.debts.len().to_string()))
+}
+
+/// Withdraw all coin balances to the recipient once the account is free of debt
+pub fn finalize_close_account(
+    deps: DepsMut,
+    env: Env,
+    account_id: &str,
+    recipient: Addr,
+) -> ContractResult<Response> {
+    let debt = DEBT_SHARES
+        .prefix(account_id)
+        .range(deps.storage, None, None, Order::Ascending)
+        .next()
+        .transpose()?;
+    if let Some((denom, _)) = debt {
+        return Err(ContractError::CannotCloseAccount {
+            account_id: account_id.to_string(),
+            reason: format!("{denom} debt couldn't be repaid"),
+        });
+    }
+
+    withdraw_coin_balances(deps, env, account_id, recipient)
+}
@@ -9,21 +9,27 @@ use mars_types::{
 };
 
 use crate::{
+    account_settings::{query_account_settings, update_account_settings},
     error::{ContractError, ContractResult},
-    execute::{create_credit_account, dispatch_actions, execute_callback},
+    execute::{create_credit_account, dispatch_actions, execute_callback, DispatchMode},
     instantiate::store_config,
+    liquidation_auction::flag_liquidatable,
+    liquidation_index::query_liquidatable_accounts,
     migrations,
+    operator::{grant_operator, query_account_operators, revoke_operator},
     perp::update_balance_after_deleverage,
     query::{
-        query_accounts, query_all_coin_balances, query_all_debt_shares,
-        query_all_total_debt_shares, query_all_trigger_orders,
-        query_all_trigger_orders_for_account, query_all_vault_positions,
+        query_accounts, query_all_coin_balances, query_all_debt_shares, query_all_recurring_orders,
+        query_all_recurring_orders_for_account, query_all_total_debt_shares,
+        query_all_trigger_orders, query_all_trigger_orders_for_account, query_all_vault_positions,
         query_all_vault_utilizations, query_config, query_positions, query_total_debt_shares,
         query_vault_bindings, query_vault_position_value, query_vault_utilization,
     },
+    recurring::execute_recurring_order,
     repay::repay_from_wallet,
-    state::NEXT_TRIGGER_ID,
-    trigger::execute_trigger_order,
+    simulate::simulate_actions,
+    state::{NEXT_RECURRING_ORDER_ID, NEXT_TRIGGER_ID},
+    trigger::{execute_trigger_order, purge_account_trigger_orders, purge_expired_trigger_orders},
     update_config::{update_config, update_nft_config, update_owner},
     utils::get_account_kind,
     vault::handle_unlock_request_reply,
@@ -42,6 +48,7 @@ pub fn instantiate(
 ) -> ContractResult<Response> {
     set_contract_version(deps.storage, format!("crates.io:{CONTRACT_NAME}"), CONTRACT_VERSION)?;
     NEXT_TRIGGER_ID.save(deps.storage, &1)?;
+    NEXT_RECURRING_ORDER_ID.save(deps.storage, &1)?;
     store_config(deps, env, &msg)?;
     Ok(Response::default())
 }
@@ -70,7 +77,15 @@ pub fn execute(
             account_id,
             account_kind,
             actions,
-        } => dispatch_actions(deps, env, info, account_id, account_kind, actions, true),
+        } => dispatch_actions(
+            deps,
+            env,
+            info,
+            account_id,
+            account_kind,
+            actions,
+            DispatchMode::Account,
+        ),
         ExecuteMsg::RepayFromWallet {
             account_id,
         } => repay_from_wallet(deps, env, info, account_id),
@@ -89,6 +104,36 @@ pub fn execute(
             account_id,
             trigger_order_id,
         } => execute_trigger_order(deps, env, info, &account_id, &trigger_order_id),
+        ExecuteMsg::PurgeExpiredTriggerOrders {
+            limit,
+        } => purge_expired_trigger_orders(deps, env, info, limit),
+        ExecuteMsg::PurgeAccountTriggerOrders {
+            account_id,
+            refund_recipient,
+        } => purge_account_trigger_orders(deps, info, &account_id, &refund_recipient),
+        ExecuteMsg::ExecuteRecurringOrder {
+            account_id,
+            order_id,
+        } => execute_recurring_order(deps, env, info, &account_id, &order_id),
+        ExecuteMsg::GrantOperator {
+            account_id,
+            operator,
+            permissions,
+        } => grant_operator(deps, env, info, &account_id, &operator, permissions),
+        ExecuteMsg::RevokeOperator {
+            account_id,
+            operator,
+        } => revoke_operator(deps, info, &account_id, &operator),
+        ExecuteMsg::UpdateAccountSettings {
+            account_id,
+            settings,
+        } => update_account_settings(deps, info, &account_id, settings),
+        ExecuteMsg::FlagLiquidatable {
+            account_id,
+        } => {
+            cw_utils::nonpayable(&info)?;
+            flag_liquidatable(deps, env, &account_id)
+        }
     }
 }
 
@@ -168,10 +213,41 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> ContractResult<Binary> {
             start_after,
             limit,
         } => to_json_binary(&query_all_trigger_orders(deps, start_after, limit)?),
+        QueryMsg::AllAccountRecurringOrders {
+            account_id,
+            start_after,
+            limit,
+        } => to_json_binary(&query_all_recurring_orders_for_account(
+            deps,
+            account_id,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::AllRecurringOrders {
+            start_after,
+            limit,
+        } => to_json_binary(&query_all_recurring_orders(deps, start_after, limit)?),
         QueryMsg::VaultBindings {
             start_after,
             limit,
         } => to_json_binary(&query_vault_bindings(deps, start_after, limit)?),
+        QueryMsg::AccountOperators {
+            account_id,
+            start_after,
+            limit,
+        } => to_json_binary(&query_account_operators(deps, env, account_id, start_after, limit)?),
+        QueryMsg::AccountSettings {
+            account_id,
+        } => to_json_binary(&query_account_settings(deps, &account_id)?),
+        QueryMsg::SimulateActions {
+            account_id,
+            actions,
+        } => to_json_binary(&simulate_actions(deps, env, &account_id, actions)?),
+        QueryMsg::LiquidatableAccounts {
+            start_after,
+            limit,
+            action,
+        } => to_json_binary(&query_liquidatable_accounts(deps, env, start_after, limit, action)?),
     };
     res.map_err(Into::into)
 }
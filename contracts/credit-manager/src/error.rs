@@ -235,7 +235,130 @@ pub enum ContractError {
     },
 
     #[error(
-        "Illegal trigger action. Trigger actions may only contain execute_perp_order and lend"
+        "Illegal trigger action. Trigger actions may only contain execute_perp_order, lend, swap_exact_in, repay, reclaim, withdraw, exit_vault and unstake_astro_lp"
     )]
     IllegalTriggerAction,
+
+    #[error("Operator grant for {operator:?} on account id {account_id:?} not found")]
+    OperatorGrantNotFound {
+        operator: String,
+        account_id: String,
+    },
+
+    #[error("Operator grant for {operator:?} on account id {account_id:?} has expired")]
+    OperatorGrantExpired {
+        operator: String,
+        account_id: String,
+    },
+
+    #[error("Invalid operator grant: {reason}")]
+    InvalidOperatorGrant {
+        reason: String,
+    },
+
+    #[error("Operator spend limit exceeded for {denom:?}. Requested: {requested:?}, remaining: {remaining:?}")]
+    SpendLimitExceeded {
+        denom: String,
+        requested: Uint128,
+        remaining: Uint128,
+    },
+
+    #[error("Spend limited denom {0:?} requires an exact action amount")]
+    ExactAmountRequired(String),
+
+    #[error("Invalid trigger order relation: {reason}")]
+    InvalidTriggerOrderRelation {
+        reason: String,
+    },
+
+    #[error("Trigger order {order_id:?} is not active until its parent order {parent_order_id:?} is executed")]
+    InactiveTriggerOrder {
+        order_id: String,
+        parent_order_id: String,
+    },
+
+    #[error("Trigger order expiry {expires_at:?} must be in the future")]
+    InvalidTriggerOrderExpiry {
+        expires_at: u64,
+    },
+
+    #[error("Trigger order {order_id:?} of account id {account_id:?} has expired")]
+    TriggerOrderExpired {
+        order_id: String,
+        account_id: String,
+    },
+
+    #[error("Action {0:?} can not be simulated")]
+    UnsupportedSimulationAction(String),
+
+    #[error("Perp position {0:?} can only be modified once per simulation")]
+    RepeatedSimulationPerpOrder(String),
+
+    #[error(
+        "Flash loan of {denom:?} not repaid. Required: {required:?}, available: {available:?}"
+    )]
+    FlashLoanNotRepaid {
+        denom: String,
+        required: Uint128,
+        available: Uint128,
+    },
+
+    #[error("Target LTV {0:?} must be greater than 0 and less than 1")]
+    InvalidTargetLtv(Decimal),
+
+    #[error("Account LTV {current_ltv:?} has already reached target LTV {target_ltv:?}")]
+    TargetLtvReached {
+        current_ltv: Decimal,
+        target_ltv: Decimal,
+    },
+
+    #[error("Invalid recurring order: {reason}")]
+    InvalidRecurringOrder {
+        reason: String,
+    },
+
+    #[error("Recurring order with id {order_id:?} for account id {account_id:?} not found")]
+    RecurringOrderNotFound {
+        order_id: String,
+        account_id: String,
+    },
+
+    #[error("Recurring order {order_id:?} can't be executed before {next_execution:?}")]
+    RecurringOrderNotDue {
+        order_id: String,
+        next_execution: u64,
+    },
+
+    #[error("Account {account_id:?} can't be closed: {reason}")]
+    CannotCloseAccount {
+        account_id: String,
+        reason: String,
+    },
+
+    #[error("{user:?} has no Red Bank position to import")]
+    NoRedBankPosition {
+        user: String,
+    },
+
+    #[error("Invalid margin for {denom} perp position: {reason}")]
+    InvalidIsolatedMargin {
+        denom: String,
+        reason: String,
+    },
+
+    #[error("Isolated {denom} perp position of account {account_id:?} exceeds its maximum loan-to-value. Max LTV health factor: {max_ltv_health_factor:?}")]
+    IsolatedPerpAboveMaxLTV {
+        account_id: String,
+        denom: String,
+        max_ltv_health_factor: String,
+    },
+
+    #[error("Invalid liquidation of {denom} perp position: {reason}")]
+    InvalidPerpLiquidation {
+        denom: String,
+        reason: String,
+    },
+
+    #[error("Account id {0:?} is liquidated more than once in the batch")]
+    DuplicateLiquidatee(String),
 }
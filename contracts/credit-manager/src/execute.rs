@@ -1,42 +1,57 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 
 use cosmwasm_std::{
-    to_json_binary, Addr, Coins, CosmosMsg, DepsMut, Env, MessageInfo, Response, StdResult,
-    Uint128, WasmMsg,
+    to_json_binary, Addr, Coin, Coins, CosmosMsg, Decimal, DepsMut, Env, MessageInfo, Response,
+    StdResult, Uint128, WasmMsg,
 };
 use mars_types::{
     account_nft::ExecuteMsg as NftExecuteMsg,
-    credit_manager::{Action, CallbackMsg, LiquidateRequest},
-    health::AccountKind,
+    credit_manager::{Action, ActionCoin, CallbackMsg, CreateTriggerOrderType, LiquidateRequest},
+    health::{AccountKind, HealthState},
     oracle::ActionKind,
 };
 use mars_vault::msg::{ExecuteMsg, ExtensionExecuteMsg};
 
 use crate::{
+    account_settings::{auto_lend, query_account_settings, reclaim_for_actions},
     borrow::borrow,
     claim_astro_lp_rewards::claim_lp_rewards,
     claim_rewards::claim_rewards,
+    close_account::{close_account, finalize_close_account, repay_all_debts},
     deposit::{assert_deposit_caps, deposit, update_or_reset_denom_deposits},
     error::{ContractError, ContractResult},
-    health::{assert_max_ltv, query_health_state},
+    flash_loan::repay_flash_loan,
+    health::{assert_isolated_perp_health, assert_max_ltv, query_health_state},
     hls::assert_hls_rules,
+    import_red_bank::import_red_bank_position,
     lend::lend,
-    liquidate::{assert_not_self_liquidation, check_health},
+    leverage::{deleverage, leverage, report_leverage},
+    liquidate::liquidation_callbacks,
     liquidate_astro_lp::liquidate_astro_lp,
     liquidate_deposit::liquidate_deposit,
     liquidate_lend::liquidate_lend,
-    perp::{close_all_perps, execute_perp_order},
+    liquidate_perp::liquidate_perp,
+    liquidation_index::update_liquidation_index,
+    operator::assert_is_authorized_for_actions,
+    perp::{close_all_perps, execute_perp_order, liquidate_isolated_perp, settle_perp_loss},
     perp_vault::{deposit_to_perp_vault, unlock_from_perp_vault, withdraw_from_perp_vault},
     reclaim::reclaim,
+    recurring::{create_recurring_order, delete_recurring_order},
     refund::refund_coin_balances,
     repay::{repay, repay_for_recipient},
     stake_astro_lp::stake_lp,
-    state::{ACCOUNT_KINDS, ACCOUNT_NFT, REENTRANCY_GUARD, VAULTS},
-    swap::swap_exact_in,
-    trigger::{create_trigger_order, delete_trigger_order},
+    state::{
+        ACCOUNT_KINDS, ACCOUNT_NFT, ISOLATED_MARGINS, MAX_SLIPPAGE, NEXT_TRIGGER_ID,
+        REENTRANCY_GUARD, VAULTS,
+    },
+    swap::{swap_collateral, swap_debt, swap_exact_in},
+    transfer::{
+        assert_same_owner, transfer_debt_to_account, transfer_lend_to_account, transfer_to_account,
+    },
+    trigger::{create_trigger_order, delete_all_trigger_orders, delete_trigger_order},
     unstake_astro_lp::unstake_lp,
     update_coin_balances::{update_coin_balance, update_coin_balance_after_vault_liquidation},
-    utils::{assert_is_authorized, get_account_kind},
+    utils::{get_account_kind, query_nft_token_owner, received_coin},
     vault::{
         enter_vault, exit_vault, exit_vault_unlocked, liquidate_vault, request_vault_unlock,
         update_vault_coin_balance,
@@ -95,20 +110,86 @@ pub fn create_credit_account(
     Ok((next_id, response))
 }
 
+/// Who is dispatching the actions on the account
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DispatchMode {
+    /// Actions sent by the account owner (or one of its operators, whose swaps are bounded by the
+    /// oracle price)
+    Account,
+    /// Actions of a trigger order, executed by a keeper on behalf of the account owner.
+    /// Ownership isn't checked, withdrawals always go to the account owner, swaps are bounded
+    /// by the oracle price and the account health is always checked.
+    Keeper,
+}
+
+/// Step of a dispatch, after the nested actions of flash loans have been flattened
+pub enum DispatchStep {
+    Action(Action),
+    RepayFlashLoan(Coin),
+    /// Internal callback executed in between the actions, before the account is checked
+    Callback(CallbackMsg),
+}
+
+/// Flash loans are expanded into the loan itself, the nested actions and the repayment.
+/// This way the nested actions go through exactly the same validation as top-level actions.
+fn flatten_flash_loans(actions: Vec<Action>, steps: &mut Vec<DispatchStep>) {
+    for action in actions {
+        match action {
+            Action::FlashLoan {
+                coin,
+                actions,
+            } => {
+                steps.push(DispatchStep::Action(Action::FlashLoan {
+                    coin: coin.clone(),
+                    actions: vec![],
+                }));
+                flatten_flash_loans(actions, steps);
+                steps.push(DispatchStep::RepayFlashLoan(coin));
+            }
+            action => steps.push(DispatchStep::Action(action)),
+        }
+    }
+}
+
 pub fn dispatch_actions(
-    mut deps: DepsMut,
+    deps: DepsMut,
     env: Env,
     info: MessageInfo,
     account_id: Option<String>,
     account_kind: Option<AccountKind>,
     actions: Vec<Action>,
-    enforce_ownership: bool,
+    mode: DispatchMode,
+) -> ContractResult<Response> {
+    let mut steps = vec![];
+    flatten_flash_loans(actions, &mut steps);
+    dispatch_steps(deps, env, info, account_id, account_kind, steps, mode)
+}
+
+pub fn dispatch_steps(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    account_id: Option<String>,
+    account_kind: Option<AccountKind>,
+    steps: Vec<DispatchStep>,
+    mode: DispatchMode,
 ) -> ContractResult<Response> {
     let mut response = Response::new();
 
+    let actions = steps
+        .iter()
+        .filter_map(|step| match step {
+            DispatchStep::Action(action) => Some(action.clone()),
+            DispatchStep::RepayFlashLoan(..) | DispatchStep::Callback(..) => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut operator_dispatch = false;
     let account_id = match account_id {
         Some(acc_id) => {
-            validate_account(&deps, &info, &acc_id, &actions, enforce_ownership)?;
+            let enforce_ownership = mode == DispatchMode::Account;
+            operator_dispatch =
+                validate_account(&mut deps, &env, &info, &acc_id, &actions, enforce_ownership)?;
             acc_id
         }
         None => {
@@ -128,18 +209,28 @@ pub fn dispatch_actions(
     let mut callbacks: Vec<CallbackMsg> = vec![];
     let mut received_coins = Coins::try_from(info.funds)?;
 
+    // Lent coins of auto-lend accounts are reclaimed if the actions may spend them
+    let settings = query_account_settings(deps.as_ref(), account_id)?;
+    if settings.auto_lend {
+        callbacks.extend(reclaim_for_actions(deps.as_ref(), account_id, &settings, &actions)?);
+    }
+
     // deposit / repay actions don't require health check.
     // It allows users to save some positions in cases of extreme volatility.
-    let no_health_check = actions.iter().all(|action| {
-        matches!(
-            action,
-            Action::Deposit(..)
-                | Action::Repay {
-                    recipient_account_id: None,
-                    ..
-                }
-        )
-    });
+    // Closing the account doesn't either, it fails unless all debts are repaid.
+    // Actions dispatched by keepers are always checked.
+    let no_health_check = mode == DispatchMode::Account
+        && actions.iter().all(|action| {
+            matches!(
+                action,
+                Action::Deposit(..)
+                    | Action::Repay {
+                        recipient_account_id: None,
+                        ..
+                    }
+                    | Action::CloseAccount { .. }
+            )
+        });
 
     // If needed (i.e. if health check is required), we query the health state
     let prev_health_state = if !no_health_check {
@@ -172,7 +263,31 @@ pub fn dispatch_actions(
     // otherwise it should compare deposit amount before and after the TX.
     let mut denoms_for_cap_check: BTreeMap<String, Option<Uint128>> = BTreeMap::new();
 
-    for action in actions {
+    // Trigger orders are created by the callbacks in the order of the actions, so their ids are
+    // known upfront. Child orders are linked to the last parent order preceding them.
+    let mut next_trigger_id: Option<u64> = None;
+    let mut last_parent_order_id: Option<String> = None;
+
+    // Accounts receiving coins, lends or debt from this account. Accounts receiving debt come with
+    // their health state prior to the actions, their max LTV is asserted afterwards.
+    let mut recipients: BTreeMap<String, Option<HealthState>> = BTreeMap::new();
+
+    for step in steps {
+        let action = match step {
+            DispatchStep::Action(action) => action,
+            DispatchStep::RepayFlashLoan(coin) => {
+                callbacks.push(CallbackMsg::RepayFlashLoan {
+                    account_id: account_id.to_string(),
+                    coin,
+                });
+                continue;
+            }
+            DispatchStep::Callback(callback) => {
+                callbacks.push(callback);
+                continue;
+            }
+        };
+
         match action {
             Action::Deposit(coin) => {
                 response = deposit(&mut deps, response, account_id, &coin, &mut received_coins)?;
@@ -185,11 +300,20 @@ pub fn dispatch_actions(
                     true,
                 )?;
             }
-            Action::Withdraw(coin) => callbacks.push(CallbackMsg::Withdraw {
-                account_id: account_id.to_string(),
-                coin,
-                recipient: info.sender.clone(),
-            }),
+            Action::Withdraw(coin) => {
+                // Keepers can't withdraw to themselves, funds are sent to the account owner
+                let recipient = match mode {
+                    DispatchMode::Account => info.sender.clone(),
+                    DispatchMode::Keeper => {
+                        Addr::unchecked(query_nft_token_owner(deps.as_ref(), account_id)?)
+                    }
+                };
+                callbacks.push(CallbackMsg::Withdraw {
+                    account_id: account_id.to_string(),
+                    coin,
+                    recipient,
+                })
+            }
             Action::WithdrawToWallet {
                 coin,
                 recipient,
@@ -254,28 +378,94 @@ pub fn dispatch_actions(
                 denom,
                 order_size: size,
                 reduce_only,
-            } => callbacks.push(CallbackMsg::ExecutePerpOrder {
-                account_id: account_id.to_string(),
-                denom,
-                size,
-                reduce_only,
-            }),
+                margin,
+                acceptable_price,
+            } => {
+                // Isolated positions are not part of the account health, they are checked on their own
+                let isolated = margin.is_some()
+                    || ISOLATED_MARGINS.has(deps.storage, (account_id.as_str(), denom.as_str()));
+                callbacks.push(CallbackMsg::ExecutePerpOrder {
+                    account_id: account_id.to_string(),
+                    denom: denom.clone(),
+                    size,
+                    reduce_only,
+                    margin,
+                    acceptable_price,
+                });
+                if isolated && !reduce_only.unwrap_or(false) {
+                    callbacks.push(CallbackMsg::AssertIsolatedPerpHealth {
+                        account_id: account_id.to_string(),
+                        denom,
+                    });
+                }
+            }
             Action::CreateTriggerOrder {
                 actions,
                 conditions,
                 keeper_fee,
-            } => callbacks.push(CallbackMsg::CreateTriggerOrder {
-                account_id: account_id.to_string(),
-                actions,
-                conditions,
-                keeper_fee,
-            }),
+                order_type,
+                oco_group,
+                expires_at,
+            } => {
+                let order_id = match next_trigger_id {
+                    Some(order_id) => order_id,
+                    None => NEXT_TRIGGER_ID.load(deps.storage)?,
+                };
+                next_trigger_id = Some(order_id + 1);
+
+                let parent_order_id = match order_type.unwrap_or(CreateTriggerOrderType::Default) {
+                    CreateTriggerOrderType::Default => None,
+                    CreateTriggerOrderType::Parent => {
+                        last_parent_order_id = Some(order_id.to_string());
+                        None
+                    }
+                    CreateTriggerOrderType::Child => {
+                        Some(last_parent_order_id.clone().ok_or_else(|| {
+                            ContractError::InvalidTriggerOrderRelation {
+                                reason: "child order has to follow a parent order".to_string(),
+                            }
+                        })?)
+                    }
+                };
+
+                callbacks.push(CallbackMsg::CreateTriggerOrder {
+                    account_id: account_id.to_string(),
+                    actions,
+                    conditions,
+                    keeper_fee,
+                    parent_order_id,
+                    oco_group,
+                    expires_at,
+                })
+            }
             Action::DeleteTriggerOrder {
                 trigger_order_id,
             } => callbacks.push(CallbackMsg::DeleteTriggerOrder {
                 account_id: account_id.to_string(),
                 trigger_order_id: trigger_order_id.to_string(),
             }),
+            Action::CreateRecurringOrder {
+                coin_in,
+                denom_out,
+                interval,
+                periods,
+                lend_output,
+                keeper_fee,
+            } => callbacks.push(CallbackMsg::CreateRecurringOrder {
+                account_id: account_id.to_string(),
+                coin_in,
+                denom_out,
+                interval,
+                periods,
+                lend_output,
+                keeper_fee,
+            }),
+            Action::DeleteRecurringOrder {
+                order_id,
+            } => callbacks.push(CallbackMsg::DeleteRecurringOrder {
+                account_id: account_id.to_string(),
+                order_id,
+            }),
             Action::EnterVault {
                 vault,
                 coin,
@@ -288,55 +478,48 @@ pub fn dispatch_actions(
                 liquidatee_account_id,
                 debt_coin,
                 request,
+            } => callbacks.extend(liquidation_callbacks(
+                deps.as_ref(),
+                env.clone(),
+                account_id,
+                &liquidatee_account_id,
+                debt_coin,
+                request,
+            )?),
+            Action::BatchLiquidate {
+                liquidations,
             } => {
-                assert_not_self_liquidation(account_id, &liquidatee_account_id)?;
-
-                let health = check_health(deps.as_ref(), env.clone(), &liquidatee_account_id)?;
-                if health.has_perps {
-                    // Close all perp positions before liquidating.
-                    // This creates the state of the account with only spot positions.
-                    callbacks.push(CallbackMsg::CloseAllPerps {
-                        account_id: liquidatee_account_id.to_string(),
-                    });
-                }
-
-                match request {
-                    LiquidateRequest::Deposit(denom) => callbacks.push(CallbackMsg::Liquidate {
-                        liquidator_account_id: account_id.to_string(),
-                        liquidatee_account_id: liquidatee_account_id.to_string(),
-                        debt_coin,
-                        request: LiquidateRequest::Deposit(denom),
-                        prev_health: health,
-                    }),
-                    LiquidateRequest::Lend(denom) => callbacks.push(CallbackMsg::Liquidate {
-                        liquidator_account_id: account_id.to_string(),
-                        liquidatee_account_id: liquidatee_account_id.to_string(),
-                        debt_coin,
-                        request: LiquidateRequest::Lend(denom),
-                        prev_health: health,
-                    }),
-                    LiquidateRequest::Vault {
-                        request_vault,
-                        position_type,
-                    } => callbacks.push(CallbackMsg::Liquidate {
-                        liquidator_account_id: account_id.to_string(),
-                        liquidatee_account_id: liquidatee_account_id.to_string(),
-                        debt_coin,
-                        request: LiquidateRequest::Vault {
-                            request_vault: request_vault.check(deps.api)?,
-                            position_type,
-                        },
-                        prev_health: health,
-                    }),
-                    LiquidateRequest::StakedAstroLp(lp_denom) => {
-                        callbacks.push(CallbackMsg::Liquidate {
-                            liquidator_account_id: account_id.to_string(),
-                            liquidatee_account_id: liquidatee_account_id.to_string(),
-                            debt_coin,
-                            request: LiquidateRequest::StakedAstroLp(lp_denom),
-                            prev_health: health,
-                        })
+                // The health of each liquidatee is checked before any liquidation is executed,
+                // a second liquidation of the same account would use a stale health
+                let mut liquidatees = HashSet::new();
+                for (idx, liquidation) in liquidations.into_iter().enumerate() {
+                    let liquidatee_account_id = liquidation.liquidatee_account_id;
+                    if !liquidatees.insert(liquidatee_account_id.clone()) {
+                        return Err(ContractError::DuplicateLiquidatee(liquidatee_account_id));
                     }
+                    let result = match liquidation_callbacks(
+                        deps.as_ref(),
+                        env.clone(),
+                        account_id,
+                        &liquidatee_account_id,
+                        liquidation.debt_coin,
+                        liquidation.request,
+                    ) {
+                        Ok(liquidation_callbacks) => {
+                            callbacks.extend(liquidation_callbacks);
+                            "liquidated"
+                        }
+                        // Liquidatees which have been liquidated (or became healthy) since the
+                        // batch was built are skipped instead of failing the whole batch
+                        Err(ContractError::NotLiquidatable {
+                            ..
+                        }) => "skipped",
+                        Err(err) => return Err(err),
+                    };
+                    response = response.add_attribute(
+                        format!("liquidation_{idx}"),
+                        format!("{liquidatee_account_id}:{result}"),
+                    );
                 }
             }
             Action::SwapExactIn {
@@ -345,12 +528,14 @@ pub fn dispatch_actions(
                 min_receive,
                 route,
             } => {
+                let oracle_slippage = swap_oracle_slippage(&deps, mode, operator_dispatch)?;
                 callbacks.push(CallbackMsg::SwapExactIn {
                     account_id: account_id.to_string(),
                     coin_in,
                     denom_out: denom_out.clone(),
                     min_receive,
                     route,
+                    oracle_slippage,
                 });
                 // add the output denom to the map to check the deposit cap in the end of the TX
                 update_or_reset_denom_deposits(
@@ -437,6 +622,164 @@ pub fn dispatch_actions(
                     account_id: account_id.to_string(),
                 })
             }
+            Action::CloseAccount {
+                recipient,
+                slippage,
+            } => callbacks.push(CallbackMsg::CloseAccount {
+                account_id: account_id.to_string(),
+                recipient: deps.api.addr_validate(&recipient)?,
+                slippage,
+            }),
+            Action::ImportRedBankPosition {} => {
+                callbacks.push(CallbackMsg::ImportRedBankPosition {
+                    account_id: account_id.to_string(),
+                    user: info.sender.clone(),
+                })
+            }
+            // The nested actions have been flattened and are followed by the repayment step.
+            // Health is only asserted at the end of the dispatch, so the loan is never
+            // checked on its own.
+            Action::FlashLoan {
+                coin,
+                ..
+            } => callbacks.push(CallbackMsg::Borrow {
+                account_id: account_id.to_string(),
+                coin,
+            }),
+            Action::SwapCollateral {
+                from,
+                to,
+                min_receive,
+                route,
+            } => {
+                let oracle_slippage = swap_oracle_slippage(&deps, mode, operator_dispatch)?;
+                callbacks.push(CallbackMsg::SwapCollateral {
+                    account_id: account_id.to_string(),
+                    from,
+                    to: to.clone(),
+                    min_receive,
+                    route,
+                    oracle_slippage,
+                });
+                // add the output denom to the map to check the deposit cap in the end of the TX
+                update_or_reset_denom_deposits(
+                    deps.as_ref(),
+                    &mut denoms_for_cap_check,
+                    &to,
+                    &received_coins,
+                    false,
+                )?;
+            }
+            Action::SwapDebt {
+                repay_denom,
+                borrow_denom,
+                max_borrow,
+                route,
+            } => {
+                callbacks.push(CallbackMsg::SwapDebt {
+                    account_id: account_id.to_string(),
+                    repay_denom: repay_denom.clone(),
+                    borrow_denom,
+                    max_borrow,
+                    route,
+                });
+                // swapped amount exceeding the debt stays in the account
+                update_or_reset_denom_deposits(
+                    deps.as_ref(),
+                    &mut denoms_for_cap_check,
+                    &repay_denom,
+                    &received_coins,
+                    false,
+                )?;
+            }
+            Action::Leverage {
+                collateral_denom,
+                debt_denom,
+                target_ltv,
+                slippage,
+            } => {
+                callbacks.push(CallbackMsg::Leverage {
+                    account_id: account_id.to_string(),
+                    collateral_denom: collateral_denom.clone(),
+                    debt_denom,
+                    target_ltv,
+                    slippage,
+                });
+                // add the collateral denom to the map to check the deposit cap in the end of the TX
+                update_or_reset_denom_deposits(
+                    deps.as_ref(),
+                    &mut denoms_for_cap_check,
+                    &collateral_denom,
+                    &received_coins,
+                    false,
+                )?;
+            }
+            Action::Deleverage {
+                collateral_denom,
+                debt_denom,
+                target_ltv,
+                slippage,
+            } => {
+                callbacks.push(CallbackMsg::Deleverage {
+                    account_id: account_id.to_string(),
+                    collateral_denom,
+                    debt_denom: debt_denom.clone(),
+                    target_ltv,
+                    slippage,
+                });
+                // swapped amount exceeding the debt stays in the account
+                update_or_reset_denom_deposits(
+                    deps.as_ref(),
+                    &mut denoms_for_cap_check,
+                    &debt_denom,
+                    &received_coins,
+                    false,
+                )?;
+            }
+            Action::TransferToAccount {
+                recipient_account_id,
+                coin,
+            } => {
+                assert_same_owner(deps.as_ref(), account_id, &recipient_account_id)?;
+                recipients.entry(recipient_account_id.clone()).or_default();
+                callbacks.push(CallbackMsg::TransferToAccount {
+                    account_id: account_id.to_string(),
+                    recipient_account_id,
+                    coin,
+                });
+            }
+            Action::TransferLendToAccount {
+                recipient_account_id,
+                coin,
+            } => {
+                assert_same_owner(deps.as_ref(), account_id, &recipient_account_id)?;
+                recipients.entry(recipient_account_id.clone()).or_default();
+                callbacks.push(CallbackMsg::TransferLendToAccount {
+                    account_id: account_id.to_string(),
+                    recipient_account_id,
+                    coin,
+                });
+            }
+            Action::TransferDebtToAccount {
+                recipient_account_id,
+                coin,
+            } => {
+                assert_same_owner(deps.as_ref(), account_id, &recipient_account_id)?;
+                let prev_health_state = recipients.entry(recipient_account_id.clone()).or_default();
+                if prev_health_state.is_none() {
+                    *prev_health_state = Some(query_health_state(
+                        deps.as_ref(),
+                        env.clone(),
+                        &recipient_account_id,
+                        ActionKind::Default,
+                    )?);
+                }
+                callbacks.push(CallbackMsg::TransferDebtToAccount {
+                    account_id: account_id.to_string(),
+                    recipient_account_id,
+                    coin,
+                });
+            }
         }
     }
 
@@ -446,6 +789,19 @@ pub fn dispatch_actions(
         return Err(ContractError::ExtraFundsReceived(received_coins));
     }
 
+    // Idle balances are lent before the health of the account is asserted.
+    // Keepers only execute the orders of the account, they don't lend its balances.
+    if settings.auto_lend && mode == DispatchMode::Account {
+        callbacks.push(CallbackMsg::AutoLend {
+            account_id: account_id.to_string(),
+        });
+    }
+
+    // Keeps track of the accounts which may become liquidatable
+    callbacks.push(CallbackMsg::UpdateLiquidationIndex {
+        account_id: account_id.to_string(),
+    });
+
     // Ensures the account state abides by the rules of the HLS account kind
     let kind = get_account_kind(deps.storage, account_id)?;
     if kind == AccountKind::HighLeveredStrategy {
@@ -465,6 +821,27 @@ pub fn dispatch_actions(
         });
     }
 
+    // The recipient accounts have to abide by the same rules. Receiving coins or lends can't
+    // weaken the LTV of an account, but it can break the rules of an HLS account.
+    for (recipient_account_id, prev_health_state) in recipients {
+        callbacks.push(CallbackMsg::UpdateLiquidationIndex {
+            account_id: recipient_account_id.clone(),
+        });
+        if get_account_kind(deps.storage, &recipient_account_id)?
+            == AccountKind::HighLeveredStrategy
+        {
+            callbacks.push(CallbackMsg::AssertHlsRules {
+                account_id: recipient_account_id.clone(),
+            });
+        }
+        if let Some(prev_health_state) = prev_health_state {
+            callbacks.push(CallbackMsg::AssertMaxLTV {
+                account_id: recipient_account_id,
+                prev_health_state,
+            });
+        }
+    }
+
     callbacks.extend([
         // After user selected actions, we assert that the relevant deposit caps
         // are not exceeded.
@@ -486,13 +863,31 @@ pub fn dispatch_actions(
         .add_attribute("account_id", account_id.to_string()))
 }
 
-fn validate_account(
+/// Swaps dispatched by keepers and operators are bounded by the oracle price, only the account
+/// owner can swap at any price
+fn swap_oracle_slippage(
     deps: &DepsMut,
+    mode: DispatchMode,
+    operator_dispatch: bool,
+) -> ContractResult<Option<Decimal>> {
+    if mode == DispatchMode::Keeper || operator_dispatch {
+        Ok(Some(MAX_SLIPPAGE.load(deps.storage)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Validates the sender can dispatch the actions on the account.
+/// Returns whether the sender is an operator of the account.
+fn validate_account(
+    deps: &mut DepsMut,
+    env: &Env,
     info: &MessageInfo,
     acc_id: &String,
     actions: &[Action],
     enforce_ownership: bool,
-) -> Result<(), ContractError> {
+) -> Result<bool, ContractError> {
+    let mut operator_dispatch = false;
     let kind = get_account_kind(deps.storage, acc_id)?;
     match kind {
         // Fund manager wallet can interact with the account managing the vault funds.
@@ -501,7 +896,8 @@ fn validate_account(
             vault_addr,
         } if info.sender != vault_addr => {
             if enforce_ownership {
-                assert_is_authorized(deps, &info.sender, acc_id)?;
+                operator_dispatch =
+                    assert_is_authorized_for_actions(deps, env, &info.sender, acc_id, actions)?;
             }
 
             let actions_not_allowed = actions.iter().any(|action| {
@@ -520,6 +916,35 @@ fn validate_account(
                         .to_string(),
                 });
             }
+
+            // Positions of the vault can't be moved to other accounts of the wallet
+            let transfers_not_allowed = actions.iter().any(|action| {
+                matches!(
+                    action,
+                    Action::TransferToAccount { .. }
+                        | Action::TransferLendToAccount { .. }
+                        | Action::TransferDebtToAccount { .. }
+                )
+            });
+            if transfers_not_allowed {
+                return Err(ContractError::Unauthorized {
+                    user: acc_id.to_string(),
+                    action:
+                        "transfer_to_account, transfer_lend_to_account, transfer_debt_to_account"
+                            .to_string(),
+                });
+            }
+
+            // Closing the account sends all funds out of it and importing a Red Bank position
+            // would load the account with debt of the fund manager wallet
+            if actions.iter().any(|action| {
+                matches!(action, Action::CloseAccount { .. } | Action::ImportRedBankPosition {})
+            }) {
+                return Err(ContractError::Unauthorized {
+                    user: acc_id.to_string(),
+                    action: "close_account, import_red_bank_position".to_string(),
+                });
+            }
         }
         // Fund manager vault can interact with the account managed by the fund manager wallet.
         // This vault can use the account without any restrictions.
@@ -528,12 +953,13 @@ fn validate_account(
         } => {}
         AccountKind::Default | AccountKind::HighLeveredStrategy => {
             if enforce_ownership {
-                assert_is_authorized(deps, &info.sender, acc_id)?;
+                operator_dispatch =
+                    assert_is_authorized_for_actions(deps, env, &info.sender, acc_id, actions)?;
             }
         }
     }
 
-    Ok(())
+    Ok(operator_dispatch)
 }
 
 pub fn execute_callback(
@@ -568,6 +994,25 @@ pub fn execute_callback(
             account_id,
             coin,
         } => lend(deps, &account_id, &coin),
+        CallbackMsg::LendReceived {
+            account_id,
+            denom,
+            prev_balance,
+        } => {
+            let coin = received_coin(deps.storage, &account_id, &denom, prev_balance)?;
+            lend(deps, &account_id, &ActionCoin::from(&coin))
+        }
+        CallbackMsg::RepayReceived {
+            account_id,
+            denom,
+            prev_balance,
+        } => {
+            let coin = received_coin(deps.storage, &account_id, &denom, prev_balance)?;
+            if coin.amount.is_zero() {
+                return Ok(Response::new());
+            }
+            repay(deps, &account_id, &ActionCoin::from(&coin))
+        }
         CallbackMsg::Reclaim {
             account_id,
             coin,
@@ -600,14 +1045,53 @@ pub fn execute_callback(
             actions,
             conditions,
             keeper_fee,
-        } => create_trigger_order(deps, &account_id, actions, conditions, keeper_fee),
+            parent_order_id,
+            oco_group,
+            expires_at,
+        } => create_trigger_order(
+            deps,
+            env,
+            &account_id,
+            actions,
+            conditions,
+            keeper_fee,
+            parent_order_id,
+            oco_group,
+            expires_at,
+        ),
         CallbackMsg::DeleteTriggerOrder {
             account_id,
             trigger_order_id,
         } => delete_trigger_order(deps, &account_id, &trigger_order_id),
+        CallbackMsg::DeleteAllTriggerOrders {
+            account_id,
+        } => delete_all_trigger_orders(deps, &account_id),
+        CallbackMsg::CreateRecurringOrder {
+            account_id,
+            coin_in,
+            denom_out,
+            interval,
+            periods,
+            lend_output,
+            keeper_fee,
+        } => create_recurring_order(
+            deps,
+            env,
+            &account_id,
+            coin_in,
+            denom_out,
+            interval,
+            periods,
+            lend_output,
+            keeper_fee,
+        ),
+        CallbackMsg::DeleteRecurringOrder {
+            account_id,
+            order_id,
+        } => delete_recurring_order(deps, &account_id, &order_id),
         CallbackMsg::CloseAllPerps {
             account_id,
-        } => close_all_perps(deps, &account_id, ActionKind::Liquidation),
+        } => close_all_perps(deps, &env, &account_id, ActionKind::Liquidation),
         CallbackMsg::EnterVault {
             account_id,
             vault,
@@ -671,14 +1155,54 @@ pub fn execute_callback(
                 &request_coin_denom,
                 prev_health,
             ),
+            LiquidateRequest::Perp {
+                denom,
+                size,
+            } => liquidate_perp(
+                deps,
+                env,
+                &liquidator_account_id,
+                &liquidatee_account_id,
+                &denom,
+                size,
+                prev_health,
+            ),
+            LiquidateRequest::IsolatedPerp(denom) => liquidate_isolated_perp(
+                deps,
+                env,
+                &liquidator_account_id,
+                &liquidatee_account_id,
+                &denom,
+            ),
         },
+        CallbackMsg::LiquidateIsolatedPerp {
+            liquidator_account_id,
+            liquidatee_account_id,
+            denom,
+        } => liquidate_isolated_perp(
+            deps,
+            env,
+            &liquidator_account_id,
+            &liquidatee_account_id,
+            &denom,
+        ),
         CallbackMsg::SwapExactIn {
             account_id,
             coin_in,
             denom_out,
             min_receive,
             route,
-        } => swap_exact_in(deps, env, &account_id, &coin_in, &denom_out, min_receive, route),
+            oracle_slippage,
+        } => swap_exact_in(
+            deps,
+            env,
+            &account_id,
+            &coin_in,
+            &denom_out,
+            min_receive,
+            route,
+            oracle_slippage,
+        ),
         CallbackMsg::UpdateCoinBalance {
             account_id,
             previous_balance,
@@ -724,6 +1248,31 @@ pub fn execute_callback(
         CallbackMsg::RefundAllCoinBalances {
             account_id,
         } => refund_coin_balances(deps, env, &account_id),
+        CallbackMsg::CloseAccount {
+            account_id,
+            recipient,
+            slippage,
+        } => close_account(deps, env, &account_id, recipient, slippage),
+        CallbackMsg::RepayAllDebts {
+            account_id,
+            slippage,
+        } => repay_all_debts(deps.as_ref(), env, &account_id, slippage),
+        CallbackMsg::FinalizeCloseAccount {
+            account_id,
+            recipient,
+        } => finalize_close_account(deps, env, &account_id, recipient),
+        CallbackMsg::ImportRedBankPosition {
+            account_id,
+            user,
+        } => import_red_bank_position(deps, &account_id, &user),
+        CallbackMsg::AutoLend {
+            account_id,
+        } => auto_lend(deps, &account_id),
+        CallbackMsg::SettlePerpLoss {
+            account_id,
+            coin,
+            action,
+        } => settle_perp_loss(deps, env, &account_id, &coin, action),
         CallbackMsg::AssertHlsRules {
             account_id,
         } => assert_hls_rules(deps.as_ref(), &account_id),
@@ -748,6 +1297,79 @@ pub fn execute_callback(
             denom,
             size,
             reduce_only,
-        } => execute_perp_order(deps, account_id.as_str(), denom.as_str(), size, reduce_only),
+            margin,
+            acceptable_price,
+        } => execute_perp_order(
+            deps,
+            &env,
+            account_id.as_str(),
+            denom.as_str(),
+            size,
+            reduce_only,
+            margin,
+            acceptable_price,
+        ),
+        CallbackMsg::AssertIsolatedPerpHealth {
+            account_id,
+            denom,
+        } => assert_isolated_perp_health(deps.as_ref(), env, &account_id, &denom),
+        CallbackMsg::UpdateLiquidationIndex {
+            account_id,
+        } => update_liquidation_index(deps, env, &account_id),
+        CallbackMsg::RepayFlashLoan {
+            account_id,
+            coin,
+        } => repay_flash_loan(deps, &account_id, &coin),
+        CallbackMsg::SwapCollateral {
+            account_id,
+            from,
+            to,
+            min_receive,
+            route,
+            oracle_slippage,
+        } => {
+            swap_collateral(deps, env, &account_id, &from, &to, min_receive, route, oracle_slippage)
+        }
+        CallbackMsg::SwapDebt {
+            account_id,
+            repay_denom,
+            borrow_denom,
+            max_borrow,
+            route,
+        } => swap_debt(deps, env, &account_id, &repay_denom, &borrow_denom, max_borrow, route),
+        CallbackMsg::Leverage {
+            account_id,
+            collateral_denom,
+            debt_denom,
+            target_ltv,
+            slippage,
+        } => leverage(deps, env, &account_id, &collateral_denom, &debt_denom, target_ltv, slippage),
+        CallbackMsg::Deleverage {
+            account_id,
+            collateral_denom,
+            debt_denom,
+            target_ltv,
+            slippage,
+        } => {
+            deleverage(deps, env, &account_id, &collateral_denom, &debt_denom, target_ltv, slippage)
+        }
+        CallbackMsg::ReportLeverage {
+            account_id,
+        } => report_leverage(deps.as_ref(), env, &account_id),
+        CallbackMsg::TransferToAccount {
+            account_id,
+            recipient_account_id,
+            coin,
+        } => transfer_to_account(deps, &account_id, &recipient_account_id, &coin),
+        CallbackMsg::TransferLendToAccount {
+            account_id,
+            recipient_account_id,
+            coin,
+        } => transfer_lend_to_account(deps, &account_id, &recipient_account_id, &coin),
+        CallbackMsg::TransferDebtToAccount {
+            account_id,
+            recipient_account_id,
+            coin,
+        } => transfer_debt_to_account(deps, &account_id, &recipient_account_id, &coin),
     }
 }
@@ -0,0 +1,46 @@
+use cosmwasm_std::{Coin, DepsMut, Response};
+use mars_types::credit_manager::ActionCoin;
+
+use crate::{
+    error::{ContractError, ContractResult},
+    repay::repay,
+    state::{COIN_BALANCES, FLASH_LOAN_FEE_RATE, REWARDS_COLLECTOR},
+    utils::{decrement_coin_balance, increment_coin_balance},
+};
+
+/// Repays a flash loan taken earlier in the same dispatch from the account balance.
+/// The borrowed amount plus the fee has to be available, otherwise the whole dispatch is reverted.
+/// The fee is transferred to the rewards-collector account.
+pub fn repay_flash_loan(deps: DepsMut, account_id: &str, coin: &Coin) -> ContractResult<Response> {
+    let fee_rate = FLASH_LOAN_FEE_RATE.load(deps.storage)?;
+    let fee = Coin {
+        denom: coin.denom.clone(),
+        amount: coin.amount.checked_mul_ceil(fee_rate)?,
+    };
+
+    let required = coin.amount.checked_add(fee.amount)?;
+    let available =
+        COIN_BALANCES.may_load(deps.storage, (account_id, &coin.denom))?.unwrap_or_default();
+    if available < required {
+        return Err(ContractError::FlashLoanNotRepaid {
+            denom: coin.denom.clone(),
+            required,
+            available,
+        });
+    }
+
+    if !fee.amount.is_zero() {
+        let rewards_collector_account = REWARDS_COLLECTOR.load(deps.storage)?.account_id;
+        decrement_coin_balance(deps.storage, account_id, &fee)?;
+        increment_coin_balance(deps.storage, &rewards_collector_account, &fee)?;
+    }
+
+    let repay_res = repay(deps, account_id, &ActionCoin::from(coin))?;
+
+    Ok(Response::new()
+        .add_submessages(repay_res.messages)
+        .add_attribute("action", "repay_flash_loan")
+        .add_attribute("account_id", account_id)
+        .add_attribute("coin_repaid", coin.to_string())
+        .add_attribute("fee", fee.to_string()))
+}
@@ -1,16 +1,18 @@
 use cosmwasm_std::{Deps, Env, Response};
 use mars_rover_health::{
-    compute::{compute_health, compute_health_state},
+    compute::{compute_health, compute_health_state, compute_isolated_perp_health},
     querier::HealthQuerier,
 };
 use mars_types::{
-    health::{HealthState, HealthValuesResponse},
+    health::{HealthState, HealthValuesResponse, IsolatedPerpHealth},
     oracle::ActionKind,
+    traits::Stringify,
 };
 
 use crate::{
     error::{ContractError, ContractResult},
     query::{query_config, query_positions},
+    state::ISOLATED_MARGINS,
 };
 
 pub fn query_health_state(
@@ -40,6 +42,48 @@ pub fn query_health_values(
     Ok(health)
 }
 
+pub fn query_isolated_perp_health(
+    deps: Deps,
+    env: Env,
+    account_id: &str,
+    denom: &str,
+    action: ActionKind,
+) -> ContractResult<IsolatedPerpHealth> {
+    let config = query_config(deps)?;
+    let health_querier =
+        HealthQuerier::new_with_config(&deps, env.contract.address.clone(), config)?;
+    let positions = query_positions(deps, account_id, action.clone())?;
+    let health = compute_isolated_perp_health(deps, health_querier, positions, action, denom)?;
+    Ok(health)
+}
+
+/// Isolated perp positions are not part of the account health, so after an order their own
+/// health is checked. Nothing to check if the position is cross margin or has been closed.
+pub fn assert_isolated_perp_health(
+    deps: Deps,
+    env: Env,
+    account_id: &str,
+    denom: &str,
+) -> ContractResult<Response> {
+    let isolated = ISOLATED_MARGINS.has(deps.storage, (account_id, denom));
+    if isolated {
+        let health = query_isolated_perp_health(deps, env, account_id, denom, ActionKind::Default)?;
+        if health.is_above_max_ltv() {
+            return Err(ContractError::IsolatedPerpAboveMaxLTV {
+                account_id: account_id.to_string(),
+                denom: denom.to_string(),
+                max_ltv_health_factor: health.max_ltv_health_factor.to_string(),
+            });
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "callback/assert_isolated_perp_health")
+        .add_attribute("account_id", account_id)
+        .add_attribute("denom", denom)
+        .add_attribute("isolated", isolated.to_string()))
+}
+
 pub fn assert_max_ltv(
     deps: Deps,
     env: Env,
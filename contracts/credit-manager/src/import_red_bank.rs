@@ -0,0 +1,52 @@
+use cosmwasm_std::{Addr, DepsMut, Response};
+
+use crate::{
+    borrow::update_debt,
+    error::{ContractError, ContractResult},
+    state::RED_BANK,
+    utils::assert_coin_is_whitelisted,
+};
+
+/// Move the Red Bank position of `user` to the account.
+/// The debts are borrowed by the account and repaid on behalf of the user, so the coin balances
+/// of the account are unchanged. The collaterals are then transferred to the account as lends,
+/// which only passes the health check of the Red Bank once the user is free of debt.
+pub fn import_red_bank_position(
+    mut deps: DepsMut,
+    account_id: &str,
+    user: &Addr,
+) -> ContractResult<Response> {
+    let red_bank = RED_BANK.load(deps.storage)?;
+    let debts = red_bank.query_user_debts(&deps.querier, user.as_str())?;
+    let collaterals = red_bank.query_user_collaterals(&deps.querier, user.as_str())?;
+
+    if debts.is_empty() && collaterals.is_empty() {
+        return Err(ContractError::NoRedBankPosition {
+            user: user.to_string(),
+        });
+    }
+
+    let mut msgs = vec![];
+    for debt in debts.iter().filter(|coin| !coin.amount.is_zero()) {
+        let (_, borrow_msg) = update_debt(&mut deps, account_id, debt)?;
+        msgs.push(borrow_msg);
+        msgs.push(red_bank.repay_on_behalf_of_msg(debt, user.as_str())?);
+    }
+
+    for collateral in collaterals.iter().filter(|coin| !coin.amount.is_zero()) {
+        assert_coin_is_whitelisted(&mut deps, &collateral.denom)?;
+        msgs.push(red_bank.transfer_collateral_msg(
+            user.as_str(),
+            account_id,
+            &collateral.denom,
+        )?);
+    }
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "import_red_bank_position")
+        .add_attribute("account_id", account_id)
+        .add_attribute("user", user)
+        .add_attribute("debts_imported", debts.len().to_string())
+        .add_attribute("collaterals_imported", collaterals.len().to_string()))
+}
@@ -5,10 +5,13 @@ use mars_types::credit_manager::InstantiateMsg;
 use crate::{
     error::ContractResult,
     state::{
-        HEALTH_CONTRACT, INCENTIVES, KEEPER_FEE_CONFIG, MAX_SLIPPAGE, MAX_UNLOCKING_POSITIONS,
-        ORACLE, OWNER, PARAMS, PERPS_LB_RATIO, RED_BANK, SWAPPER, ZAPPER,
+        FLASH_LOAN_FEE_RATE, HEALTH_CONTRACT, INCENTIVES, KEEPER_FEE_CONFIG, MAX_SLIPPAGE,
+        MAX_UNLOCKING_POSITIONS, ORACLE, OWNER, PARAMS, PERPS_LB_RATIO, RED_BANK, SWAPPER, ZAPPER,
+    },
+    utils::{
+        assert_flash_loan_fee_rate, assert_keeper_fee_config, assert_max_slippage,
+        assert_perps_lb_ratio,
     },
-    utils::{assert_max_slippage, assert_perps_lb_ratio},
 };
 
 pub fn store_config(deps: DepsMut, env: Env, msg: &InstantiateMsg) -> ContractResult<()> {
@@ -32,9 +35,13 @@ pub fn store_config(deps: DepsMut, env: Env, msg: &InstantiateMsg) -> ContractRe
     assert_perps_lb_ratio(msg.perps_liquidation_bonus_ratio)?;
     PERPS_LB_RATIO.save(deps.storage, &msg.perps_liquidation_bonus_ratio)?;
 
+    assert_flash_loan_fee_rate(msg.flash_loan_fee_rate)?;
+    FLASH_LOAN_FEE_RATE.save(deps.storage, &msg.flash_loan_fee_rate)?;
+
     HEALTH_CONTRACT.save(deps.storage, &msg.health_contract.check(deps.api)?)?;
     PARAMS.save(deps.storage, &msg.params.check(deps.api)?)?;
     INCENTIVES.save(deps.storage, &msg.incentives.check(deps.api, env.contract.address)?)?;
+    assert_keeper_fee_config(&msg.keeper_fee_config)?;
     KEEPER_FEE_CONFIG.save(deps.storage, &msg.keeper_fee_config)?;
 
     Ok(())
@@ -0,0 +1,224 @@
+use std::cmp::min;
+
+use cosmwasm_std::{Coin, Decimal, Deps, Env, Response, Uint128};
+use mars_rover_health::{compute::health_computer, querier::HealthQuerier};
+use mars_types::{
+    credit_manager::{ActionAmount, ActionCoin, CallbackMsg},
+    health::{BorrowTarget, HealthValuesResponse},
+    oracle::ActionKind,
+};
+
+use crate::{
+    error::{ContractError, ContractResult},
+    health::query_health_values,
+    query::{query_config, query_positions},
+    repay::current_debt_for_denom,
+    state::{COIN_BALANCES, ORACLE},
+    utils::{assert_slippage, callback_msgs},
+};
+
+/// Borrow `debt_denom` and swap it into `collateral_denom` so that the account ends up at
+/// the target LTV. The borrow amount solves:
+///     target_ltv = (debt + borrow) / (collateral + borrow * (1 - slippage))
+/// and is capped at `HealthComputer::max_borrow_amount_estimate` for the swap.
+pub fn leverage(
+    deps: Deps,
+    env: Env,
+    account_id: &str,
+    collateral_denom: &str,
+    debt_denom: &str,
+    target_ltv: Decimal,
+    slippage: Decimal,
+) -> ContractResult<Response> {
+    assert_slippage(deps.storage, slippage)?;
+    assert_target_ltv(target_ltv)?;
+
+    let config = query_config(deps)?;
+    let querier = HealthQuerier::new_with_config(&deps, env.contract.address.clone(), config)?;
+    let positions = query_positions(deps, account_id, ActionKind::Default)?;
+    let computer = health_computer(
+        deps,
+        querier,
+        positions,
+        ActionKind::Default,
+        &[collateral_denom, debt_denom],
+    )?;
+    let health: HealthValuesResponse = computer.compute_health()?.into();
+
+    let target_debt_value = health.total_collateral_value.checked_mul_floor(target_ltv)?;
+    if target_debt_value <= health.total_debt_value {
+        return Err(ContractError::TargetLtvReached {
+            current_ltv: current_ltv(&health),
+            target_ltv,
+        });
+    }
+
+    let debt_price = query_price(deps, debt_denom)?;
+    let borrow_value = (target_debt_value - health.total_debt_value).checked_div_floor(
+        Decimal::one().checked_sub(target_ltv.checked_mul(Decimal::one() - slippage)?)?,
+    )?;
+    let max_borrow = computer.max_borrow_amount_estimate(
+        debt_denom,
+        &BorrowTarget::Swap {
+            denom_out: collateral_denom.to_string(),
+            slippage,
+        },
+    )?;
+    let borrow_amount = min(borrow_value.checked_div_floor(debt_price)?, max_borrow);
+
+    if borrow_amount.is_zero() {
+        return Err(ContractError::NoAmount);
+    }
+
+    let coin_to_borrow = Coin {
+        denom: debt_denom.to_string(),
+        amount: borrow_amount,
+    };
+    let callbacks = vec![
+        CallbackMsg::Borrow {
+            account_id: account_id.to_string(),
+            coin: coin_to_borrow.clone(),
+        },
+        CallbackMsg::SwapExactIn {
+            account_id: account_id.to_string(),
+            coin_in: ActionCoin::from(&coin_to_borrow),
+            denom_out: collateral_denom.to_string(),
+            min_receive: Uint128::zero(),
+            route: None,
+            oracle_slippage: Some(slippage),
+        },
+        CallbackMsg::ReportLeverage {
+            account_id: account_id.to_string(),
+        },
+    ];
+
+    Ok(Response::new()
+        .add_messages(callback_msgs(&env, &callbacks)?)
+        .add_attribute("action", "leverage")
+        .add_attribute("account_id", account_id)
+        .add_attribute("coin_borrowed", coin_to_borrow.to_string())
+        .add_attribute("collateral_denom", collateral_denom)
+        .add_attribute("target_ltv", target_ltv.to_string()))
+}
+
+/// Swap `collateral_denom` into `debt_denom` and repay the debt so that the account ends up at
+/// the target LTV. The collateral value to swap solves:
+///     target_ltv = (debt - swapped * (1 - slippage)) / (collateral - swapped)
+/// and is capped at the collateral balance and the amount needed to repay the whole debt.
+pub fn deleverage(
+    deps: Deps,
+    env: Env,
+    account_id: &str,
+    collateral_denom: &str,
+    debt_denom: &str,
+    target_ltv: Decimal,
+    slippage: Decimal,
+) -> ContractResult<Response> {
+    assert_slippage(deps.storage, slippage)?;
+    assert_target_ltv(target_ltv)?;
+
+    let health = query_health_values(deps, env.clone(), account_id, ActionKind::Default)?;
+
+    let target_debt_value = health.total_collateral_value.checked_mul_floor(target_ltv)?;
+    if target_debt_value >= health.total_debt_value {
+        return Err(ContractError::TargetLtvReached {
+            current_ltv: current_ltv(&health),
+            target_ltv,
+        });
+    }
+
+    let collateral_price = query_price(deps, collateral_denom)?;
+    let debt_price = query_price(deps, debt_denom)?;
+    let (debt_amount, _) = current_debt_for_denom(deps, account_id, debt_denom)?;
+    let out_ratio = Decimal::one() - slippage;
+
+    // Swapping the collateral can only reach the target if the swap output is worth more
+    // than the target LTV share of the swapped collateral, otherwise swap as much as possible
+    let swap_value = match out_ratio.checked_sub(target_ltv) {
+        Ok(ratio) if !ratio.is_zero() => {
+            (health.total_debt_value - target_debt_value).checked_div_ceil(ratio)?
+        }
+        _ => Uint128::MAX,
+    };
+    let full_repay_value = debt_amount.checked_mul_ceil(debt_price)?.checked_div_ceil(out_ratio)?;
+    let collateral_balance =
+        COIN_BALANCES.may_load(deps.storage, (account_id, collateral_denom))?.unwrap_or_default();
+    let swap_amount = min(
+        min(swap_value, full_repay_value).checked_div_ceil(collateral_price)?,
+        collateral_balance,
+    );
+
+    if swap_amount.is_zero() {
+        return Err(ContractError::NoAmount);
+    }
+
+    // Only the swap output repays the debt, the coins already held by the account are left as is
+    let prev_balance =
+        COIN_BALANCES.may_load(deps.storage, (account_id, debt_denom))?.unwrap_or_default();
+    let callbacks = vec![
+        CallbackMsg::SwapExactIn {
+            account_id: account_id.to_string(),
+            coin_in: ActionCoin {
+                denom: collateral_denom.to_string(),
+                amount: ActionAmount::Exact(swap_amount),
+            },
+            denom_out: debt_denom.to_string(),
+            min_receive: Uint128::zero(),
+            route: None,
+            oracle_slippage: Some(slippage),
+        },
+        CallbackMsg::RepayReceived {
+            account_id: account_id.to_string(),
+            denom: debt_denom.to_string(),
+            prev_balance,
+        },
+        CallbackMsg::ReportLeverage {
+            account_id: account_id.to_string(),
+        },
+    ];
+
+    Ok(Response::new()
+        .add_messages(callback_msgs(&env, &callbacks)?)
+        .add_attribute("action", "deleverage")
+        .add_attribute("account_id", account_id)
+        .add_attribute("coin_swapped", format!("{}{}", swap_amount, collateral_denom))
+        .add_attribute("debt_denom", debt_denom)
+        .add_attribute("target_ltv", target_ltv.to_string()))
+}
+
+/// Emit the LTV and leverage (collateral / equity) achieved by a leverage or deleverage
+pub fn report_leverage(deps: Deps, env: Env, account_id: &str) -> ContractResult<Response> {
+    let health = query_health_values(deps, env, account_id, ActionKind::Default)?;
+
+    let equity = health.total_collateral_value.saturating_sub(health.total_debt_value);
+    let leverage = if equity.is_zero() {
+        "inf".to_string()
+    } else {
+        Decimal::checked_from_ratio(health.total_collateral_value, equity)?.to_string()
+    };
+
+    Ok(Response::new()
+        .add_attribute("action", "report_leverage")
+        .add_attribute("account_id", account_id)
+        .add_attribute("ltv", current_ltv(&health).to_string())
+        .add_attribute("leverage", leverage))
+}
+
+fn assert_target_ltv(target_ltv: Decimal) -> ContractResult<()> {
+    if target_ltv.is_zero() || target_ltv >= Decimal::one() {
+        return Err(ContractError::InvalidTargetLtv(target_ltv));
+    }
+    Ok(())
+}
+
+fn current_ltv(health: &HealthValuesResponse) -> Decimal {
+    if health.total_collateral_value.is_zero() {
+        return Decimal::zero();
+    }
+    Decimal::from_ratio(health.total_debt_value, health.total_collateral_value)
+}
+
+fn query_price(deps: Deps, denom: &str) -> ContractResult<Decimal> {
+    let oracle = ORACLE.load(deps.storage)?;
+    Ok(oracle.query_price(&deps.querier, denom, ActionKind::Default)?.price)
+}
@@ -1,28 +1,40 @@
+pub mod account_settings;
 pub mod borrow;
 pub mod claim_astro_lp_rewards;
 pub mod claim_rewards;
+pub mod close_account;
 pub mod contract;
 pub mod deposit;
 pub mod error;
 pub mod execute;
+pub mod flash_loan;
 pub mod health;
 pub mod hls;
+pub mod import_red_bank;
 pub mod instantiate;
 pub mod lend;
+pub mod leverage;
 pub mod liquidate;
 pub mod liquidate_astro_lp;
 pub mod liquidate_deposit;
 pub mod liquidate_lend;
+pub mod liquidate_perp;
+pub mod liquidation_auction;
+pub mod liquidation_index;
 pub mod migrations;
+pub mod operator;
 pub mod perp;
 pub mod perp_vault;
 pub mod query;
 pub mod reclaim;
+pub mod recurring;
 pub mod refund;
 pub mod repay;
+pub mod simulate;
 pub mod stake_astro_lp;
 pub mod state;
 pub mod swap;
+pub mod transfer;
 pub mod trigger;
 pub mod unstake_astro_lp;
 pub mod update_coin_balances;
@@ -1,17 +1,124 @@
 use cosmwasm_std::{Coin, Decimal, Deps, DepsMut, Env, Int128, QuerierWrapper, Uint128};
 use mars_liquidation::liquidation::{calculate_liquidation_amounts, HealthData};
 use mars_types::{
-    adapters::oracle::Oracle, health::HealthValuesResponse, oracle::ActionKind, traits::Stringify,
+    adapters::{oracle::Oracle, vault::VaultUnchecked},
+    credit_manager::{CallbackMsg, LiquidateRequest},
+    health::HealthValuesResponse,
+    oracle::ActionKind,
+    traits::Stringify,
 };
 
 use crate::{
     error::{ContractError, ContractResult},
-    health::query_health_values,
+    health::{query_health_values, query_isolated_perp_health},
+    liquidation_auction::liquidatable_for,
     repay::current_debt_for_denom,
-    state::{ORACLE, PARAMS, PERPS_LB_RATIO, REWARDS_COLLECTOR},
+    state::{ISOLATED_MARGINS, ORACLE, PARAMS, PERPS_LB_RATIO, REWARDS_COLLECTOR},
     utils::increment_coin_balance,
 };
 
+/// Callbacks liquidating the requested position of the liquidatee.
+/// Returns `NotLiquidatable` if the liquidatee's account (or isolated perp position) isn't
+/// liquidatable, so that batch liquidations can skip it.
+pub fn liquidation_callbacks(
+    deps: Deps,
+    env: Env,
+    liquidator_account_id: &str,
+    liquidatee_account_id: &str,
+    debt_coin: Coin,
+    request: LiquidateRequest<VaultUnchecked>,
+) -> ContractResult<Vec<CallbackMsg>> {
+    assert_not_self_liquidation(liquidator_account_id, liquidatee_account_id)?;
+
+    // An isolated perp position has its own health and is liquidated using its margin
+    // only, the rest of the account is left untouched
+    if let LiquidateRequest::IsolatedPerp(denom) = request {
+        if !ISOLATED_MARGINS.has(deps.storage, (liquidatee_account_id, &denom)) {
+            return Err(ContractError::InvalidIsolatedMargin {
+                denom,
+                reason: format!("account {liquidatee_account_id} has no isolated position"),
+            });
+        }
+        let health = query_isolated_perp_health(
+            deps,
+            env,
+            liquidatee_account_id,
+            &denom,
+            ActionKind::Liquidation,
+        )?;
+        if !health.is_liquidatable() {
+            return Err(ContractError::NotLiquidatable {
+                account_id: liquidatee_account_id.to_string(),
+                lqdt_health_factor: health.liquidation_health_factor.to_string(),
+            });
+        }
+        return Ok(vec![
+            CallbackMsg::LiquidateIsolatedPerp {
+                liquidator_account_id: liquidator_account_id.to_string(),
+                liquidatee_account_id: liquidatee_account_id.to_string(),
+                denom,
+            },
+            CallbackMsg::UpdateLiquidationIndex {
+                account_id: liquidatee_account_id.to_string(),
+            },
+        ]);
+    }
+
+    let health = check_health(deps, env, liquidatee_account_id)?;
+
+    // A partial perp liquidation only reduces the requested position
+    let partial_perp = matches!(request, LiquidateRequest::Perp { .. });
+
+    let mut callbacks = vec![];
+    if !partial_perp {
+        // Trigger orders of a liquidated account are deleted, keeper fees are refunded
+        callbacks.push(CallbackMsg::DeleteAllTriggerOrders {
+            account_id: liquidatee_account_id.to_string(),
+        });
+
+        if health.has_perps {
+            // Close all perp positions before liquidating.
+            // This creates the state of the account with only spot positions.
+            callbacks.push(CallbackMsg::CloseAllPerps {
+                account_id: liquidatee_account_id.to_string(),
+            });
+        }
+    }
+
+    let request = match request {
+        LiquidateRequest::Deposit(denom) => LiquidateRequest::Deposit(denom),
+        LiquidateRequest::Lend(denom) => LiquidateRequest::Lend(denom),
+        LiquidateRequest::Vault {
+            request_vault,
+            position_type,
+        } => LiquidateRequest::Vault {
+            request_vault: request_vault.check(deps.api)?,
+            position_type,
+        },
+        LiquidateRequest::StakedAstroLp(lp_denom) => LiquidateRequest::StakedAstroLp(lp_denom),
+        LiquidateRequest::Perp {
+            denom,
+            size,
+        } => LiquidateRequest::Perp {
+            denom,
+            size,
+        },
+        LiquidateRequest::IsolatedPerp(denom) => LiquidateRequest::IsolatedPerp(denom),
+    };
+    callbacks.push(CallbackMsg::Liquidate {
+        liquidator_account_id: liquidator_account_id.to_string(),
+        liquidatee_account_id: liquidatee_account_id.to_string(),
+        debt_coin,
+        request,
+        prev_health: health,
+    });
+    callbacks.push(CallbackMsg::UpdateLiquidationIndex {
+        account_id: liquidatee_account_id.to_string(),
+    });
+
+    Ok(callbacks)
+}
+
 /// Checks if the liquidatee's credit account is liquidatable.
 /// If not, returns an error.
 /// If liquidatable, returns the health values.
@@ -76,7 +183,7 @@ impl LiquidationResult {
 /// Difference between Liquidator Request Coin and Liquidatee Request Coin goes to rewards-collector account as protocol fee.
 pub fn calculate_liquidation(
     deps: &mut DepsMut,
-    _env: Env,
+    env: Env,
     liquidatee_account_id: &str,
     debt_coin: &Coin,
     request_coin: &str,
@@ -143,6 +250,7 @@ pub fn calculate_liquidation(
             &debt_coin_params,
             &health,
             perps_lb_ratio,
+            liquidatable_for(deps.storage, &env, liquidatee_account_id)?,
         )?;
 
     let result = LiquidationResult {
@@ -0,0 +1,229 @@
+use std::cmp::min;
+
+use cosmwasm_std::{
+    coin, Coin, Deps, DepsMut, Env, Int128, Order, Response, StdError, StdResult, Uint128,
+};
+use mars_liquidation::liquidation::{calculate_perp_liquidation_amounts, HealthData};
+use mars_types::{
+    credit_manager::CallbackMsg, health::HealthValuesResponse, oracle::ActionKind, perps::PnL,
+};
+
+use crate::{
+    error::{ContractError, ContractResult},
+    liquidation_auction::liquidatable_for,
+    state::{
+        COIN_BALANCES, ISOLATED_MARGINS, ORACLE, PARAMS, PERPS, PERPS_LB_RATIO, RED_BANK,
+        REWARDS_COLLECTOR,
+    },
+    utils::{decrement_coin_balance, increment_coin_balance},
+};
+
+/// Reduce a perp position of a liquidatable account by `size`.
+/// The size is capped like the debt repaid by a debt liquidation: at most `close_factor` (of the
+/// base denom) of the position is closed at once, unless the rest would be below the minimum
+/// position value.
+/// The liquidator receives a bonus for the PnL loss realized by the reduced size. It is paid from
+/// the base denom deposit of the liquidatee, or from its lends or other deposits if there is none.
+/// The PnL itself is settled by the `ExecutePerpOrder` callback of the liquidatee.
+/// Other positions of the account are left untouched.
+pub fn liquidate_perp(
+    deps: DepsMut,
+    env: Env,
+    liquidator_account_id: &str,
+    liquidatee_account_id: &str,
+    denom: &str,
+    size: Uint128,
+    prev_health: HealthValuesResponse,
+) -> ContractResult<Response> {
+    if ISOLATED_MARGINS.has(deps.storage, (liquidatee_account_id, denom)) {
+        return Err(ContractError::InvalidPerpLiquidation {
+            denom: denom.to_string(),
+            reason: "isolated positions are liquidated with their margin only".to_string(),
+        });
+    }
+
+    let perps = PERPS.load(deps.storage)?;
+    let position = perps
+        .query_position(&deps.querier, liquidatee_account_id, denom, None, None)?
+        .ok_or(ContractError::InvalidPerpLiquidation {
+        denom: denom.to_string(),
+        reason: format!("account {liquidatee_account_id} has no open position"),
+    })?;
+    let base_denom = position.base_denom.clone();
+
+    let oracle = ORACLE.load(deps.storage)?;
+    let params = PARAMS.load(deps.storage)?;
+    let base_denom_params = params
+        .query_asset_params(&deps.querier, &base_denom)?
+        .ok_or(ContractError::AssetParamsNotFound(base_denom.clone()))?;
+
+    // Maximum size closed at once is restricted by the close factor, as the debt repaid by a
+    // debt liquidation. The whole position is closed if the rest would be too small to be kept.
+    let position_size = position.size.unsigned_abs();
+    let max_size = position_size.checked_mul_floor(base_denom_params.close_factor)?;
+    let min_position_value = params.query_perp_params(&deps.querier, denom)?.min_position_value;
+    let rest_value = (position_size - max_size).checked_mul_floor(position.current_price)?;
+    let max_size = if rest_value < min_position_value {
+        position_size
+    } else {
+        max_size
+    };
+    let size = min(size, max_size);
+    if size.is_zero() {
+        return Err(ContractError::InvalidPerpLiquidation {
+            denom: denom.to_string(),
+            reason: "size must be greater than zero".to_string(),
+        });
+    }
+
+    let order_size = Int128::try_from(size).map_err(StdError::from)?;
+    let order_size = if position.size.is_negative() {
+        order_size
+    } else {
+        Int128::zero().checked_sub(order_size)?
+    };
+
+    // The whole PnL of the position is realized when it is modified, the bonus only accounts for
+    // the loss of the closed size
+    let position = perps.query_position(
+        &deps.querier,
+        liquidatee_account_id,
+        denom,
+        Some(order_size),
+        Some(true),
+    )?;
+    let pnl_loss = match position.map(|p| p.unrealized_pnl.to_coins(&base_denom).pnl) {
+        Some(PnL::Loss(loss)) => loss.amount.multiply_ratio(size, position_size),
+        _ => Uint128::zero(),
+    };
+    let base_denom_price =
+        oracle.query_price(&deps.querier, &base_denom, ActionKind::Liquidation)?.price;
+    let pnl_loss_value = pnl_loss.checked_mul_floor(base_denom_price)?;
+
+    let mut response = Response::new();
+
+    let (coin_liquidated, protocol_fee_coin) =
+        match bonus_collateral(deps.as_ref(), liquidatee_account_id, &base_denom)? {
+            Some(collateral) => {
+                let collateral_price = oracle
+                    .query_price(&deps.querier, &collateral.coin.denom, ActionKind::Liquidation)?
+                    .price;
+                let collateral_params = params
+                    .query_asset_params(&deps.querier, &collateral.coin.denom)?
+                    .ok_or(ContractError::AssetParamsNotFound(collateral.coin.denom.clone()))?;
+
+                let health: HealthData = prev_health.try_into()?;
+                let (amount_to_liquidate, amount_received_by_liquidator) =
+                    calculate_perp_liquidation_amounts(
+                        collateral.coin.amount,
+                        collateral_price,
+                        &collateral_params,
+                        pnl_loss_value,
+                        &health,
+                        PERPS_LB_RATIO.load(deps.storage)?,
+                        liquidatable_for(deps.storage, &env, liquidatee_account_id)?,
+                    )?;
+
+                let coin_liquidated = coin(amount_to_liquidate.u128(), &collateral.coin.denom);
+                let protocol_fee_coin = coin(
+                    (amount_to_liquidate - amount_received_by_liquidator).u128(),
+                    &collateral.coin.denom,
+                );
+                if !amount_to_liquidate.is_zero() {
+                    if collateral.lent {
+                        // Liquidatee's lent coin reclaimed from Red Bank
+                        let red_bank = RED_BANK.load(deps.storage)?;
+                        response = response.add_message(red_bank.reclaim_msg(
+                            &coin_liquidated,
+                            liquidatee_account_id,
+                            true,
+                        )?);
+                    } else {
+                        decrement_coin_balance(
+                            deps.storage,
+                            liquidatee_account_id,
+                            &coin_liquidated,
+                        )?;
+                    }
+                    increment_coin_balance(
+                        deps.storage,
+                        liquidator_account_id,
+                        &coin(amount_received_by_liquidator.u128(), &collateral.coin.denom),
+                    )?;
+                }
+                if !protocol_fee_coin.amount.is_zero() {
+                    let rewards_collector_account =
+                        REWARDS_COLLECTOR.load(deps.storage)?.account_id;
+                    increment_coin_balance(
+                        deps.storage,
+                        &rewards_collector_account,
+                        &protocol_fee_coin,
+                    )?;
+                }
+                (coin_liquidated, protocol_fee_coin)
+            }
+            // Nothing is left to pay the bonus with, the position is reduced anyway
+            None => (coin(0, &base_denom), coin(0, &base_denom)),
+        };
+
+    let msg = (CallbackMsg::ExecutePerpOrder {
+        account_id: liquidatee_account_id.to_string(),
+        denom: denom.to_string(),
+        size: order_size,
+        reduce_only: Some(true),
+        margin: None,
+        acceptable_price: None,
+    })
+    .into_cosmos_msg(&env.contract.address)?;
+
+    Ok(response
+        .add_message(msg)
+        .add_attribute("action", "liquidate_perp")
+        .add_attribute("account_id", liquidator_account_id)
+        .add_attribute("liquidatee_account_id", liquidatee_account_id)
+        .add_attribute("denom", denom)
+        .add_attribute("order_size", order_size.to_string())
+        .add_attribute("coin_liquidated", coin_liquidated.to_string())
+        .add_attribute("protocol_fee_coin", protocol_fee_coin.to_string()))
+}
+
+/// Collateral the liquidation bonus of a perp liquidation is paid with
+struct BonusCollateral {
+    coin: Coin,
+    lent: bool,
+}
+
+/// The base denom deposit of the account if there is one. Otherwise its base denom lend, then
+/// its other deposits and lends.
+fn bonus_collateral(
+    deps: Deps,
+    account_id: &str,
+    base_denom: &str,
+) -> ContractResult<Option<BonusCollateral>> {
+    let deposits = COIN_BALANCES
+        .prefix(account_id)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|res| {
+            let (denom, amount) = res?;
+            Ok(BonusCollateral {
+                coin: coin(amount.u128(), denom),
+                lent: false,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    let red_bank = RED_BANK.load(deps.storage)?;
+    let lends = red_bank.query_all_lent(&deps.querier, account_id)?.into_iter().map(|coin| {
+        BonusCollateral {
+            coin,
+            lent: true,
+        }
+    });
+
+    let (base_denom_collaterals, other_collaterals): (Vec<_>, Vec<_>) = deposits
+        .into_iter()
+        .chain(lends)
+        .filter(|collateral| !collateral.coin.amount.is_zero())
+        .partition(|collateral| collateral.coin.denom == base_denom);
+
+    Ok(base_denom_collaterals.into_iter().chain(other_collaterals).next())
+}
@@ -0,0 +1,51 @@
+use cosmwasm_std::{DepsMut, Env, Response, StdResult, Storage};
+use mars_types::oracle::ActionKind;
+
+use crate::{error::ContractResult, health::query_health_values, state::LIQUIDATABLE_SINCE};
+
+/// Flag a liquidatable account, starting the auction of its liquidation bonus. Accounts already
+/// flagged keep their auction start. The flag of an account which is healthy again is removed.
+pub fn flag_liquidatable(deps: DepsMut, env: Env, account_id: &str) -> ContractResult<Response> {
+    let health =
+        query_health_values(deps.as_ref(), env.clone(), account_id, ActionKind::Liquidation)?;
+
+    let liquidatable_since = if health.liquidatable {
+        let since = LIQUIDATABLE_SINCE
+            .may_load(deps.storage, account_id)?
+            .unwrap_or_else(|| env.block.time.seconds());
+        LIQUIDATABLE_SINCE.save(deps.storage, account_id, &since)?;
+        since.to_string()
+    } else {
+        LIQUIDATABLE_SINCE.remove(deps.storage, account_id);
+        "none".to_string()
+    };
+
+    Ok(Response::new()
+        .add_attribute("action", "flag_liquidatable")
+        .add_attribute("account_id", account_id)
+        .add_attribute("liquidatable_since", liquidatable_since))
+}
+
+/// Ends the liquidation bonus auction of a flagged account once it is healthy again
+pub fn end_auction_if_healthy(deps: DepsMut, env: Env, account_id: &str) -> ContractResult<()> {
+    if !LIQUIDATABLE_SINCE.has(deps.storage, account_id) {
+        return Ok(());
+    }
+
+    let health = query_health_values(deps.as_ref(), env, account_id, ActionKind::Liquidation)?;
+    if !health.liquidatable {
+        LIQUIDATABLE_SINCE.remove(deps.storage, account_id);
+    }
+
+    Ok(())
+}
+
+/// Seconds since the account was flagged as liquidatable, `None` if not flagged
+pub fn liquidatable_for(
+    storage: &dyn Storage,
+    env: &Env,
+    account_id: &str,
+) -> StdResult<Option<u64>> {
+    let since = LIQUIDATABLE_SINCE.may_load(storage, account_id)?;
+    Ok(since.map(|since| env.block.time.seconds().saturating_sub(since)))
+}
@@ -0,0 +1,204 @@
+use cosmwasm_std::{coin, Deps, DepsMut, Env, Order, Response, StdResult, Uint128};
+use cw_paginate::{DEFAULT_LIMIT, MAX_LIMIT};
+use cw_storage_plus::Bound;
+use mars_liquidation::liquidation::{calculate_liquidation_amounts, HealthData};
+use mars_types::{
+    credit_manager::{
+        LiquidatableAccount, LiquidatableAccountsResponse, LiquidateRequest, LiquidationCandidate,
+    },
+    health::HealthValuesResponse,
+    oracle::ActionKind,
+    perps::PnL,
+};
+
+use crate::{
+    error::ContractResult,
+    health::query_health_values,
+    liquidation_auction::{end_auction_if_healthy, liquidatable_for},
+    query::query_positions,
+    state::{DEBT_SHARES, LIQUIDATION_INDEX, ORACLE, PARAMS, PERPS, PERPS_LB_RATIO},
+};
+
+/// Keeps the account in the liquidation index as long as it has debt or perp positions
+pub fn update_liquidation_index(
+    mut deps: DepsMut,
+    env: Env,
+    account_id: &str,
+) -> ContractResult<Response> {
+    let has_debt = DEBT_SHARES
+        .prefix(account_id)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_some();
+    let indexed = has_debt
+        || !PERPS
+            .load(deps.storage)?
+            .query_positions_by_account(&deps.querier, account_id, ActionKind::Default)?
+            .is_empty();
+
+    if indexed {
+        LIQUIDATION_INDEX.save(deps.storage, account_id, &())?;
+    } else {
+        LIQUIDATION_INDEX.remove(deps.storage, account_id);
+    }
+
+    end_auction_if_healthy(deps.branch(), env, account_id)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "callback/update_liquidation_index")
+        .add_attribute("account_id", account_id)
+        .add_attribute("indexed", indexed.to_string()))
+}
+
+pub fn query_liquidatable_accounts(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    action: ActionKind,
+) -> ContractResult<LiquidatableAccountsResponse> {
+    let start = start_after.as_ref().map(|account_id| Bound::exclusive(account_id.as_str()));
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let account_ids = LIQUIDATION_INDEX
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = if account_ids.len() == limit {
+        account_ids.last().cloned()
+    } else {
+        None
+    };
+
+    let mut accounts = vec![];
+    let mut failed_account_ids = vec![];
+    for account_id in account_ids {
+        // An account whose health can't be computed doesn't prevent checking the others
+        match liquidatable_account(deps, &env, &account_id, action.clone()) {
+            Ok(Some(account)) => accounts.push(account),
+            Ok(None) => {}
+            Err(_) => failed_account_ids.push(account_id),
+        }
+    }
+
+    Ok(LiquidatableAccountsResponse {
+        accounts,
+        failed_account_ids,
+        next_start_after,
+    })
+}
+
+fn liquidatable_account(
+    deps: Deps,
+    env: &Env,
+    account_id: &str,
+    action: ActionKind,
+) -> ContractResult<Option<LiquidatableAccount>> {
+    let health = query_health_values(deps, env.clone(), account_id, action.clone())?;
+    if !health.liquidatable {
+        return Ok(None);
+    }
+    let liquidation = best_liquidation(deps, env, account_id, &health, action)?;
+    Ok(Some(LiquidatableAccount {
+        account_id: account_id.to_string(),
+        health,
+        liquidation,
+    }))
+}
+
+/// Find the liquidation repaying the most debt (by value) among the deposits and lends of the
+/// account. Without debt, the perp position with the largest loss is liquidated instead.
+fn best_liquidation(
+    deps: Deps,
+    env: &Env,
+    account_id: &str,
+    health: &HealthValuesResponse,
+    action: ActionKind,
+) -> ContractResult<Option<LiquidationCandidate>> {
+    let positions = query_positions(deps, account_id, action.clone())?;
+
+    if positions.debts.is_empty() {
+        let candidate = positions
+            .perps
+            .iter()
+            .filter(|position| position.isolated_margin.is_none())
+            .filter_map(|position| {
+                match position.unrealized_pnl.to_coins(&position.base_denom).pnl {
+                    PnL::Loss(loss) => Some((position, loss)),
+                    _ => None,
+                }
+            })
+            .max_by_key(|(_, loss)| loss.amount)
+            .map(|(position, loss)| LiquidationCandidate {
+                debt_coin: coin(0, loss.denom),
+                request: LiquidateRequest::Perp {
+                    denom: position.denom.clone(),
+                    size: position.size.unsigned_abs(),
+                },
+            });
+        return Ok(candidate);
+    }
+
+    let oracle = ORACLE.load(deps.storage)?;
+    let params = PARAMS.load(deps.storage)?;
+    let perps_lb_ratio = PERPS_LB_RATIO.load(deps.storage)?;
+    let health_data: HealthData = health.clone().try_into()?;
+    let liquidatable_for = liquidatable_for(deps.storage, env, account_id)?;
+
+    let mut collaterals = vec![];
+    let requested_coins = positions
+        .deposits
+        .iter()
+        .map(|c| (c, LiquidateRequest::Deposit(c.denom.clone())))
+        .chain(positions.lends.iter().map(|c| (c, LiquidateRequest::Lend(c.denom.clone()))));
+    for (collateral, request) in requested_coins {
+        let Some(collateral_params) =
+            params.query_asset_params(&deps.querier, &collateral.denom)?
+        else {
+            continue;
+        };
+        let price = oracle.query_price(&deps.querier, &collateral.denom, action.clone())?.price;
+        collaterals.push((collateral.amount, price, collateral_params, request));
+    }
+
+    let mut best: Option<(Uint128, LiquidationCandidate)> = None;
+    for debt in &positions.debts {
+        let Some(debt_params) = params.query_asset_params(&deps.querier, &debt.denom)? else {
+            continue;
+        };
+        let debt_price = oracle.query_price(&deps.querier, &debt.denom, action.clone())?.price;
+
+        for (collateral_amount, collateral_price, collateral_params, request) in &collaterals {
+            // Pairs which can't be liquidated (e.g. amounts too small) are ignored
+            let Ok((debt_amount, _, _)) = calculate_liquidation_amounts(
+                *collateral_amount,
+                *collateral_price,
+                collateral_params,
+                debt.amount,
+                debt.amount,
+                debt_price,
+                &debt_params,
+                &health_data,
+                perps_lb_ratio,
+                liquidatable_for,
+            ) else {
+                continue;
+            };
+
+            let debt_value = debt_amount.checked_mul_floor(debt_price)?;
+            if debt_value.is_zero() || best.as_ref().is_some_and(|(value, _)| debt_value <= *value)
+            {
+                continue;
+            }
+            best = Some((
+                debt_value,
+                LiquidationCandidate {
+                    debt_coin: coin(debt_amount.u128(), &debt.denom),
+                    request: request.clone(),
+                },
+            ));
+        }
+    }
+
+    Ok(best.map(|(_, candidate)| candidate))
+}
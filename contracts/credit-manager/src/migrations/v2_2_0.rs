@@ -1,19 +1,58 @@
-use cosmwasm_std::{DepsMut, Response};
+use cosmwasm_std::{Decimal, DepsMut, Order, Response, StdResult};
 use cw2::{assert_contract_version, set_contract_version};
+use mars_types::perps::PositionResponse;
 
 use crate::{
     contract::{CONTRACT_NAME, CONTRACT_VERSION},
     error::ContractError,
-    state::NEXT_TRIGGER_ID,
+    state::{
+        DEBT_SHARES, FLASH_LOAN_FEE_RATE, LIQUIDATION_INDEX, NEXT_RECURRING_ORDER_ID,
+        NEXT_TRIGGER_ID, PERPS,
+    },
 };
 
 const FROM_VERSION: &str = "2.1.0";
 
+/// Max number of perp positions the perps contract returns per page
+const PERP_POSITIONS_LIMIT: u32 = 30;
+
 pub fn migrate(deps: DepsMut) -> Result<Response, ContractError> {
     // make sure we're migrating the correct contract and from the correct version
     assert_contract_version(deps.storage, &format!("crates.io:{CONTRACT_NAME}"), FROM_VERSION)?;
 
     NEXT_TRIGGER_ID.save(deps.storage, &1)?;
+    NEXT_RECURRING_ORDER_ID.save(deps.storage, &1)?;
+    FLASH_LOAN_FEE_RATE.save(deps.storage, &Decimal::zero())?;
+
+    // Index the accounts with debt
+    let debt_keys = DEBT_SHARES
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (account_id, _) in debt_keys {
+        LIQUIDATION_INDEX.save(deps.storage, &account_id, &())?;
+    }
+
+    // Index the accounts with perp positions, they are stored in the perps contract
+    if let Some(perps) = PERPS.may_load(deps.storage)? {
+        let mut start_after = None;
+        loop {
+            let positions =
+                perps.query_positions(&deps.querier, start_after, Some(PERP_POSITIONS_LIMIT))?;
+            for res in positions.iter() {
+                LIQUIDATION_INDEX.save(deps.storage, &res.account_id, &())?;
+            }
+
+            start_after = match positions.last() {
+                Some(PositionResponse {
+                    account_id,
+                    position: Some(position),
+                }) if positions.len() == PERP_POSITIONS_LIMIT as usize => {
+                    Some((account_id.clone(), position.denom.clone()))
+                }
+                _ => break,
+            };
+        }
+    }
 
     set_contract_version(deps.storage, format!("crates.io:{CONTRACT_NAME}"), CONTRACT_VERSION)?;
 
@@ -0,0 +1,363 @@
+use cosmwasm_std::{
+    ensure, Addr, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult, Uint128,
+};
+use cw_paginate::{DEFAULT_LIMIT, MAX_LIMIT};
+use cw_storage_plus::Bound;
+use mars_types::credit_manager::{
+    Action, ActionAmount, ActionCoin, ActionType, OperatorGrant, OperatorGrantResponse,
+    OperatorPermissions,
+};
+
+use crate::{
+    error::{ContractError, ContractResult},
+    state::OPERATOR_GRANTS,
+    utils::{assert_is_authorized, query_nft_token_owner},
+};
+
+/// Action types which can never be granted to an operator as they send funds out of the account
+/// or act on positions of the sender outside of the account
+const NON_GRANTABLE_ACTIONS: [ActionType; 4] = [
+    ActionType::Withdraw,
+    ActionType::WithdrawToWallet,
+    ActionType::CloseAccount,
+    ActionType::ImportRedBankPosition,
+];
+
+pub fn grant_operator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    account_id: &str,
+    operator: &str,
+    permissions: OperatorPermissions,
+) -> ContractResult<Response> {
+    let owner = assert_is_account_owner(deps.as_ref(), &info.sender, account_id)?;
+    let operator_addr = deps.api.addr_validate(operator)?;
+
+    ensure!(
+        operator_addr != owner,
+        ContractError::InvalidOperatorGrant {
+            reason: "account owner cannot be an operator".to_string(),
+        }
+    );
+    ensure!(
+        !permissions.allowed_actions.is_empty(),
+        ContractError::InvalidOperatorGrant {
+            reason: "no actions allowed".to_string(),
+        }
+    );
+    if let Some(action) =
+        permissions.allowed_actions.iter().find(|a| NON_GRANTABLE_ACTIONS.contains(*a))
+    {
+        return Err(ContractError::InvalidOperatorGrant {
+            reason: format!("{action} cannot be granted to an operator"),
+        });
+    }
+    ensure!(
+        permissions.expires_at > env.block.time.seconds(),
+        ContractError::InvalidOperatorGrant {
+            reason: "expiration must be in the future".to_string(),
+        }
+    );
+
+    let expires_at = permissions.expires_at;
+    OPERATOR_GRANTS.save(
+        deps.storage,
+        (account_id, operator_addr.as_str()),
+        &OperatorGrant {
+            granter: owner.to_string(),
+            permissions,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "grant_operator")
+        .add_attribute("account_id", account_id)
+        .add_attribute("operator", operator_addr)
+        .add_attribute("expires_at", expires_at.to_string()))
+}
+
+pub fn revoke_operator(
+    deps: DepsMut,
+    info: MessageInfo,
+    account_id: &str,
+    operator: &str,
+) -> ContractResult<Response> {
+    assert_is_account_owner(deps.as_ref(), &info.sender, account_id)?;
+
+    ensure!(
+        OPERATOR_GRANTS.has(deps.storage, (account_id, operator)),
+        ContractError::OperatorGrantNotFound {
+            operator: operator.to_string(),
+            account_id: account_id.to_string(),
+        }
+    );
+    OPERATOR_GRANTS.remove(deps.storage, (account_id, operator));
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_operator")
+        .add_attribute("account_id", account_id)
+        .add_attribute("operator", operator))
+}
+
+pub fn assert_is_account_owner(deps: Deps, user: &Addr, account_id: &str) -> ContractResult<Addr> {
+    let owner = query_nft_token_owner(deps, account_id)?;
+    if user != &owner {
+        return Err(ContractError::NotTokenOwner {
+            user: user.to_string(),
+            account_id: account_id.to_string(),
+        });
+    }
+    Ok(user.clone())
+}
+
+/// Assert that the sender is authorized to dispatch the given actions on the account.
+///
+/// Senders that are neither the NFT owner nor the perps contract are checked against the
+/// operator grants of the account. Spend limits of the grant are consumed by the actions.
+/// Returns whether the sender is an operator of the account.
+pub fn assert_is_authorized_for_actions(
+    deps: &mut DepsMut,
+    env: &Env,
+    user: &Addr,
+    account_id: &str,
+    actions: &[Action],
+) -> ContractResult<bool> {
+    match assert_is_authorized(deps, user, account_id) {
+        Err(ContractError::NotTokenOwner {
+            ..
+        }) if OPERATOR_GRANTS.has(deps.storage, (account_id, user.as_str())) => {
+            use_operator_grant(deps, env, user, account_id, actions)?;
+            Ok(true)
+        }
+        res => res.map(|_| false),
+    }
+}
+
+fn use_operator_grant(
+    deps: &mut DepsMut,
+    env: &Env,
+    operator: &Addr,
+    account_id: &str,
+    actions: &[Action],
+) -> ContractResult<()> {
+    let mut grant = OPERATOR_GRANTS.load(deps.storage, (account_id, operator.as_str()))?;
+
+    ensure!(
+        env.block.time.seconds() < grant.permissions.expires_at,
+        ContractError::OperatorGrantExpired {
+            operator: operator.to_string(),
+            account_id: account_id.to_string(),
+        }
+    );
+
+    // Grants are issued by a specific owner and don't survive a transfer of the account NFT
+    let owner = query_nft_token_owner(deps.as_ref(), account_id)?;
+    ensure!(
+        owner == grant.granter,
+        ContractError::NotTokenOwner {
+            user: operator.to_string(),
+            account_id: account_id.to_string(),
+        }
+    );
+
+    for action in actions {
+        assert_operator_action(&mut grant.permissions, operator, action)?;
+    }
+
+    OPERATOR_GRANTS.save(deps.storage, (account_id, operator.as_str()), &grant)?;
+
+    Ok(())
+}
+
+fn assert_operator_action(
+    permissions: &mut OperatorPermissions,
+    operator: &Addr,
+    action: &Action,
+) -> ContractResult<()> {
+    let action_type = action.action_type();
+    ensure!(
+        permissions.allowed_actions.contains(&action_type),
+        ContractError::Unauthorized {
+            user: operator.to_string(),
+            action: action_type.to_string(),
+        }
+    );
+
+    match action {
+        // Repaying for another account moves funds out of the account
+        Action::Repay {
+            recipient_account_id: Some(_),
+            ..
+        } => {
+            return Err(ContractError::Unauthorized {
+                user: operator.to_string(),
+                action: "repay for another account".to_string(),
+            });
+        }
+        // Transfers move positions to accounts the grant doesn't cover
+        Action::TransferToAccount {
+            ..
+        }
+        | Action::TransferLendToAccount {
+            ..
+        }
+        | Action::TransferDebtToAccount {
+            ..
+        } => {
+            return Err(ContractError::Unauthorized {
+                user: operator.to_string(),
+                action: "transfer to another account".to_string(),
+            });
+        }
+        // Trigger orders are executed without ownership checks,
+        // so the nested actions have to be allowed as well
+        Action::CreateTriggerOrder {
+            actions,
+            ..
+        } => {
+            for nested_action in actions {
+                assert_operator_action(permissions, operator, nested_action)?;
+            }
+        }
+        _ => {}
+    }
+
+    for coin in spent_coins(action) {
+        let Some(limit) = permissions.spend_limits.iter_mut().find(|l| l.denom == coin.denom)
+        else {
+            continue;
+        };
+        let Some(requested) = coin.amount.value() else {
+            return Err(ContractError::ExactAmountRequired(coin.denom));
+        };
+        let remaining = limit.amount;
+        limit.amount =
+            remaining.checked_sub(requested).map_err(|_| ContractError::SpendLimitExceeded {
+                denom: coin.denom.clone(),
+                requested,
+                remaining,
+            })?;
+    }
+
+    Ok(())
+}
+
+/// Coins the action draws from the account (or borrows for it)
+pub fn spent_coins(action: &Action) -> Vec<ActionCoin> {
+    match action {
+        Action::Withdraw(coin)
+        | Action::Lend(coin)
+        | Action::WithdrawToWallet {
+            coin,
+            ..
+        }
+        | Action::Repay {
+            coin,
+            ..
+        }
+        | Action::DepositToPerpVault {
+            coin,
+            ..
+        }
+        | Action::EnterVault {
+            coin,
+            ..
+        } => vec![coin.clone()],
+        Action::SwapExactIn {
+            coin_in: coin,
+            ..
+        }
+        | Action::SwapCollateral {
+            from: coin,
+            ..
+        } => vec![coin.clone()],
+        Action::SwapDebt {
+            borrow_denom,
+            max_borrow,
+            ..
+        } => vec![ActionCoin {
+            denom: borrow_denom.clone(),
+            amount: ActionAmount::Exact(*max_borrow),
+        }],
+        // The amounts are only known when the actions are executed,
+        // so they can't be used with a spend limit of the denom
+        Action::Leverage {
+            debt_denom: denom,
+            ..
+        }
+        | Action::Deleverage {
+            collateral_denom: denom,
+            ..
+        } => vec![ActionCoin {
+            denom: denom.clone(),
+            amount: ActionAmount::AccountBalance,
+        }],
+        Action::CreateRecurringOrder {
+            keeper_fee,
+            periods,
+            ..
+        } => vec![ActionCoin {
+            denom: keeper_fee.denom.clone(),
+            amount: ActionAmount::Exact(keeper_fee.amount.saturating_mul(Uint128::from(*periods))),
+        }],
+        Action::WithdrawLiquidity {
+            lp_token,
+            ..
+        }
+        | Action::StakeAstroLp {
+            lp_token,
+        } => vec![lp_token.clone()],
+        Action::ProvideLiquidity {
+            coins_in,
+            ..
+        } => coins_in.clone(),
+        Action::Borrow(coin)
+        | Action::FlashLoan {
+            coin,
+            ..
+        }
+        | Action::CreateTriggerOrder {
+            keeper_fee: coin,
+            ..
+        }
+        | Action::Liquidate {
+            debt_coin: coin,
+            ..
+        } => vec![ActionCoin::from(coin)],
+        Action::BatchLiquidate {
+            liquidations,
+        } => liquidations
+            .iter()
+            .map(|liquidation| ActionCoin::from(&liquidation.debt_coin))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+pub fn query_account_operators(
+    deps: Deps,
+    env: Env,
+    account_id: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<OperatorGrantResponse>> {
+    let start = start_after.as_ref().map(|operator| Bound::exclusive(operator.as_str()));
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let now = env.block.time.seconds();
+
+    OPERATOR_GRANTS
+        .prefix(&account_id)
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| !matches!(item, Ok((_, grant)) if grant.permissions.expires_at <= now))
+        .take(limit)
+        .map(|item| {
+            let (operator, grant) = item?;
+            Ok(OperatorGrantResponse {
+                account_id: account_id.clone(),
+                operator,
+                grant,
+            })
+        })
+        .collect()
+}
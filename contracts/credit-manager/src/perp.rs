@@ -1,29 +1,46 @@
 use std::cmp::min;
 
 use cosmwasm_std::{
-    coin, ensure_eq, BankMsg, Coin, CosmosMsg, DepsMut, Env, Int128, MessageInfo, Response, Uint128,
+    coin, ensure_eq, BankMsg, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, Int128, MessageInfo,
+    Order, Response, StdError, StdResult, Storage, Uint128,
 };
+use mars_liquidation::liquidation::{calculate_perp_liquidation_amounts, HealthData};
 use mars_types::{
+    adapters::perps::Perps,
+    credit_manager::{CallbackMsg, ChangeExpected, PerpSettlementPolicy},
+    health::IsolatedPerpHealth,
     oracle::ActionKind,
-    perps::{PnL, PnlAmounts},
+    perps::{PerpPosition, PnL, PnlAmounts},
+    traits::Stringify,
 };
 
 use crate::{
+    account_settings::query_account_settings,
     borrow,
     error::{ContractError, ContractResult},
-    state::{COIN_BALANCES, PERPS, RED_BANK},
-    utils::{decrement_coin_balance, increment_coin_balance},
+    health::query_isolated_perp_health,
+    liquidation_auction::liquidatable_for,
+    state::{
+        COIN_BALANCES, ISOLATED_MARGINS, MAX_SLIPPAGE, ORACLE, PARAMS, PERPS, PERPS_LB_RATIO,
+        RED_BANK, REWARDS_COLLECTOR, SWAPPER,
+    },
+    swap::oracle_min_receive,
+    utils::{decrement_coin_balance, increment_coin_balance, update_balance_msg},
 };
 
 /// Deducts a specified payment from the user's account. If the user's balance in the
 /// specified denomination (e.g., USDC) is insufficient, the function first attempts to
 /// reclaim the shortfall from the Red Bank. If the reclaimed amount is still not enough,
-/// it borrows the remaining required amount from the Red Bank.
+/// the shortfall is covered according to the perp settlement policy of the account:
+/// other collateral is swapped into the denom (see `settlement_swaps`) and/or the remaining
+/// required amount is borrowed from the Red Bank.
 fn deduct_payment(
     deps: &mut DepsMut,
+    env: &Env,
     account_id: &str,
     payment: &Coin,
     action: Option<ActionKind>,
+    policy: &PerpSettlementPolicy,
     mut res: Response,
 ) -> ContractResult<Response> {
     // Determine if the payment is related to a liquidation event.
@@ -53,24 +70,78 @@ fn deduct_payment(
     // Attempt to reclaim funds from the Red Bank if the user has lent assets.
     let red_bank = RED_BANK.load(deps.storage)?;
     let lent_amount = red_bank.query_lent(&deps.querier, account_id, &payment.denom)?;
-
-    // If there are lent assets, reclaim as much as possible from the Red Bank.
-    if !lent_amount.is_zero() {
-        let reclaim_amount = min(left_amount_to_pay, lent_amount);
-        let reclaim_msg = red_bank.reclaim_msg(
+    let reclaim_amount = min(left_amount_to_pay, lent_amount);
+    let reclaim_msg = if !reclaim_amount.is_zero() {
+        Some(red_bank.reclaim_msg(
             &coin(reclaim_amount.u128(), &payment.denom),
             account_id,
             liquidation_related,
-        )?;
-        res = res.add_message(reclaim_msg);
+        )?)
+    } else {
+        None
+    };
 
-        // If the reclaimed amount fully covers the remaining payment, return the response.
-        if reclaim_amount >= left_amount_to_pay {
-            return Ok(res);
+    // Update the remaining amount to be paid after reclaiming from the Red Bank.
+    left_amount_to_pay -= reclaim_amount;
+
+    // Swap other collateral into the payment denom if the account prefers it over borrowing.
+    // The swap output is credited to the account before the reclaimed coins are received,
+    // the rest of the payment is then deducted from the new balance in a callback.
+    if let PerpSettlementPolicy::SwapFrom(denoms) = policy {
+        if !left_amount_to_pay.is_zero() {
+            let balances = denoms
+                .iter()
+                .map(|denom| {
+                    let amount = COIN_BALANCES
+                        .may_load(deps.storage, (account_id, denom.as_str()))?
+                        .unwrap_or_default();
+                    Ok(coin(amount.u128(), denom))
+                })
+                .collect::<StdResult<Vec<_>>>()?;
+            let left = coin(left_amount_to_pay.u128(), &payment.denom);
+            let swaps = settlement_swaps(deps.as_ref(), &left, denoms, &balances)?;
+
+            if !swaps.is_empty() {
+                let swapper = SWAPPER.load(deps.storage)?;
+                for (coin_in, min_receive) in swaps {
+                    decrement_coin_balance(deps.storage, account_id, &coin_in)?;
+                    res = res.add_message(swapper.swap_exact_in_msg(
+                        &coin_in,
+                        &payment.denom,
+                        min_receive,
+                        None,
+                    )?);
+                }
+                res = res.add_message(update_balance_msg(
+                    &deps.querier,
+                    &env.contract.address,
+                    account_id,
+                    &payment.denom,
+                    ChangeExpected::Increase,
+                )?);
+                if let Some(msg) = reclaim_msg {
+                    res = res.add_message(msg);
+                }
+
+                let settle_msg = CallbackMsg::SettlePerpLoss {
+                    account_id: account_id.to_string(),
+                    coin: left,
+                    action,
+                }
+                .into_cosmos_msg(&env.contract.address)?;
+                return Ok(res.add_message(settle_msg));
+            }
         }
+    }
+
+    // If there are lent assets, reclaim as much as possible from the Red Bank.
+    if let Some(msg) = reclaim_msg {
+        res = res.add_message(msg);
+    }
 
-        // Update the remaining amount to be paid after reclaiming from the Red Bank.
-        left_amount_to_pay -= reclaim_amount;
+    // If the reclaimed amount fully covers the remaining payment, return the response.
+    if left_amount_to_pay.is_zero() {
+        return Ok(res);
     }
 
     // If there is still a shortfall, borrow the remaining amount from the Red Bank.
@@ -81,12 +152,71 @@ fn deduct_payment(
     Ok(res.add_message(borrow_msg))
 }
 
+/// Pay the rest of a perp loss from the coin balance after collateral has been swapped into the
+/// payment denom, borrowing what is still missing.
+pub fn settle_perp_loss(
+    mut deps: DepsMut,
+    env: Env,
+    account_id: &str,
+    payment: &Coin,
+    action: Option<ActionKind>,
+) -> ContractResult<Response> {
+    let res = Response::new()
+        .add_attribute("action", "settle_perp_loss")
+        .add_attribute("account_id", account_id)
+        .add_attribute("payment", payment.to_string());
+    deduct_payment(&mut deps, &env, account_id, payment, action, &PerpSettlementPolicy::Borrow, res)
+}
+
+/// Coins to swap into the denom of the shortfall, following the order of `denoms`, together with
+/// the minimum amount to receive for each swap. The minimum is the oracle value of the coin reduced
+/// by the max slippage of the config, and the swapped amounts are grossed up by the slippage so that
+/// the minimum received covers the shortfall where the balances allow it.
+pub fn settlement_swaps(
+    deps: Deps,
+    shortfall: &Coin,
+    denoms: &[String],
+    balances: &[Coin],
+) -> ContractResult<Vec<(Coin, Uint128)>> {
+    let slippage = MAX_SLIPPAGE.load(deps.storage)?;
+    let out_ratio = Decimal::one().checked_sub(slippage)?;
+    let oracle = ORACLE.load(deps.storage)?;
+    let out_price = oracle.query_price(&deps.querier, &shortfall.denom, ActionKind::Default)?.price;
+
+    let mut left = shortfall.amount;
+    let mut swaps = vec![];
+    for denom in denoms.iter().filter(|denom| **denom != shortfall.denom) {
+        if left.is_zero() {
+            break;
+        }
+        let balance =
+            balances.iter().find(|c| &c.denom == denom).map(|c| c.amount).unwrap_or_default();
+        if balance.is_zero() {
+            continue;
+        }
+
+        let in_price = oracle.query_price(&deps.querier, denom, ActionKind::Default)?.price;
+        let needed =
+            left.checked_mul_ceil(out_price.checked_div(in_price)?)?.checked_div_ceil(out_ratio)?;
+        let coin_in = coin(min(needed, balance).u128(), denom);
+        let min_receive = oracle_min_receive(deps, &coin_in, &shortfall.denom, slippage)?;
+
+        left = left.saturating_sub(min_receive);
+        swaps.push((coin_in, min_receive));
+    }
+    Ok(swaps)
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn execute_perp_order(
     mut deps: DepsMut,
+    env: &Env,
     account_id: &str,
     denom: &str,
     order_size: Int128,
     reduce_only: Option<bool>,
+    margin: Option<Uint128>,
+    acceptable_price: Option<Decimal>,
 ) -> ContractResult<Response> {
     let perps = PERPS.load(deps.storage)?;
 
@@ -101,17 +231,71 @@ pub fn execute_perp_order(
     // computes the PnL **again** to assert the amount is correct.
     let position =
         perps.query_position(&deps.querier, account_id, denom, Some(order_size), reduce_only)?;
+    let isolated_margin = ISOLATED_MARGINS.may_load(deps.storage, (account_id, denom))?;
+    if margin.is_some() && position.is_some() && isolated_margin.is_none() {
+        return Err(ContractError::InvalidIsolatedMargin {
+            denom: denom.to_string(),
+            reason: "a cross margin position can't become isolated".to_string(),
+        });
+    }
+
     Ok(match position {
         Some(position) => {
             // Modify existing position
+            let mut isolated_margin = add_isolated_margin(
+                deps.storage,
+                account_id,
+                denom,
+                &position.base_denom,
+                isolated_margin,
+                margin,
+            )?;
+
             let pnl = position.unrealized_pnl.to_coins(&position.base_denom).pnl;
             let pnl_string = position.unrealized_pnl.pnl.to_string();
-            let (funds, response) =
-                update_state_based_on_pnl(&mut deps, account_id, pnl, None, response)?;
+            let (funds, response) = match isolated_margin.as_mut() {
+                Some(margin) => {
+                    let (funds, unpaid_loss) = settle_isolated_pnl(margin, pnl)?;
+                    if !unpaid_loss.is_zero() {
+                        return Err(ContractError::InvalidIsolatedMargin {
+                            denom: denom.to_string(),
+                            reason:
+                                "the loss exceeds the margin, the position can only be liquidated"
+                                    .to_string(),
+                        });
+                    }
+                    (funds, response)
+                }
+                None => update_state_based_on_pnl(&mut deps, env, account_id, pnl, None, response)?,
+            };
             let funds = funds.map_or_else(Vec::new, |c| vec![c]);
 
-            let msg =
-                perps.execute_perp_order(account_id, denom, order_size, reduce_only, funds)?;
+            // A reduce only order can't flip the position, it is closed instead
+            let new_size = position.size.checked_add(order_size)?;
+            let new_size = if reduce_only.unwrap_or(false)
+                && new_size.is_negative() != position.size.is_negative()
+            {
+                Int128::zero()
+            } else {
+                new_size
+            };
+            save_isolated_margin(
+                deps.storage,
+                account_id,
+                denom,
+                &position.base_denom,
+                isolated_margin,
+                new_size.is_zero(),
+            )?;
+
+            let msg = perps.execute_perp_order(
+                account_id,
+                denom,
+                order_size,
+                reduce_only,
+                acceptable_price,
+                funds,
+            )?;
 
             response
                 .add_message(msg)
@@ -121,22 +305,62 @@ pub fn execute_perp_order(
                 .add_attribute("realized_pnl", pnl_string)
                 .add_attribute("reduce_only", reduce_only.unwrap_or(false).to_string())
                 .add_attribute("order_size", order_size.to_string())
-                .add_attribute("new_size", position.size.checked_add(order_size)?.to_string())
+                .add_attribute("new_size", new_size.to_string())
+                .add_attribute("isolated_margin", isolated_margin.unwrap_or_default().to_string())
         }
         None => {
             // Open new position
             let opening_fee = perps.query_opening_fee(&deps.querier, denom, order_size)?;
             let fee = opening_fee.fee;
 
+            let mut isolated_margin = add_isolated_margin(
+                deps.storage,
+                account_id,
+                denom,
+                &fee.denom,
+                isolated_margin,
+                margin,
+            )?;
+
             let funds = if !fee.amount.is_zero() {
-                response = deduct_payment(&mut deps, account_id, &fee, None, response)?;
+                response = match isolated_margin.as_mut() {
+                    Some(margin) => {
+                        let (_, unpaid_fee) = settle_isolated_pnl(margin, PnL::Loss(fee.clone()))?;
+                        if !unpaid_fee.is_zero() {
+                            return Err(ContractError::InvalidIsolatedMargin {
+                                denom: denom.to_string(),
+                                reason: "margin must cover the opening fee".to_string(),
+                            });
+                        }
+                        response
+                    }
+                    None => {
+                        let policy =
+                            query_account_settings(deps.as_ref(), account_id)?.perp_settlement;
+                        deduct_payment(&mut deps, env, account_id, &fee, None, &policy, response)?
+                    }
+                };
                 vec![fee.clone()]
             } else {
                 vec![]
             };
-
-            let msg =
-                perps.execute_perp_order(account_id, denom, order_size, reduce_only, funds)?;
+            save_isolated_margin(
+                deps.storage,
+                account_id,
+                denom,
+                &fee.denom,
+                isolated_margin,
+                false,
+            )?;
+
+            let msg = perps.execute_perp_order(
+                account_id,
+                denom,
+                order_size,
+                reduce_only,
+                acceptable_price,
+                funds,
+            )?;
 
             response
                 .add_message(msg)
@@ -146,20 +370,248 @@ pub fn execute_perp_order(
                 .add_attribute("reduce_only", reduce_only.unwrap_or(false).to_string())
                 .add_attribute("new_size", order_size.to_string())
                 .add_attribute("opening_fee", fee.to_string())
+                .add_attribute("isolated_margin", isolated_margin.unwrap_or_default().to_string())
         }
     })
 }
 
-/// Check if liquidatee has any perp positions.
+/// Moves the margin added to an isolated position out of the coin balance of the account.
+/// Returns the new margin of the position, `None` for cross margin positions.
+fn add_isolated_margin(
+    storage: &mut dyn Storage,
+    account_id: &str,
+    denom: &str,
+    base_denom: &str,
+    current: Option<Uint128>,
+    margin: Option<Uint128>,
+) -> ContractResult<Option<Uint128>> {
+    let Some(margin) = margin else {
+        return Ok(current);
+    };
+    if margin.is_zero() {
+        return Err(ContractError::InvalidIsolatedMargin {
+            denom: denom.to_string(),
+            reason: "margin must be greater than zero".to_string(),
+        });
+    }
+
+    decrement_coin_balance(storage, account_id, &coin(margin.u128(), base_denom))?;
+    Ok(Some(current.unwrap_or_default().checked_add(margin)?))
+}
+
+/// Stores the margin of an isolated position. Once the position is closed the margin left is
+/// released to the coin balance of the account.
+fn save_isolated_margin(
+    storage: &mut dyn Storage,
+    account_id: &str,
+    denom: &str,
+    base_denom: &str,
+    margin: Option<Uint128>,
+    closed: bool,
+) -> ContractResult<()> {
+    match margin {
+        Some(margin) if !closed => ISOLATED_MARGINS.save(storage, (account_id, denom), &margin)?,
+        Some(margin) => {
+            ISOLATED_MARGINS.remove(storage, (account_id, denom));
+            increment_coin_balance(storage, account_id, &coin(margin.u128(), base_denom))?;
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+/// Settles the PnL of an isolated position against its margin: profits are added to the margin
+/// and losses are paid from it, so the rest of the account isn't affected.
+/// Returns the coin to send to the perps contract and the part of the loss the margin can't cover
+/// (if the price gapped past the liquidation price). The unpaid loss is never taken from the rest
+/// of the account, the vault takes it as bad debt (the position is closed with
+/// `CloseIsolatedPosition`, or the perps contract accepts the lower payment after a deleverage).
+fn settle_isolated_pnl(margin: &mut Uint128, pnl: PnL) -> ContractResult<(Option<Coin>, Uint128)> {
+    Ok(match pnl {
+        PnL::Loss(loss) => {
+            let from_margin = min(*margin, loss.amount);
+            *margin -= from_margin;
+
+            let funds = if from_margin.is_zero() {
+                None
+            } else {
+                Some(coin(from_margin.u128(), &loss.denom))
+            };
+            (funds, loss.amount - from_margin)
+        }
+        PnL::Profit(profit) => {
+            *margin = margin.checked_add(profit.amount)?;
+            (None, Uint128::zero())
+        }
+        PnL::BreakEven => (None, Uint128::zero()),
+    })
+}
+
+/// Closes an isolated position with the loss capped at its margin. The margin of the position is
+/// removed from the state and the margin left after the PnL is returned (not yet credited to the
+/// account), together with the message closing the position.
+fn close_isolated_position(
+    storage: &mut dyn Storage,
+    perps: &Perps,
+    account_id: &str,
+    position: &PerpPosition,
+    action: ActionKind,
+) -> ContractResult<(Uint128, CosmosMsg)> {
+    let mut margin = ISOLATED_MARGINS.load(storage, (account_id, &position.denom))?;
+    ISOLATED_MARGINS.remove(storage, (account_id, &position.denom));
+
+    let pnl = position.unrealized_pnl.to_coins(&position.base_denom).pnl;
+    let (funds, _) = settle_isolated_pnl(&mut margin, pnl)?;
+    let funds = funds.map_or_else(Vec::new, |c| vec![c]);
+
+    let msg = perps.close_isolated_position_msg(account_id, &position.denom, funds, action)?;
+    Ok((margin, msg))
+}
+
+/// Close a liquidatable isolated perp position. The loss is settled against the margin of the
+/// position only, a loss exceeding the margin is bad debt of the perps vault. The liquidation
+/// bonus is computed by `mars-liquidation` from the PnL loss and paid from the margin left
+/// (a protocol fee is taken from it for the rewards collector), the rest is returned to the coin
+/// balance of the liquidatee. Other positions are left untouched.
+/// The position is asserted to be liquidatable when the action is dispatched.
+pub fn liquidate_isolated_perp(
+    deps: DepsMut,
+    env: Env,
+    liquidator_account_id: &str,
+    liquidatee_account_id: &str,
+    denom: &str,
+) -> ContractResult<Response> {
+    if !ISOLATED_MARGINS.has(deps.storage, (liquidatee_account_id, denom)) {
+        return Err(ContractError::InvalidIsolatedMargin {
+            denom: denom.to_string(),
+            reason: format!("account {liquidatee_account_id} has no isolated position"),
+        });
+    }
+
+    // The position is closed with the liquidation pricing, as in `close_all_perps`
+    let perps = PERPS.load(deps.storage)?;
+    let position = perps
+        .query_positions_by_account(&deps.querier, liquidatee_account_id, ActionKind::Liquidation)?
+        .into_iter()
+        .find(|position| position.denom == denom)
+        .ok_or(ContractError::InvalidIsolatedMargin {
+            denom: denom.to_string(),
+            reason: format!("account {liquidatee_account_id} has no open position"),
+        })?;
+    let base_denom = position.base_denom.clone();
+
+    // The health has to be queried while the margin is still stored
+    let isolated_health = query_isolated_perp_health(
+        deps.as_ref(),
+        env.clone(),
+        liquidatee_account_id,
+        denom,
+        ActionKind::Liquidation,
+    )?;
+
+    let pnl = position.unrealized_pnl.to_coins(&base_denom).pnl;
+    let pnl_string = position.unrealized_pnl.pnl.to_string();
+    let pnl_loss = match &pnl {
+        PnL::Loss(loss) => loss.amount,
+        _ => Uint128::zero(),
+    };
+    let (margin_left, msg) = close_isolated_position(
+        deps.storage,
+        &perps,
+        liquidatee_account_id,
+        &position,
+        ActionKind::Liquidation,
+    )?;
+
+    let oracle = ORACLE.load(deps.storage)?;
+    let base_denom_price =
+        oracle.query_price(&deps.querier, &base_denom, ActionKind::Liquidation)?.price;
+    let base_denom_params = PARAMS
+        .load(deps.storage)?
+        .query_asset_params(&deps.querier, &base_denom)?
+        .ok_or(ContractError::AssetParamsNotFound(base_denom.clone()))?;
+
+    let pnl_loss_value = pnl_loss.checked_mul_floor(base_denom_price)?;
+    let health = isolated_health_data(&isolated_health, pnl_loss_value)?;
+    let (amount_to_liquidate, amount_received_by_liquidator) = calculate_perp_liquidation_amounts(
+        margin_left,
+        base_denom_price,
+        &base_denom_params,
+        pnl_loss_value,
+        &health,
+        PERPS_LB_RATIO.load(deps.storage)?,
+        liquidatable_for(deps.storage, &env, liquidatee_account_id)?,
+    )?;
+
+    let bonus = coin(amount_received_by_liquidator.u128(), &base_denom);
+    let protocol_fee_coin =
+        coin((amount_to_liquidate - amount_received_by_liquidator).u128(), &base_denom);
+    increment_coin_balance(deps.storage, liquidator_account_id, &bonus)?;
+    if !protocol_fee_coin.amount.is_zero() {
+        let rewards_collector_account = REWARDS_COLLECTOR.load(deps.storage)?.account_id;
+        increment_coin_balance(deps.storage, &rewards_collector_account, &protocol_fee_coin)?;
+    }
+    increment_coin_balance(
+        deps.storage,
+        liquidatee_account_id,
+        &coin((margin_left - amount_to_liquidate).u128(), &base_denom),
+    )?;
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", "liquidate_isolated_perp")
+        .add_attribute("liquidator_account_id", liquidator_account_id)
+        .add_attribute("liquidatee_account_id", liquidatee_account_id)
+        .add_attribute("denom", denom)
+        .add_attribute("realized_pnl", pnl_string)
+        .add_attribute("liquidator_bonus", bonus.to_string())
+        .add_attribute("protocol_fee_coin", protocol_fee_coin.to_string()))
+}
+
+/// Health data of an isolated position for the liquidation bonus: its margin is the only
+/// collateral and its PnL loss the only debt.
+fn isolated_health_data(
+    health: &IsolatedPerpHealth,
+    pnl_loss_value: Uint128,
+) -> ContractResult<HealthData> {
+    let liquidation_health_factor = health
+        .liquidation_health_factor
+        .ok_or_else(|| StdError::generic_err("Liquidation health factor not available"))?;
+    let collateralization_ratio = if pnl_loss_value.is_zero() {
+        Decimal::MAX
+    } else {
+        Decimal::checked_from_ratio(health.margin_value, pnl_loss_value)?
+    };
+    Ok(HealthData {
+        liquidation_health_factor,
+        collateralization_ratio,
+        perps_pnl_loss: pnl_loss_value,
+        account_net_value: Int128::try_from(health.margin_value)
+            .map_err(StdError::from)?
+            .checked_sub(Int128::try_from(pnl_loss_value).map_err(StdError::from)?)?,
+    })
+}
+
+/// Check if liquidatee has any cross margin perp positions.
 /// If so, close them before liquidating.
+/// Isolated positions are not backed by the rest of the account, they are left open together
+/// with their margin (see `close_isolated_perps` to close them).
 pub fn close_all_perps(
     mut deps: DepsMut,
+    env: &Env,
     account_id: &str,
     action: ActionKind,
 ) -> ContractResult<Response> {
     let perps = PERPS.load(deps.storage)?;
-    let perp_positions =
-        perps.query_positions_by_account(&deps.querier, account_id, action.clone())?;
+    let isolated_denoms = ISOLATED_MARGINS
+        .prefix(account_id)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let perp_positions = perps
+        .query_positions_by_account(&deps.querier, account_id, action.clone())?
+        .into_iter()
+        .filter(|position| !isolated_denoms.contains(&position.denom))
+        .collect::<Vec<_>>();
     if perp_positions.is_empty() {
         return Ok(Response::new()
             .add_attribute("action", "close_all_perps")
@@ -180,11 +632,11 @@ pub fn close_all_perps(
 
     let pnl = pnl_amounts_accumulator.to_coins(&base_denom).pnl;
     let (funds, response) =
-        update_state_based_on_pnl(&mut deps, account_id, pnl, Some(action.clone()), response)?;
+        update_state_based_on_pnl(&mut deps, env, account_id, pnl, Some(action.clone()), response)?;
     let funds = funds.map_or_else(Vec::new, |c| vec![c]);
 
-    // Close all perp positions at once
-    let close_msg = perps.close_all_msg(account_id, funds, action)?;
+    // Close all cross margin perp positions at once
+    let close_msg = perps.close_all_msg(account_id, funds, action, isolated_denoms)?;
 
     Ok(response
         .add_message(close_msg)
@@ -193,14 +645,55 @@ pub fn close_all_perps(
         .add_attribute("number_of_positions", perp_positions.len().to_string()))
 }
 
+/// Close the isolated perp positions of an account, each loss being capped at the margin of its
+/// position. The margins left are released to the coin balance of the account.
+pub fn close_isolated_perps(
+    deps: DepsMut,
+    account_id: &str,
+    action: ActionKind,
+) -> ContractResult<Response> {
+    let isolated_denoms = ISOLATED_MARGINS
+        .prefix(account_id)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    if isolated_denoms.is_empty() {
+        return Ok(Response::new());
+    }
+
+    let perps = PERPS.load(deps.storage)?;
+    let positions = perps
+        .query_positions_by_account(&deps.querier, account_id, action.clone())?
+        .into_iter()
+        .filter(|position| isolated_denoms.contains(&position.denom))
+        .collect::<Vec<_>>();
+
+    let mut response = Response::new();
+    for position in &positions {
+        let (margin_left, msg) =
+            close_isolated_position(deps.storage, &perps, account_id, position, action.clone())?;
+        increment_coin_balance(
+            deps.storage,
+            account_id,
+            &coin(margin_left.u128(), &position.base_denom),
+        )?;
+        response = response.add_message(msg);
+    }
+
+    Ok(response
+        .add_attribute("action", "close_isolated_perps")
+        .add_attribute("account_id", account_id)
+        .add_attribute("number_of_positions", positions.len().to_string()))
+}
+
 /// Prepare the necessary messages and funds to be sent to the perps contract based on the PnL.
 /// - If PnL is negative, we need to send funds to the perps contract, and
 /// decrement the internally tracked user coin balance. If no enough usdc in the user's account,
-/// we need to borrow from the Red Bank.
+/// we need to swap other collateral or borrow from the Red Bank (see `deduct_payment`).
 /// - If PnL is positive, we need to increment the internally tracked user coin.
 /// - Otherwise, no action is needed.
 fn update_state_based_on_pnl(
     deps: &mut DepsMut,
+    env: &Env,
     account_id: &str,
     pnl: PnL,
     action: Option<ActionKind>,
@@ -208,7 +701,8 @@ fn update_state_based_on_pnl(
 ) -> ContractResult<(Option<Coin>, Response)> {
     let res = match pnl {
         PnL::Loss(coin) => {
-            let res = deduct_payment(deps, account_id, &coin, action, res)?;
+            let policy = query_account_settings(deps.as_ref(), account_id)?.perp_settlement;
+            let res = deduct_payment(deps, env, account_id, &coin, action, &policy, res)?;
             (Some(coin), res)
         }
         PnL::Profit(coin) => {
@@ -222,7 +716,7 @@ fn update_state_based_on_pnl(
 
 pub fn update_balance_after_deleverage(
     mut deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     account_id: String,
     pnl: PnL,
@@ -240,11 +734,45 @@ pub fn update_balance_after_deleverage(
         }
     );
 
+    // The deleveraged position is already closed in the perps contract. If it was isolated, its
+    // margin is left in the state while the position isn't found anymore.
+    let mut closed_isolated_denom = None;
+    let isolated_denoms = ISOLATED_MARGINS
+        .prefix(&account_id)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for denom in isolated_denoms {
+        if perps.query_position(&deps.querier, &account_id, &denom, None, None)?.is_none() {
+            closed_isolated_denom = Some(denom);
+            break;
+        }
+    }
+
     let response = Response::new();
 
     let pnl_string = pnl.to_signed_uint()?.to_string();
-    let (funds, mut response) =
-        update_state_based_on_pnl(&mut deps, &account_id, pnl, Some(action), response)?;
+    let (funds, mut response) = match closed_isolated_denom {
+        // The PnL of an isolated position is settled against its margin only and the margin left
+        // is released. The perps contract takes the loss exceeding the margin as bad debt.
+        Some(denom) => {
+            let mut margin = ISOLATED_MARGINS.load(deps.storage, (&account_id, &denom))?;
+            ISOLATED_MARGINS.remove(deps.storage, (&account_id, &denom));
+
+            let (funds, bad_debt) = settle_isolated_pnl(&mut margin, pnl)?;
+            let base_denom = perps.query_config(&deps.querier)?.base_denom;
+            if !margin.is_zero() {
+                increment_coin_balance(
+                    deps.storage,
+                    &account_id,
+                    &coin(margin.u128(), base_denom),
+                )?;
+            }
+            (funds, response.add_attribute("bad_debt", bad_debt.to_string()))
+        }
+        None => {
+            update_state_based_on_pnl(&mut deps, &env, &account_id, pnl, Some(action), response)?
+        }
+    };
 
     // Amount sent will be validated in the perps contract in reply entry point
     if let Some(f) = funds {
@@ -257,7 +785,14 @@ pub fn update_balance_after_deleverage(
         }
     }
 
+    // The account may be left without debt or perp positions
+    let index_msg = (CallbackMsg::UpdateLiquidationIndex {
+        account_id: account_id.clone(),
+    })
+    .into_cosmos_msg(&env.contract.address)?;
+
     Ok(response
+        .add_message(index_msg)
         .add_attribute("action", "update_balance_after_deleverage")
         .add_attribute("account_id", account_id)
         .add_attribute("realized_pnl", pnl_string))
@@ -8,20 +8,22 @@ use mars_types::{
     adapters::vault::{Vault, VaultBase, VaultPosition, VaultPositionValue, VaultUnchecked},
     credit_manager::{
         Account, CoinBalanceResponseItem, ConfigResponse, DebtAmount, DebtShares, Positions,
-        SharesResponseItem, TriggerOrderResponse, VaultBinding, VaultPositionResponseItem,
-        VaultUtilizationResponse,
+        RecurringOrderResponse, SharesResponseItem, TriggerOrderResponse, VaultBinding,
+        VaultPositionResponseItem, VaultUtilizationResponse,
     },
     health::AccountKind,
     oracle::ActionKind,
+    perps::PerpPosition,
 };
 
 use crate::{
     error::ContractResult,
     state::{
-        ACCOUNT_KINDS, ACCOUNT_NFT, COIN_BALANCES, DEBT_SHARES, HEALTH_CONTRACT, INCENTIVES,
-        KEEPER_FEE_CONFIG, MAX_SLIPPAGE, MAX_UNLOCKING_POSITIONS, ORACLE, OWNER, PARAMS, PERPS,
-        PERPS_LB_RATIO, RED_BANK, REWARDS_COLLECTOR, SWAPPER, TOTAL_DEBT_SHARES, TRIGGER_ORDERS,
-        VAULTS, VAULT_POSITIONS, ZAPPER,
+        ACCOUNT_KINDS, ACCOUNT_NFT, COIN_BALANCES, DEBT_SHARES, FLASH_LOAN_FEE_RATE,
+        HEALTH_CONTRACT, INCENTIVES, ISOLATED_MARGINS, KEEPER_FEE_CONFIG, MAX_SLIPPAGE,
+        MAX_UNLOCKING_POSITIONS, ORACLE, OWNER, PARAMS, PERPS, PERPS_LB_RATIO, RECURRING_ORDERS,
+        RED_BANK, REWARDS_COLLECTOR, SWAPPER, TOTAL_DEBT_SHARES, TRIGGER_ORDERS, VAULTS,
+        VAULT_POSITIONS, ZAPPER,
     },
     utils::debt_shares_to_amount,
     vault::vault_utilization_in_deposit_cap_denom,
@@ -67,6 +69,7 @@ pub fn query_config(deps: Deps) -> ContractResult<ConfigResponse> {
         rewards_collector: REWARDS_COLLECTOR.may_load(deps.storage)?,
         keeper_fee_config: KEEPER_FEE_CONFIG.load(deps.storage)?,
         perps_liquidation_bonus_ratio: PERPS_LB_RATIO.load(deps.storage)?,
+        flash_loan_fee_rate: FLASH_LOAN_FEE_RATE.load(deps.storage)?,
     })
 }
 
@@ -85,14 +88,25 @@ pub fn query_positions(
         staked_astro_lps: INCENTIVES
             .load(deps.storage)?
             .query_all_staked_astro_lp_coins(&deps.querier, account_id)?,
-        perps: PERPS.load(deps.storage)?.query_positions_by_account(
-            &deps.querier,
-            account_id,
-            action,
-        )?,
+        perps: query_perp_positions(deps, account_id, action)?,
     })
 }
 
+/// Perp positions of the account, with the margin of isolated positions filled in
+fn query_perp_positions(
+    deps: Deps,
+    account_id: &str,
+    action: ActionKind,
+) -> ContractResult<Vec<PerpPosition>> {
+    let mut positions =
+        PERPS.load(deps.storage)?.query_positions_by_account(&deps.querier, account_id, action)?;
+    for position in positions.iter_mut() {
+        position.isolated_margin =
+            ISOLATED_MARGINS.may_load(deps.storage, (account_id, &position.denom))?;
+    }
+    Ok(positions)
+}
+
 pub fn query_all_coin_balances(
     deps: Deps,
     start_after: Option<(String, String)>,
@@ -205,6 +219,54 @@ pub fn query_all_trigger_orders(
     )
 }
 
+pub fn query_all_recurring_orders_for_account(
+    deps: Deps,
+    account_id: String,
+    start_after_order_id: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<PaginationResponse<RecurringOrderResponse>> {
+    let start = start_after_order_id.as_ref().map(|order_id| Bound::exclusive(order_id.as_str()));
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+    paginate_prefix_query(
+        &RECURRING_ORDERS,
+        deps.storage,
+        &account_id,
+        start,
+        Some(limit),
+        |_order_id, order| {
+            Ok(RecurringOrderResponse {
+                account_id: account_id.clone(),
+                order,
+            })
+        },
+    )
+}
+
+pub fn query_all_recurring_orders(
+    deps: Deps,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<PaginationResponse<RecurringOrderResponse>> {
+    let start: Option<Bound<'_, (&str, &str)>> = start_after
+        .as_ref()
+        .map(|(account_id, order_id)| Bound::exclusive((account_id.as_str(), order_id.as_str())));
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+    paginate_map_query(
+        &RECURRING_ORDERS,
+        deps.storage,
+        start,
+        Some(limit),
+        |(account_id, _order_id), order| {
+            Ok(RecurringOrderResponse {
+                account_id: account_id.to_string(),
+                order,
+            })
+        },
+    )
+}
+
 pub fn query_vault_utilization(
     deps: Deps,
     env: Env,
@@ -0,0 +1,209 @@
+use cosmwasm_std::{
+    ensure, BankMsg, Coin, CosmosMsg, DepsMut, Env, MessageInfo, Order, Response, StdError,
+    StdResult, Storage, Uint128,
+};
+use mars_types::credit_manager::{Action, ActionCoin, CallbackMsg, RecurringOrder};
+
+use crate::{
+    error::ContractError,
+    execute::{dispatch_steps, DispatchMode, DispatchStep},
+    state::{COIN_BALANCES, NEXT_RECURRING_ORDER_ID, RECURRING_ORDERS},
+    trigger::assert_keeper_fee,
+    utils::{decrement_coin_balance, increment_coin_balance},
+};
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_recurring_order(
+    deps: DepsMut,
+    env: Env,
+    account_id: &str,
+    coin_in: Coin,
+    denom_out: String,
+    interval: u64,
+    periods: u32,
+    lend_output: bool,
+    keeper_fee: Coin,
+) -> Result<Response, ContractError> {
+    ensure!(
+        !coin_in.amount.is_zero(),
+        ContractError::InvalidRecurringOrder {
+            reason: "amount to swap must be greater than zero".to_string(),
+        }
+    );
+    ensure!(
+        coin_in.denom != denom_out,
+        ContractError::InvalidRecurringOrder {
+            reason: "denom in and denom out must be different".to_string(),
+        }
+    );
+    ensure!(
+        interval > 0 && periods > 0,
+        ContractError::InvalidRecurringOrder {
+            reason: "interval and periods must be greater than zero".to_string(),
+        }
+    );
+    assert_keeper_fee(deps.storage, &keeper_fee)?;
+
+    // Deduct the keeper fees of all periods from the account
+    let total_keeper_fee = Coin {
+        denom: keeper_fee.denom.clone(),
+        amount: keeper_fee.amount.checked_mul(Uint128::from(periods))?,
+    };
+    decrement_coin_balance(deps.storage, account_id, &total_keeper_fee)?;
+
+    // Generate & increment id
+    let order_id = NEXT_RECURRING_ORDER_ID.load(deps.storage)?;
+    NEXT_RECURRING_ORDER_ID.save(deps.storage, &(order_id + 1))?;
+
+    let order_id = order_id.to_string();
+    RECURRING_ORDERS.save(
+        deps.storage,
+        (account_id, &order_id),
+        &RecurringOrder {
+            order_id: order_id.clone(),
+            coin_in,
+            denom_out,
+            interval,
+            periods_remaining: periods,
+            next_execution: env.block.time.seconds(),
+            lend_output,
+            keeper_fee,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_recurring_order")
+        .add_attribute("account_id", account_id)
+        .add_attribute("order_id", order_id)
+        .add_attribute("keeper_fee", total_keeper_fee.to_string()))
+}
+
+pub fn delete_recurring_order(
+    deps: DepsMut,
+    account_id: &str,
+    order_id: &str,
+) -> Result<Response, ContractError> {
+    let order = RECURRING_ORDERS.may_load(deps.storage, (account_id, order_id))?.ok_or(
+        ContractError::RecurringOrderNotFound {
+            order_id: order_id.to_string(),
+            account_id: account_id.to_string(),
+        },
+    )?;
+
+    RECURRING_ORDERS.remove(deps.storage, (account_id, order_id));
+    increment_coin_balance(deps.storage, account_id, &remaining_keeper_fee(&order)?)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "delete_recurring_order")
+        .add_attribute("account_id", account_id)
+        .add_attribute("order_id", order_id))
+}
+
+pub fn execute_recurring_order(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    account_id: &str,
+    order_id: &str,
+) -> Result<Response, ContractError> {
+    // Use may_load so we can give a better error message
+    let mut order = RECURRING_ORDERS.may_load(deps.storage, (account_id, order_id))?.ok_or(
+        ContractError::RecurringOrderNotFound {
+            order_id: order_id.to_string(),
+            account_id: account_id.to_string(),
+        },
+    )?;
+
+    let now = env.block.time.seconds();
+    ensure!(
+        now >= order.next_execution,
+        ContractError::RecurringOrderNotDue {
+            order_id: order_id.to_string(),
+            next_execution: order.next_execution,
+        }
+    );
+
+    order.periods_remaining -= 1;
+    if order.periods_remaining == 0 {
+        RECURRING_ORDERS.remove(deps.storage, (account_id, order_id));
+    } else {
+        // The schedule is kept regardless of when the keeper executes the order. Periods missed
+        // by the keepers are skipped, so that they can't be caught up by executing in a row.
+        let missed_periods = (now - order.next_execution) / order.interval;
+        order.next_execution = missed_periods
+            .checked_add(1)
+            .and_then(|periods| periods.checked_mul(order.interval))
+            .and_then(|delay| order.next_execution.checked_add(delay))
+            .ok_or_else(|| {
+                StdError::generic_err("next execution of the recurring order overflows")
+            })?;
+        RECURRING_ORDERS.save(deps.storage, (account_id, order_id), &order)?;
+    }
+
+    // `min_receive` of the swap is derived from the oracle price and the max slippage
+    // when dispatched by a keeper
+    let mut steps = vec![DispatchStep::Action(Action::SwapExactIn {
+        coin_in: ActionCoin::from(&order.coin_in),
+        denom_out: order.denom_out.clone(),
+        min_receive: Uint128::zero(),
+        route: None,
+    })];
+    // Only the swap output is lent, the rest of the balance is left untouched
+    if order.lend_output {
+        let prev_balance = COIN_BALANCES
+            .may_load(deps.storage, (account_id, &order.denom_out))?
+            .unwrap_or_default();
+        steps.push(DispatchStep::Callback(CallbackMsg::LendReceived {
+            account_id: account_id.to_string(),
+            denom: order.denom_out.clone(),
+            prev_balance,
+        }));
+    }
+
+    let keeper_address = info.sender.to_string();
+    let res = dispatch_steps(
+        deps,
+        env,
+        info,
+        Some(account_id.to_string()),
+        None,
+        steps,
+        DispatchMode::Keeper,
+    )?;
+
+    // Send keeper fee to method caller
+    let transfer_msg = CosmosMsg::Bank(BankMsg::Send {
+        to_address: keeper_address,
+        amount: vec![order.keeper_fee],
+    });
+
+    Ok(res
+        .add_message(transfer_msg)
+        .add_attribute("action", "execute_recurring_order")
+        .add_attribute("order_id", order_id)
+        .add_attribute("periods_remaining", order.periods_remaining.to_string()))
+}
+
+/// Deletes all recurring orders of the account and returns them
+pub fn remove_all_recurring_orders(
+    storage: &mut dyn Storage,
+    account_id: &str,
+) -> StdResult<Vec<RecurringOrder>> {
+    let orders = RECURRING_ORDERS
+        .prefix(account_id)
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, order)| order))
+        .collect::<StdResult<Vec<_>>>()?;
+    for order in &orders {
+        RECURRING_ORDERS.remove(storage, (account_id, &order.order_id));
+    }
+    Ok(orders)
+}
+
+/// Keeper fees of the periods which haven't been executed yet
+pub fn remaining_keeper_fee(order: &RecurringOrder) -> StdResult<Coin> {
+    Ok(Coin {
+        denom: order.keeper_fee.denom.clone(),
+        amount: order.keeper_fee.amount.checked_mul(Uint128::from(order.periods_remaining))?,
+    })
+}
@@ -8,8 +8,19 @@ use crate::{
 };
 
 pub fn refund_coin_balances(deps: DepsMut, env: Env, account_id: &str) -> ContractResult<Response> {
-    let coins = query_coin_balances(deps.as_ref(), account_id)?;
     let account_nft_owner = query_nft_token_owner(deps.as_ref(), account_id)?;
+    withdraw_coin_balances(deps, env, account_id, Addr::unchecked(account_nft_owner))
+}
+
+/// Withdraw all coin balances of the account to the recipient.
+/// Coins which can't be withdrawn at the moment are left in the account.
+pub fn withdraw_coin_balances(
+    deps: DepsMut,
+    env: Env,
+    account_id: &str,
+    recipient: Addr,
+) -> ContractResult<Response> {
+    let coins = query_coin_balances(deps.as_ref(), account_id)?;
     let mut frozen_coin_denoms = vec![];
     let withdraw_msgs = coins
         .into_iter()
@@ -32,7 +43,7 @@ pub fn refund_coin_balances(deps: DepsMut, env: Env, account_id: &str) -> Contra
                 msg: to_json_binary(&ExecuteMsg::Callback(CallbackMsg::Withdraw {
                     account_id: account_id.to_string(),
                     coin: action_coin,
-                    recipient: Addr::unchecked(account_nft_owner.clone()),
+                    recipient: recipient.clone(),
                 }))?,
             }))
         })
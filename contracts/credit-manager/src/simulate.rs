@@ -0,0 +1,620 @@
+use std::cmp::min;
+
+use cosmwasm_std::{Coin, Deps, Env, Int128, OverflowError, OverflowOperation, Uint128};
+use mars_rover_health::{compute::compute_health, querier::HealthQuerier};
+use mars_types::{
+    credit_manager::{
+        Action, ActionAmount, ActionCoin, DebtAmount, PerpSettlementPolicy, Positions,
+        SimulateActionsResponse,
+    },
+    oracle::ActionKind,
+    perps::{PerpPosition, PnL, PnlAmounts},
+    swapper::SwapperRoute,
+};
+
+use crate::{
+    account_settings::query_account_settings,
+    borrow::DEFAULT_DEBT_SHARES_PER_COIN_BORROWED,
+    error::{ContractError, ContractResult},
+    perp::settlement_swaps,
+    query::{query_config, query_positions},
+    recurring::remaining_keeper_fee,
+    repay::current_debt_for_denom,
+    state::{
+        FLASH_LOAN_FEE_RATE, ORACLE, PERPS, RECURRING_ORDERS, RED_BANK, SWAPPER, TOTAL_DEBT_SHARES,
+        TRIGGER_ORDERS, ZAPPER,
+    },
+    swap::debt_swap_borrow_amount,
+};
+
+/// Applies the actions to an in-memory copy of the account positions, mirroring what
+/// `dispatch_actions` and the callbacks would do, and computes the health of the result.
+pub fn simulate_actions(
+    deps: Deps,
+    env: Env,
+    account_id: &str,
+    actions: Vec<Action>,
+) -> ContractResult<SimulateActionsResponse> {
+    let mut simulation = Simulation {
+        deps,
+        positions: query_positions(deps, account_id, ActionKind::Default)?,
+        coins_out: vec![],
+        perp_denoms: vec![],
+    };
+    for action in actions {
+        simulation.apply(action)?;
+    }
+
+    let config = query_config(deps)?;
+    let querier = HealthQuerier::new_with_config(&deps, env.contract.address, config)?;
+    let health = compute_health(deps, querier, simulation.positions.clone(), ActionKind::Default)?;
+
+    Ok(SimulateActionsResponse {
+        positions: simulation.positions,
+        health,
+        coins_out: simulation.coins_out,
+    })
+}
+
+struct Simulation<'a> {
+    deps: Deps<'a>,
+    positions: Positions,
+    coins_out: Vec<Coin>,
+    /// Denoms of the perp positions modified so far
+    perp_denoms: Vec<String>,
+}
+
+impl Simulation<'_> {
+    fn apply(&mut self, action: Action) -> ContractResult<()> {
+        match action {
+            Action::Deposit(coin) => add_coin(&mut self.positions.deposits, &coin)?,
+            Action::Withdraw(coin)
+            | Action::WithdrawToWallet {
+                coin,
+                ..
+            } => {
+                let coin = resolve_amount(&coin, &self.positions.deposits)?;
+                sub_coin(&mut self.positions.deposits, &coin)?;
+                add_coin(&mut self.coins_out, &coin)?;
+            }
+            Action::Borrow(coin) => {
+                if coin.amount.is_zero() {
+                    return Err(ContractError::NoAmount);
+                }
+                self.borrow(&coin)?;
+                add_coin(&mut self.positions.deposits, &coin)?;
+            }
+            Action::Lend(coin) => {
+                let coin = resolve_amount(&coin, &self.positions.deposits)?;
+                sub_coin(&mut self.positions.deposits, &coin)?;
+                add_coin(&mut self.positions.lends, &coin)?;
+            }
+            Action::Reclaim(coin) => {
+                let coin = resolve_amount(&coin, &self.positions.lends)?;
+                sub_coin(&mut self.positions.lends, &coin)?;
+                add_coin(&mut self.positions.deposits, &coin)?;
+            }
+            Action::Repay {
+                recipient_account_id,
+                coin,
+            } => self.repay(recipient_account_id, &coin)?,
+            Action::ExecutePerpOrder {
+                denom,
+                order_size,
+                reduce_only,
+                margin,
+                ..
+            } => self.execute_perp_order(&denom, order_size, reduce_only, margin)?,
+            Action::CreateTriggerOrder {
+                keeper_fee,
+                ..
+            } => sub_coin(&mut self.positions.deposits, &keeper_fee)?,
+            Action::DeleteTriggerOrder {
+                trigger_order_id,
+            } => {
+                let account_id = self.positions.account_id.as_str();
+                let order = TRIGGER_ORDERS
+                    .may_load(self.deps.storage, (account_id, &trigger_order_id))?
+                    .ok_or_else(|| ContractError::TriggerOrderNotFound {
+                        order_id: trigger_order_id.clone(),
+                        account_id: account_id.to_string(),
+                    })?;
+                add_coin(&mut self.positions.deposits, &order.keeper_fee)?;
+            }
+            Action::CreateRecurringOrder {
+                keeper_fee,
+                periods,
+                ..
+            } => {
+                let total_keeper_fee = Coin {
+                    denom: keeper_fee.denom,
+                    amount: keeper_fee.amount.checked_mul(Uint128::from(periods))?,
+                };
+                sub_coin(&mut self.positions.deposits, &total_keeper_fee)?;
+            }
+            Action::DeleteRecurringOrder {
+                order_id,
+            } => {
+                let account_id = self.positions.account_id.as_str();
+                let order = RECURRING_ORDERS
+                    .may_load(self.deps.storage, (account_id, &order_id))?
+                    .ok_or_else(|| ContractError::RecurringOrderNotFound {
+                        order_id: order_id.clone(),
+                        account_id: account_id.to_string(),
+                    })?;
+                add_coin(&mut self.positions.deposits, &remaining_keeper_fee(&order)?)?;
+            }
+            Action::SwapExactIn {
+                coin_in,
+                denom_out,
+                route,
+                ..
+            } => {
+                let coin_in = resolve_amount(&coin_in, &self.positions.deposits)?;
+                self.swap(&coin_in, &denom_out, route)?;
+            }
+            Action::ProvideLiquidity {
+                coins_in,
+                lp_token_out,
+                ..
+            } => {
+                let coins_in = coins_in
+                    .iter()
+                    .map(|coin| resolve_amount(coin, &self.positions.deposits))
+                    .collect::<ContractResult<Vec<_>>>()?;
+                let lp_amount = ZAPPER.load(self.deps.storage)?.estimate_provide_liquidity(
+                    &self.deps.querier,
+                    &lp_token_out,
+                    &coins_in,
+                )?;
+                for coin in &coins_in {
+                    sub_coin(&mut self.positions.deposits, coin)?;
+                }
+                add_coin(&mut self.positions.deposits, &Coin::new(lp_amount.u128(), lp_token_out))?;
+            }
+            Action::WithdrawLiquidity {
+                lp_token,
+                ..
+            } => {
+                let lp_token = resolve_amount(&lp_token, &self.positions.deposits)?;
+                let coins_out = ZAPPER
+                    .load(self.deps.storage)?
+                    .estimate_withdraw_liquidity(&self.deps.querier, &lp_token)?;
+                sub_coin(&mut self.positions.deposits, &lp_token)?;
+                for coin in &coins_out {
+                    add_coin(&mut self.positions.deposits, coin)?;
+                }
+            }
+            Action::StakeAstroLp {
+                lp_token,
+            } => {
+                let lp_token = resolve_amount(&lp_token, &self.positions.deposits)?;
+                sub_coin(&mut self.positions.deposits, &lp_token)?;
+                add_coin(&mut self.positions.staked_astro_lps, &lp_token)?;
+            }
+            Action::UnstakeAstroLp {
+                lp_token,
+            } => {
+                let lp_token = resolve_amount(&lp_token, &self.positions.staked_astro_lps)?;
+                sub_coin(&mut self.positions.staked_astro_lps, &lp_token)?;
+                add_coin(&mut self.positions.deposits, &lp_token)?;
+            }
+            Action::RefundAllCoinBalances {} => {
+                for coin in std::mem::take(&mut self.positions.deposits) {
+                    add_coin(&mut self.coins_out, &coin)?;
+                }
+            }
+            Action::FlashLoan {
+                coin,
+                actions,
+            } => {
+                self.borrow(&coin)?;
+                add_coin(&mut self.positions.deposits, &coin)?;
+                for action in actions {
+                    self.apply(action)?;
+                }
+                self.repay_flash_loan(&coin)?;
+            }
+            Action::SwapCollateral {
+                from,
+                to,
+                route,
+                ..
+            } => {
+                let balance = balance_of(&self.positions.deposits, &from.denom);
+                let lent = balance_of(&self.positions.lends, &from.denom);
+                let amount = match from.amount {
+                    ActionAmount::Exact(a) => a,
+                    ActionAmount::AccountBalance => balance.checked_add(lent)?,
+                };
+                if amount.is_zero() {
+                    return Err(ContractError::NoAmount);
+                }
+                let amount_to_reclaim = amount.saturating_sub(balance);
+                if !amount_to_reclaim.is_zero() {
+                    let coin = Coin::new(amount_to_reclaim.u128(), &from.denom);
+                    sub_coin(&mut self.positions.lends, &coin)?;
+                    add_coin(&mut self.positions.deposits, &coin)?;
+                }
+                self.swap(&Coin::new(amount.u128(), from.denom), &to, route)?;
+            }
+            Action::SwapDebt {
+                repay_denom,
+                borrow_denom,
+                max_borrow,
+                route,
+            } => {
+                let debt = self
+                    .positions
+                    .debts
+                    .iter()
+                    .find(|d| d.denom == repay_denom)
+                    .ok_or(ContractError::NoDebt)?;
+                let borrow_amount = debt_swap_borrow_amount(
+                    self.deps,
+                    &Coin::new(debt.amount.u128(), &repay_denom),
+                    &borrow_denom,
+                    max_borrow,
+                )?;
+                let coin_to_borrow = Coin::new(borrow_amount.u128(), borrow_denom);
+                self.borrow(&coin_to_borrow)?;
+                add_coin(&mut self.positions.deposits, &coin_to_borrow)?;
+                let prev_balance = balance_of(&self.positions.deposits, &repay_denom);
+                self.swap(&coin_to_borrow, &repay_denom, route)?;
+                let received =
+                    balance_of(&self.positions.deposits, &repay_denom).saturating_sub(prev_balance);
+                if !received.is_zero() {
+                    self.repay(
+                        None,
+                        &ActionCoin {
+                            denom: repay_denom,
+                            amount: ActionAmount::Exact(received),
+                        },
+                    )?;
+                }
+            }
+            // Only the positions of this account are simulated, not the ones of the recipient
+            Action::TransferToAccount {
+                coin,
+                ..
+            } => {
+                let coin = resolve_amount(&coin, &self.positions.deposits)?;
+                sub_coin(&mut self.positions.deposits, &coin)?;
+            }
+            Action::TransferLendToAccount {
+                coin,
+                ..
+            } => {
+                let lent = balance_of(&self.positions.lends, &coin.denom);
+                let amount = min(lent, coin.amount.value().unwrap_or(lent));
+                if amount.is_zero() {
+                    return Err(ContractError::NoneLent);
+                }
+                sub_coin(&mut self.positions.lends, &Coin::new(amount.u128(), &coin.denom))?;
+            }
+            Action::TransferDebtToAccount {
+                coin,
+                ..
+            } => {
+                let idx = self
+                    .positions
+                    .debts
+                    .iter()
+                    .position(|d| d.denom == coin.denom)
+                    .ok_or(ContractError::NoDebt)?;
+                let debt = &mut self.positions.debts[idx];
+                let amount = min(debt.amount, coin.amount.value().unwrap_or(debt.amount));
+                if amount.is_zero() {
+                    return Err(ContractError::NoAmount);
+                }
+                if amount == debt.amount {
+                    self.positions.debts.remove(idx);
+                } else {
+                    debt.shares =
+                        debt.shares.checked_sub(debt.shares.multiply_ratio(amount, debt.amount))?;
+                    debt.amount = debt.amount.checked_sub(amount)?;
+                }
+            }
+            // Rewards, vaults, the perp vault, liquidations and leverage loops depend on state of
+            // other contracts which can't be estimated reliably
+            action => {
+                return Err(ContractError::UnsupportedSimulationAction(
+                    action.action_type().to_string(),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// Swap the coin with the amount estimated by the swapper
+    fn swap(
+        &mut self,
+        coin_in: &Coin,
+        denom_out: &str,
+        route: Option<SwapperRoute>,
+    ) -> ContractResult<()> {
+        let amount_out = SWAPPER.load(self.deps.storage)?.estimate_exact_in_swap(
+            &self.deps.querier,
+            coin_in,
+            denom_out,
+            route,
+        )?;
+        sub_coin(&mut self.positions.deposits, coin_in)?;
+        add_coin(&mut self.positions.deposits, &Coin::new(amount_out.u128(), denom_out))
+    }
+
+    /// Add the debt using the same debt share calculation as `borrow::update_debt`
+    fn borrow(&mut self, coin: &Coin) -> ContractResult<()> {
+        let red_bank = RED_BANK.load(self.deps.storage)?;
+        let total_debt_amount = red_bank.query_debt(&self.deps.querier, &coin.denom)?;
+        let shares = if total_debt_amount.is_zero() {
+            coin.amount.checked_mul(DEFAULT_DEBT_SHARES_PER_COIN_BORROWED)?
+        } else {
+            TOTAL_DEBT_SHARES
+                .load(self.deps.storage, &coin.denom)?
+                .checked_multiply_ratio(coin.amount, total_debt_amount)?
+        };
+
+        let debts = &mut self.positions.debts;
+        match debts.binary_search_by(|d| d.denom.cmp(&coin.denom)) {
+            Ok(idx) => {
+                debts[idx].amount = debts[idx].amount.checked_add(coin.amount)?;
+                debts[idx].shares = debts[idx].shares.checked_add(shares)?;
+            }
+            Err(idx) => debts.insert(
+                idx,
+                DebtAmount {
+                    denom: coin.denom.clone(),
+                    shares,
+                    amount: coin.amount,
+                },
+            ),
+        }
+        Ok(())
+    }
+
+    fn repay(
+        &mut self,
+        recipient_account_id: Option<String>,
+        coin: &ActionCoin,
+    ) -> ContractResult<()> {
+        let balance = balance_of(&self.positions.deposits, &coin.denom);
+        let amount = coin.amount.value().unwrap_or(balance);
+
+        let recipient_account_id =
+            recipient_account_id.filter(|recipient| *recipient != self.positions.account_id);
+        let Some(recipient_account_id) = recipient_account_id else {
+            let idx = self
+                .positions
+                .debts
+                .iter()
+                .position(|d| d.denom == coin.denom)
+                .ok_or(ContractError::NoDebt)?;
+            let debt = &mut self.positions.debts[idx];
+            let amount_to_repay = min(debt.amount, amount);
+            if amount_to_repay == debt.amount {
+                self.positions.debts.remove(idx);
+            } else {
+                let shares_to_repay = debt.shares.multiply_ratio(amount_to_repay, debt.amount);
+                debt.shares = debt.shares.checked_sub(shares_to_repay)?;
+                debt.amount = debt.amount.checked_sub(amount_to_repay)?;
+            }
+            return sub_coin(
+                &mut self.positions.deposits,
+                &Coin::new(amount_to_repay.u128(), &coin.denom),
+            );
+        };
+
+        // Repaying for another account only reduces the balance of this account
+        let (debt_amount, _) =
+            current_debt_for_denom(self.deps, &recipient_account_id, &coin.denom)?;
+        sub_coin(
+            &mut self.positions.deposits,
+            &Coin::new(min(debt_amount, amount).u128(), &coin.denom),
+        )
+    }
+
+    /// Mirrors `flash_loan::repay_flash_loan`, the fee leaves the account
+    fn repay_flash_loan(&mut self, coin: &Coin) -> ContractResult<()> {
+        let fee_rate = FLASH_LOAN_FEE_RATE.load(self.deps.storage)?;
+        let fee = Coin::new(coin.amount.checked_mul_ceil(fee_rate)?.u128(), &coin.denom);
+
+        let required = coin.amount.checked_add(fee.amount)?;
+        let available = balance_of(&self.positions.deposits, &coin.denom);
+        if available < required {
+            return Err(ContractError::FlashLoanNotRepaid {
+                denom: coin.denom.clone(),
+                required,
+                available,
+            });
+        }
+
+        sub_coin(&mut self.positions.deposits, &fee)?;
+        self.repay(None, &ActionCoin::from(coin))
+    }
+
+    /// Mirrors `perp::execute_perp_order`: the PnL realized by modifying an existing position is
+    /// settled with the account balance (or the margin of an isolated position), for new positions
+    /// only the opening fee is paid.
+    ///
+    /// The PnL and fees are estimated by the perps contract from its own state, so a position can
+    /// only be modified once: the estimates of a second order would ignore the first one.
+    fn execute_perp_order(
+        &mut self,
+        denom: &str,
+        order_size: Int128,
+        reduce_only: Option<bool>,
+        margin: Option<Uint128>,
+    ) -> ContractResult<()> {
+        if self.perp_denoms.iter().any(|perp_denom| perp_denom == denom) {
+            return Err(ContractError::RepeatedSimulationPerpOrder(denom.to_string()));
+        }
+        self.perp_denoms.push(denom.to_string());
+
+        let perps = PERPS.load(self.deps.storage)?;
+        let account_id = self.positions.account_id.clone();
+
+        let idx = self.positions.perps.iter().position(|p| p.denom == denom);
+        let (size, mut realized_pnl, isolated_margin) = match idx {
+            Some(idx) => {
+                let position = self.positions.perps.remove(idx);
+                (position.size, position.realized_pnl, position.isolated_margin)
+            }
+            None => (Int128::zero(), PnlAmounts::default(), None),
+        };
+        let new_size = size.checked_add(order_size)?;
+        let fees = perps.query_position_fees(&self.deps.querier, &account_id, denom, new_size)?;
+
+        let mut isolated_margin = match margin {
+            Some(margin) => {
+                sub_coin(
+                    &mut self.positions.deposits,
+                    &Coin::new(margin.u128(), &fees.base_denom),
+                )?;
+                Some(isolated_margin.unwrap_or_default().checked_add(margin)?)
+            }
+            None => isolated_margin,
+        };
+
+        let modified_position = perps.query_position(
+            &self.deps.querier,
+            &account_id,
+            denom,
+            Some(order_size),
+            reduce_only,
+        )?;
+        let (pnl_amounts, unpaid_loss_reason) = match modified_position {
+            Some(position) => (
+                position.unrealized_pnl,
+                "the loss exceeds the margin, the position can only be liquidated",
+            ),
+            None => (
+                PnlAmounts::from_opening_fee(fees.opening_fee)?,
+                "margin must cover the opening fee",
+            ),
+        };
+        realized_pnl.add(&pnl_amounts)?;
+
+        match (pnl_amounts.to_coins(&fees.base_denom).pnl, isolated_margin.as_mut()) {
+            (PnL::Profit(coin), Some(margin)) => *margin = margin.checked_add(coin.amount)?,
+            (PnL::Profit(coin), None) => add_coin(&mut self.positions.deposits, &coin)?,
+            (PnL::Loss(coin), Some(margin)) => {
+                // The loss of an isolated position is never paid by the rest of the account
+                if *margin < coin.amount {
+                    return Err(ContractError::InvalidIsolatedMargin {
+                        denom: denom.to_string(),
+                        reason: unpaid_loss_reason.to_string(),
+                    });
+                }
+                *margin -= coin.amount;
+            }
+            (PnL::Loss(coin), None) => self.deduct_payment(&coin)?,
+            (PnL::BreakEven, _) => {}
+        }
+
+        if new_size.is_zero() {
+            if let Some(margin) = isolated_margin {
+                add_coin(
+                    &mut self.positions.deposits,
+                    &Coin::new(margin.u128(), &fees.base_denom),
+                )?;
+            }
+        } else {
+            let current_price = ORACLE
+                .load(self.deps.storage)?
+                .query_price(&self.deps.querier, denom, ActionKind::Default)?
+                .price;
+            let exec_price = fees.opening_exec_price.unwrap_or(current_price);
+            self.positions.perps.push(PerpPosition {
+                denom: denom.to_string(),
+                base_denom: fees.base_denom,
+                size: new_size,
+                entry_price: current_price,
+                current_price,
+                entry_exec_price: exec_price,
+                current_exec_price: exec_price,
+                unrealized_pnl: PnlAmounts::default(),
+                realized_pnl,
+                isolated_margin,
+            });
+        }
+        Ok(())
+    }
+
+    /// Mirrors `perp::deduct_payment`: pay from the balance first, then reclaim lent coins,
+    /// swap collateral if the settlement policy of the account says so and borrow whatever is
+    /// still missing.
+    fn deduct_payment(&mut self, payment: &Coin) -> ContractResult<()> {
+        let from_balance =
+            min(balance_of(&self.positions.deposits, &payment.denom), payment.amount);
+        sub_coin(&mut self.positions.deposits, &Coin::new(from_balance.u128(), &payment.denom))?;
+        let left = payment.amount - from_balance;
+
+        let from_lends = min(balance_of(&self.positions.lends, &payment.denom), left);
+        sub_coin(&mut self.positions.lends, &Coin::new(from_lends.u128(), &payment.denom))?;
+        let mut left = left - from_lends;
+
+        let settings = query_account_settings(self.deps, &self.positions.account_id)?;
+        if let PerpSettlementPolicy::SwapFrom(denoms) = settings.perp_settlement {
+            if !left.is_zero() {
+                let shortfall = Coin::new(left.u128(), &payment.denom);
+                let swaps =
+                    settlement_swaps(self.deps, &shortfall, &denoms, &self.positions.deposits)?;
+                for (coin_in, _) in swaps {
+                    self.swap(&coin_in, &payment.denom, None)?;
+                }
+
+                let from_swaps = min(balance_of(&self.positions.deposits, &payment.denom), left);
+                sub_coin(
+                    &mut self.positions.deposits,
+                    &Coin::new(from_swaps.u128(), &payment.denom),
+                )?;
+                left -= from_swaps;
+            }
+        }
+
+        if !left.is_zero() {
+            self.borrow(&Coin::new(left.u128(), &payment.denom))?;
+        }
+        Ok(())
+    }
+}
+
+fn balance_of(coins: &[Coin], denom: &str) -> Uint128 {
+    coins.iter().find(|c| c.denom == denom).map(|c| c.amount).unwrap_or_default()
+}
+
+/// Same semantics as `utils::get_amount_from_action_coin`, resolved against simulated balances
+fn resolve_amount(coin: &ActionCoin, balances: &[Coin]) -> ContractResult<Coin> {
+    let amount = coin.amount.value().unwrap_or_else(|| balance_of(balances, &coin.denom));
+    if amount.is_zero() {
+        return Err(ContractError::NoAmount);
+    }
+    Ok(Coin::new(amount.u128(), &coin.denom))
+}
+
+fn add_coin(coins: &mut Vec<Coin>, coin: &Coin) -> ContractResult<()> {
+    if coin.amount.is_zero() {
+        return Ok(());
+    }
+    // Keep the coins sorted by denom, like the balances queried from storage
+    match coins.binary_search_by(|c| c.denom.cmp(&coin.denom)) {
+        Ok(idx) => coins[idx].amount = coins[idx].amount.checked_add(coin.amount)?,
+        Err(idx) => coins.insert(idx, coin.clone()),
+    }
+    Ok(())
+}
+
+fn sub_coin(coins: &mut Vec<Coin>, coin: &Coin) -> ContractResult<()> {
+    if coin.amount.is_zero() {
+        return Ok(());
+    }
+    let Some(idx) = coins.iter().position(|c| c.denom == coin.denom) else {
+        return Err(OverflowError::new(OverflowOperation::Sub, 0, coin.amount).into());
+    };
+    coins[idx].amount = coins[idx].amount.checked_sub(coin.amount)?;
+    if coins[idx].amount.is_zero() {
+        coins.remove(idx);
+    }
+    Ok(())
+}
@@ -7,7 +7,9 @@ use mars_types::{
         params::Params, perps::Perps, red_bank::RedBank, rewards_collector::RewardsCollector,
         swapper::Swapper, vault::VaultPositionAmount, zapper::Zapper,
     },
-    credit_manager::{KeeperFeeConfig, TriggerOrder},
+    credit_manager::{
+        AccountSettings, KeeperFeeConfig, OperatorGrant, RecurringOrder, TriggerOrder,
+    },
     health::AccountKind,
 };
 use mars_utils::guard::Guard;
@@ -33,15 +35,30 @@ pub const MAX_SLIPPAGE: Item<Decimal> = Item::new("max_slippage");
 
 // Trigger order id counter
 pub const NEXT_TRIGGER_ID: Item<u64> = Item::new("next_trigger_id");
+// Recurring order id counter
+pub const NEXT_RECURRING_ORDER_ID: Item<u64> = Item::new("next_recurring_order_id");
 pub const KEEPER_FEE_CONFIG: Item<KeeperFeeConfig> = Item::new("keeper_fee_config");
 // Positions
 pub const ACCOUNT_KINDS: Map<&str, AccountKind> = Map::new("account_types"); // Map<AccountId, AccountKind>
+pub const ACCOUNT_SETTINGS: Map<&str, AccountSettings> = Map::new("account_settings"); // Map<AccountId, AccountSettings>
 pub const COIN_BALANCES: Map<(&str, &str), Uint128> = Map::new("coin_balance"); // Map<(AccountId, Denom), Amount>
 pub const DEBT_SHARES: Map<(&str, &str), Uint128> = Map::new("debt_shares"); // Map<(AccountId, Denom), Shares>
 pub const TOTAL_DEBT_SHARES: Map<&str, Uint128> = Map::new("total_debt_shares"); // Map<Denom, Shares>
+pub const ISOLATED_MARGINS: Map<(&str, &str), Uint128> = Map::new("isolated_margins"); // Map<(AccountId, PerpDenom), Amount>
+
+// Accounts with debt or perp positions, i.e. the accounts which may become liquidatable
+pub const LIQUIDATION_INDEX: Map<&str, ()> = Map::new("liquidation_index"); // Map<AccountId, ()>
+
+// Accounts flagged as liquidatable, start of their liquidation bonus auction
+pub const LIQUIDATABLE_SINCE: Map<&str, u64> = Map::new("liquidatable_since"); // Map<AccountId, Timestamp in seconds>
 
 pub const VAULT_POSITIONS: Map<(&str, Addr), VaultPositionAmount> = Map::new("vault_positions"); // Map<(AccountId, VaultAddr), VaultPositionAmount>
 pub const TRIGGER_ORDERS: Map<(&str, &str), TriggerOrder> = Map::new("trigger_orders"); // Map<(AccountId, TriggerOrderId), TriggerOrder>
+pub const TRIGGER_ORDER_EXPIRIES: Map<(u64, &str, &str), ()> = Map::new("trigger_order_expiries"); // Map<(ExpiresAt, AccountId, TriggerOrderId), ()>
+pub const RECURRING_ORDERS: Map<(&str, &str), RecurringOrder> = Map::new("recurring_orders"); // Map<(AccountId, RecurringOrderId), RecurringOrder>
+
+// Delegated operators
+pub const OPERATOR_GRANTS: Map<(&str, &str), OperatorGrant> = Map::new("operator_grants"); // Map<(AccountId, OperatorAddr), OperatorGrant>
 
 // Temporary state to save variables to be used on reply handling
 pub const VAULT_REQUEST_TEMP_STORAGE: Item<RequestTempStorage> =
@@ -54,3 +71,5 @@ pub const REWARDS_COLLECTOR: Item<RewardsCollector> = Item::new("rewards_collect
 pub const VAULTS: Map<&str, Addr> = Map::new("vaults");
 
 pub const PERPS_LB_RATIO: Item<Decimal> = Item::new("perps_lb_ratio");
+
+pub const FLASH_LOAN_FEE_RATE: Item<Decimal> = Item::new("flash_loan_fee_rate");
@@ -1,13 +1,17 @@
-use cosmwasm_std::{Coin, DepsMut, Env, Response, Uint128};
+use std::cmp::{max, min};
+
+use cosmwasm_std::{Coin, Decimal, Deps, DepsMut, Env, Response, Uint128};
 use mars_types::{
-    credit_manager::{ActionAmount, ActionCoin, ChangeExpected},
+    credit_manager::{ActionAmount, ActionCoin, CallbackMsg, ChangeExpected},
+    oracle::ActionKind,
     swapper::SwapperRoute,
 };
 
 use crate::{
     error::{ContractError, ContractResult},
-    state::{COIN_BALANCES, SWAPPER},
-    utils::{assert_withdraw_enabled, decrement_coin_balance, update_balance_msg},
+    repay::current_debt_for_denom,
+    state::{COIN_BALANCES, MAX_SLIPPAGE, ORACLE, RED_BANK, SWAPPER},
+    utils::{assert_withdraw_enabled, callback_msgs, decrement_coin_balance, update_balance_msg},
 };
 
 pub fn swap_exact_in(
@@ -18,6 +22,7 @@ pub fn swap_exact_in(
     denom_out: &str,
     min_receive: Uint128,
     route: Option<SwapperRoute>,
+    oracle_slippage: Option<Decimal>,
 ) -> ContractResult<Response> {
     // Prevent swapping the asset if withdraw is disabled
     assert_withdraw_enabled(deps.storage, &deps.querier, &coin_in.denom)?;
@@ -36,6 +41,14 @@ pub fn swap_exact_in(
         return Err(ContractError::NoAmount);
     }
 
+    let min_receive = match oracle_slippage {
+        Some(slippage) => max(
+            min_receive,
+            oracle_min_receive(deps.as_ref(), &coin_in_to_trade, denom_out, slippage)?,
+        ),
+        None => min_receive,
+    };
+
     decrement_coin_balance(deps.storage, account_id, &coin_in_to_trade)?;
 
     // Updates coin balances for account after the swap has taken place
@@ -55,5 +68,157 @@ pub fn swap_exact_in(
         .add_attribute("action", "swapper")
         .add_attribute("account_id", account_id)
         .add_attribute("coin_in", coin_in_to_trade.to_string())
-        .add_attribute("denom_out", denom_out))
+        .add_attribute("denom_out", denom_out)
+        .add_attribute("min_receive", min_receive))
+}
+
+/// Swap collateral into another denom. Lent collateral is reclaimed from the Red Bank first
+/// if the coin balance doesn't cover the amount to swap.
+pub fn swap_collateral(
+    deps: DepsMut,
+    env: Env,
+    account_id: &str,
+    from: &ActionCoin,
+    to: &str,
+    min_receive: Uint128,
+    route: Option<SwapperRoute>,
+    oracle_slippage: Option<Decimal>,
+) -> ContractResult<Response> {
+    let balance =
+        COIN_BALANCES.may_load(deps.storage, (account_id, &from.denom))?.unwrap_or_default();
+    let lent = RED_BANK.load(deps.storage)?.query_lent(&deps.querier, account_id, &from.denom)?;
+    let amount = match from.amount {
+        ActionAmount::Exact(a) => a,
+        ActionAmount::AccountBalance => balance.checked_add(lent)?,
+    };
+
+    if amount.is_zero() {
+        return Err(ContractError::NoAmount);
+    }
+
+    let mut callbacks = vec![];
+    let amount_to_reclaim = amount.saturating_sub(balance);
+    if !amount_to_reclaim.is_zero() {
+        callbacks.push(CallbackMsg::Reclaim {
+            account_id: account_id.to_string(),
+            coin: ActionCoin {
+                denom: from.denom.clone(),
+                amount: ActionAmount::Exact(amount_to_reclaim),
+            },
+        });
+    }
+    callbacks.push(CallbackMsg::SwapExactIn {
+        account_id: account_id.to_string(),
+        coin_in: ActionCoin {
+            denom: from.denom.clone(),
+            amount: ActionAmount::Exact(amount),
+        },
+        denom_out: to.to_string(),
+        min_receive,
+        route,
+        oracle_slippage,
+    });
+
+    Ok(Response::new()
+        .add_messages(callback_msgs(&env, &callbacks)?)
+        .add_attribute("action", "swap_collateral")
+        .add_attribute("account_id", account_id)
+        .add_attribute("coin_swapped", format!("{}{}", amount, from.denom))
+        .add_attribute("coin_reclaimed", format!("{}{}", amount_to_reclaim, from.denom))
+        .add_attribute("denom_out", to))
+}
+
+/// Exchange the debt of `repay_denom` for a debt of `borrow_denom`.
+/// No more is borrowed than needed to repay the debt (see `debt_swap_borrow_amount`).
+pub fn swap_debt(
+    deps: DepsMut,
+    env: Env,
+    account_id: &str,
+    repay_denom: &str,
+    borrow_denom: &str,
+    max_borrow: Uint128,
+    route: Option<SwapperRoute>,
+) -> ContractResult<Response> {
+    let (debt_amount, _) = current_debt_for_denom(deps.as_ref(), account_id, repay_denom)?;
+    let debt = Coin {
+        denom: repay_denom.to_string(),
+        amount: debt_amount,
+    };
+    let borrow_amount = debt_swap_borrow_amount(deps.as_ref(), &debt, borrow_denom, max_borrow)?;
+    let slippage = MAX_SLIPPAGE.load(deps.storage)?;
+
+    let coin_to_borrow = Coin {
+        denom: borrow_denom.to_string(),
+        amount: borrow_amount,
+    };
+    // Only the swap output repays the debt, the coins already held by the account are left as is
+    let prev_balance =
+        COIN_BALANCES.may_load(deps.storage, (account_id, repay_denom))?.unwrap_or_default();
+    let callbacks = vec![
+        CallbackMsg::Borrow {
+            account_id: account_id.to_string(),
+            coin: coin_to_borrow.clone(),
+        },
+        CallbackMsg::SwapExactIn {
+            account_id: account_id.to_string(),
+            coin_in: ActionCoin::from(&coin_to_borrow),
+            denom_out: repay_denom.to_string(),
+            min_receive: Uint128::zero(),
+            route,
+            oracle_slippage: Some(slippage),
+        },
+        CallbackMsg::RepayReceived {
+            account_id: account_id.to_string(),
+            denom: repay_denom.to_string(),
+            prev_balance,
+        },
+    ];
+
+    Ok(Response::new()
+        .add_messages(callback_msgs(&env, &callbacks)?)
+        .add_attribute("action", "swap_debt")
+        .add_attribute("account_id", account_id)
+        .add_attribute("coin_borrowed", coin_to_borrow.to_string())
+        .add_attribute("repay_denom", repay_denom))
+}
+
+/// Amount of `borrow_denom` to borrow in order to repay the `debt` with a debt swap.
+/// It's the oracle value of the debt grossed up by the max slippage, capped at `max_borrow`.
+pub fn debt_swap_borrow_amount(
+    deps: Deps,
+    debt: &Coin,
+    borrow_denom: &str,
+    max_borrow: Uint128,
+) -> ContractResult<Uint128> {
+    let slippage = MAX_SLIPPAGE.load(deps.storage)?;
+    let debt_value = oracle_amount_out(deps, debt, borrow_denom)?;
+    let borrow_needed = debt_value.checked_div_ceil(Decimal::one().checked_sub(slippage)?)?;
+    let borrow_amount = min(max_borrow, borrow_needed);
+
+    if borrow_amount.is_zero() {
+        return Err(ContractError::NoAmount);
+    }
+
+    Ok(borrow_amount)
+}
+
+/// Minimum amount of `denom_out` to receive for `coin_in`, based on the oracle prices
+/// and the allowed slippage.
+pub fn oracle_min_receive(
+    deps: Deps,
+    coin_in: &Coin,
+    denom_out: &str,
+    slippage: Decimal,
+) -> ContractResult<Uint128> {
+    let expected_out = oracle_amount_out(deps, coin_in, denom_out)?;
+    Ok(expected_out.checked_mul_floor(Decimal::one().checked_sub(slippage)?)?)
+}
+
+/// Amount of `denom_out` worth `coin_in`, based on the oracle prices
+fn oracle_amount_out(deps: Deps, coin_in: &Coin, denom_out: &str) -> ContractResult<Uint128> {
+    let oracle = ORACLE.load(deps.storage)?;
+    let price_in = oracle.query_price(&deps.querier, &coin_in.denom, ActionKind::Default)?.price;
+    let price_out = oracle.query_price(&deps.querier, denom_out, ActionKind::Default)?.price;
+
+    Ok(coin_in.amount.checked_mul_floor(price_in.checked_div(price_out)?)?)
 }
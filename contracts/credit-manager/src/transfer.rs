@@ -0,0 +1,134 @@
+use std::cmp::min;
+
+use cosmwasm_std::{Coin, Deps, DepsMut, Response, Uint128};
+use mars_types::credit_manager::ActionCoin;
+
+use crate::{
+    error::{ContractError, ContractResult},
+    repay::current_debt_for_denom,
+    state::{COIN_BALANCES, DEBT_SHARES, RED_BANK},
+    utils::{decrement_coin_balance, increment_coin_balance, query_nft_token_owner},
+};
+
+/// Assert that both accounts are owned by the same wallet
+pub fn assert_same_owner(
+    deps: Deps,
+    account_id: &str,
+    recipient_account_id: &str,
+) -> ContractResult<()> {
+    let owner = query_nft_token_owner(deps, account_id)?;
+    let recipient_owner = query_nft_token_owner(deps, recipient_account_id)?;
+    if owner != recipient_owner {
+        return Err(ContractError::NotTokenOwner {
+            user: owner,
+            account_id: recipient_account_id.to_string(),
+        });
+    }
+    Ok(())
+}
+
+pub fn transfer_to_account(
+    deps: DepsMut,
+    account_id: &str,
+    recipient_account_id: &str,
+    coin: &ActionCoin,
+) -> ContractResult<Response> {
+    let balance =
+        COIN_BALANCES.may_load(deps.storage, (account_id, &coin.denom))?.unwrap_or_default();
+    let coin_to_transfer = Coin {
+        denom: coin.denom.clone(),
+        amount: coin.amount.value().unwrap_or(balance),
+    };
+
+    if coin_to_transfer.amount.is_zero() {
+        return Err(ContractError::NoAmount);
+    }
+
+    decrement_coin_balance(deps.storage, account_id, &coin_to_transfer)?;
+    increment_coin_balance(deps.storage, recipient_account_id, &coin_to_transfer)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "transfer_to_account")
+        .add_attribute("account_id", account_id)
+        .add_attribute("recipient_account_id", recipient_account_id)
+        .add_attribute("coin_transferred", coin_to_transfer.to_string()))
+}
+
+/// The lent coins are reclaimed for the account and lent again for the recipient,
+/// so the Red Bank notifies the incentives contract about both balance changes.
+pub fn transfer_lend_to_account(
+    deps: DepsMut,
+    account_id: &str,
+    recipient_account_id: &str,
+    coin: &ActionCoin,
+) -> ContractResult<Response> {
+    let red_bank = RED_BANK.load(deps.storage)?;
+    let lent_amount = red_bank.query_lent(&deps.querier, account_id, &coin.denom)?;
+    let coin_to_transfer = Coin {
+        denom: coin.denom.clone(),
+        amount: min(lent_amount, coin.amount.value().unwrap_or(Uint128::MAX)),
+    };
+
+    if coin_to_transfer.amount.is_zero() {
+        return Err(ContractError::NoneLent);
+    }
+
+    Ok(Response::new()
+        .add_message(red_bank.reclaim_msg(&coin_to_transfer, account_id, false)?)
+        .add_message(red_bank.lend_msg(&coin_to_transfer, recipient_account_id)?)
+        .add_attribute("action", "transfer_lend_to_account")
+        .add_attribute("account_id", account_id)
+        .add_attribute("recipient_account_id", recipient_account_id)
+        .add_attribute("coin_transferred", coin_to_transfer.to_string()))
+}
+
+/// Debt shares are moved between the accounts, the total debt of the credit manager
+/// in the Red Bank doesn't change.
+pub fn transfer_debt_to_account(
+    deps: DepsMut,
+    account_id: &str,
+    recipient_account_id: &str,
+    coin: &ActionCoin,
+) -> ContractResult<Response> {
+    let (debt_amount, debt_shares) =
+        current_debt_for_denom(deps.as_ref(), account_id, &coin.denom)?;
+    let amount_to_transfer = min(debt_amount, coin.amount.value().unwrap_or(debt_amount));
+
+    if amount_to_transfer.is_zero() {
+        return Err(ContractError::NoAmount);
+    }
+
+    let shares_to_transfer = if amount_to_transfer == debt_amount {
+        DEBT_SHARES.remove(deps.storage, (account_id, &coin.denom));
+        debt_shares
+    } else {
+        let shares = debt_shares.multiply_ratio(amount_to_transfer, debt_amount);
+        DEBT_SHARES.save(
+            deps.storage,
+            (account_id, &coin.denom),
+            &debt_shares.checked_sub(shares)?,
+        )?;
+        shares
+    };
+
+    DEBT_SHARES.update(deps.storage, (recipient_account_id, &coin.denom), |shares| {
+        shares
+            .unwrap_or_else(Uint128::zero)
+            .checked_add(shares_to_transfer)
+            .map_err(ContractError::Overflow)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "transfer_debt_to_account")
+        .add_attribute("account_id", account_id)
+        .add_attribute("recipient_account_id", recipient_account_id)
+        .add_attribute("debt_shares_transferred", shares_to_transfer)
+        .add_attribute(
+            "coin_transferred",
+            Coin {
+                denom: coin.denom.clone(),
+                amount: amount_to_transfer,
+            }
+            .to_string(),
+        ))
+}
@@ -1,73 +1,116 @@
 use cosmwasm_std::{
-    ensure, ensure_eq, BankMsg, Coin, CosmosMsg, Decimal, DepsMut, Env, MessageInfo, Response,
+    ensure, ensure_eq, BankMsg, Coin, Coins, CosmosMsg, Decimal, DepsMut, Env, MessageInfo, Order,
+    Response, StdResult, Storage,
 };
+use cw_paginate::{DEFAULT_LIMIT, MAX_LIMIT};
+use cw_storage_plus::Bound;
 use mars_types::{
     credit_manager::{Action, Condition, TriggerOrder},
     oracle::ActionKind,
 };
+use mars_vault::msg::{ExtensionQueryMsg, QueryMsg as VaultQueryMsg, VaultInfoResponseExt};
 
 use crate::{
     error::ContractError,
-    execute::dispatch_actions,
+    execute::{dispatch_actions, DispatchMode},
     health::query_health_values,
-    state::{KEEPER_FEE_CONFIG, NEXT_TRIGGER_ID, ORACLE, TRIGGER_ORDERS},
+    recurring::{remaining_keeper_fee, remove_all_recurring_orders},
+    state::{
+        ACCOUNT_NFT, KEEPER_FEE_CONFIG, NEXT_TRIGGER_ID, ORACLE, PERPS, TRIGGER_ORDERS,
+        TRIGGER_ORDER_EXPIRIES,
+    },
     utils::{decrement_coin_balance, increment_coin_balance},
 };
 
+#[allow(clippy::too_many_arguments)]
 pub fn create_trigger_order(
     deps: DepsMut,
+    env: Env,
     account_id: &str,
     actions: Vec<Action>,
     conditions: Vec<Condition>,
     keeper_fee: Coin,
+    parent_order_id: Option<String>,
+    oco_group: Option<String>,
+    expires_at: Option<u64>,
 ) -> Result<Response, ContractError> {
-    // Ensure that the trigger order does not contain any illegal actions
-    // Initially, this is limited to just execute_perp_order and lend
-    let contains_legal_actions = actions
-        .iter()
-        .all(|action| matches!(action, Action::ExecutePerpOrder { .. } | Action::Lend(..)));
+    // Ensure that the trigger order does not contain any illegal actions.
+    // Trigger orders are executed by keepers (see `DispatchMode::Keeper`), so:
+    // - swaps are bounded by the max slippage against the oracle price,
+    // - withdrawals are sent to the account owner,
+    // - repayments are only allowed for the account itself,
+    // - the account has to pass the max LTV health check afterwards.
+    let contains_legal_actions = actions.iter().all(|action| {
+        matches!(
+            action,
+            Action::ExecutePerpOrder { .. }
+                | Action::Lend(..)
+                | Action::SwapExactIn { .. }
+                | Action::Repay {
+                    recipient_account_id: None,
+                    ..
+                }
+                | Action::Reclaim(..)
+                | Action::Withdraw(..)
+                | Action::ExitVault { .. }
+                | Action::UnstakeAstroLp { .. }
+        )
+    });
     ensure!(contains_legal_actions, ContractError::IllegalTriggerAction);
 
+    // The parent order is created by a preceding callback of the same transaction
+    if let Some(parent_order_id) = &parent_order_id {
+        ensure!(
+            TRIGGER_ORDERS.has(deps.storage, (account_id, parent_order_id)),
+            ContractError::InvalidTriggerOrderRelation {
+                reason: format!("parent order {parent_order_id} not found"),
+            }
+        );
+    }
+
+    if let Some(expires_at) = expires_at {
+        ensure!(
+            expires_at > env.block.time.seconds(),
+            ContractError::InvalidTriggerOrderExpiry {
+                expires_at,
+            }
+        );
+    }
+
     // Generate & increment id
     let order_id = NEXT_TRIGGER_ID.load(deps.storage)?;
     NEXT_TRIGGER_ID.save(deps.storage, &(order_id + 1))?;
 
     // Ensure keeper fees are valid according to configuration
-    let cfg = KEEPER_FEE_CONFIG.load(deps.storage)?;
-    ensure!(
-        keeper_fee.amount >= cfg.min_fee.amount,
-        ContractError::KeeperFeeTooSmall {
-            expected_min_amount: cfg.min_fee.amount,
-            received_amount: keeper_fee.amount,
-        }
-    );
-    ensure_eq!(
-        keeper_fee.denom,
-        cfg.min_fee.denom,
-        ContractError::InvalidKeeperFeeDenom {
-            expected_denom: cfg.min_fee.denom,
-            received_denom: keeper_fee.denom
-        }
-    );
+    assert_keeper_fee(deps.storage, &keeper_fee)?;
 
     // Deduct keeper_fee from account
     decrement_coin_balance(deps.storage, account_id, &keeper_fee)?;
 
     // Store trigger in state
+    let order_id = order_id.to_string();
     TRIGGER_ORDERS.save(
         deps.storage,
-        (account_id, &order_id.to_string()),
+        (account_id, &order_id),
         &TriggerOrder {
-            order_id: order_id.to_string(),
+            order_id: order_id.clone(),
             actions,
             conditions,
             keeper_fee,
+            parent_order_id: parent_order_id.clone(),
+            oco_group: oco_group.clone(),
+            expires_at,
         },
     )?;
+    if let Some(expires_at) = expires_at {
+        TRIGGER_ORDER_EXPIRIES.save(deps.storage, (expires_at, account_id, &order_id), &())?;
+    }
 
     Ok(Response::new()
         .add_attribute("action", "create_trigger_order")
-        .add_attribute("order_id", order_id.to_string()))
+        .add_attribute("order_id", order_id)
+        .add_attribute("parent_order_id", parent_order_id.unwrap_or_default())
+        .add_attribute("oco_group", oco_group.unwrap_or_default()))
 }
 
 pub fn delete_trigger_order(
@@ -75,23 +118,23 @@ pub fn delete_trigger_order(
     account_id: &str,
     trigger_order_id: &str,
 ) -> Result<Response, ContractError> {
-    // Use may_load so we can give a better error message
-    let order_to_remove = TRIGGER_ORDERS
-        .may_load(deps.storage, (account_id, trigger_order_id))?
-        .ok_or(ContractError::TriggerOrderNotFound {
+    // Use has so we can give a better error message
+    ensure!(
+        TRIGGER_ORDERS.has(deps.storage, (account_id, trigger_order_id)),
+        ContractError::TriggerOrderNotFound {
             order_id: trigger_order_id.to_string(),
             account_id: account_id.to_string(),
-        })?;
-
-    // Refund keeper fee
-    increment_coin_balance(deps.storage, account_id, &order_to_remove.keeper_fee)?;
+        }
+    );
 
-    // Remove order
-    TRIGGER_ORDERS.remove(deps.storage, (account_id, trigger_order_id));
+    // Remove order (and its children), refunding keeper fees
+    let removed_orders = remove_trigger_order(deps.storage, account_id, trigger_order_id)?;
+    let removed_order_ids = refund_keeper_fees(deps.storage, account_id, &removed_orders)?;
 
     Ok(Response::new()
         .add_attribute("action", "cancel_trigger_order")
-        .add_attribute("order_id", trigger_order_id))
+        .add_attribute("order_id", trigger_order_id)
+        .add_attribute("removed_order_ids", removed_order_ids.join(",")))
 }
 
 pub fn execute_trigger_order(
@@ -109,6 +152,27 @@ pub fn execute_trigger_order(
         },
     )?;
 
+    if let Some(expires_at) = order.expires_at {
+        ensure!(
+            env.block.time.seconds() < expires_at,
+            ContractError::TriggerOrderExpired {
+                order_id: trigger_order_id.to_string(),
+                account_id: account_id.to_string(),
+            }
+        );
+    }
+
+    // Child orders are only active once their parent order has been executed
+    if let Some(parent_order_id) = &order.parent_order_id {
+        ensure!(
+            !TRIGGER_ORDERS.has(deps.storage, (account_id, parent_order_id)),
+            ContractError::InactiveTriggerOrder {
+                order_id: trigger_order_id.to_string(),
+                parent_order_id: parent_order_id.to_string(),
+            }
+        );
+    }
+
     let oracle = ORACLE.load(deps.storage)?;
     let mut used_conditional_types = vec![];
 
@@ -157,6 +221,50 @@ pub fn execute_trigger_order(
                 let relative_price = base_price.checked_div(quote_price)?;
                 comparison.is_met(relative_price, price)
             }
+            Condition::BlockTime {
+                after,
+                before,
+            } => {
+                used_conditional_types.push("block_time");
+                let block_time = env.block.time.seconds();
+                !matches!(after, Some(after) if block_time <= after)
+                    && !matches!(before, Some(before) if block_time >= before)
+            }
+            Condition::PerpPositionPnl {
+                denom,
+                threshold,
+                comparison,
+            } => {
+                used_conditional_types.push("perp_position_pnl");
+                let perps = PERPS.load(deps.storage)?;
+                perps.query_position(&deps.querier, account_id, &denom, None, None)?.is_some_and(
+                    |position| comparison.is_met(position.unrealized_pnl.pnl, threshold),
+                )
+            }
+            Condition::FundingRate {
+                denom,
+                rate,
+                comparison,
+            } => {
+                used_conditional_types.push("funding_rate");
+                let perps = PERPS.load(deps.storage)?;
+                let market = perps.query_perp_market_state(&deps.querier, &denom)?;
+                comparison.is_met(market.current_funding_rate, rate)
+            }
+            Condition::VaultSharePrice {
+                vault_address,
+                price,
+                comparison,
+            } => {
+                used_conditional_types.push("vault_share_price");
+                let vault_info: VaultInfoResponseExt = deps.querier.query_wasm_smart(
+                    deps.api.addr_validate(&vault_address)?,
+                    &VaultQueryMsg::VaultExtension(ExtensionQueryMsg::VaultInfo {}),
+                )?;
+                vault_info
+                    .share_price
+                    .is_some_and(|share_price| comparison.is_met(share_price, price))
+            }
         };
 
         if !conditions_met {
@@ -164,9 +272,28 @@ pub fn execute_trigger_order(
         }
     }
 
-    TRIGGER_ORDERS.remove(deps.storage, (account_id, trigger_order_id));
+    remove_trigger_order_state(deps.storage, account_id, trigger_order_id, order.expires_at);
     let keeper_address = info.sender.to_string();
 
+    // Delete the orders linked to the executed one: the other orders of its OCO group and its
+    // siblings (children of the same parent). Children of the executed order become active.
+    let linked_order_ids = find_trigger_orders(deps.storage, account_id, |other| {
+        (order.oco_group.is_some() && other.oco_group == order.oco_group)
+            || (order.parent_order_id.is_some() && other.parent_order_id == order.parent_order_id)
+    })?;
+    let mut cancelled_order_ids = vec![];
+    for order_id in linked_order_ids {
+        // The order may have been already removed together with its parent
+        if TRIGGER_ORDERS.has(deps.storage, (account_id, &order_id)) {
+            let removed_orders = remove_trigger_order(deps.storage, account_id, &order_id)?;
+            cancelled_order_ids.extend(refund_keeper_fees(
+                deps.storage,
+                account_id,
+                &removed_orders,
+            )?);
+        }
+    }
+
     // Execute actions on behalf of user
     let mut res = dispatch_actions(
         deps,
@@ -175,14 +302,15 @@ pub fn execute_trigger_order(
         Some(account_id.to_string()),
         None,
         order.actions,
-        false,
+        DispatchMode::Keeper,
     )?;
 
     // Add relevant attributes
     res = res
         .add_attribute("action", "execute_trigger_order")
         .add_attribute("order_id", trigger_order_id)
-        .add_attribute("conditionals", used_conditional_types.join(","));
+        .add_attribute("conditionals", used_conditional_types.join(","))
+        .add_attribute("cancelled_order_ids", cancelled_order_ids.join(","));
 
     // Send keeper fee to method caller
     let transfer_msg = CosmosMsg::Bank(BankMsg::Send {
@@ -192,3 +320,227 @@ pub fn execute_trigger_order(
 
     Ok(res.add_message(transfer_msg))
 }
+
+/// Deletes all trigger and recurring orders of the account, refunding the keeper fees to the
+/// account. Used when the account is liquidated.
+pub fn delete_all_trigger_orders(
+    deps: DepsMut,
+    account_id: &str,
+) -> Result<Response, ContractError> {
+    let mut removed_order_ids = vec![];
+    for order_id in find_trigger_orders(deps.storage, account_id, |_| true)? {
+        if TRIGGER_ORDERS.has(deps.storage, (account_id, &order_id)) {
+            let removed_orders = remove_trigger_order(deps.storage, account_id, &order_id)?;
+            removed_order_ids.extend(refund_keeper_fees(
+                deps.storage,
+                account_id,
+                &removed_orders,
+            )?);
+        }
+    }
+
+    let mut removed_recurring_order_ids = vec![];
+    for order in remove_all_recurring_orders(deps.storage, account_id)? {
+        increment_coin_balance(deps.storage, account_id, &remaining_keeper_fee(&order)?)?;
+        removed_recurring_order_ids.push(order.order_id);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "delete_all_trigger_orders")
+        .add_attribute("account_id", account_id)
+        .add_attribute("removed_order_ids", removed_order_ids.join(","))
+        .add_attribute("removed_recurring_order_ids", removed_recurring_order_ids.join(",")))
+}
+
+/// Deletes up to `limit` expired trigger orders. The keeper fees are refunded to the accounts,
+/// minus the cleanup reward which is paid to the sender.
+pub fn purge_expired_trigger_orders(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let cleanup_reward = KEEPER_FEE_CONFIG.load(deps.storage)?.cleanup_reward.unwrap_or_default();
+
+    // Orders can't be executed from their expiry onwards
+    let max_bound = Bound::exclusive((env.block.time.seconds() + 1, "", ""));
+    let expired_orders = TRIGGER_ORDER_EXPIRIES
+        .keys(deps.storage, None, Some(max_bound), Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut rewards = Coins::default();
+    let mut purged_order_ids = vec![];
+    for (_, account_id, order_id) in expired_orders {
+        // The order may have been already removed together with its parent
+        if !TRIGGER_ORDERS.has(deps.storage, (&account_id, &order_id)) {
+            continue;
+        }
+
+        for order in remove_trigger_order(deps.storage, &account_id, &order_id)? {
+            let reward = order.keeper_fee.amount.checked_mul_floor(cleanup_reward)?;
+            rewards.add(Coin {
+                denom: order.keeper_fee.denom.clone(),
+                amount: reward,
+            })?;
+            increment_coin_balance(
+                deps.storage,
+                &account_id,
+                &Coin {
+                    denom: order.keeper_fee.denom,
+                    amount: order.keeper_fee.amount.checked_sub(reward)?,
+                },
+            )?;
+            purged_order_ids.push(format!("{account_id}:{}", order.order_id));
+        }
+    }
+
+    let mut res = Response::new()
+        .add_attribute("action", "purge_expired_trigger_orders")
+        .add_attribute("purged_orders", purged_order_ids.join(","));
+
+    if !rewards.is_empty() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: rewards.into_vec(),
+        });
+    }
+
+    Ok(res)
+}
+
+/// Deletes all trigger and recurring orders of a burned account, sending the keeper fees to the
+/// recipient.
+pub fn purge_account_trigger_orders(
+    deps: DepsMut,
+    info: MessageInfo,
+    account_id: &str,
+    refund_recipient: &str,
+) -> Result<Response, ContractError> {
+    let account_nft = ACCOUNT_NFT.load(deps.storage)?;
+    ensure_eq!(
+        &info.sender,
+        account_nft.address(),
+        ContractError::Unauthorized {
+            user: info.sender.to_string(),
+            action: "purge account trigger orders".to_string(),
+        }
+    );
+    let refund_recipient = deps.api.addr_validate(refund_recipient)?;
+
+    let mut refunds = Coins::default();
+    let mut removed_order_ids = vec![];
+    for order_id in find_trigger_orders(deps.storage, account_id, |_| true)? {
+        if !TRIGGER_ORDERS.has(deps.storage, (account_id, &order_id)) {
+            continue;
+        }
+        for order in remove_trigger_order(deps.storage, account_id, &order_id)? {
+            refunds.add(order.keeper_fee)?;
+            removed_order_ids.push(order.order_id);
+        }
+    }
+
+    let mut removed_recurring_order_ids = vec![];
+    for order in remove_all_recurring_orders(deps.storage, account_id)? {
+        refunds.add(remaining_keeper_fee(&order)?)?;
+        removed_recurring_order_ids.push(order.order_id);
+    }
+
+    let mut res = Response::new()
+        .add_attribute("action", "purge_account_trigger_orders")
+        .add_attribute("account_id", account_id)
+        .add_attribute("removed_order_ids", removed_order_ids.join(","))
+        .add_attribute("removed_recurring_order_ids", removed_recurring_order_ids.join(","));
+
+    if !refunds.is_empty() {
+        res = res.add_message(BankMsg::Send {
+            to_address: refund_recipient.to_string(),
+            amount: refunds.into_vec(),
+        });
+    }
+
+    Ok(res)
+}
+
+/// Assert that the keeper fee is valid according to the keeper fee configuration
+pub fn assert_keeper_fee(storage: &dyn Storage, keeper_fee: &Coin) -> Result<(), ContractError> {
+    let cfg = KEEPER_FEE_CONFIG.load(storage)?;
+    ensure!(
+        keeper_fee.amount >= cfg.min_fee.amount,
+        ContractError::KeeperFeeTooSmall {
+            expected_min_amount: cfg.min_fee.amount,
+            received_amount: keeper_fee.amount,
+        }
+    );
+    ensure_eq!(
+        keeper_fee.denom,
+        cfg.min_fee.denom,
+        ContractError::InvalidKeeperFeeDenom {
+            expected_denom: cfg.min_fee.denom,
+            received_denom: keeper_fee.denom.clone()
+        }
+    );
+    Ok(())
+}
+
+/// Remove the trigger order together with its children. Returns all removed orders.
+fn remove_trigger_order(
+    storage: &mut dyn Storage,
+    account_id: &str,
+    order_id: &str,
+) -> Result<Vec<TriggerOrder>, ContractError> {
+    let order = TRIGGER_ORDERS.load(storage, (account_id, order_id))?;
+    remove_trigger_order_state(storage, account_id, order_id, order.expires_at);
+
+    let child_order_ids = find_trigger_orders(storage, account_id, |other| {
+        other.parent_order_id.as_deref() == Some(order_id)
+    })?;
+    let mut removed_orders = vec![order];
+    for child_order_id in child_order_ids {
+        removed_orders.extend(remove_trigger_order(storage, account_id, &child_order_id)?);
+    }
+
+    Ok(removed_orders)
+}
+
+fn remove_trigger_order_state(
+    storage: &mut dyn Storage,
+    account_id: &str,
+    order_id: &str,
+    expires_at: Option<u64>,
+) {
+    TRIGGER_ORDERS.remove(storage, (account_id, order_id));
+    if let Some(expires_at) = expires_at {
+        TRIGGER_ORDER_EXPIRIES.remove(storage, (expires_at, account_id, order_id));
+    }
+}
+
+/// Refund the keeper fees of the removed orders to the account. Returns the ids of the orders.
+fn refund_keeper_fees(
+    storage: &mut dyn Storage,
+    account_id: &str,
+    orders: &[TriggerOrder],
+) -> Result<Vec<String>, ContractError> {
+    orders
+        .iter()
+        .map(|order| {
+            increment_coin_balance(storage, account_id, &order.keeper_fee)?;
+            Ok(order.order_id.clone())
+        })
+        .collect()
+}
+
+/// Ids of the account's trigger orders matching the predicate
+fn find_trigger_orders(
+    storage: &dyn Storage,
+    account_id: &str,
+    predicate: impl Fn(&TriggerOrder) -> bool,
+) -> StdResult<Vec<String>> {
+    TRIGGER_ORDERS
+        .prefix(account_id)
+        .range(storage, None, None, Order::Ascending)
+        .filter(|item| !matches!(item, Ok((_, order)) if !predicate(order)))
+        .map(|item| item.map(|(order_id, _)| order_id))
+        .collect()
+}
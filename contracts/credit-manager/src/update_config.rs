@@ -12,11 +12,14 @@ use crate::{
     error::ContractResult,
     execute::create_credit_account,
     state::{
-        ACCOUNT_NFT, HEALTH_CONTRACT, INCENTIVES, KEEPER_FEE_CONFIG, MAX_SLIPPAGE,
-        MAX_UNLOCKING_POSITIONS, ORACLE, OWNER, PARAMS, PERPS, PERPS_LB_RATIO, RED_BANK,
-        REWARDS_COLLECTOR, SWAPPER, ZAPPER,
+        ACCOUNT_NFT, FLASH_LOAN_FEE_RATE, HEALTH_CONTRACT, INCENTIVES, KEEPER_FEE_CONFIG,
+        MAX_SLIPPAGE, MAX_UNLOCKING_POSITIONS, ORACLE, OWNER, PARAMS, PERPS, PERPS_LB_RATIO,
+        RED_BANK, REWARDS_COLLECTOR, SWAPPER, ZAPPER,
+    },
+    utils::{
+        assert_flash_loan_fee_rate, assert_keeper_fee_config, assert_max_slippage,
+        assert_perps_lb_ratio,
     },
-    utils::{assert_max_slippage, assert_perps_lb_ratio},
 };
 
 pub fn update_config(
@@ -91,6 +94,14 @@ pub fn update_config(
             response.add_attribute("key", "perps_lb_ratio").add_attribute("value", num.to_string());
     }
 
+    if let Some(rate) = updates.flash_loan_fee_rate {
+        assert_flash_loan_fee_rate(rate)?;
+        FLASH_LOAN_FEE_RATE.save(deps.storage, &rate)?;
+        response = response
+            .add_attribute("key", "flash_loan_fee_rate")
+            .add_attribute("value", rate.to_string());
+    }
+
     if let Some(unchecked) = updates.health_contract {
         HEALTH_CONTRACT.save(deps.storage, &unchecked.check(deps.api)?)?;
         response = response
@@ -117,6 +128,7 @@ pub fn update_config(
     }
 
     if let Some(kfc) = updates.keeper_fee_config {
+        assert_keeper_fee_config(&kfc)?;
         KEEPER_FEE_CONFIG.save(deps.storage, &kfc)?;
         response = response.add_attributes(vec![
             ("key", "keeper_fee_config"),
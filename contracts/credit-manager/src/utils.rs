@@ -1,11 +1,11 @@
 use std::{collections::HashSet, hash::Hash};
 
 use cosmwasm_std::{
-    ensure, to_json_binary, Addr, Coin, CosmosMsg, Decimal, Deps, DepsMut, QuerierWrapper,
+    ensure, to_json_binary, Addr, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, QuerierWrapper,
     StdResult, Storage, Uint128, WasmMsg,
 };
 use mars_types::{
-    credit_manager::{ActionCoin, CallbackMsg, ChangeExpected, ExecuteMsg},
+    credit_manager::{ActionCoin, CallbackMsg, ChangeExpected, ExecuteMsg, KeeperFeeConfig},
     health::AccountKind,
 };
 
@@ -48,6 +48,15 @@ pub fn assert_max_slippage(max_slippage: Decimal) -> ContractResult<()> {
     Ok(())
 }
 
+pub fn assert_keeper_fee_config(config: &KeeperFeeConfig) -> ContractResult<()> {
+    if config.cleanup_reward.is_some_and(|reward| reward > Decimal::one()) {
+        return Err(ContractError::InvalidConfig {
+            reason: "Cleanup reward must be less than or equal to 1".to_string(),
+        });
+    }
+    Ok(())
+}
+
 pub fn assert_slippage(storage: &dyn Storage, slippage: Decimal) -> ContractResult<()> {
     let max_slippage = MAX_SLIPPAGE.load(storage)?;
     if slippage > max_slippage {
@@ -68,6 +77,15 @@ pub fn assert_perps_lb_ratio(perps_lb_ratio: Decimal) -> ContractResult<()> {
     Ok(())
 }
 
+pub fn assert_flash_loan_fee_rate(flash_loan_fee_rate: Decimal) -> ContractResult<()> {
+    if flash_loan_fee_rate >= Decimal::one() {
+        return Err(ContractError::InvalidConfig {
+            reason: "Flash loan fee rate must be less than 1".to_string(),
+        });
+    }
+    Ok(())
+}
+
 pub fn assert_withdraw_enabled(
     storage: &dyn Storage,
     querier: &QuerierWrapper,
@@ -137,6 +155,20 @@ pub fn decrement_coin_balance(
     Ok(new_value)
 }
 
+/// Coins of `denom` received by the account since its coin balance was `prev_balance`
+pub fn received_coin(
+    storage: &dyn Storage,
+    account_id: &str,
+    denom: &str,
+    prev_balance: Uint128,
+) -> StdResult<Coin> {
+    let balance = COIN_BALANCES.may_load(storage, (account_id, denom))?.unwrap_or_default();
+    Ok(Coin {
+        denom: denom.to_string(),
+        amount: balance.saturating_sub(prev_balance),
+    })
+}
+
 pub fn update_balance_msg(
     querier: &QuerierWrapper,
     credit_manager_addr: &Addr,
@@ -156,6 +188,11 @@ pub fn update_balance_msg(
     }))
 }
 
+/// Converts callbacks into messages to the credit manager itself
+pub fn callback_msgs(env: &Env, callbacks: &[CallbackMsg]) -> StdResult<Vec<CosmosMsg>> {
+    callbacks.iter().map(|callback| callback.into_cosmos_msg(&env.contract.address)).collect()
+}
+
 pub fn update_balances_msgs(
     querier: &QuerierWrapper,
     credit_manager_addr: &Addr,
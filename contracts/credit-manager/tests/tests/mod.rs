@@ -1,8 +1,10 @@
 pub use mars_testing::multitest::helpers;
 
+mod test_auto_lend;
 mod test_borrow;
 mod test_claim_astro_lp_rewards;
 mod test_claim_rewards;
+mod test_close_account;
 mod test_coin_balances;
 mod test_create_credit_account;
 mod test_deposit;
@@ -13,11 +15,16 @@ mod test_enumerate_coin_balances;
 mod test_enumerate_debt_shares;
 mod test_enumerate_total_debt_shares;
 mod test_enumerate_vault_positions;
+mod test_flash_loan;
 mod test_fund_manager_accounts;
 mod test_health;
 mod test_hls_accounts;
+mod test_import_red_bank_position;
 mod test_instantiate;
+mod test_isolated_perp;
 mod test_lend;
+mod test_leverage;
+mod test_liquidatable_accounts;
 mod test_liquidate_deposit;
 mod test_liquidate_guard;
 mod test_liquidate_if_perps_open;
@@ -27,17 +34,23 @@ mod test_liquidate_vault;
 mod test_liquidation_pricing;
 mod test_migration_v2;
 mod test_no_health_check;
+mod test_operators;
 mod test_perp;
 mod test_perp_vault;
 mod test_perps_deleverage;
+mod test_perps_settlement;
 mod test_reclaim;
+mod test_recurring_orders;
 mod test_reentrancy_guard;
 mod test_refund_balances;
 mod test_repay;
 mod test_repay_for_recipient;
 mod test_repay_from_wallet;
+mod test_simulate_actions;
 mod test_stake_astro_lp;
 mod test_swap;
+mod test_swap_collateral_debt;
+mod test_transfer_to_account;
 mod test_trigger;
 mod test_unstake_astro_lp;
 mod test_update_admin;
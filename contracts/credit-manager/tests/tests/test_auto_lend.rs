@@ -0,0 +1,283 @@
+use std::str::FromStr;
+
+use cosmwasm_std::{Addr, Coin, Int128, Uint128};
+use mars_credit_manager::error::ContractError;
+use mars_types::{
+    credit_manager::{
+        AccountSettings,
+        Action::{
+            Deposit, ExecutePerpOrder, RefundAllCoinBalances, TransferToAccount, WithdrawToWallet,
+        },
+    },
+    params::PerpParamsUpdate,
+};
+
+use super::helpers::{
+    assert_err, coin_info, default_perp_params, uatom_info, uosmo_info, AccountToFund, MockEnv,
+};
+
+#[test]
+fn only_owner_can_update_account_settings() {
+    let user = Addr::unchecked("user");
+    let bad_guy = Addr::unchecked("bad_guy");
+
+    let mut mock = MockEnv::new().build().unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    assert_eq!(mock.query_account_settings(&account_id), AccountSettings::default());
+
+    let res = mock.update_account_settings(
+        &bad_guy,
+        &account_id,
+        AccountSettings {
+            auto_lend: true,
+            ..Default::default()
+        },
+    );
+    assert_err(
+        res,
+        ContractError::NotTokenOwner {
+            user: bad_guy.to_string(),
+            account_id: account_id.clone(),
+        },
+    );
+
+    mock.update_account_settings(
+        &user,
+        &account_id,
+        AccountSettings {
+            auto_lend: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert!(mock.query_account_settings(&account_id).auto_lend);
+}
+
+#[test]
+fn idle_balances_are_lent_and_reclaimed_when_spent() {
+    let osmo_info = uosmo_info();
+    let user = Addr::unchecked("user");
+
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![Coin::new(300u128, osmo_info.denom.clone())],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+    mock.update_account_settings(
+        &user,
+        &account_id,
+        AccountSettings {
+            auto_lend: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deposit(osmo_info.to_coin(300))],
+        &[osmo_info.to_coin(300)],
+    )
+    .unwrap();
+
+    let position = mock.query_positions(&account_id);
+    assert!(position.deposits.is_empty());
+    assert_eq!(position.lends, vec![osmo_info.to_coin(300)]);
+
+    // The lent coins are reclaimed to be withdrawn, the rest is lent again
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![WithdrawToWallet {
+            coin: osmo_info.to_action_coin(100),
+            recipient: user.to_string(),
+        }],
+        &[],
+    )
+    .unwrap();
+
+    let position = mock.query_positions(&account_id);
+    assert!(position.deposits.is_empty());
+    assert_eq!(position.lends, vec![osmo_info.to_coin(200)]);
+
+    let balance = mock.query_balance(&user, &osmo_info.denom);
+    assert_eq!(balance.amount, Uint128::new(100));
+}
+
+#[test]
+fn lends_are_reclaimed_when_refunding_all_coin_balances() {
+    let osmo_info = uosmo_info();
+    let user = Addr::unchecked("user");
+
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![Coin::new(300u128, osmo_info.denom.clone())],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+    mock.update_account_settings(
+        &user,
+        &account_id,
+        AccountSettings {
+            auto_lend: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deposit(osmo_info.to_coin(300))],
+        &[osmo_info.to_coin(300)],
+    )
+    .unwrap();
+    assert_eq!(mock.query_positions(&account_id).lends, vec![osmo_info.to_coin(300)]);
+
+    mock.update_credit_account(&account_id, &user, vec![RefundAllCoinBalances {}], &[]).unwrap();
+
+    let position = mock.query_positions(&account_id);
+    assert!(position.deposits.is_empty());
+    assert!(position.lends.is_empty());
+
+    let balance = mock.query_balance(&user, &osmo_info.denom);
+    assert_eq!(balance.amount, Uint128::new(300));
+}
+
+#[test]
+fn lends_are_reclaimed_when_transferred_to_another_account() {
+    let osmo_info = uosmo_info();
+    let user = Addr::unchecked("user");
+
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![Coin::new(300u128, osmo_info.denom.clone())],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+    let recipient_account_id = mock.create_credit_account(&user).unwrap();
+    mock.update_account_settings(
+        &user,
+        &account_id,
+        AccountSettings {
+            auto_lend: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deposit(osmo_info.to_coin(300))],
+        &[osmo_info.to_coin(300)],
+    )
+    .unwrap();
+    assert_eq!(mock.query_positions(&account_id).lends, vec![osmo_info.to_coin(300)]);
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![TransferToAccount {
+            recipient_account_id: recipient_account_id.clone(),
+            coin: osmo_info.to_action_coin(100),
+        }],
+        &[],
+    )
+    .unwrap();
+
+    let position = mock.query_positions(&account_id);
+    assert!(position.deposits.is_empty());
+    assert_eq!(position.lends, vec![osmo_info.to_coin(200)]);
+
+    let position = mock.query_positions(&recipient_account_id);
+    assert_eq!(position.deposits, vec![osmo_info.to_coin(100)]);
+    assert!(position.lends.is_empty());
+}
+
+#[test]
+fn lends_are_reclaimed_for_isolated_perp_margin() {
+    let atom_info = uatom_info();
+    let usdc_info = coin_info("uusdc");
+    let user = Addr::unchecked("user");
+    let vault_depositor = Addr::unchecked("vault_depositor");
+
+    let mut mock = MockEnv::new()
+        .set_params(&[atom_info.clone(), usdc_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![usdc_info.to_coin(10000)],
+        })
+        .fund_account(AccountToFund {
+            addr: vault_depositor.clone(),
+            funds: vec![usdc_info.to_coin(100000)],
+        })
+        .build()
+        .unwrap();
+    mock.update_perp_params(PerpParamsUpdate::AddOrUpdate {
+        params: default_perp_params(&atom_info.denom),
+    });
+
+    let vault_depositor_account_id = mock.create_credit_account(&vault_depositor).unwrap();
+    mock.update_credit_account(
+        &vault_depositor_account_id,
+        &vault_depositor,
+        vec![Deposit(usdc_info.to_coin(100000))],
+        &[usdc_info.to_coin(100000)],
+    )
+    .unwrap();
+    mock.deposit_to_perp_vault(&vault_depositor_account_id, &usdc_info.to_coin(100000), None)
+        .unwrap();
+
+    let account_id = mock.create_credit_account(&user).unwrap();
+    mock.update_account_settings(
+        &user,
+        &account_id,
+        AccountSettings {
+            auto_lend: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deposit(usdc_info.to_coin(10000))],
+        &[usdc_info.to_coin(10000)],
+    )
+    .unwrap();
+    assert_eq!(mock.query_positions(&account_id).lends, vec![usdc_info.to_coin(10000)]);
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![ExecutePerpOrder {
+            denom: atom_info.denom.clone(),
+            order_size: Int128::from_str("1200").unwrap(),
+            reduce_only: None,
+            margin: Some(Uint128::new(2000)),
+            acceptable_price: None,
+        }],
+        &[],
+    )
+    .unwrap();
+
+    // The margin is taken from the reclaimed coins, the rest is lent again
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.perps.len(), 1);
+    assert!(position.perps[0].isolated_margin.is_some());
+    assert!(position.deposits.is_empty());
+    assert_eq!(position.lends, vec![usdc_info.to_coin(8000)]);
+}
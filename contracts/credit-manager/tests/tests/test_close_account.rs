@@ -0,0 +1,419 @@
+use cosmwasm_std::{Addr, Coin, Decimal, Uint128};
+use mars_credit_manager::error::ContractError;
+use mars_swapper_mock::contract::MOCK_SWAP_RESULT;
+use mars_types::{
+    credit_manager::Action::{
+        Borrow, CloseAccount, CreateRecurringOrder, Deposit, Lend, ProvideLiquidity,
+        WithdrawToWallet,
+    },
+    oracle::ActionKind,
+};
+
+use super::helpers::{
+    assert_err, coin_info, lp_token_info, uatom_info, uosmo_info, AccountToFund, MockEnv,
+};
+
+#[test]
+fn close_account_reclaims_lends_and_repays_debt() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+    let user = Addr::unchecked("user");
+    let recipient = Addr::unchecked("recipient");
+
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone(), atom_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![
+                Coin::new(1000u128, osmo_info.denom.clone()),
+                Coin::new(300u128, atom_info.denom.clone()),
+            ],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(osmo_info.to_coin(1000)),
+            Deposit(atom_info.to_coin(300)),
+            Lend(atom_info.to_action_coin(200)),
+            Borrow(atom_info.to_coin(50)),
+        ],
+        &[osmo_info.to_coin(1000), atom_info.to_coin(300)],
+    )
+    .unwrap();
+
+    let position = mock.query_positions(&account_id);
+    let debt = position.debts[0].amount;
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![CloseAccount {
+            recipient: recipient.to_string(),
+            slippage: Decimal::percent(5),
+        }],
+        &[],
+    )
+    .unwrap();
+
+    let position = mock.query_positions(&account_id);
+    assert!(position.deposits.is_empty());
+    assert!(position.lends.is_empty());
+    assert!(position.debts.is_empty());
+
+    let osmo_balance = mock.query_balance(&recipient, &osmo_info.denom).amount;
+    assert_eq!(osmo_balance, Uint128::new(1000));
+    let atom_balance = mock.query_balance(&recipient, &atom_info.denom).amount;
+    assert_eq!(atom_balance, Uint128::new(350) - debt);
+}
+
+#[test]
+fn close_account_swaps_collateral_into_debt_denom() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+    let user = Addr::unchecked("user");
+    let recipient = Addr::unchecked("recipient");
+
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone(), atom_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![Coin::new(100u128, atom_info.denom.clone())],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    // Borrowed funds are moved out of the account, so the debt can only be repaid with a swap
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(atom_info.to_coin(100)),
+            Borrow(osmo_info.to_coin(50)),
+            WithdrawToWallet {
+                coin: osmo_info.to_action_coin(50),
+                recipient: user.to_string(),
+            },
+        ],
+        &[atom_info.to_coin(100)],
+    )
+    .unwrap();
+
+    let position = mock.query_positions(&account_id);
+    let debt = position.debts[0].amount;
+
+    // The debt is worth ~13 uatom, grossed up by the 10% slippage 15 uatom are swapped
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![CloseAccount {
+            recipient: recipient.to_string(),
+            slippage: Decimal::percent(10),
+        }],
+        &[],
+    )
+    .unwrap();
+
+    let position = mock.query_positions(&account_id);
+    assert!(position.deposits.is_empty());
+    assert!(position.debts.is_empty());
+
+    let atom_balance = mock.query_balance(&recipient, &atom_info.denom).amount;
+    assert_eq!(atom_balance, Uint128::new(85));
+    let osmo_balance = mock.query_balance(&recipient, &osmo_info.denom).amount;
+    assert_eq!(osmo_balance, MOCK_SWAP_RESULT - debt);
+}
+
+#[test]
+fn close_account_fails_without_enough_collateral() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+    let user = Addr::unchecked("user");
+
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone(), atom_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![Coin::new(10u128, atom_info.denom.clone())],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(atom_info.to_coin(10)),
+            Borrow(osmo_info.to_coin(30)),
+            WithdrawToWallet {
+                coin: osmo_info.to_action_coin(30),
+                recipient: user.to_string(),
+            },
+        ],
+        &[atom_info.to_coin(10)],
+    )
+    .unwrap();
+
+    // With 50% slippage the 10 uatom are only expected to cover 20 of the 30 uosmo debt
+    let res = mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![CloseAccount {
+            recipient: user.to_string(),
+            slippage: Decimal::percent(50),
+        }],
+        &[],
+    );
+    assert_err(
+        res,
+        ContractError::CannotCloseAccount {
+            account_id: account_id.clone(),
+            reason: format!("not enough collateral to repay the {} debt", osmo_info.denom),
+        },
+    );
+
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.deposits, vec![atom_info.to_coin(10)]);
+}
+
+#[test]
+fn close_account_swaps_surplus_of_other_debt_denom() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+    let user = Addr::unchecked("user");
+    let recipient = Addr::unchecked("recipient");
+
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone(), atom_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![Coin::new(100u128, atom_info.denom.clone())],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    // The atom balance exceeds the atom debt, the surplus repays the osmo debt
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(atom_info.to_coin(100)),
+            Borrow(atom_info.to_coin(10)),
+            Borrow(osmo_info.to_coin(50)),
+            WithdrawToWallet {
+                coin: osmo_info.to_action_coin(50),
+                recipient: user.to_string(),
+            },
+        ],
+        &[atom_info.to_coin(100)],
+    )
+    .unwrap();
+
+    let position = mock.query_positions(&account_id);
+    let atom_debt = position.debts.iter().find(|d| d.denom == atom_info.denom).unwrap().amount;
+    let osmo_debt = position.debts.iter().find(|d| d.denom == osmo_info.denom).unwrap().amount;
+
+    // 15 uatom are swapped as in `close_account_swaps_collateral_into_debt_denom`
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![CloseAccount {
+            recipient: recipient.to_string(),
+            slippage: Decimal::percent(10),
+        }],
+        &[],
+    )
+    .unwrap();
+
+    let position = mock.query_positions(&account_id);
+    assert!(position.deposits.is_empty());
+    assert!(position.debts.is_empty());
+
+    let atom_balance = mock.query_balance(&recipient, &atom_info.denom).amount;
+    assert_eq!(atom_balance, Uint128::new(95) - atom_debt);
+    let osmo_balance = mock.query_balance(&recipient, &osmo_info.denom).amount;
+    assert_eq!(osmo_balance, MOCK_SWAP_RESULT - osmo_debt);
+}
+
+#[test]
+fn close_account_skips_collateral_without_price() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+    let akt_info = coin_info("uakt");
+    let user = Addr::unchecked("user");
+    let recipient = Addr::unchecked("recipient");
+
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone(), atom_info.clone(), akt_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![
+                Coin::new(100u128, atom_info.denom.clone()),
+                Coin::new(100u128, akt_info.denom.clone()),
+            ],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(atom_info.to_coin(100)),
+            Deposit(akt_info.to_coin(100)),
+            Borrow(osmo_info.to_coin(50)),
+            WithdrawToWallet {
+                coin: osmo_info.to_action_coin(50),
+                recipient: user.to_string(),
+            },
+        ],
+        &[atom_info.to_coin(100), akt_info.to_coin(100)],
+    )
+    .unwrap();
+
+    // uakt comes first among the collateral but it can't be valued anymore
+    mock.remove_price(&akt_info.denom, ActionKind::Default);
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![CloseAccount {
+            recipient: recipient.to_string(),
+            slippage: Decimal::percent(10),
+        }],
+        &[],
+    )
+    .unwrap();
+
+    let position = mock.query_positions(&account_id);
+    assert!(position.deposits.is_empty());
+    assert!(position.debts.is_empty());
+
+    let akt_balance = mock.query_balance(&recipient, &akt_info.denom).amount;
+    assert_eq!(akt_balance, Uint128::new(100));
+    let atom_balance = mock.query_balance(&recipient, &atom_info.denom).amount;
+    assert_eq!(atom_balance, Uint128::new(85));
+}
+
+#[test]
+fn close_account_withdraws_lp_tokens() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+    let lp_token = lp_token_info();
+    let user = Addr::unchecked("user");
+    let recipient = Addr::unchecked("recipient");
+
+    let mut mock = MockEnv::new()
+        .set_params(&[lp_token.clone(), atom_info.clone(), osmo_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![atom_info.to_coin(100), osmo_info.to_coin(50)],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(atom_info.to_coin(100)),
+            Deposit(osmo_info.to_coin(50)),
+            ProvideLiquidity {
+                coins_in: vec![atom_info.to_action_coin(100), osmo_info.to_action_coin(50)],
+                lp_token_out: lp_token.denom.clone(),
+                slippage: Decimal::zero(),
+            },
+        ],
+        &[atom_info.to_coin(100), osmo_info.to_coin(50)],
+    )
+    .unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![CloseAccount {
+            recipient: recipient.to_string(),
+            slippage: Decimal::percent(10),
+        }],
+        &[],
+    )
+    .unwrap();
+
+    let position = mock.query_positions(&account_id);
+    assert!(position.deposits.is_empty());
+
+    let lp_balance = mock.query_balance(&recipient, &lp_token.denom).amount;
+    assert!(lp_balance.is_zero());
+    let atom_balance = mock.query_balance(&recipient, &atom_info.denom).amount;
+    assert_eq!(atom_balance, Uint128::new(100));
+    let osmo_balance = mock.query_balance(&recipient, &osmo_info.denom).amount;
+    assert_eq!(osmo_balance, Uint128::new(50));
+}
+
+#[test]
+fn close_account_deletes_orders_and_refunds_keeper_fees() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+    let usdc_info = coin_info("uusdc");
+    let user = Addr::unchecked("user");
+    let recipient = Addr::unchecked("recipient");
+    let keeper_fee = usdc_info.to_coin(1000000);
+    let keeper_fees = usdc_info.to_coin(2000000);
+
+    let mut mock = MockEnv::new()
+        .set_params(&[atom_info.clone(), osmo_info.clone(), usdc_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![atom_info.to_coin(1000), keeper_fees.clone()],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(atom_info.to_coin(1000)),
+            Deposit(keeper_fees.clone()),
+            CreateRecurringOrder {
+                coin_in: atom_info.to_coin(100),
+                denom_out: osmo_info.denom.clone(),
+                interval: 86400,
+                periods: 2,
+                lend_output: false,
+                keeper_fee,
+            },
+        ],
+        &[atom_info.to_coin(1000), keeper_fees.clone()],
+    )
+    .unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![CloseAccount {
+            recipient: recipient.to_string(),
+            slippage: Decimal::percent(10),
+        }],
+        &[],
+    )
+    .unwrap();
+
+    let orders = mock.query_recurring_orders_for_account(account_id.clone(), None, None).data;
+    assert!(orders.is_empty());
+
+    let usdc_balance = mock.query_balance(&recipient, &usdc_info.denom).amount;
+    assert_eq!(usdc_balance, keeper_fees.amount);
+    let atom_balance = mock.query_balance(&recipient, &atom_info.denom).amount;
+    assert_eq!(atom_balance, Uint128::new(1000));
+}
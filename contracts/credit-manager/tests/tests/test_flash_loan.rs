@@ -0,0 +1,85 @@
+use cosmwasm_std::{coins, Addr, Decimal, Uint128};
+use mars_credit_manager::error::ContractError;
+use mars_types::credit_manager::Action::{Deposit, FlashLoan, Lend, Reclaim};
+
+use super::helpers::{assert_err, get_coin, uosmo_info, AccountToFund, MockEnv};
+
+#[test]
+fn flash_loan_is_repaid_with_fee() {
+    let coin_info = uosmo_info();
+    let user = Addr::unchecked("user");
+    let mut mock = MockEnv::new()
+        .set_params(&[coin_info.clone()])
+        .flash_loan_fee_rate(Decimal::percent(1))
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: coins(300, coin_info.denom.clone()),
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    // Borrowing 800 against 300 would be above max LTV, but the loan is repaid in the same dispatch
+    let actions = vec![
+        Deposit(coin_info.to_coin(300)),
+        FlashLoan {
+            coin: coin_info.to_coin(800),
+            actions: vec![
+                Lend(coin_info.to_action_coin(800)),
+                Reclaim(coin_info.to_action_coin(800)),
+            ],
+        },
+    ];
+    let simulation = mock.simulate_actions(&account_id, actions.clone()).unwrap();
+
+    mock.update_credit_account(&account_id, &user, actions, &[coin_info.to_coin(300)]).unwrap();
+
+    let position = mock.query_positions(&account_id);
+    assert!(position.debts.is_empty());
+    assert_eq!(position.deposits, vec![coin_info.to_coin(292)]);
+    assert_eq!(simulation.positions.deposits, position.deposits);
+
+    // The fee is sent to the rewards-collector account
+    let rewards_collector_acc_id = mock.query_rewards_collector_account();
+    let position = mock.query_positions(&rewards_collector_acc_id);
+    let fee = get_coin(&coin_info.denom, &position.deposits);
+    assert_eq!(fee.amount, Uint128::new(8));
+}
+
+#[test]
+fn flash_loan_has_to_be_repaid() {
+    let coin_info = uosmo_info();
+    let user = Addr::unchecked("user");
+    let mut mock = MockEnv::new()
+        .set_params(&[coin_info.clone()])
+        .flash_loan_fee_rate(Decimal::percent(1))
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: coins(300, coin_info.denom.clone()),
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    let res = mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(coin_info.to_coin(300)),
+            FlashLoan {
+                coin: coin_info.to_coin(800),
+                actions: vec![Lend(coin_info.to_action_coin(800))],
+            },
+        ],
+        &[coin_info.to_coin(300)],
+    );
+
+    assert_err(
+        res,
+        ContractError::FlashLoanNotRepaid {
+            denom: coin_info.denom,
+            required: Uint128::new(808),
+            available: Uint128::new(300),
+        },
+    );
+}
@@ -491,6 +491,8 @@ fn cannot_have_perps_in_hls_account() {
                 denom: atom_info.denom.clone(),
                 order_size: Int128::from_str("400").unwrap(),
                 reduce_only: None,
+                margin: None,
+                acceptable_price: None,
             },
         ],
         &[osmo_coin_deposited.clone()],
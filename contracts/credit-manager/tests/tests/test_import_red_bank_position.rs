@@ -0,0 +1,58 @@
+use cosmwasm_std::{Addr, Coin, Uint128};
+use mars_credit_manager::error::ContractError;
+use mars_types::credit_manager::Action::ImportRedBankPosition;
+
+use super::helpers::{assert_err, uatom_info, uosmo_info, AccountToFund, MockEnv};
+
+#[test]
+fn red_bank_position_is_imported_into_account() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+    let user = Addr::unchecked("user");
+
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone(), atom_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![Coin::new(1000u128, atom_info.denom.clone())],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.deposit_to_red_bank(&user, &atom_info.to_coin(1000)).unwrap();
+    mock.borrow_from_red_bank(&user, &osmo_info.to_coin(100)).unwrap();
+    let user_debt = mock.query_red_bank_user_debt(&user, &osmo_info.denom).amount;
+
+    mock.update_credit_account(&account_id, &user, vec![ImportRedBankPosition {}], &[]).unwrap();
+
+    let position = mock.query_positions(&account_id);
+    assert!(position.deposits.is_empty());
+    assert_eq!(position.lends, vec![atom_info.to_coin(1000)]);
+    assert_eq!(position.debts.len(), 1);
+    assert_eq!(position.debts[0].denom, osmo_info.denom);
+    assert!(position.debts[0].amount >= user_debt);
+
+    let user_debt = mock.query_red_bank_user_debt(&user, &osmo_info.denom).amount;
+    assert_eq!(user_debt, Uint128::zero());
+
+    // Borrowed coins stay in the wallet of the user
+    let osmo_balance = mock.query_balance(&user, &osmo_info.denom).amount;
+    assert_eq!(osmo_balance, Uint128::new(100));
+}
+
+#[test]
+fn import_fails_without_red_bank_position() {
+    let user = Addr::unchecked("user");
+
+    let mut mock = MockEnv::new().set_params(&[uosmo_info()]).build().unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    let res = mock.update_credit_account(&account_id, &user, vec![ImportRedBankPosition {}], &[]);
+    assert_err(
+        res,
+        ContractError::NoRedBankPosition {
+            user: user.to_string(),
+        },
+    );
+}
@@ -73,6 +73,7 @@ fn raises_on_invalid_params_addr() {
 fn keeper_fee_config_set_on_instantiate() {
     let keeper_fee_config = KeeperFeeConfig {
         min_fee: coin(1000, "uusd"),
+        cleanup_reward: None,
     };
 
     let mock = MockEnv::new().keeper_fee_config(keeper_fee_config.clone()).build().unwrap();
@@ -0,0 +1,483 @@
+use std::str::FromStr;
+
+use cosmwasm_std::{coin, Addr, Decimal, Int128, Uint128};
+use mars_credit_manager::error::ContractError;
+use mars_mock_oracle::msg::CoinPrice;
+use mars_types::{
+    credit_manager::{
+        Action::{Borrow, Deposit, ExecutePerpOrder, Liquidate},
+        LiquidateRequest,
+    },
+    oracle::ActionKind,
+    params::{PerpParams, PerpParamsUpdate},
+    perps::PnL,
+};
+
+use super::helpers::{
+    assert_err, coin_info, default_perp_params, get_coin, uatom_info, uosmo_info, AccountToFund,
+    MockEnv,
+};
+
+#[test]
+fn isolated_margin_is_released_when_position_is_closed() {
+    let atom_info = uatom_info();
+    let usdc_info = coin_info("uusdc");
+    let user = Addr::unchecked("user");
+
+    let (mut mock, account_id) = setup(&user);
+    let perp_size = Int128::from_str("1200").unwrap();
+
+    // Opening fee is paid from the margin, the rest of the balance is untouched
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![ExecutePerpOrder {
+            denom: atom_info.denom.clone(),
+            order_size: perp_size,
+            reduce_only: None,
+            margin: Some(Uint128::new(2000)),
+            acceptable_price: None,
+        }],
+        &[],
+    )
+    .unwrap();
+
+    let position = mock.query_positions(&account_id);
+    assert_eq!(get_coin(&usdc_info.denom, &position.deposits), usdc_info.to_coin(8000));
+    assert_eq!(position.perps[0].isolated_margin, Some(Uint128::new(1951)));
+
+    let health = mock.query_health(&account_id, ActionKind::Default);
+    assert_eq!(health.perps_pnl_loss, Uint128::zero());
+
+    let loss = pnl_loss(&mock, &account_id, &atom_info.denom);
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![ExecutePerpOrder {
+            denom: atom_info.denom.clone(),
+            order_size: Int128::zero() - perp_size,
+            reduce_only: Some(true),
+            margin: None,
+            acceptable_price: None,
+        }],
+        &[],
+    )
+    .unwrap();
+
+    let position = mock.query_positions(&account_id);
+    assert!(position.perps.is_empty());
+    assert_eq!(
+        get_coin(&usdc_info.denom, &position.deposits).amount,
+        Uint128::new(8000 + 1951) - loss
+    );
+}
+
+#[test]
+fn cross_margin_position_cannot_become_isolated() {
+    let atom_info = uatom_info();
+    let user = Addr::unchecked("user");
+
+    let (mut mock, account_id) = setup(&user);
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![ExecutePerpOrder {
+            denom: atom_info.denom.clone(),
+            order_size: Int128::from_str("1200").unwrap(),
+            reduce_only: None,
+            margin: None,
+            acceptable_price: None,
+        }],
+        &[],
+    )
+    .unwrap();
+
+    let res = mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![ExecutePerpOrder {
+            denom: atom_info.denom.clone(),
+            order_size: Int128::from_str("100").unwrap(),
+            reduce_only: None,
+            margin: Some(Uint128::new(1000)),
+            acceptable_price: None,
+        }],
+        &[],
+    );
+    assert_err(
+        res,
+        ContractError::InvalidIsolatedMargin {
+            denom: atom_info.denom,
+            reason: "a cross margin position can't become isolated".to_string(),
+        },
+    );
+}
+
+#[test]
+fn isolated_position_is_liquidated_with_its_margin_only() {
+    let osmo_info = uosmo_info();
+    let atom_info = uatom_info();
+    let usdc_info = coin_info("uusdc");
+    let user = Addr::unchecked("user");
+    let liquidator = Addr::unchecked("liquidator");
+
+    let (mut mock, account_id) = setup(&user);
+    let liquidator_account_id = mock.create_credit_account(&liquidator).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![ExecutePerpOrder {
+            denom: atom_info.denom.clone(),
+            order_size: Int128::from_str("1200").unwrap(),
+            reduce_only: None,
+            margin: Some(Uint128::new(2000)),
+            acceptable_price: None,
+        }],
+        &[],
+    )
+    .unwrap();
+
+    // The loss makes the isolated position liquidatable, the account itself stays healthy
+    for pricing in [ActionKind::Default, ActionKind::Liquidation] {
+        mock.price_change(CoinPrice {
+            pricing,
+            denom: atom_info.denom.clone(),
+            price: Decimal::from_str("0.7").unwrap(),
+        });
+    }
+    let health = mock.query_health(&account_id, ActionKind::Liquidation);
+    assert!(!health.liquidatable);
+
+    let loss = pnl_loss(&mock, &account_id, &atom_info.denom);
+    mock.update_credit_account(
+        &liquidator_account_id,
+        &liquidator,
+        vec![Liquidate {
+            liquidatee_account_id: account_id.clone(),
+            debt_coin: usdc_info.to_coin(0),
+            request: LiquidateRequest::IsolatedPerp(atom_info.denom.clone()),
+        }],
+        &[],
+    )
+    .unwrap();
+
+    // The bonus for the PnL loss and the protocol fee are paid from the margin left,
+    // the rest of the margin is returned to the liquidatee
+    let margin_left = Uint128::new(1951) - loss;
+
+    let position = mock.query_positions(&liquidator_account_id);
+    let bonus = get_coin(&usdc_info.denom, &position.deposits).amount;
+    assert!(!bonus.is_zero());
+
+    let rewards_collector_account_id = mock.query_rewards_collector_account();
+    let position = mock.query_positions(&rewards_collector_account_id);
+    let protocol_fee = get_coin(&usdc_info.denom, &position.deposits).amount;
+    assert!(!protocol_fee.is_zero());
+
+    let position = mock.query_positions(&account_id);
+    assert!(position.perps.is_empty());
+    assert_eq!(get_coin(&osmo_info.denom, &position.deposits), osmo_info.to_coin(1000000));
+    assert_eq!(
+        get_coin(&usdc_info.denom, &position.deposits),
+        usdc_info.to_coin((Uint128::new(8000) + margin_left - bonus - protocol_fee).u128())
+    );
+}
+
+#[test]
+fn isolated_loss_exceeding_margin_is_bad_debt_of_the_vault() {
+    let osmo_info = uosmo_info();
+    let atom_info = uatom_info();
+    let usdc_info = coin_info("uusdc");
+    let user = Addr::unchecked("user");
+    let liquidator = Addr::unchecked("liquidator");
+
+    let (mut mock, account_id) = setup(&user);
+    let liquidator_account_id = mock.create_credit_account(&liquidator).unwrap();
+
+    let perp_size = Int128::from_str("1200").unwrap();
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![ExecutePerpOrder {
+            denom: atom_info.denom.clone(),
+            order_size: perp_size,
+            reduce_only: None,
+            margin: Some(Uint128::new(2000)),
+            acceptable_price: None,
+        }],
+        &[],
+    )
+    .unwrap();
+
+    // The price gaps past the liquidation price, the loss exceeds the margin
+    for pricing in [ActionKind::Default, ActionKind::Liquidation] {
+        mock.price_change(CoinPrice {
+            pricing,
+            denom: atom_info.denom.clone(),
+            price: Decimal::from_str("0.4").unwrap(),
+        });
+    }
+    let loss = pnl_loss(&mock, &account_id, &atom_info.denom);
+    assert!(loss > Uint128::new(1951));
+
+    // The position can't be closed by borrowing against the rest of the account
+    let res = mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![ExecutePerpOrder {
+            denom: atom_info.denom.clone(),
+            order_size: Int128::zero() - perp_size,
+            reduce_only: Some(true),
+            margin: None,
+            acceptable_price: None,
+        }],
+        &[],
+    );
+    assert_err(
+        res,
+        ContractError::InvalidIsolatedMargin {
+            denom: atom_info.denom.clone(),
+            reason: "the loss exceeds the margin, the position can only be liquidated".to_string(),
+        },
+    );
+
+    let perps_balance_before = mock.query_balance(mock.perps.address(), &usdc_info.denom);
+    let res = mock
+        .update_credit_account(
+            &liquidator_account_id,
+            &liquidator,
+            vec![Liquidate {
+                liquidatee_account_id: account_id.clone(),
+                debt_coin: usdc_info.to_coin(0),
+                request: LiquidateRequest::IsolatedPerp(atom_info.denom.clone()),
+            }],
+            &[],
+        )
+        .unwrap();
+
+    // Only the margin is paid to the vault, the rest of the loss is bad debt
+    let bad_debt = res
+        .events
+        .iter()
+        .flat_map(|event| event.attributes.iter())
+        .find(|attr| attr.key == "bad_debt")
+        .map(|attr| Uint128::from_str(&attr.value).unwrap())
+        .unwrap();
+    assert_eq!(bad_debt, loss - Uint128::new(1951));
+    let perps_balance = mock.query_balance(mock.perps.address(), &usdc_info.denom);
+    assert!(perps_balance.amount <= perps_balance_before.amount + Uint128::new(1951));
+
+    // The rest of the account is untouched and no debt is taken
+    let position = mock.query_positions(&account_id);
+    assert!(position.perps.is_empty());
+    assert!(position.debts.is_empty());
+    assert_eq!(get_coin(&osmo_info.denom, &position.deposits), osmo_info.to_coin(1000000));
+    assert_eq!(get_coin(&usdc_info.denom, &position.deposits), usdc_info.to_coin(8000));
+
+    // No margin is left for the liquidation bonus
+    let position = mock.query_positions(&liquidator_account_id);
+    assert!(position.deposits.is_empty());
+}
+
+#[test]
+fn deleveraged_isolated_loss_exceeding_margin_is_bad_debt_of_the_vault() {
+    let osmo_info = uosmo_info();
+    let atom_info = uatom_info();
+    let usdc_info = coin_info("uusdc");
+    let user = Addr::unchecked("user");
+
+    let (mut mock, account_id) = setup(&user);
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![ExecutePerpOrder {
+            denom: atom_info.denom.clone(),
+            order_size: Int128::from_str("1200").unwrap(),
+            reduce_only: None,
+            margin: Some(Uint128::new(2000)),
+            acceptable_price: None,
+        }],
+        &[],
+    )
+    .unwrap();
+
+    // The price gaps past the liquidation price, the loss exceeds the margin
+    for pricing in [ActionKind::Default, ActionKind::Liquidation] {
+        mock.price_change(CoinPrice {
+            pricing,
+            denom: atom_info.denom.clone(),
+            price: Decimal::from_str("0.4").unwrap(),
+        });
+    }
+    let loss = pnl_loss(&mock, &account_id, &atom_info.denom);
+    assert!(loss > Uint128::new(1951));
+
+    // The long OI exceeds its max, the position can be deleveraged
+    mock.update_perp_params(PerpParamsUpdate::AddOrUpdate {
+        params: PerpParams {
+            max_long_oi_value: Uint128::zero(),
+            ..default_perp_params(&atom_info.denom)
+        },
+    });
+
+    let perps_balance_before = mock.query_balance(mock.perps.address(), &usdc_info.denom);
+    let res = mock.deleverage(&account_id, &atom_info.denom).unwrap();
+
+    // Only the margin is paid to the vault, the rest of the loss is bad debt
+    let bad_debts = res
+        .events
+        .iter()
+        .flat_map(|event| event.attributes.iter())
+        .filter(|attr| attr.key == "bad_debt")
+        .map(|attr| Uint128::from_str(&attr.value).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(bad_debts, vec![loss - Uint128::new(1951); 2]);
+    let perps_balance = mock.query_balance(mock.perps.address(), &usdc_info.denom);
+    assert!(perps_balance.amount <= perps_balance_before.amount + Uint128::new(1951));
+
+    // The rest of the account is untouched and no debt is taken
+    let position = mock.query_positions(&account_id);
+    assert!(position.perps.is_empty());
+    assert!(position.debts.is_empty());
+    assert_eq!(get_coin(&osmo_info.denom, &position.deposits), osmo_info.to_coin(1000000));
+    assert_eq!(get_coin(&usdc_info.denom, &position.deposits), usdc_info.to_coin(8000));
+}
+
+#[test]
+fn account_liquidation_leaves_isolated_positions_open() {
+    let osmo_info = uosmo_info();
+    let atom_info = uatom_info();
+    let usdc_info = coin_info("uusdc");
+    let user = Addr::unchecked("user");
+    let liquidator = Addr::unchecked("liquidator");
+
+    let (mut mock, account_id) = setup(&user);
+    mock.update_perp_params(PerpParamsUpdate::AddOrUpdate {
+        params: default_perp_params(&osmo_info.denom),
+    });
+
+    let liquidator_account_id = mock.create_credit_account(&liquidator).unwrap();
+    mock.fund_addr(&liquidator, vec![atom_info.to_coin(1000)]);
+    mock.update_credit_account(
+        &liquidator_account_id,
+        &liquidator,
+        vec![Deposit(atom_info.to_coin(1000))],
+        &[atom_info.to_coin(1000)],
+    )
+    .unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            ExecutePerpOrder {
+                denom: atom_info.denom.clone(),
+                order_size: Int128::from_str("1200").unwrap(),
+                reduce_only: None,
+                margin: Some(Uint128::new(2000)),
+                acceptable_price: None,
+            },
+            ExecutePerpOrder {
+                denom: osmo_info.denom.clone(),
+                order_size: Int128::from_str("-4000").unwrap(),
+                reduce_only: None,
+                margin: None,
+                acceptable_price: None,
+            },
+            Borrow(atom_info.to_coin(800000)),
+        ],
+        &[],
+    )
+    .unwrap();
+
+    // The borrowed atom makes the account liquidatable once its price rises.
+    // The isolated long position profits from it and stays healthy.
+    for pricing in [ActionKind::Default, ActionKind::Liquidation] {
+        mock.price_change(CoinPrice {
+            pricing,
+            denom: atom_info.denom.clone(),
+            price: Decimal::from_str("5").unwrap(),
+        });
+    }
+    let health = mock.query_health(&account_id, ActionKind::Liquidation);
+    assert!(health.liquidatable);
+
+    mock.update_credit_account(
+        &liquidator_account_id,
+        &liquidator,
+        vec![Liquidate {
+            liquidatee_account_id: account_id.clone(),
+            debt_coin: atom_info.to_coin(100),
+            request: LiquidateRequest::Deposit(osmo_info.denom.clone()),
+        }],
+        &[],
+    )
+    .unwrap();
+
+    // The cross margin position is closed, the isolated one is left open with its margin
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.perps.len(), 1);
+    assert_eq!(position.perps[0].denom, atom_info.denom);
+    assert_eq!(position.perps[0].isolated_margin, Some(Uint128::new(1951)));
+}
+
+fn setup(user: &Addr) -> (MockEnv, String) {
+    let osmo_info = uosmo_info();
+    let atom_info = uatom_info();
+    let usdc_info = coin_info("uusdc");
+    let vault_depositor = Addr::unchecked("vault_depositor");
+
+    let osmo_coin_deposited = osmo_info.to_coin(1000000);
+    let usdc_coin_deposited = usdc_info.to_coin(10000);
+    let vault_coin_deposited = coin(100000, usdc_info.denom.clone());
+
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info, atom_info.clone(), usdc_info])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![osmo_coin_deposited.clone(), usdc_coin_deposited.clone()],
+        })
+        .fund_account(AccountToFund {
+            addr: vault_depositor.clone(),
+            funds: vec![vault_coin_deposited.clone()],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(user).unwrap();
+    let vault_depositor_account_id = mock.create_credit_account(&vault_depositor).unwrap();
+
+    mock.update_perp_params(PerpParamsUpdate::AddOrUpdate {
+        params: default_perp_params(&atom_info.denom),
+    });
+
+    mock.update_credit_account(
+        &vault_depositor_account_id,
+        &vault_depositor,
+        vec![Deposit(vault_coin_deposited.clone())],
+        &[vault_coin_deposited.clone()],
+    )
+    .unwrap();
+    mock.deposit_to_perp_vault(&vault_depositor_account_id, &vault_coin_deposited, None).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        user,
+        vec![Deposit(osmo_coin_deposited.clone()), Deposit(usdc_coin_deposited.clone())],
+        &[osmo_coin_deposited, usdc_coin_deposited],
+    )
+    .unwrap();
+
+    (mock, account_id)
+}
+
+/// Loss realized by closing the whole position
+fn pnl_loss(mock: &MockEnv, account_id: &str, denom: &str) -> Uint128 {
+    let position = mock.query_perp_position(account_id, denom).position.unwrap();
+    match position.unrealized_pnl.to_coins(&position.base_denom).pnl {
+        PnL::Loss(coin) => coin.amount,
+        _ => panic!("expected loss"),
+    }
+}
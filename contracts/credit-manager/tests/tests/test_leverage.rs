@@ -0,0 +1,209 @@
+use std::str::FromStr;
+
+use cosmwasm_std::{Addr, Coin, Decimal, Uint128};
+use cw_multi_test::AppResponse;
+use mars_credit_manager::error::ContractError;
+use mars_swapper_mock::contract::MOCK_SWAP_RESULT;
+use mars_types::credit_manager::Action::{Borrow, Deleverage, Deposit, Leverage};
+
+use super::helpers::{assert_err, uatom_info, uosmo_info, AccountToFund, MockEnv};
+
+fn attribute(res: &AppResponse, key: &str) -> Decimal {
+    let attr = res.events.iter().flat_map(|e| &e.attributes).find(|a| a.key == key).unwrap();
+    Decimal::from_str(&attr.value).unwrap()
+}
+
+#[test]
+fn leverage_borrows_up_to_target_ltv() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+
+    let user = Addr::unchecked("user");
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone(), atom_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![Coin::new(2000u128, osmo_info.denom.clone())],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    let res = mock
+        .update_credit_account(
+            &account_id,
+            &user,
+            vec![
+                Deposit(osmo_info.to_coin(2000)),
+                Leverage {
+                    collateral_denom: osmo_info.denom.clone(),
+                    debt_denom: atom_info.denom.clone(),
+                    target_ltv: Decimal::percent(50),
+                    slippage: Decimal::percent(20),
+                },
+            ],
+            &[osmo_info.to_coin(2000)],
+        )
+        .unwrap();
+
+    // 2000 uosmo is worth 500 uatom, borrow = (500 * 0.5 - 0) / (1 - 0.5 * (1 - 0.2)) = 416.66
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.debts.len(), 1);
+    assert_eq!(position.debts[0].denom, atom_info.denom);
+    assert_eq!(position.debts[0].amount, Uint128::new(416));
+    assert_eq!(position.deposits, vec![osmo_info.to_coin(2000 + MOCK_SWAP_RESULT.u128())]);
+
+    let ltv = attribute(&res, "ltv");
+    assert!(ltv > Decimal::zero() && ltv < Decimal::percent(50));
+    assert!(attribute(&res, "leverage") > Decimal::one());
+}
+
+#[test]
+fn deleverage_repays_down_to_target_ltv() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+
+    let user = Addr::unchecked("user");
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone(), atom_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![Coin::new(1000u128, atom_info.denom.clone())],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deposit(atom_info.to_coin(1000)), Borrow(osmo_info.to_coin(1000))],
+        &[atom_info.to_coin(1000)],
+    )
+    .unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deleverage {
+            collateral_denom: atom_info.denom.clone(),
+            debt_denom: osmo_info.denom.clone(),
+            target_ltv: Decimal::percent(10),
+            slippage: Decimal::percent(20),
+        }],
+        &[],
+    )
+    .unwrap();
+
+    // Collateral is worth 1250 and debt 250, swap = (250 - 125) / (1 - 0.2 - 0.1) = 178.57
+    let position = mock.query_positions(&account_id);
+    assert!(position.debts.is_empty());
+    assert_eq!(
+        position.deposits,
+        vec![atom_info.to_coin(821), osmo_info.to_coin(MOCK_SWAP_RESULT.u128())]
+    );
+}
+
+#[test]
+fn deleverage_repays_only_swap_output() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+
+    let user = Addr::unchecked("user");
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone(), atom_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![Coin::new(1000u128, atom_info.denom.clone())],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deposit(atom_info.to_coin(1000)), Borrow(osmo_info.to_coin(2000))],
+        &[atom_info.to_coin(1000)],
+    )
+    .unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deleverage {
+            collateral_denom: atom_info.denom.clone(),
+            debt_denom: osmo_info.denom.clone(),
+            target_ltv: Decimal::percent(10),
+            slippage: Decimal::percent(20),
+        }],
+        &[],
+    )
+    .unwrap();
+
+    // Collateral is worth 1500 and debt 500, swap = (500 - 150) / (1 - 0.2 - 0.1) = 500.
+    // The swap output (1337 uosmo) doesn't cover the whole debt.
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.debts.len(), 1);
+    assert_eq!(position.debts[0].amount, Uint128::new(2000) - MOCK_SWAP_RESULT);
+
+    // The uosmo held before the swap isn't used to repay the debt
+    assert_eq!(position.deposits, vec![atom_info.to_coin(500), osmo_info.to_coin(2000)]);
+}
+
+#[test]
+fn leverage_target_ltv_is_validated() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+
+    let user = Addr::unchecked("user");
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone(), atom_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![Coin::new(1000u128, atom_info.denom.clone())],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deposit(atom_info.to_coin(1000)), Borrow(osmo_info.to_coin(1000))],
+        &[atom_info.to_coin(1000)],
+    )
+    .unwrap();
+
+    let res = mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Leverage {
+            collateral_denom: osmo_info.denom.clone(),
+            debt_denom: atom_info.denom.clone(),
+            target_ltv: Decimal::one(),
+            slippage: Decimal::percent(20),
+        }],
+        &[],
+    );
+    assert_err(res, ContractError::InvalidTargetLtv(Decimal::one()));
+
+    let res = mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Leverage {
+            collateral_denom: osmo_info.denom.clone(),
+            debt_denom: atom_info.denom.clone(),
+            target_ltv: Decimal::percent(10),
+            slippage: Decimal::percent(20),
+        }],
+        &[],
+    );
+    assert_err(
+        res,
+        ContractError::TargetLtvReached {
+            current_ltv: Decimal::percent(20),
+            target_ltv: Decimal::percent(10),
+        },
+    );
+}
@@ -0,0 +1,200 @@
+use std::str::FromStr;
+
+use cosmwasm_std::{coins, Addr, Coin, Decimal};
+use mars_mock_oracle::msg::CoinPrice;
+use mars_types::{
+    credit_manager::{
+        Action::{Borrow, Deposit, Liquidate, Withdraw},
+        LiquidateRequest,
+    },
+    oracle::ActionKind,
+};
+
+use super::helpers::{coin_info, get_debt, uatom_info_with_cf, uosmo_info, AccountToFund, MockEnv};
+
+#[test]
+fn only_accounts_with_debt_are_indexed() {
+    let uosmo_info = uosmo_info();
+    let user = Addr::unchecked("user");
+    let mut mock = MockEnv::new()
+        .set_params(&[uosmo_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: coins(3000, uosmo_info.denom.clone()),
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deposit(uosmo_info.to_coin(3000))],
+        &[Coin::new(3000, uosmo_info.denom.clone())],
+    )
+    .unwrap();
+
+    // Even with a price drop an account without debt is never liquidatable
+    mock.price_change(CoinPrice {
+        pricing: ActionKind::Liquidation,
+        denom: uosmo_info.denom.clone(),
+        price: Decimal::from_str("0.01").unwrap(),
+    });
+
+    let res = mock.query_liquidatable_accounts(None, None, ActionKind::Liquidation);
+    assert!(res.accounts.is_empty());
+    assert_eq!(res.next_start_after, None);
+}
+
+/// Liquidation bot flow: find the liquidatable accounts and liquidate them with their candidate
+#[test]
+fn keeper_liquidates_returned_candidates() {
+    let uosmo_info = uosmo_info();
+    let uatom_info = uatom_info_with_cf(Decimal::from_str("0.505").unwrap());
+    let liquidator = Addr::unchecked("liquidator");
+    let liquidatee = Addr::unchecked("liquidatee");
+    let healthy = Addr::unchecked("healthy");
+    let mut mock = MockEnv::new()
+        .set_params(&[uosmo_info.clone(), uatom_info.clone()])
+        .fund_account(AccountToFund {
+            addr: liquidatee.clone(),
+            funds: coins(3000, uosmo_info.denom.clone()),
+        })
+        .fund_account(AccountToFund {
+            addr: healthy.clone(),
+            funds: coins(3000, uosmo_info.denom.clone()),
+        })
+        .fund_account(AccountToFund {
+            addr: liquidator.clone(),
+            funds: coins(3000, uatom_info.denom.clone()),
+        })
+        .build()
+        .unwrap();
+    let liquidatee_account_id = mock.create_credit_account(&liquidatee).unwrap();
+    let healthy_account_id = mock.create_credit_account(&healthy).unwrap();
+
+    mock.update_credit_account(
+        &liquidatee_account_id,
+        &liquidatee,
+        vec![
+            Deposit(uosmo_info.to_coin(3000)),
+            Borrow(uatom_info.to_coin(1000)),
+            Withdraw(uatom_info.to_action_coin(400)),
+        ],
+        &[Coin::new(3000, uosmo_info.denom.clone())],
+    )
+    .unwrap();
+    mock.update_credit_account(
+        &healthy_account_id,
+        &healthy,
+        vec![Deposit(uosmo_info.to_coin(3000)), Borrow(uatom_info.to_coin(100))],
+        &[Coin::new(3000, uosmo_info.denom.clone())],
+    )
+    .unwrap();
+
+    let res = mock.query_liquidatable_accounts(None, None, ActionKind::Liquidation);
+    assert!(res.accounts.is_empty());
+
+    mock.price_change(CoinPrice {
+        pricing: ActionKind::Liquidation,
+        denom: uatom_info.denom.clone(),
+        price: Decimal::from_atomics(128u128, 2).unwrap(),
+    });
+
+    let res = mock.query_liquidatable_accounts(None, None, ActionKind::Liquidation);
+    assert_eq!(res.next_start_after, None);
+    assert_eq!(res.accounts.len(), 1);
+    let account = res.accounts[0].clone();
+    assert_eq!(account.account_id, liquidatee_account_id);
+    assert!(account.health.liquidatable);
+
+    let candidate = account.liquidation.unwrap();
+    assert_eq!(candidate.debt_coin.denom, uatom_info.denom);
+    assert_eq!(candidate.request, LiquidateRequest::Deposit(uosmo_info.denom.clone()));
+
+    let debt_before = get_debt("uatom", &mock.query_positions(&liquidatee_account_id).debts);
+
+    let liquidator_account_id = mock.create_credit_account(&liquidator).unwrap();
+    mock.update_credit_account(
+        &liquidator_account_id,
+        &liquidator,
+        vec![
+            Deposit(candidate.debt_coin.clone()),
+            Liquidate {
+                liquidatee_account_id: liquidatee_account_id.clone(),
+                debt_coin: candidate.debt_coin.clone(),
+                request: candidate.request,
+            },
+        ],
+        &[candidate.debt_coin.clone()],
+    )
+    .unwrap();
+
+    // The whole candidate debt is repaid
+    let debt_after = get_debt("uatom", &mock.query_positions(&liquidatee_account_id).debts);
+    assert_eq!(debt_before.amount - debt_after.amount, candidate.debt_coin.amount);
+
+    // Healthy account is still indexed but never returned
+    let res = mock.query_liquidatable_accounts(
+        Some(liquidatee_account_id),
+        None,
+        ActionKind::Liquidation,
+    );
+    assert!(res.accounts.is_empty());
+}
+
+#[test]
+fn accounts_without_price_are_reported() {
+    let uosmo_info = uosmo_info();
+    let uatom_info = uatom_info_with_cf(Decimal::from_str("0.505").unwrap());
+    let uakt_info = coin_info("uakt");
+    let liquidatee = Addr::unchecked("liquidatee");
+    let unpriced = Addr::unchecked("unpriced");
+    let mut mock = MockEnv::new()
+        .set_params(&[uosmo_info.clone(), uatom_info.clone(), uakt_info.clone()])
+        .fund_account(AccountToFund {
+            addr: liquidatee.clone(),
+            funds: coins(3000, uosmo_info.denom.clone()),
+        })
+        .fund_account(AccountToFund {
+            addr: unpriced.clone(),
+            funds: coins(3000, uakt_info.denom.clone()),
+        })
+        .build()
+        .unwrap();
+    let unpriced_account_id = mock.create_credit_account(&unpriced).unwrap();
+    let liquidatee_account_id = mock.create_credit_account(&liquidatee).unwrap();
+
+    mock.update_credit_account(
+        &unpriced_account_id,
+        &unpriced,
+        vec![Deposit(uakt_info.to_coin(3000)), Borrow(uatom_info.to_coin(100))],
+        &[Coin::new(3000, uakt_info.denom.clone())],
+    )
+    .unwrap();
+    mock.update_credit_account(
+        &liquidatee_account_id,
+        &liquidatee,
+        vec![
+            Deposit(uosmo_info.to_coin(3000)),
+            Borrow(uatom_info.to_coin(1000)),
+            Withdraw(uatom_info.to_action_coin(400)),
+        ],
+        &[Coin::new(3000, uosmo_info.denom.clone())],
+    )
+    .unwrap();
+
+    mock.remove_price(&uakt_info.denom, ActionKind::Liquidation);
+    mock.price_change(CoinPrice {
+        pricing: ActionKind::Liquidation,
+        denom: uatom_info.denom.clone(),
+        price: Decimal::from_atomics(128u128, 2).unwrap(),
+    });
+
+    // The account without price doesn't prevent returning the liquidatable one
+    let res = mock.query_liquidatable_accounts(None, None, ActionKind::Liquidation);
+    assert_eq!(res.next_start_after, None);
+    assert_eq!(res.accounts.len(), 1);
+    assert_eq!(res.accounts[0].account_id, liquidatee_account_id);
+    assert_eq!(res.failed_account_ids, vec![unpriced_account_id]);
+}
@@ -1,17 +1,20 @@
 use std::str::FromStr;
 
 use cosmwasm_std::{coins, Addr, Coin, Decimal, OverflowError, OverflowOperation, Uint128};
+use cw_multi_test::{AppResponse, Executor};
+use cw_utils::PaymentError;
 use mars_credit_manager::error::{
     ContractError,
-    ContractError::{AboveMaxLTV, LiquidationNotProfitable, NotLiquidatable},
+    ContractError::{AboveMaxLTV, DuplicateLiquidatee, LiquidationNotProfitable, NotLiquidatable},
 };
 use mars_mock_oracle::msg::CoinPrice;
 use mars_types::{
     credit_manager::{
-        Action::{Borrow, Deposit, EnterVault, Liquidate, Withdraw},
-        LiquidateRequest,
+        Action::{BatchLiquidate, Borrow, Deposit, EnterVault, Liquidate, Withdraw},
+        BatchLiquidation, ExecuteMsg, LiquidateRequest,
     },
     oracle::ActionKind,
+    params::{AssetParamsUnchecked, AssetParamsUpdate, LiquidationAuction},
 };
 
 use super::helpers::{
@@ -891,3 +894,295 @@ fn improve_hf_but_acc_unhealthy() {
         prev_health.liquidation_health_factor.unwrap() < health.liquidation_health_factor.unwrap()
     );
 }
+
+#[test]
+fn batch_liquidation_skips_healthy_accounts() {
+    let uosmo_info = uosmo_info();
+    let uatom_info = uatom_info_with_cf(Decimal::from_str("0.505").unwrap());
+    let liquidator = Addr::unchecked("liquidator");
+    let liquidatee = Addr::unchecked("liquidatee");
+    let healthy = Addr::unchecked("healthy");
+    let mut mock = MockEnv::new()
+        .set_params(&[uosmo_info.clone(), uatom_info.clone()])
+        .fund_account(AccountToFund {
+            addr: liquidatee.clone(),
+            funds: coins(3000, uosmo_info.denom.clone()),
+        })
+        .fund_account(AccountToFund {
+            addr: healthy.clone(),
+            funds: coins(3000, uosmo_info.denom.clone()),
+        })
+        .fund_account(AccountToFund {
+            addr: liquidator.clone(),
+            funds: coins(3000, uatom_info.denom.clone()),
+        })
+        .build()
+        .unwrap();
+    let liquidatee_account_id = mock.create_credit_account(&liquidatee).unwrap();
+    let healthy_account_id = mock.create_credit_account(&healthy).unwrap();
+
+    mock.update_credit_account(
+        &liquidatee_account_id,
+        &liquidatee,
+        vec![
+            Deposit(uosmo_info.to_coin(3000)),
+            Borrow(uatom_info.to_coin(1000)),
+            Withdraw(uatom_info.to_action_coin(400)),
+        ],
+        &[Coin::new(3000, uosmo_info.denom.clone())],
+    )
+    .unwrap();
+    mock.update_credit_account(
+        &healthy_account_id,
+        &healthy,
+        vec![Deposit(uosmo_info.to_coin(3000)), Borrow(uatom_info.to_coin(100))],
+        &[Coin::new(3000, uosmo_info.denom.clone())],
+    )
+    .unwrap();
+
+    mock.price_change(CoinPrice {
+        pricing: ActionKind::Liquidation,
+        denom: uatom_info.denom.clone(),
+        price: Decimal::from_atomics(128u128, 2).unwrap(),
+    });
+
+    let liquidator_account_id = mock.create_credit_account(&liquidator).unwrap();
+
+    let res = mock
+        .update_credit_account(
+            &liquidator_account_id,
+            &liquidator,
+            vec![
+                Deposit(uatom_info.to_coin(600)),
+                BatchLiquidate {
+                    liquidations: vec![
+                        BatchLiquidation {
+                            liquidatee_account_id: liquidatee_account_id.clone(),
+                            debt_coin: uatom_info.to_coin(561),
+                            request: LiquidateRequest::Deposit(uosmo_info.denom.clone()),
+                        },
+                        BatchLiquidation {
+                            liquidatee_account_id: healthy_account_id.clone(),
+                            debt_coin: uatom_info.to_coin(39),
+                            request: LiquidateRequest::Deposit(uosmo_info.denom.clone()),
+                        },
+                    ],
+                },
+            ],
+            &[uatom_info.to_coin(600)],
+        )
+        .unwrap();
+
+    assert_eq!(attribute(&res, "liquidation_0"), format!("{liquidatee_account_id}:liquidated"));
+    assert_eq!(attribute(&res, "liquidation_1"), format!("{healthy_account_id}:skipped"));
+
+    // Same result as a single liquidation (see `max_debt_repayed`)
+    let position = mock.query_positions(&liquidatee_account_id);
+    let atom_debt = get_debt("uatom", &position.debts);
+    assert_eq!(atom_debt.amount, Uint128::new(496));
+
+    // Skipped account is left untouched
+    let position = mock.query_positions(&healthy_account_id);
+    assert_eq!(get_coin("uosmo", &position.deposits), uosmo_info.to_coin(3000));
+    let atom_debt = get_debt("uatom", &position.debts);
+    assert_eq!(atom_debt.amount, Uint128::new(101));
+
+    // Debt coin of the skipped liquidation stays in the liquidator's account
+    let position = mock.query_positions(&liquidator_account_id);
+    let atom_balance = get_coin("uatom", &position.deposits);
+    assert_eq!(atom_balance.amount, Uint128::new(95));
+}
+
+#[test]
+fn batch_liquidation_rejects_duplicate_liquidatees() {
+    let uosmo_info = uosmo_info();
+    let uatom_info = uatom_info_with_cf(Decimal::from_str("0.505").unwrap());
+    let liquidator = Addr::unchecked("liquidator");
+    let liquidatee = Addr::unchecked("liquidatee");
+    let mut mock = MockEnv::new()
+        .set_params(&[uosmo_info.clone(), uatom_info.clone()])
+        .fund_account(AccountToFund {
+            addr: liquidatee.clone(),
+            funds: coins(3000, uosmo_info.denom.clone()),
+        })
+        .fund_account(AccountToFund {
+            addr: liquidator.clone(),
+            funds: coins(3000, uatom_info.denom.clone()),
+        })
+        .build()
+        .unwrap();
+    let liquidatee_account_id = mock.create_credit_account(&liquidatee).unwrap();
+
+    mock.update_credit_account(
+        &liquidatee_account_id,
+        &liquidatee,
+        vec![
+            Deposit(uosmo_info.to_coin(3000)),
+            Borrow(uatom_info.to_coin(1000)),
+            Withdraw(uatom_info.to_action_coin(400)),
+        ],
+        &[Coin::new(3000, uosmo_info.denom.clone())],
+    )
+    .unwrap();
+
+    mock.price_change(CoinPrice {
+        pricing: ActionKind::Liquidation,
+        denom: uatom_info.denom.clone(),
+        price: Decimal::from_atomics(128u128, 2).unwrap(),
+    });
+
+    let liquidator_account_id = mock.create_credit_account(&liquidator).unwrap();
+
+    // Both liquidations would be computed with the health before the first one
+    let res = mock.update_credit_account(
+        &liquidator_account_id,
+        &liquidator,
+        vec![
+            Deposit(uatom_info.to_coin(600)),
+            BatchLiquidate {
+                liquidations: vec![
+                    BatchLiquidation {
+                        liquidatee_account_id: liquidatee_account_id.clone(),
+                        debt_coin: uatom_info.to_coin(300),
+                        request: LiquidateRequest::Deposit(uosmo_info.denom.clone()),
+                    },
+                    BatchLiquidation {
+                        liquidatee_account_id: liquidatee_account_id.clone(),
+                        debt_coin: uatom_info.to_coin(300),
+                        request: LiquidateRequest::Deposit(uosmo_info.denom.clone()),
+                    },
+                ],
+            },
+        ],
+        &[uatom_info.to_coin(600)],
+    );
+
+    assert_err(res, DuplicateLiquidatee(liquidatee_account_id));
+}
+
+#[test]
+fn auctioned_liquidation_bonus_grows_with_time_since_flagged() {
+    let uosmo_info = uosmo_info();
+    let uatom_info = uatom_info_with_cf(Decimal::from_str("0.505").unwrap());
+    let liquidator = Addr::unchecked("liquidator");
+    let liquidatee = Addr::unchecked("liquidatee");
+    let mut mock = MockEnv::new()
+        .set_params(&[uosmo_info.clone(), uatom_info.clone()])
+        .fund_account(AccountToFund {
+            addr: liquidatee.clone(),
+            funds: coins(3000, uosmo_info.denom.clone()),
+        })
+        .fund_account(AccountToFund {
+            addr: liquidator.clone(),
+            funds: coins(3000, uatom_info.denom.clone()),
+        })
+        .build()
+        .unwrap();
+    let liquidatee_account_id = mock.create_credit_account(&liquidatee).unwrap();
+
+    // LB of uosmo goes from min_lb (2%) to max_lb (10%) over 1000 seconds
+    let mut uosmo_params: AssetParamsUnchecked = uosmo_info.clone().into();
+    uosmo_params.liquidation_auction = Some(LiquidationAuction {
+        duration: 1000,
+    });
+    mock.update_asset_params(AssetParamsUpdate::AddOrUpdate {
+        params: uosmo_params,
+    });
+
+    mock.update_credit_account(
+        &liquidatee_account_id,
+        &liquidatee,
+        vec![
+            Deposit(uosmo_info.to_coin(3000)),
+            Borrow(uatom_info.to_coin(1000)),
+            Withdraw(uatom_info.to_action_coin(400)),
+        ],
+        &[Coin::new(3000, uosmo_info.denom.clone())],
+    )
+    .unwrap();
+
+    // Flagging a healthy account doesn't start the auction
+    let res = mock.flag_liquidatable(&liquidator, &liquidatee_account_id).unwrap();
+    assert_eq!(attribute(&res, "liquidatable_since"), "none");
+
+    mock.price_change(CoinPrice {
+        pricing: ActionKind::Liquidation,
+        denom: uatom_info.denom.clone(),
+        price: Decimal::from_atomics(128u128, 2).unwrap(),
+    });
+
+    let res = mock.flag_liquidatable(&liquidator, &liquidatee_account_id).unwrap();
+    assert_eq!(attribute(&res, "liquidatable_since"), mock.query_block_time().to_string());
+
+    let liquidator_account_id = mock.create_credit_account(&liquidator).unwrap();
+    mock.update_credit_account(
+        &liquidator_account_id,
+        &liquidator,
+        vec![Deposit(uatom_info.to_coin(300))],
+        &[uatom_info.to_coin(300)],
+    )
+    .unwrap();
+
+    // Each liquidation repays 100 uatom (debt value 128), the liquidator receives
+    // 128 * (1 + LB) / 0.25 uosmo minus the protocol fee (4 uosmo)
+    let liquidate = |mock: &mut MockEnv| {
+        mock.update_credit_account(
+            &liquidator_account_id,
+            &liquidator,
+            vec![Liquidate {
+                liquidatee_account_id: liquidatee_account_id.clone(),
+                debt_coin: uatom_info.to_coin(100),
+                request: LiquidateRequest::Deposit(uosmo_info.denom.clone()),
+            }],
+            &[],
+        )
+        .unwrap();
+        let position = mock.query_positions(&liquidator_account_id);
+        get_coin("uosmo", &position.deposits).amount.u128()
+    };
+
+    // Auction just started, LB = 2%
+    assert_eq!(liquidate(&mut mock), 516);
+
+    // Half of the auction, LB = 6%
+    mock.increment_by_time(500);
+    assert_eq!(liquidate(&mut mock), 516 + 536);
+
+    // Auction ended, LB = max_lb = 10%
+    mock.increment_by_time(1000);
+    assert_eq!(liquidate(&mut mock), 516 + 536 + 556);
+}
+
+#[test]
+fn flagging_account_is_nonpayable() {
+    let uosmo_info = uosmo_info();
+    let liquidator = Addr::unchecked("liquidator");
+    let mut mock = MockEnv::new()
+        .set_params(&[uosmo_info.clone()])
+        .fund_account(AccountToFund {
+            addr: liquidator.clone(),
+            funds: coins(100, uosmo_info.denom.clone()),
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&liquidator).unwrap();
+
+    let res = mock.app.execute_contract(
+        liquidator.clone(),
+        mock.rover.clone(),
+        &ExecuteMsg::FlagLiquidatable {
+            account_id,
+        },
+        &[uosmo_info.to_coin(100)],
+    );
+    assert_err(res, ContractError::Payment(PaymentError::NonPayable {}));
+}
+
+fn attribute(res: &AppResponse, key: &str) -> String {
+    res.events
+        .iter()
+        .flat_map(|e| &e.attributes)
+        .find(|a| a.key == key)
+        .map(|a| a.value.clone())
+        .unwrap()
+}
@@ -86,11 +86,15 @@ fn close_perps_when_enough_usdc_in_account_to_cover_loss() {
                 denom: uosmo_info.denom.clone(),
                 order_size: Int128::from_str("200").unwrap(),
                 reduce_only: None,
+                margin: None,
+                acceptable_price: None,
             },
             ExecutePerpOrder {
                 denom: uatom_info.denom.clone(),
                 order_size: Int128::from_str("-400").unwrap(),
                 reduce_only: None,
+                margin: None,
+                acceptable_price: None,
             },
         ],
         &[uosmo_coin_deposited.clone(), uusdc_coin_deposited.clone()],
@@ -265,11 +269,15 @@ fn close_perps_when_not_enough_usdc_in_account_to_cover_loss() {
                 denom: uosmo_info.denom.clone(),
                 order_size: Int128::from_str("200").unwrap(),
                 reduce_only: None,
+                margin: None,
+                acceptable_price: None,
             },
             ExecutePerpOrder {
                 denom: uatom_info.denom.clone(),
                 order_size: Int128::from_str("-400").unwrap(),
                 reduce_only: None,
+                margin: None,
+                acceptable_price: None,
             },
         ],
         &[uosmo_coin_deposited.clone(), uusdc_coin_deposited.clone()],
@@ -449,11 +457,15 @@ fn close_perps_with_profit() {
                 denom: uosmo_info.denom.clone(),
                 order_size: Int128::from_str("200").unwrap(),
                 reduce_only: None,
+                margin: None,
+                acceptable_price: None,
             },
             ExecutePerpOrder {
                 denom: utia_info.denom.clone(),
                 order_size: Int128::from_str("-400").unwrap(),
                 reduce_only: None,
+                margin: None,
+                acceptable_price: None,
             },
         ],
         &[uosmo_coin_deposited.clone(), uusdc_coin_deposited.clone()],
@@ -636,6 +648,8 @@ fn liquidation_uses_correct_price_kind_if_perps_open() {
                 denom: utia_info.denom.clone(),
                 order_size: Int128::from_str("-400").unwrap(),
                 reduce_only: None,
+                margin: None,
+                acceptable_price: None,
             },
         ],
         &[uosmo_coin_deposited.clone()],
@@ -874,6 +888,8 @@ fn liquidate_if_no_debt_after_closing_perps(
                 denom: uatom_info.denom.clone(),
                 order_size: Int128::from_str("-500").unwrap(),
                 reduce_only: None,
+                margin: None,
+                acceptable_price: None,
             },
         ],
         &[uusdc_coin_deposited.clone(), uosmo_coin_deposited.clone()],
@@ -1153,6 +1169,8 @@ fn liquidate_if_bad_debt_created(
                 denom: uatom_info.denom.clone(),
                 order_size: Int128::from_str("-400").unwrap(),
                 reduce_only: None,
+                margin: None,
+                acceptable_price: None,
             },
             Deposit(uatom_coin_deposited.clone()),
         ],
@@ -1288,6 +1306,344 @@ fn liquidate_if_bad_debt_created(
     );
 }
 
+/// Tests partial liquidation of a single perp position, other positions of the liquidatee are left untouched
+#[test]
+fn partial_perp_liquidation_reduces_requested_position_only() {
+    let uosmo_info = uosmo_info();
+    let uatom_info = uatom_info();
+    let uusdc_info = uusdc_info();
+
+    let liquidator = Addr::unchecked("liquidator");
+    let liquidatee = Addr::unchecked("liquidatee");
+    let vault_depositor = Addr::unchecked("vault_depositor");
+
+    let vault_coin_deposited = coin(100000, uusdc_info.denom.clone());
+    let uosmo_coin_deposited = uosmo_info.to_coin(3000);
+    let uusdc_coin_deposited = uusdc_info.to_coin(650);
+
+    let mut mock = MockEnv::new()
+        .set_params(&[uosmo_info.clone(), uatom_info.clone(), uusdc_info.clone()])
+        .fund_account(AccountToFund {
+            addr: liquidatee.clone(),
+            funds: vec![uosmo_coin_deposited.clone(), uusdc_coin_deposited.clone()],
+        })
+        .fund_account(AccountToFund {
+            addr: vault_depositor.clone(),
+            funds: vec![vault_coin_deposited.clone()],
+        })
+        .build()
+        .unwrap();
+    let liquidatee_account_id = mock.create_credit_account(&liquidatee).unwrap();
+    let liquidator_account_id = mock.create_credit_account(&liquidator).unwrap();
+    let vault_depositor_account_id = mock.create_credit_account(&vault_depositor).unwrap();
+
+    // setup perps
+    mock.update_perp_params(PerpParamsUpdate::AddOrUpdate {
+        params: default_perp_params(&uosmo_info.denom),
+    });
+    mock.update_perp_params(PerpParamsUpdate::AddOrUpdate {
+        params: default_perp_params(&uatom_info.denom),
+    });
+
+    // deposit to vault
+    mock.update_credit_account(
+        &vault_depositor_account_id,
+        &vault_depositor,
+        vec![Deposit(vault_coin_deposited.clone())],
+        &[vault_coin_deposited.clone()],
+    )
+    .unwrap();
+    mock.deposit_to_perp_vault(&vault_depositor_account_id, &vault_coin_deposited, None).unwrap();
+
+    // setup liquidatee's position
+    mock.update_credit_account(
+        &liquidatee_account_id,
+        &liquidatee,
+        vec![
+            Deposit(uosmo_coin_deposited.clone()),
+            Deposit(uusdc_coin_deposited.clone()),
+            Borrow(uatom_info.to_coin(2400)),
+            ExecutePerpOrder {
+                denom: uosmo_info.denom.clone(),
+                order_size: Int128::from_str("200").unwrap(),
+                reduce_only: None,
+                margin: None,
+                acceptable_price: None,
+            },
+            ExecutePerpOrder {
+                denom: uatom_info.denom.clone(),
+                order_size: Int128::from_str("-400").unwrap(),
+                reduce_only: None,
+                margin: None,
+                acceptable_price: None,
+            },
+        ],
+        &[uosmo_coin_deposited.clone(), uusdc_coin_deposited.clone()],
+    )
+    .unwrap();
+
+    for pricing in [ActionKind::Default, ActionKind::Liquidation] {
+        mock.price_change(CoinPrice {
+            pricing,
+            denom: uatom_info.denom.clone(),
+            price: Decimal::from_atomics(26u128, 1).unwrap(),
+        });
+    }
+
+    let prev_health = mock.query_health(&liquidatee_account_id, ActionKind::Liquidation);
+    assert!(prev_health.liquidatable);
+    let prev_position = mock.query_positions(&liquidatee_account_id);
+
+    mock.update_credit_account(
+        &liquidator_account_id,
+        &liquidator,
+        vec![Liquidate {
+            liquidatee_account_id: liquidatee_account_id.clone(),
+            debt_coin: uatom_info.to_coin(0),
+            request: LiquidateRequest::Perp {
+                denom: uatom_info.denom.clone(),
+                size: Uint128::new(100),
+            },
+        }],
+        &[],
+    )
+    .unwrap();
+
+    // Only the requested position is reduced, spot positions are left as they were
+    let position = mock.query_positions(&liquidatee_account_id);
+    assert_eq!(position.perps.len(), 2);
+    let uosmo_perp_position =
+        mock.query_perp_position(&liquidatee_account_id, &uosmo_info.denom).position.unwrap();
+    assert_eq!(uosmo_perp_position.size, Int128::from_str("200").unwrap());
+    let uatom_perp_position =
+        mock.query_perp_position(&liquidatee_account_id, &uatom_info.denom).position.unwrap();
+    assert_eq!(uatom_perp_position.size, Int128::from_str("-300").unwrap());
+    assert_eq!(get_coin("uosmo", &position.deposits), get_coin("uosmo", &prev_position.deposits));
+    assert_eq!(get_coin("uatom", &position.deposits), get_coin("uatom", &prev_position.deposits));
+
+    // Liquidator receives a bonus in the base denom for the loss of the closed size
+    let position = mock.query_positions(&liquidator_account_id);
+    assert_eq!(position.deposits.len(), 1);
+    assert!(!get_coin("uusdc", &position.deposits).amount.is_zero());
+
+    // Liq HF should improve
+    let health = mock.query_health(&liquidatee_account_id, ActionKind::Liquidation);
+    assert!(
+        prev_health.liquidation_health_factor.unwrap() < health.liquidation_health_factor.unwrap()
+    );
+}
+
+#[test]
+fn partial_perp_liquidation_is_capped_by_close_factor() {
+    let uosmo_info = uosmo_info();
+    let uatom_info = uatom_info();
+    let uusdc_info = uusdc_info();
+
+    let liquidator = Addr::unchecked("liquidator");
+    let liquidatee = Addr::unchecked("liquidatee");
+    let vault_depositor = Addr::unchecked("vault_depositor");
+
+    let vault_coin_deposited = coin(100000, uusdc_info.denom.clone());
+    let uosmo_coin_deposited = uosmo_info.to_coin(3000);
+    let uusdc_coin_deposited = uusdc_info.to_coin(650);
+
+    let mut mock = MockEnv::new()
+        .set_params(&[uosmo_info.clone(), uatom_info.clone(), uusdc_info.clone()])
+        .fund_account(AccountToFund {
+            addr: liquidatee.clone(),
+            funds: vec![uosmo_coin_deposited.clone(), uusdc_coin_deposited.clone()],
+        })
+        .fund_account(AccountToFund {
+            addr: vault_depositor.clone(),
+            funds: vec![vault_coin_deposited.clone()],
+        })
+        .build()
+        .unwrap();
+    let liquidatee_account_id = mock.create_credit_account(&liquidatee).unwrap();
+    let liquidator_account_id = mock.create_credit_account(&liquidator).unwrap();
+    let vault_depositor_account_id = mock.create_credit_account(&vault_depositor).unwrap();
+
+    // setup perps
+    mock.update_perp_params(PerpParamsUpdate::AddOrUpdate {
+        params: default_perp_params(&uatom_info.denom),
+    });
+
+    // deposit to vault
+    mock.update_credit_account(
+        &vault_depositor_account_id,
+        &vault_depositor,
+        vec![Deposit(vault_coin_deposited.clone())],
+        &[vault_coin_deposited.clone()],
+    )
+    .unwrap();
+    mock.deposit_to_perp_vault(&vault_depositor_account_id, &vault_coin_deposited, None).unwrap();
+
+    // setup liquidatee's position
+    mock.update_credit_account(
+        &liquidatee_account_id,
+        &liquidatee,
+        vec![
+            Deposit(uosmo_coin_deposited.clone()),
+            Deposit(uusdc_coin_deposited.clone()),
+            Borrow(uatom_info.to_coin(2400)),
+            ExecutePerpOrder {
+                denom: uatom_info.denom.clone(),
+                order_size: Int128::from_str("-400").unwrap(),
+                reduce_only: None,
+                margin: None,
+                acceptable_price: None,
+            },
+        ],
+        &[uosmo_coin_deposited.clone(), uusdc_coin_deposited.clone()],
+    )
+    .unwrap();
+
+    for pricing in [ActionKind::Default, ActionKind::Liquidation] {
+        mock.price_change(CoinPrice {
+            pricing,
+            denom: uatom_info.denom.clone(),
+            price: Decimal::from_atomics(26u128, 1).unwrap(),
+        });
+    }
+
+    let prev_health = mock.query_health(&liquidatee_account_id, ActionKind::Liquidation);
+    assert!(prev_health.liquidatable);
+
+    // Liquidator requests to close the whole position
+    mock.update_credit_account(
+        &liquidator_account_id,
+        &liquidator,
+        vec![Liquidate {
+            liquidatee_account_id: liquidatee_account_id.clone(),
+            debt_coin: uatom_info.to_coin(0),
+            request: LiquidateRequest::Perp {
+                denom: uatom_info.denom.clone(),
+                size: Uint128::new(400),
+            },
+        }],
+        &[],
+    )
+    .unwrap();
+
+    // Only close factor (80%) of the position is closed
+    let uatom_perp_position =
+        mock.query_perp_position(&liquidatee_account_id, &uatom_info.denom).position.unwrap();
+    assert_eq!(uatom_perp_position.size, Int128::from_str("-80").unwrap());
+}
+
+#[test]
+fn partial_perp_liquidation_bonus_is_paid_from_lends() {
+    let uosmo_info = uosmo_info();
+    let uatom_info = uatom_info();
+    let uusdc_info = uusdc_info();
+
+    let liquidator = Addr::unchecked("liquidator");
+    let liquidatee = Addr::unchecked("liquidatee");
+    let vault_depositor = Addr::unchecked("vault_depositor");
+
+    let vault_coin_deposited = coin(100000, uusdc_info.denom.clone());
+    let uosmo_coin_deposited = uosmo_info.to_coin(3000);
+    let uusdc_coin_deposited = uusdc_info.to_coin(650);
+
+    let mut mock = MockEnv::new()
+        .set_params(&[uosmo_info.clone(), uatom_info.clone(), uusdc_info.clone()])
+        .fund_account(AccountToFund {
+            addr: liquidatee.clone(),
+            funds: vec![uosmo_coin_deposited.clone(), uusdc_coin_deposited.clone()],
+        })
+        .fund_account(AccountToFund {
+            addr: vault_depositor.clone(),
+            funds: vec![vault_coin_deposited.clone()],
+        })
+        .build()
+        .unwrap();
+    let liquidatee_account_id = mock.create_credit_account(&liquidatee).unwrap();
+    let liquidator_account_id = mock.create_credit_account(&liquidator).unwrap();
+    let vault_depositor_account_id = mock.create_credit_account(&vault_depositor).unwrap();
+
+    // setup perps
+    mock.update_perp_params(PerpParamsUpdate::AddOrUpdate {
+        params: default_perp_params(&uatom_info.denom),
+    });
+
+    // deposit to vault
+    mock.update_credit_account(
+        &vault_depositor_account_id,
+        &vault_depositor,
+        vec![Deposit(vault_coin_deposited.clone())],
+        &[vault_coin_deposited.clone()],
+    )
+    .unwrap();
+    mock.deposit_to_perp_vault(&vault_depositor_account_id, &vault_coin_deposited, None).unwrap();
+
+    // setup liquidatee's position, all usdc is lent
+    mock.update_credit_account(
+        &liquidatee_account_id,
+        &liquidatee,
+        vec![
+            Deposit(uosmo_coin_deposited.clone()),
+            Deposit(uusdc_coin_deposited.clone()),
+            Borrow(uatom_info.to_coin(2400)),
+            ExecutePerpOrder {
+                denom: uatom_info.denom.clone(),
+                order_size: Int128::from_str("-400").unwrap(),
+                reduce_only: None,
+                margin: None,
+                acceptable_price: None,
+            },
+            Lend(uusdc_info.to_action_coin_full_balance()),
+        ],
+        &[uosmo_coin_deposited.clone(), uusdc_coin_deposited.clone()],
+    )
+    .unwrap();
+
+    for pricing in [ActionKind::Default, ActionKind::Liquidation] {
+        mock.price_change(CoinPrice {
+            pricing,
+            denom: uatom_info.denom.clone(),
+            price: Decimal::from_atomics(26u128, 1).unwrap(),
+        });
+    }
+
+    let prev_health = mock.query_health(&liquidatee_account_id, ActionKind::Liquidation);
+    assert!(prev_health.liquidatable);
+    let prev_position = mock.query_positions(&liquidatee_account_id);
+    assert!(prev_position.deposits.iter().all(|c| c.denom != uusdc_info.denom));
+    let prev_uusdc_lent = get_coin("uusdc", &prev_position.lends).amount;
+    assert!(!prev_uusdc_lent.is_zero());
+
+    mock.update_credit_account(
+        &liquidator_account_id,
+        &liquidator,
+        vec![Liquidate {
+            liquidatee_account_id: liquidatee_account_id.clone(),
+            debt_coin: uatom_info.to_coin(0),
+            request: LiquidateRequest::Perp {
+                denom: uatom_info.denom.clone(),
+                size: Uint128::new(100),
+            },
+        }],
+        &[],
+    )
+    .unwrap();
+
+    // Liquidator receives a bonus reclaimed from the lent usdc of the liquidatee
+    let position = mock.query_positions(&liquidator_account_id);
+    assert_eq!(position.deposits.len(), 1);
+    let bonus = get_coin("uusdc", &position.deposits).amount;
+    assert!(!bonus.is_zero());
+
+    let position = mock.query_positions(&liquidatee_account_id);
+    let uusdc_lent = position
+        .lends
+        .iter()
+        .find(|c| c.denom == uusdc_info.denom)
+        .map(|c| c.amount)
+        .unwrap_or_default();
+    assert!(uusdc_lent < prev_uusdc_lent);
+    assert_eq!(get_coin("uosmo", &position.deposits), get_coin("uosmo", &prev_position.deposits));
+}
+
 fn pnl_profit(pnl: PnL) -> Uint128 {
     match pnl {
         PnL::Profit(coin) => coin.amount,
@@ -1,7 +1,17 @@
-use cosmwasm_std::{attr, testing::mock_env, Empty, Event};
+use cosmwasm_std::{
+    attr, testing::mock_env, Addr, Decimal, Empty, Event, Int128, Order, StdResult, Uint128,
+};
 use cw2::{ContractVersion, VersionError};
-use mars_credit_manager::{contract::migrate, error::ContractError, state::NEXT_TRIGGER_ID};
+use mars_credit_manager::{
+    contract::migrate,
+    error::ContractError,
+    state::{DEBT_SHARES, FLASH_LOAN_FEE_RATE, LIQUIDATION_INDEX, NEXT_TRIGGER_ID, PERPS},
+};
 use mars_testing::mock_dependencies;
+use mars_types::{
+    adapters::perps::Perps,
+    perps::{PerpPosition, PnlAmounts, PositionResponse},
+};
 
 #[test]
 fn wrong_contract_name() {
@@ -47,6 +57,9 @@ fn successful_migration() {
     let order_id = NEXT_TRIGGER_ID.load(deps.as_ref().storage).unwrap();
     assert_eq!(order_id, 1);
 
+    let flash_loan_fee_rate = FLASH_LOAN_FEE_RATE.load(deps.as_ref().storage).unwrap();
+    assert_eq!(flash_loan_fee_rate, Decimal::zero());
+
     assert_eq!(res.messages, vec![]);
     assert_eq!(res.events, vec![] as Vec<Event>);
     assert!(res.data.is_none());
@@ -61,3 +74,47 @@ fn successful_migration() {
     };
     assert_eq!(cw2::get_contract_version(deps.as_ref().storage).unwrap(), new_contract_version);
 }
+
+#[test]
+fn accounts_with_debt_or_perps_are_indexed() {
+    let mut deps = mock_dependencies(&[]);
+    cw2::set_contract_version(deps.as_mut().storage, "crates.io:mars-credit-manager", "2.1.0")
+        .unwrap();
+
+    DEBT_SHARES.save(deps.as_mut().storage, ("1", "uosmo"), &Uint128::new(100)).unwrap();
+    PERPS.save(deps.as_mut().storage, &Perps::new(Addr::unchecked("perps"))).unwrap();
+
+    // More positions than a single page of the perps contract
+    let perp_account_ids = (100..135).map(|id| id.to_string()).collect::<Vec<_>>();
+    for account_id in perp_account_ids.iter() {
+        deps.querier.set_perp_position(perp_position(account_id, "uatom"));
+    }
+
+    migrate(deps.as_mut(), mock_env(), Empty {}).unwrap();
+
+    let indexed_account_ids = LIQUIDATION_INDEX
+        .keys(deps.as_ref().storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    let mut expected_account_ids = vec!["1".to_string()];
+    expected_account_ids.extend(perp_account_ids);
+    assert_eq!(indexed_account_ids, expected_account_ids);
+}
+
+fn perp_position(account_id: &str, denom: &str) -> PositionResponse {
+    PositionResponse {
+        account_id: account_id.to_string(),
+        position: Some(PerpPosition {
+            denom: denom.to_string(),
+            base_denom: "uusdc".to_string(),
+            size: Int128::new(100),
+            entry_price: Decimal::one(),
+            current_price: Decimal::one(),
+            entry_exec_price: Decimal::one(),
+            current_exec_price: Decimal::one(),
+            unrealized_pnl: PnlAmounts::default(),
+            realized_pnl: PnlAmounts::default(),
+            isolated_margin: None,
+        }),
+    }
+}
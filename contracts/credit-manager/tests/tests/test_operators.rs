@@ -0,0 +1,335 @@
+use cosmwasm_std::{coins, Addr, Decimal, Uint128};
+use mars_credit_manager::error::ContractError;
+use mars_types::credit_manager::{
+    Action::{Borrow, Deposit, Lend, Repay, SwapExactIn, WithdrawToWallet},
+    ActionType, OperatorPermissions,
+};
+
+use super::helpers::{assert_err, uatom_info, uosmo_info, AccountToFund, MockEnv};
+
+fn lend_only_permissions(expires_at: u64) -> OperatorPermissions {
+    OperatorPermissions {
+        allowed_actions: vec![ActionType::Lend, ActionType::Repay],
+        spend_limits: vec![],
+        expires_at,
+    }
+}
+
+#[test]
+fn only_owner_can_grant_operator() {
+    let user = Addr::unchecked("user");
+    let bot = Addr::unchecked("bot");
+    let mut mock = MockEnv::new().build().unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    let expires_at = mock.query_block_time() + 3600;
+    let res = mock.grant_operator(&bot, &account_id, &bot, lend_only_permissions(expires_at));
+
+    assert_err(
+        res,
+        ContractError::NotTokenOwner {
+            user: bot.to_string(),
+            account_id,
+        },
+    );
+}
+
+#[test]
+fn withdraw_actions_cannot_be_granted() {
+    let user = Addr::unchecked("user");
+    let bot = Addr::unchecked("bot");
+    let mut mock = MockEnv::new().build().unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    let expires_at = mock.query_block_time() + 3600;
+    let res = mock.grant_operator(
+        &user,
+        &account_id,
+        &bot,
+        OperatorPermissions {
+            allowed_actions: vec![ActionType::Lend, ActionType::WithdrawToWallet],
+            spend_limits: vec![],
+            expires_at,
+        },
+    );
+
+    assert_err(
+        res,
+        ContractError::InvalidOperatorGrant {
+            reason: "withdraw_to_wallet cannot be granted to an operator".to_string(),
+        },
+    );
+}
+
+#[test]
+fn operator_can_only_dispatch_allowed_actions() {
+    let coin_info = uosmo_info();
+    let user = Addr::unchecked("user");
+    let bot = Addr::unchecked("bot");
+    let mut mock = MockEnv::new()
+        .set_params(&[coin_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: coins(300, coin_info.denom.clone()),
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deposit(coin_info.to_coin(300))],
+        &[coin_info.to_coin(300)],
+    )
+    .unwrap();
+
+    let expires_at = mock.query_block_time() + 3600;
+    mock.grant_operator(&user, &account_id, &bot, lend_only_permissions(expires_at)).unwrap();
+
+    // Operator can lend on behalf of the owner
+    mock.update_credit_account(&account_id, &bot, vec![Lend(coin_info.to_action_coin(100))], &[])
+        .unwrap();
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.lends[0].amount, Uint128::new(100));
+
+    // Operator can't borrow
+    let res =
+        mock.update_credit_account(&account_id, &bot, vec![Borrow(coin_info.to_coin(10))], &[]);
+    assert_err(
+        res,
+        ContractError::Unauthorized {
+            user: bot.to_string(),
+            action: "borrow".to_string(),
+        },
+    );
+
+    // Operator can't withdraw funds to its wallet
+    let res = mock.update_credit_account(
+        &account_id,
+        &bot,
+        vec![WithdrawToWallet {
+            coin: coin_info.to_action_coin(10),
+            recipient: bot.to_string(),
+        }],
+        &[],
+    );
+    assert_err(
+        res,
+        ContractError::Unauthorized {
+            user: bot.to_string(),
+            action: "withdraw_to_wallet".to_string(),
+        },
+    );
+
+    // Operator can't repay debts of other accounts
+    let res = mock.update_credit_account(
+        &account_id,
+        &bot,
+        vec![Repay {
+            recipient_account_id: Some("another_account".to_string()),
+            coin: coin_info.to_action_coin(10),
+        }],
+        &[],
+    );
+    assert_err(
+        res,
+        ContractError::Unauthorized {
+            user: bot.to_string(),
+            action: "repay for another account".to_string(),
+        },
+    );
+}
+
+#[test]
+fn operator_spend_limits_are_consumed() {
+    let coin_info = uosmo_info();
+    let user = Addr::unchecked("user");
+    let bot = Addr::unchecked("bot");
+    let mut mock = MockEnv::new()
+        .set_params(&[coin_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: coins(300, coin_info.denom.clone()),
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deposit(coin_info.to_coin(300))],
+        &[coin_info.to_coin(300)],
+    )
+    .unwrap();
+
+    let expires_at = mock.query_block_time() + 3600;
+    mock.grant_operator(
+        &user,
+        &account_id,
+        &bot,
+        OperatorPermissions {
+            allowed_actions: vec![ActionType::Lend],
+            spend_limits: vec![coin_info.to_coin(150)],
+            expires_at,
+        },
+    )
+    .unwrap();
+
+    mock.update_credit_account(&account_id, &bot, vec![Lend(coin_info.to_action_coin(100))], &[])
+        .unwrap();
+
+    let operators = mock.query_account_operators(&account_id, None, None);
+    assert_eq!(operators.len(), 1);
+    assert_eq!(operators[0].operator, bot.to_string());
+    assert_eq!(operators[0].grant.permissions.spend_limits, vec![coin_info.to_coin(50)]);
+
+    let res = mock.update_credit_account(
+        &account_id,
+        &bot,
+        vec![Lend(coin_info.to_action_coin(100))],
+        &[],
+    );
+    assert_err(
+        res,
+        ContractError::SpendLimitExceeded {
+            denom: coin_info.denom.clone(),
+            requested: Uint128::new(100),
+            remaining: Uint128::new(50),
+        },
+    );
+
+    let res = mock.update_credit_account(
+        &account_id,
+        &bot,
+        vec![Lend(coin_info.to_action_coin_full_balance())],
+        &[],
+    );
+    assert_err(res, ContractError::ExactAmountRequired(coin_info.denom));
+}
+
+#[test]
+fn expired_and_revoked_grants_are_rejected() {
+    let coin_info = uosmo_info();
+    let user = Addr::unchecked("user");
+    let bot = Addr::unchecked("bot");
+    let mut mock = MockEnv::new()
+        .set_params(&[coin_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: coins(300, coin_info.denom.clone()),
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deposit(coin_info.to_coin(300))],
+        &[coin_info.to_coin(300)],
+    )
+    .unwrap();
+
+    let expires_at = mock.query_block_time() + 3600;
+    mock.grant_operator(&user, &account_id, &bot, lend_only_permissions(expires_at)).unwrap();
+
+    mock.increment_by_time(3600);
+
+    // Expired grants are not listed anymore
+    let operators = mock.query_account_operators(&account_id, None, None);
+    assert!(operators.is_empty());
+
+    let res = mock.update_credit_account(
+        &account_id,
+        &bot,
+        vec![Lend(coin_info.to_action_coin(10))],
+        &[],
+    );
+    assert_err(
+        res,
+        ContractError::OperatorGrantExpired {
+            operator: bot.to_string(),
+            account_id: account_id.clone(),
+        },
+    );
+
+    // Renew the grant and revoke it again
+    let expires_at = mock.query_block_time() + 3600;
+    mock.grant_operator(&user, &account_id, &bot, lend_only_permissions(expires_at)).unwrap();
+    mock.revoke_operator(&user, &account_id, &bot).unwrap();
+
+    let res = mock.update_credit_account(
+        &account_id,
+        &bot,
+        vec![Lend(coin_info.to_action_coin(10))],
+        &[],
+    );
+    assert_err(
+        res,
+        ContractError::NotTokenOwner {
+            user: bot.to_string(),
+            account_id,
+        },
+    );
+}
+
+#[test]
+fn operator_swaps_are_bounded_by_oracle_price() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+    let user = Addr::unchecked("user");
+    let bot = Addr::unchecked("bot");
+    let mut mock = MockEnv::new()
+        .max_slippage(Decimal::percent(10))
+        .set_params(&[atom_info.clone(), osmo_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![atom_info.to_coin(20000)],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deposit(atom_info.to_coin(20000))],
+        &[atom_info.to_coin(20000)],
+    )
+    .unwrap();
+
+    let expires_at = mock.query_block_time() + 3600;
+    mock.grant_operator(
+        &user,
+        &account_id,
+        &bot,
+        OperatorPermissions {
+            allowed_actions: vec![ActionType::SwapExactIn],
+            spend_limits: vec![],
+            expires_at,
+        },
+    )
+    .unwrap();
+
+    // 10000 uatom are worth 40000 uosmo at oracle prices, the mock swapper returns 1337 uosmo
+    let swap = SwapExactIn {
+        coin_in: atom_info.to_action_coin(10000),
+        denom_out: osmo_info.denom.clone(),
+        min_receive: Uint128::one(),
+        route: None,
+    };
+
+    // The operator's min_receive is replaced by the oracle floor
+    let res = mock.update_credit_account(&account_id, &bot, vec![swap.clone()], &[]);
+    let err = res.unwrap_err();
+    assert!(err.root_cause().to_string().contains("Min amount not reached"));
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.deposits, vec![atom_info.to_coin(20000)]);
+
+    // The owner can swap at any price
+    mock.update_credit_account(&account_id, &user, vec![swap], &[]).unwrap();
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.deposits.len(), 2);
+}
@@ -3,10 +3,12 @@ use std::str::FromStr;
 use cosmwasm_std::{coin, Addr, Coin, Decimal, Int128, Uint128};
 use mars_credit_manager::error::ContractError;
 use mars_mock_oracle::msg::CoinPrice;
+use mars_perps::error::ContractError as PerpsContractError;
 use mars_types::{
     credit_manager::{
+        AccountSettings,
         Action::{Deposit, ExecutePerpOrder, Lend, Withdraw},
-        ActionAmount, ActionCoin, Positions,
+        ActionAmount, ActionCoin, PerpSettlementPolicy, Positions,
     },
     oracle::ActionKind,
     params::PerpParamsUpdate,
@@ -107,6 +109,8 @@ fn open_position_with_correct_payment(
             denom: atom_info.denom.clone(),
             order_size: perp_size,
             reduce_only: None,
+            margin: None,
+            acceptable_price: None,
         }],
         &[],
     )
@@ -234,6 +238,8 @@ fn close_losing_position_with_correct_payment(
             denom: atom_info.denom.clone(),
             order_size: perp_size,
             reduce_only: None,
+            margin: None,
+            acceptable_price: None,
         }],
         &[],
     )
@@ -270,6 +276,8 @@ fn close_losing_position_with_correct_payment(
             denom: atom_info.denom,
             order_size,
             reduce_only: Some(true),
+            margin: None,
+            acceptable_price: None,
         }],
         &[],
     )
@@ -320,6 +328,8 @@ fn close_perp_position_with_profit() {
             denom: atom_info.denom.clone(),
             order_size: perp_size,
             reduce_only: None,
+            margin: None,
+            acceptable_price: None,
         }],
         &[],
     )
@@ -366,6 +376,8 @@ fn close_perp_position_with_profit() {
             denom: atom_info.denom.clone(),
             order_size: Int128::zero() - perp_size,
             reduce_only: Some(true),
+            margin: None,
+            acceptable_price: None,
         }],
         &[],
     )
@@ -422,6 +434,8 @@ fn increase_position_with_realized_pnl() {
             denom: atom_info.denom.clone(),
             order_size: perp_size,
             reduce_only: None,
+            margin: None,
+            acceptable_price: None,
         }],
         &[],
     )
@@ -465,6 +479,8 @@ fn increase_position_with_realized_pnl() {
             denom: atom_info.denom.clone(),
             order_size: delta_change,
             reduce_only: None,
+            margin: None,
+            acceptable_price: None,
         }],
         &[],
     )
@@ -523,6 +539,8 @@ fn increase_position_with_realized_pnl() {
             denom: atom_info.denom.clone(),
             order_size: delta_change,
             reduce_only: None,
+            margin: None,
+            acceptable_price: None,
         }],
         &[],
     )
@@ -576,6 +594,8 @@ fn decrease_position_with_realized_pnl() {
             denom: atom_info.denom.clone(),
             order_size: perp_size,
             reduce_only: None,
+            margin: None,
+            acceptable_price: None,
         }],
         &[],
     )
@@ -618,6 +638,8 @@ fn decrease_position_with_realized_pnl() {
             denom: atom_info.denom.clone(),
             order_size: delta_change,
             reduce_only: None,
+            margin: None,
+            acceptable_price: None,
         }],
         &[],
     )
@@ -676,6 +698,8 @@ fn decrease_position_with_realized_pnl() {
             denom: atom_info.denom,
             order_size: delta_change,
             reduce_only: None,
+            margin: None,
+            acceptable_price: None,
         }],
         &[],
     )
@@ -730,6 +754,8 @@ fn flip_position_with_realized_pnl() {
             denom: atom_info.denom.clone(),
             order_size: perp_size,
             reduce_only: None,
+            margin: None,
+            acceptable_price: None,
         }],
         &[],
     )
@@ -774,6 +800,8 @@ fn flip_position_with_realized_pnl() {
             denom: atom_info.denom.clone(),
             order_size: delta_change,
             reduce_only: None,
+            margin: None,
+            acceptable_price: None,
         }],
         &[],
     )
@@ -832,6 +860,8 @@ fn flip_position_with_realized_pnl() {
             denom: atom_info.denom.clone(),
             order_size: delta_change,
             reduce_only: None,
+            margin: None,
+            acceptable_price: None,
         }],
         &[],
     )
@@ -889,6 +919,8 @@ fn cannot_open_perp_above_max_ltv() {
             denom: atom_info.denom.clone(),
             order_size: perp_size,
             reduce_only: None,
+            margin: None,
+            acceptable_price: None,
         }],
         &[],
     );
@@ -901,6 +933,66 @@ fn cannot_open_perp_above_max_ltv() {
     );
 }
 
+#[test]
+fn opening_fee_is_paid_by_swapping_collateral_with_swap_policy() {
+    let osmo_info = uosmo_info();
+    let atom_info = uatom_info();
+    let usdc_info = coin_info("uusdc");
+
+    let osmo_coin_deposited = osmo_info.to_coin(1000000);
+    let usdc_coin_deposited = usdc_info.to_coin(0);
+
+    let cm_user = Addr::unchecked("user");
+
+    let (mut mock, account_id) = setup(
+        &osmo_info,
+        &atom_info,
+        &usdc_info,
+        &osmo_coin_deposited,
+        &usdc_coin_deposited,
+        &cm_user,
+    );
+    mock.update_account_settings(
+        &cm_user,
+        &account_id,
+        AccountSettings {
+            perp_settlement: PerpSettlementPolicy::SwapFrom(vec![osmo_info.denom.clone()]),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // The mock swapper returns as much uusdc as the uosmo sent
+    let swapper = Addr::unchecked(mock.query_config().swapper);
+    mock.fund_addr(&swapper, vec![usdc_info.to_coin(10000)]);
+
+    let perp_size = Int128::from_str("1200").unwrap();
+    let opening_fee = mock.query_perp_opening_fee(&atom_info.denom, perp_size);
+    assert_eq!(opening_fee.fee.amount.u128(), 49);
+
+    mock.update_credit_account(
+        &account_id,
+        &cm_user,
+        vec![ExecutePerpOrder {
+            denom: atom_info.denom.clone(),
+            order_size: perp_size,
+            reduce_only: None,
+            margin: None,
+            acceptable_price: None,
+        }],
+        &[],
+    )
+    .unwrap();
+
+    // Both denoms have the same price, with the default max slippage of 99% the minimum received
+    // is 1% of the uosmo swapped: 4900 uosmo are swapped to cover the fee of 49 uusdc
+    let position = mock.query_positions(&account_id);
+    assert!(position.debts.is_empty());
+    assert_present(&position, &osmo_info.denom, Uint128::new(1000000 - 4900));
+    assert_present(&position, &usdc_info.denom, Uint128::new(4900 - 49));
+    assert_eq!(position.perps.len(), 1);
+}
+
 #[test]
 fn health_check_works_if_no_spot_base_denom() {
     let osmo_info = uosmo_info();
@@ -931,6 +1023,8 @@ fn health_check_works_if_no_spot_base_denom() {
             denom: atom_info.denom.clone(),
             order_size: perp_size,
             reduce_only: None,
+            margin: None,
+            acceptable_price: None,
         }],
         &[],
     )
@@ -967,6 +1061,8 @@ fn health_check_works_if_no_spot_base_denom() {
             denom: atom_info.denom,
             order_size: Int128::zero().checked_sub(perp_size).unwrap(),
             reduce_only: Some(true),
+            margin: None,
+            acceptable_price: None,
         }],
         &[],
     )
@@ -977,6 +1073,70 @@ fn health_check_works_if_no_spot_base_denom() {
     assert!(position.perps.is_empty());
 }
 
+#[test]
+fn perp_order_fails_if_execution_price_worse_than_acceptable() {
+    let osmo_info = uosmo_info();
+    let atom_info = uatom_info();
+    let usdc_info = coin_info("uusdc");
+
+    let osmo_coin_deposited = osmo_info.to_coin(1000000);
+    let usdc_coin_deposited = usdc_info.to_coin(100);
+
+    let cm_user = Addr::unchecked("user");
+
+    let (mut mock, account_id) = setup(
+        &osmo_info,
+        &atom_info,
+        &usdc_info,
+        &osmo_coin_deposited,
+        &usdc_coin_deposited,
+        &cm_user,
+    );
+
+    // Buying 1200 with no skew and skew scale of 1000000:
+    // exec price = 1 * (1 + (0 + 0.0012) / 2) = 1.0006
+    let perp_size = Int128::from_str("1200").unwrap();
+    let res = mock.update_credit_account(
+        &account_id,
+        &cm_user,
+        vec![ExecutePerpOrder {
+            denom: atom_info.denom.clone(),
+            order_size: perp_size,
+            reduce_only: None,
+            margin: None,
+            acceptable_price: Some(Decimal::from_str("1.0005").unwrap()),
+        }],
+        &[],
+    );
+    let err: PerpsContractError = res.unwrap_err().downcast().unwrap();
+    assert_eq!(
+        err,
+        PerpsContractError::UnacceptableExecutionPrice {
+            exec_price: Decimal::from_str("1.0006").unwrap(),
+            acceptable_price: Decimal::from_str("1.0005").unwrap(),
+        }
+    );
+    assert!(mock.query_positions(&account_id).perps.is_empty());
+
+    mock.update_credit_account(
+        &account_id,
+        &cm_user,
+        vec![ExecutePerpOrder {
+            denom: atom_info.denom.clone(),
+            order_size: perp_size,
+            reduce_only: None,
+            margin: None,
+            acceptable_price: Some(Decimal::from_str("1.0006").unwrap()),
+        }],
+        &[],
+    )
+    .unwrap();
+
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.perps.len(), 1);
+    assert_eq!(position.perps[0].size, perp_size);
+}
+
 fn setup(
     osmo_info: &CoinInfo,
     atom_info: &CoinInfo,
@@ -11,7 +11,7 @@ use mars_types::{
         Positions,
     },
     oracle::ActionKind,
-    params::{PerpParams, PerpParamsUpdate},
+    params::{FundingModel, PerpParams, PerpParamsUpdate},
     perps::PnL,
 };
 use test_case::test_case;
@@ -235,7 +235,9 @@ fn deleverage(
     // setup perp params
     // tia perp
     let tia_perp_params = PerpParams {
-        max_funding_velocity: Decimal::from_str("36").unwrap(),
+        funding_model: FundingModel::Velocity {
+            max_funding_velocity: Decimal::from_str("36").unwrap(),
+        },
         skew_scale: Uint128::new(4504227000000u128),
         ..default_perp_params(&tia_info.denom)
     };
@@ -244,7 +246,9 @@ fn deleverage(
     });
     // atom perp
     let mut atom_perp_params = PerpParams {
-        max_funding_velocity: Decimal::from_str("36").unwrap(),
+        funding_model: FundingModel::Velocity {
+            max_funding_velocity: Decimal::from_str("36").unwrap(),
+        },
         skew_scale: Uint128::new(7227323000000u128),
         ..default_perp_params(&atom_info.denom)
     };
@@ -450,6 +454,8 @@ fn open_perp(mock: &mut MockEnv, user: &Addr, acc_id: &str, denom: &str, size: I
             denom: denom.to_string(),
             order_size: size,
             reduce_only: None,
+            margin: None,
+            acceptable_price: None,
         }],
         &[],
     )
@@ -0,0 +1,501 @@
+use std::str::FromStr;
+
+use anyhow::Result as AnyResult;
+use cosmwasm_std::{Addr, Decimal, Int128, Uint128};
+use cw_multi_test::AppResponse;
+use mars_mock_oracle::msg::CoinPrice;
+use mars_owner::OwnerError;
+use mars_perps::error::ContractError as PerpsContractError;
+use mars_types::{
+    credit_manager::Action::{Deposit, ExecutePerpOrder},
+    oracle::ActionKind,
+    params::{PerpParams, PerpParamsUpdate},
+    perps::{self, MarketSettlement, MarketStateResponse},
+};
+
+use super::helpers::{coin_info, uatom_info, uosmo_info, AccountToFund, MockEnv};
+use crate::tests::helpers::{default_perp_params, get_coin};
+
+#[test]
+fn only_owner_can_delist_market() {
+    let mut mock = MockEnv::new().build().unwrap();
+
+    let res = mock.delist_perp_market(&Addr::unchecked("random-user"), "uatom");
+    let err: PerpsContractError = res.unwrap_err().downcast().unwrap();
+    assert_eq!(err, PerpsContractError::Owner(OwnerError::NotOwner {}));
+}
+
+#[test]
+fn cannot_settle_listed_market() {
+    let atom_info = uatom_info();
+    let mut mock = MockEnv::new().set_params(&[atom_info.clone()]).build().unwrap();
+    mock.update_perp_params(PerpParamsUpdate::AddOrUpdate {
+        params: default_perp_params(&atom_info.denom),
+    });
+
+    let res = mock.settle_perp_market(&Addr::unchecked("keeper"), &atom_info.denom, None);
+    let err: PerpsContractError = res.unwrap_err().downcast().unwrap();
+    assert_eq!(
+        err,
+        PerpsContractError::MarketNotDelisted {
+            denom: atom_info.denom
+        }
+    );
+}
+
+#[test]
+fn delisted_market_settled_at_snapshot_prices() {
+    let cm_user_1 = Addr::unchecked("user_1");
+    let cm_user_2 = Addr::unchecked("user_2");
+    let vault_depositor = Addr::unchecked("vault_depositor");
+    let keeper = Addr::unchecked("keeper");
+    let contract_owner = Addr::unchecked("owner");
+
+    let mut osmo_info = uosmo_info();
+    osmo_info.price = Decimal::from_atomics(5u128, 1).unwrap();
+    let mut atom_info = uatom_info();
+    atom_info.price = Decimal::from_atomics(10u128, 0).unwrap();
+    let mut usdc_info = coin_info("uusdc");
+    usdc_info.price = Decimal::one();
+    let osmo_cm_deposit = osmo_info.to_coin(10_000_000_000);
+    let usdc_cm_deposit = usdc_info.to_coin(10_000_000_000);
+    let usdc_vault_deposit = usdc_info.to_coin(10_000_000_000);
+
+    let mut mock = MockEnv::new()
+        .owner(contract_owner.as_str())
+        .set_params(&[osmo_info.clone(), atom_info.clone(), usdc_info.clone()])
+        .fund_accounts(
+            vec![cm_user_1.clone(), cm_user_2.clone()],
+            vec![osmo_cm_deposit.clone(), usdc_cm_deposit.clone()],
+        )
+        .fund_account(AccountToFund {
+            addr: vault_depositor.clone(),
+            funds: vec![usdc_vault_deposit.clone()],
+        })
+        .build()
+        .unwrap();
+
+    let atom_perp_params = PerpParams {
+        skew_scale: Uint128::new(1_000_000_000),
+        ..default_perp_params(&atom_info.denom)
+    };
+    mock.update_perp_params(PerpParamsUpdate::AddOrUpdate {
+        params: atom_perp_params.clone(),
+    });
+
+    // fund the perps vault to cover pnl profit
+    let vault_depositor_acc = mock.create_credit_account(&vault_depositor).unwrap();
+    mock.update_credit_account(
+        &vault_depositor_acc,
+        &vault_depositor,
+        vec![Deposit(usdc_vault_deposit.clone())],
+        &[usdc_vault_deposit.clone()],
+    )
+    .unwrap();
+    mock.deposit_to_perp_vault(&vault_depositor_acc, &usdc_vault_deposit, None).unwrap();
+
+    let acc_1 = mock.create_credit_account(&cm_user_1).unwrap();
+    let acc_2 = mock.create_credit_account(&cm_user_2).unwrap();
+    for (user, acc) in [(&cm_user_1, &acc_1), (&cm_user_2, &acc_2)] {
+        mock.update_credit_account(
+            acc,
+            user,
+            vec![Deposit(osmo_cm_deposit.clone()), Deposit(usdc_cm_deposit.clone())],
+            &[osmo_cm_deposit.clone(), usdc_cm_deposit.clone()],
+        )
+        .unwrap();
+    }
+
+    // acc_1 long (profit), acc_2 short (loss)
+    open_perp(&mut mock, &cm_user_1, &acc_1, &atom_info.denom, Int128::new(2_000_000)).unwrap();
+    open_perp(&mut mock, &cm_user_2, &acc_2, &atom_info.denom, Int128::new(-1_000_000)).unwrap();
+
+    mock.increment_by_time(86400);
+    change_price(&mut mock, &atom_info.denom, Decimal::from_str("12").unwrap());
+
+    mock.delist_perp_market(&contract_owner, &atom_info.denom).unwrap();
+    let delisted_at = mock.query_block_time();
+    assert_eq!(
+        mock.query_perp_market_settlement(&atom_info.denom).unwrap(),
+        MarketSettlement {
+            denom_price: Decimal::from_str("12").unwrap(),
+            base_denom_price: Decimal::one(),
+            delisted_at,
+        }
+    );
+
+    // the market can't be traded anymore
+    let res = open_perp(&mut mock, &cm_user_1, &acc_1, &atom_info.denom, Int128::new(1_000));
+    let err: PerpsContractError = res.unwrap_err().downcast().unwrap();
+    assert_eq!(
+        err,
+        PerpsContractError::MarketDelisted {
+            denom: atom_info.denom.clone()
+        }
+    );
+
+    let pnl_1 = unrealized_pnl(&mock, &acc_1);
+    let pnl_2 = unrealized_pnl(&mock, &acc_2);
+    assert!(pnl_1 > Int128::zero());
+    assert!(pnl_2 < Int128::zero());
+
+    // neither price moves nor funding change the settlement
+    mock.increment_by_time(86400);
+    change_price(&mut mock, &atom_info.denom, Decimal::from_str("20").unwrap());
+    assert_eq!(unrealized_pnl(&mock, &acc_1), pnl_1);
+    assert_eq!(unrealized_pnl(&mock, &acc_2), pnl_2);
+
+    let vault_usdc_balance_before = mock.query_balance(mock.perps.address(), &usdc_info.denom);
+    let usdc_deposit_1 = usdc_deposit(&mock, &acc_1, &usdc_info.denom);
+    let usdc_deposit_2 = usdc_deposit(&mock, &acc_2, &usdc_info.denom);
+
+    // settle one position per batch, at the skew left by the previous batches
+    let mut settled_pnl = Int128::zero();
+    for _ in 0..2 {
+        let remaining =
+            [(&acc_1, unrealized_pnl(&mock, &acc_1)), (&acc_2, unrealized_pnl(&mock, &acc_2))]
+                .into_iter()
+                .filter(|(acc, _)| !mock.query_positions(acc).perps.is_empty())
+                .collect::<Vec<_>>();
+
+        mock.settle_perp_market(&keeper, &atom_info.denom, Some(1)).unwrap();
+
+        let settled = remaining
+            .into_iter()
+            .filter(|(acc, _)| mock.query_positions(acc).perps.is_empty())
+            .collect::<Vec<_>>();
+        assert_eq!(settled.len(), 1);
+        settled_pnl += settled[0].1;
+    }
+
+    let pnl_paid_1 = Int128::try_from(usdc_deposit(&mock, &acc_1, &usdc_info.denom)).unwrap()
+        - Int128::try_from(usdc_deposit_1).unwrap();
+    let pnl_paid_2 = Int128::try_from(usdc_deposit(&mock, &acc_2, &usdc_info.denom)).unwrap()
+        - Int128::try_from(usdc_deposit_2).unwrap();
+    assert_eq!(pnl_paid_1 + pnl_paid_2, settled_pnl);
+    assert!(pnl_paid_1 > Int128::zero());
+    assert!(pnl_paid_2 < Int128::zero());
+
+    // the vault pays the profits and receives the losses
+    let vault_usdc_balance = mock.query_balance(mock.perps.address(), &usdc_info.denom);
+    assert_eq!(
+        Int128::try_from(vault_usdc_balance.amount).unwrap()
+            - Int128::try_from(vault_usdc_balance_before.amount).unwrap(),
+        Int128::zero() - settled_pnl
+    );
+
+    // the market is removed once no position is left
+    assert!(mock.query_perp_market_settlement(&atom_info.denom).is_some());
+    mock.settle_perp_market(&keeper, &atom_info.denom, None).unwrap();
+    assert!(mock.query_perp_market_settlement(&atom_info.denom).is_none());
+    let res: Result<MarketStateResponse, _> = mock.app.wrap().query_wasm_smart(
+        mock.perps.address(),
+        &perps::QueryMsg::MarketState {
+            denom: atom_info.denom.clone(),
+        },
+    );
+    assert!(res.is_err());
+
+    // the market can be listed again
+    mock.update_perp_params(PerpParamsUpdate::AddOrUpdate {
+        params: atom_perp_params,
+    });
+    open_perp(&mut mock, &cm_user_1, &acc_1, &atom_info.denom, Int128::new(1_000)).unwrap();
+}
+
+#[test]
+fn settlement_leaves_positions_of_other_markets_open() {
+    let cm_user = Addr::unchecked("user");
+    let vault_depositor = Addr::unchecked("vault_depositor");
+    let keeper = Addr::unchecked("keeper");
+    let contract_owner = Addr::unchecked("owner");
+
+    let mut osmo_info = uosmo_info();
+    osmo_info.price = Decimal::from_atomics(5u128, 1).unwrap();
+    let mut atom_info = uatom_info();
+    atom_info.price = Decimal::from_atomics(10u128, 0).unwrap();
+    let mut usdc_info = coin_info("uusdc");
+    usdc_info.price = Decimal::one();
+    let usdc_deposit = usdc_info.to_coin(10_000_000_000);
+
+    let mut mock = MockEnv::new()
+        .owner(contract_owner.as_str())
+        .set_params(&[osmo_info.clone(), atom_info.clone(), usdc_info.clone()])
+        .fund_accounts(vec![cm_user.clone(), vault_depositor.clone()], vec![usdc_deposit.clone()])
+        .build()
+        .unwrap();
+
+    for denom in [&atom_info.denom, &osmo_info.denom] {
+        mock.update_perp_params(PerpParamsUpdate::AddOrUpdate {
+            params: default_perp_params(denom),
+        });
+    }
+
+    let vault_depositor_acc = mock.create_credit_account(&vault_depositor).unwrap();
+    mock.update_credit_account(
+        &vault_depositor_acc,
+        &vault_depositor,
+        vec![Deposit(usdc_deposit.clone())],
+        &[usdc_deposit.clone()],
+    )
+    .unwrap();
+    mock.deposit_to_perp_vault(&vault_depositor_acc, &usdc_deposit, None).unwrap();
+
+    let acc = mock.create_credit_account(&cm_user).unwrap();
+    mock.update_credit_account(
+        &acc,
+        &cm_user,
+        vec![Deposit(usdc_deposit.clone())],
+        &[usdc_deposit.clone()],
+    )
+    .unwrap();
+    open_perp(&mut mock, &cm_user, &acc, &atom_info.denom, Int128::new(1_000_000)).unwrap();
+    open_perp(&mut mock, &cm_user, &acc, &osmo_info.denom, Int128::new(1_000_000)).unwrap();
+
+    mock.delist_perp_market(&contract_owner, &atom_info.denom).unwrap();
+    mock.settle_perp_market(&keeper, &atom_info.denom, None).unwrap();
+    assert!(mock.query_perp_market_settlement(&atom_info.denom).is_none());
+
+    let perps = mock.query_positions(&acc).perps;
+    assert_eq!(perps.len(), 1);
+    assert_eq!(perps[0].denom, osmo_info.denom);
+}
+
+#[test]
+fn isolated_loss_exceeding_margin_is_written_off_on_settlement() {
+    let cm_user = Addr::unchecked("user");
+    let vault_depositor = Addr::unchecked("vault_depositor");
+    let keeper = Addr::unchecked("keeper");
+    let contract_owner = Addr::unchecked("owner");
+
+    let mut osmo_info = uosmo_info();
+    osmo_info.price = Decimal::from_atomics(5u128, 1).unwrap();
+    let mut atom_info = uatom_info();
+    atom_info.price = Decimal::from_atomics(10u128, 0).unwrap();
+    let mut usdc_info = coin_info("uusdc");
+    usdc_info.price = Decimal::one();
+    let usdc_deposit = usdc_info.to_coin(10_000_000_000);
+
+    let mut mock = MockEnv::new()
+        .owner(contract_owner.as_str())
+        .set_params(&[osmo_info.clone(), atom_info.clone(), usdc_info.clone()])
+        .fund_accounts(vec![cm_user.clone(), vault_depositor.clone()], vec![usdc_deposit.clone()])
+        .build()
+        .unwrap();
+
+    mock.update_perp_params(PerpParamsUpdate::AddOrUpdate {
+        params: PerpParams {
+            skew_scale: Uint128::new(1_000_000_000),
+            ..default_perp_params(&atom_info.denom)
+        },
+    });
+
+    let vault_depositor_acc = mock.create_credit_account(&vault_depositor).unwrap();
+    mock.update_credit_account(
+        &vault_depositor_acc,
+        &vault_depositor,
+        vec![Deposit(usdc_deposit.clone())],
+        &[usdc_deposit.clone()],
+    )
+    .unwrap();
+    mock.deposit_to_perp_vault(&vault_depositor_acc, &usdc_deposit, None).unwrap();
+
+    let acc = mock.create_credit_account(&cm_user).unwrap();
+    mock.update_credit_account(
+        &acc,
+        &cm_user,
+        vec![
+            Deposit(usdc_deposit.clone()),
+            ExecutePerpOrder {
+                denom: atom_info.denom.clone(),
+                order_size: Int128::new(-1_000_000),
+                reduce_only: None,
+                margin: Some(Uint128::new(5_000_000)),
+                acceptable_price: None,
+            },
+        ],
+        &[usdc_deposit.clone()],
+    )
+    .unwrap();
+    let margin = mock.query_positions(&acc).perps[0].isolated_margin.unwrap();
+
+    // the loss of the short position exceeds its margin
+    change_price(&mut mock, &atom_info.denom, Decimal::from_str("16").unwrap());
+    mock.delist_perp_market(&contract_owner, &atom_info.denom).unwrap();
+    let loss = Int128::zero() - unrealized_pnl(&mock, &acc);
+    assert!(loss > Int128::try_from(margin).unwrap());
+
+    let vault_usdc_balance_before = mock.query_balance(mock.perps.address(), &usdc_info.denom);
+    let res = mock.settle_perp_market(&keeper, &atom_info.denom, None).unwrap();
+    assert!(mock.query_perp_market_settlement(&atom_info.denom).is_none());
+
+    // only the margin is paid to the vault, the rest of the loss is written off
+    let written_off_pnl = attribute_values(&res, "written_off_pnl");
+    assert_eq!(written_off_pnl, vec![loss - Int128::try_from(margin).unwrap()]);
+    let vault_usdc_balance = mock.query_balance(mock.perps.address(), &usdc_info.denom);
+    assert_eq!(vault_usdc_balance.amount - vault_usdc_balance_before.amount, margin);
+
+    // the rest of the account is untouched and no debt is taken
+    let position = mock.query_positions(&acc);
+    assert!(position.perps.is_empty());
+    assert!(position.debts.is_empty());
+    assert_eq!(
+        get_coin(&usdc_info.denom, &position.deposits).amount,
+        usdc_deposit.amount - Uint128::new(5_000_000)
+    );
+}
+
+#[test]
+fn account_unable_to_pay_its_loss_does_not_block_settlement() {
+    let cm_user_1 = Addr::unchecked("user_1");
+    let cm_user_2 = Addr::unchecked("user_2");
+    let vault_depositor = Addr::unchecked("vault_depositor");
+    let keeper = Addr::unchecked("keeper");
+    let contract_owner = Addr::unchecked("owner");
+
+    let mut osmo_info = uosmo_info();
+    osmo_info.price = Decimal::from_atomics(5u128, 1).unwrap();
+    let mut atom_info = uatom_info();
+    atom_info.price = Decimal::from_atomics(10u128, 0).unwrap();
+    let mut usdc_info = coin_info("uusdc");
+    usdc_info.price = Decimal::one();
+    let osmo_cm_deposit = osmo_info.to_coin(10_000_000_000);
+    let usdc_cm_deposit = usdc_info.to_coin(10_000_000_000);
+
+    let mut mock = MockEnv::new()
+        .owner(contract_owner.as_str())
+        .set_params(&[osmo_info.clone(), atom_info.clone(), usdc_info.clone()])
+        .fund_accounts(
+            vec![cm_user_1.clone(), vault_depositor.clone()],
+            vec![usdc_cm_deposit.clone()],
+        )
+        .fund_account(AccountToFund {
+            addr: cm_user_2.clone(),
+            funds: vec![osmo_cm_deposit.clone()],
+        })
+        .build()
+        .unwrap();
+
+    mock.update_perp_params(PerpParamsUpdate::AddOrUpdate {
+        params: PerpParams {
+            skew_scale: Uint128::new(1_000_000_000),
+            ..default_perp_params(&atom_info.denom)
+        },
+    });
+
+    let vault_depositor_acc = mock.create_credit_account(&vault_depositor).unwrap();
+    mock.update_credit_account(
+        &vault_depositor_acc,
+        &vault_depositor,
+        vec![Deposit(usdc_cm_deposit.clone())],
+        &[usdc_cm_deposit.clone()],
+    )
+    .unwrap();
+    mock.deposit_to_perp_vault(&vault_depositor_acc, &usdc_cm_deposit, None).unwrap();
+
+    // acc_1 long (profit), acc_2 short (loss) without any usdc: its loss has to be borrowed
+    let acc_1 = mock.create_credit_account(&cm_user_1).unwrap();
+    mock.update_credit_account(
+        &acc_1,
+        &cm_user_1,
+        vec![Deposit(usdc_cm_deposit.clone())],
+        &[usdc_cm_deposit.clone()],
+    )
+    .unwrap();
+    let acc_2 = mock.create_credit_account(&cm_user_2).unwrap();
+    mock.update_credit_account(
+        &acc_2,
+        &cm_user_2,
+        vec![Deposit(osmo_cm_deposit.clone())],
+        &[osmo_cm_deposit.clone()],
+    )
+    .unwrap();
+    open_perp(&mut mock, &cm_user_1, &acc_1, &atom_info.denom, Int128::new(2_000_000)).unwrap();
+    open_perp(&mut mock, &cm_user_2, &acc_2, &atom_info.denom, Int128::new(-1_000_000)).unwrap();
+
+    // the loss of acc_2 exceeds the liquidity of the red bank, it can't be borrowed
+    change_price(&mut mock, &atom_info.denom, Decimal::from_str("12").unwrap());
+    mock.delist_perp_market(&contract_owner, &atom_info.denom).unwrap();
+    let pnl_1 = unrealized_pnl(&mock, &acc_1);
+    assert!(pnl_1 > Int128::zero());
+    assert!(unrealized_pnl(&mock, &acc_2) < Int128::zero());
+
+    let debts_2 = mock.query_positions(&acc_2).debts;
+    let vault_usdc_balance_before = mock.query_balance(mock.perps.address(), &usdc_info.denom);
+    let usdc_deposit_1 = usdc_deposit(&mock, &acc_1, &usdc_info.denom);
+
+    let res = mock.settle_perp_market(&keeper, &atom_info.denom, None).unwrap();
+    assert!(mock.query_perp_market_settlement(&atom_info.denom).is_none());
+
+    // the loss of acc_2 is written off, acc_1 is paid its profit
+    let written_off_pnl = attribute_values(&res, "written_off_pnl");
+    assert_eq!(written_off_pnl.len(), 2);
+    assert!(written_off_pnl.contains(&Int128::zero()));
+    assert!(written_off_pnl.iter().any(|pnl| *pnl > Int128::zero()));
+
+    let position_2 = mock.query_positions(&acc_2);
+    assert!(position_2.perps.is_empty());
+    assert_eq!(position_2.debts, debts_2);
+    assert_eq!(get_coin(&osmo_info.denom, &position_2.deposits), osmo_cm_deposit);
+
+    let pnl_paid_1 = usdc_deposit(&mock, &acc_1, &usdc_info.denom) - usdc_deposit_1;
+    assert_eq!(Int128::try_from(pnl_paid_1).unwrap(), pnl_1);
+    let vault_usdc_balance = mock.query_balance(mock.perps.address(), &usdc_info.denom);
+    assert_eq!(vault_usdc_balance_before.amount - vault_usdc_balance.amount, pnl_paid_1);
+}
+
+fn open_perp(
+    mock: &mut MockEnv,
+    user: &Addr,
+    acc_id: &str,
+    denom: &str,
+    size: Int128,
+) -> AnyResult<AppResponse> {
+    mock.update_credit_account(
+        acc_id,
+        user,
+        vec![ExecutePerpOrder {
+            denom: denom.to_string(),
+            order_size: size,
+            reduce_only: None,
+            margin: None,
+            acceptable_price: None,
+        }],
+        &[],
+    )
+}
+
+fn change_price(mock: &mut MockEnv, denom: &str, price: Decimal) {
+    mock.price_change(CoinPrice {
+        pricing: ActionKind::Default,
+        denom: denom.to_string(),
+        price,
+    });
+    mock.price_change(CoinPrice {
+        pricing: ActionKind::Liquidation,
+        denom: denom.to_string(),
+        price,
+    });
+}
+
+/// Unrealized PnL of the account's only perp position, zero if it has none
+fn unrealized_pnl(mock: &MockEnv, acc_id: &str) -> Int128 {
+    let positions = mock.query_positions(acc_id);
+    let Some(position) = positions.perps.first() else {
+        return Int128::zero();
+    };
+    position.unrealized_pnl.to_coins(&position.base_denom).pnl.to_signed_uint().unwrap()
+}
+
+fn usdc_deposit(mock: &MockEnv, acc_id: &str, denom: &str) -> Uint128 {
+    get_coin(denom, &mock.query_positions(acc_id).deposits).amount
+}
+
+fn attribute_values(res: &AppResponse, key: &str) -> Vec<Int128> {
+    res.events
+        .iter()
+        .flat_map(|event| event.attributes.iter())
+        .filter(|attr| attr.key == key)
+        .map(|attr| Int128::from_str(&attr.value).unwrap())
+        .collect()
+}
@@ -0,0 +1,281 @@
+use cosmwasm_std::{Addr, Uint128};
+use mars_credit_manager::error::ContractError;
+use mars_swapper_mock::contract::MOCK_SWAP_RESULT;
+use mars_testing::multitest::helpers::AccountToFund;
+use mars_types::credit_manager::Action::{CreateRecurringOrder, DeleteRecurringOrder, Deposit};
+
+use super::helpers::{assert_err, coin_info, uatom_info, uosmo_info, MockEnv};
+
+const DAY: u64 = 86400;
+
+#[test]
+fn recurring_order_is_executed_every_interval() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+    let usdc_info = coin_info("uusdc");
+    let user = Addr::unchecked("user");
+    let keeper = Addr::unchecked("keeper");
+    let keeper_fee = usdc_info.to_coin(1000000);
+    let keeper_fees = usdc_info.to_coin(3000000);
+
+    let mut mock = MockEnv::new()
+        .set_params(&[atom_info.clone(), osmo_info.clone(), usdc_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![atom_info.to_coin(1000), keeper_fees.clone()],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(atom_info.to_coin(1000)),
+            Deposit(keeper_fees.clone()),
+            CreateRecurringOrder {
+                coin_in: atom_info.to_coin(100),
+                denom_out: osmo_info.denom.clone(),
+                interval: DAY,
+                periods: 3,
+                lend_output: false,
+                keeper_fee: keeper_fee.clone(),
+            },
+        ],
+        &[atom_info.to_coin(1000), keeper_fees],
+    )
+    .unwrap();
+
+    // Keeper fees of all periods are held by the order
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.deposits, vec![atom_info.to_coin(1000)]);
+
+    let orders = mock.query_recurring_orders_for_account(account_id.clone(), None, None).data;
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].order.periods_remaining, 3);
+
+    // First period is due right away
+    mock.execute_recurring_order(&keeper, &account_id, "1").unwrap();
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.deposits.len(), 2);
+    assert_eq!(position.deposits[0].amount, Uint128::new(900));
+    assert_eq!(position.deposits[1].denom, osmo_info.denom);
+    assert_eq!(position.deposits[1].amount, MOCK_SWAP_RESULT);
+
+    // Next period is only due after the interval
+    let next_execution = mock.query_block_time() + DAY;
+    let res = mock.execute_recurring_order(&keeper, &account_id, "1");
+    assert_err(
+        res,
+        ContractError::RecurringOrderNotDue {
+            order_id: "1".to_string(),
+            next_execution,
+        },
+    );
+
+    mock.increment_by_time(DAY);
+    mock.execute_recurring_order(&keeper, &account_id, "1").unwrap();
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.deposits[0].amount, Uint128::new(800));
+    assert_eq!(position.deposits[1].amount, MOCK_SWAP_RESULT * Uint128::new(2));
+
+    let orders = mock.query_recurring_orders_for_account(account_id.clone(), None, None).data;
+    assert_eq!(orders[0].order.periods_remaining, 1);
+
+    let keeper_balance = mock.query_balance(&keeper, &usdc_info.denom);
+    assert_eq!(keeper_balance.amount, Uint128::new(2000000));
+}
+
+#[test]
+fn deleting_recurring_order_refunds_remaining_keeper_fees() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+    let usdc_info = coin_info("uusdc");
+    let user = Addr::unchecked("user");
+    let keeper = Addr::unchecked("keeper");
+    let keeper_fee = usdc_info.to_coin(1000000);
+    let keeper_fees = usdc_info.to_coin(2000000);
+
+    let mut mock = MockEnv::new()
+        .set_params(&[atom_info.clone(), osmo_info.clone(), usdc_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![atom_info.to_coin(1000), keeper_fees.clone()],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(atom_info.to_coin(1000)),
+            Deposit(keeper_fees.clone()),
+            CreateRecurringOrder {
+                coin_in: atom_info.to_coin(100),
+                denom_out: osmo_info.denom.clone(),
+                interval: DAY,
+                periods: 2,
+                lend_output: false,
+                keeper_fee: keeper_fee.clone(),
+            },
+        ],
+        &[atom_info.to_coin(1000), keeper_fees],
+    )
+    .unwrap();
+
+    mock.execute_recurring_order(&keeper, &account_id, "1").unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![DeleteRecurringOrder {
+            order_id: "1".to_string(),
+        }],
+        &[],
+    )
+    .unwrap();
+
+    let position = mock.query_positions(&account_id);
+    assert!(position.deposits.contains(&keeper_fee));
+    assert!(mock
+        .query_recurring_orders_for_account(account_id.clone(), None, None)
+        .data
+        .is_empty());
+
+    let res = mock.execute_recurring_order(&keeper, &account_id, "1");
+    assert_err(
+        res,
+        ContractError::RecurringOrderNotFound {
+            order_id: "1".to_string(),
+            account_id,
+        },
+    );
+}
+
+#[test]
+fn only_swap_output_is_lent_and_schedule_does_not_drift() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+    let usdc_info = coin_info("uusdc");
+    let user = Addr::unchecked("user");
+    let keeper = Addr::unchecked("keeper");
+    let keeper_fee = usdc_info.to_coin(1000000);
+    let keeper_fees = usdc_info.to_coin(3000000);
+
+    let mut mock = MockEnv::new()
+        .set_params(&[atom_info.clone(), osmo_info.clone(), usdc_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![atom_info.to_coin(1000), osmo_info.to_coin(500), keeper_fees.clone()],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(atom_info.to_coin(1000)),
+            Deposit(osmo_info.to_coin(500)),
+            Deposit(keeper_fees.clone()),
+            CreateRecurringOrder {
+                coin_in: atom_info.to_coin(100),
+                denom_out: osmo_info.denom.clone(),
+                interval: DAY,
+                periods: 3,
+                lend_output: true,
+                keeper_fee,
+            },
+        ],
+        &[atom_info.to_coin(1000), osmo_info.to_coin(500), keeper_fees],
+    )
+    .unwrap();
+    let created_at = mock.query_block_time();
+
+    // The osmo held before the swap isn't lent
+    mock.execute_recurring_order(&keeper, &account_id, "1").unwrap();
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.deposits, vec![atom_info.to_coin(900), osmo_info.to_coin(500)]);
+    assert_eq!(position.lends, vec![osmo_info.to_coin(MOCK_SWAP_RESULT.u128())]);
+
+    // A late execution doesn't delay the next ones
+    mock.increment_by_time(DAY + 3600);
+    mock.execute_recurring_order(&keeper, &account_id, "1").unwrap();
+
+    let orders = mock.query_recurring_orders_for_account(account_id.clone(), None, None).data;
+    assert_eq!(orders[0].order.next_execution, created_at + 2 * DAY);
+
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.deposits, vec![atom_info.to_coin(800), osmo_info.to_coin(500)]);
+    assert_eq!(
+        position.lends,
+        vec![osmo_info.to_coin((MOCK_SWAP_RESULT * Uint128::new(2)).u128())]
+    );
+}
+
+#[test]
+fn missed_periods_are_skipped() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+    let usdc_info = coin_info("uusdc");
+    let user = Addr::unchecked("user");
+    let keeper = Addr::unchecked("keeper");
+    let keeper_fee = usdc_info.to_coin(1000000);
+    let keeper_fees = usdc_info.to_coin(3000000);
+
+    let mut mock = MockEnv::new()
+        .set_params(&[atom_info.clone(), osmo_info.clone(), usdc_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![atom_info.to_coin(1000), keeper_fees.clone()],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(atom_info.to_coin(1000)),
+            Deposit(keeper_fees.clone()),
+            CreateRecurringOrder {
+                coin_in: atom_info.to_coin(100),
+                denom_out: osmo_info.denom.clone(),
+                interval: DAY,
+                periods: 3,
+                lend_output: false,
+                keeper_fee,
+            },
+        ],
+        &[atom_info.to_coin(1000), keeper_fees],
+    )
+    .unwrap();
+    let created_at = mock.query_block_time();
+    mock.execute_recurring_order(&keeper, &account_id, "1").unwrap();
+
+    // Keeper is offline for the second and third periods
+    mock.increment_by_time(3 * DAY + 3600);
+    mock.execute_recurring_order(&keeper, &account_id, "1").unwrap();
+
+    // Missed periods can't be caught up, the next one is due on schedule
+    let next_execution = created_at + 4 * DAY;
+    let res = mock.execute_recurring_order(&keeper, &account_id, "1");
+    assert_err(
+        res,
+        ContractError::RecurringOrderNotDue {
+            order_id: "1".to_string(),
+            next_execution,
+        },
+    );
+
+    let orders = mock.query_recurring_orders_for_account(account_id.clone(), None, None).data;
+    assert_eq!(orders[0].order.periods_remaining, 1);
+    assert_eq!(orders[0].order.next_execution, next_execution);
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.deposits[0].amount, Uint128::new(800));
+}
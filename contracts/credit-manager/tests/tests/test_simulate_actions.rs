@@ -0,0 +1,162 @@
+use std::str::FromStr;
+
+use cosmwasm_std::{coins, Addr, Int128, Uint128};
+use mars_credit_manager::error::ContractError;
+use mars_swapper_mock::contract::MOCK_SWAP_RESULT;
+use mars_types::{
+    credit_manager::Action::{
+        Borrow, ClaimRewards, Deposit, ExecutePerpOrder, Lend, SwapExactIn, Withdraw,
+        WithdrawToWallet,
+    },
+    oracle::ActionKind,
+    params::PerpParamsUpdate,
+};
+
+use super::helpers::{
+    coin_info, default_perp_params, uatom_info, uosmo_info, AccountToFund, MockEnv,
+};
+
+#[test]
+fn simulation_matches_execution() {
+    let osmo_info = uosmo_info();
+    let atom_info = uatom_info();
+    let user = Addr::unchecked("user");
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone(), atom_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: coins(300, osmo_info.denom.clone()),
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    let actions = vec![
+        Deposit(osmo_info.to_coin(300)),
+        Borrow(atom_info.to_coin(50)),
+        Lend(osmo_info.to_action_coin(100)),
+        WithdrawToWallet {
+            coin: atom_info.to_action_coin(20),
+            recipient: user.to_string(),
+        },
+    ];
+
+    let simulation = mock.simulate_actions(&account_id, actions.clone()).unwrap();
+    assert_eq!(simulation.coins_out, vec![atom_info.to_coin(20)]);
+
+    // Nothing has been executed
+    let position = mock.query_positions(&account_id);
+    assert!(position.deposits.is_empty());
+    assert!(position.debts.is_empty());
+
+    mock.update_credit_account(&account_id, &user, actions, &[osmo_info.to_coin(300)]).unwrap();
+
+    let position = mock.query_positions(&account_id);
+    assert_eq!(simulation.positions.deposits, position.deposits);
+    assert_eq!(simulation.positions.debts, position.debts);
+    assert_eq!(simulation.positions.lends, position.lends);
+    assert_eq!(simulation.health, mock.query_health(&account_id, ActionKind::Default));
+}
+
+#[test]
+fn account_balance_amounts_use_simulated_balances() {
+    let osmo_info = uosmo_info();
+    let atom_info = uatom_info();
+    let user = Addr::unchecked("user");
+    let mut mock =
+        MockEnv::new().set_params(&[osmo_info.clone(), atom_info.clone()]).build().unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    let simulation = mock
+        .simulate_actions(
+            &account_id,
+            vec![
+                Deposit(atom_info.to_coin(10_000)),
+                SwapExactIn {
+                    coin_in: atom_info.to_action_coin_full_balance(),
+                    denom_out: osmo_info.denom.clone(),
+                    min_receive: Uint128::zero(),
+                    route: None,
+                },
+                Withdraw(osmo_info.to_action_coin(37)),
+            ],
+        )
+        .unwrap();
+
+    // The swap is estimated by the swapper
+    assert_eq!(
+        simulation.positions.deposits,
+        vec![osmo_info.to_coin(MOCK_SWAP_RESULT.u128() - 37)]
+    );
+    assert_eq!(simulation.coins_out, vec![osmo_info.to_coin(37)]);
+
+    // Withdrawing more than the simulated balance fails like the execution would
+    let res = mock.simulate_actions(
+        &account_id,
+        vec![Deposit(atom_info.to_coin(100)), Withdraw(atom_info.to_action_coin(101))],
+    );
+    assert!(res.unwrap_err().to_string().contains("Cannot Sub with 100 and 101"));
+}
+
+#[test]
+fn unsupported_actions_cannot_be_simulated() {
+    let user = Addr::unchecked("user");
+    let mut mock = MockEnv::new().build().unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    let res = mock.simulate_actions(&account_id, vec![ClaimRewards {}]);
+    assert!(res.unwrap_err().to_string().contains("Action \"claim_rewards\" can not be simulated"));
+}
+
+#[test]
+fn perp_position_can_only_be_modified_once() {
+    let atom_info = uatom_info();
+    let usdc_info = coin_info("uusdc");
+    let user = Addr::unchecked("user");
+    let vault_depositor = Addr::unchecked("vault_depositor");
+    let vault_coin = usdc_info.to_coin(100000);
+
+    let mut mock = MockEnv::new()
+        .set_params(&[atom_info.clone(), usdc_info.clone()])
+        .fund_account(AccountToFund {
+            addr: vault_depositor.clone(),
+            funds: vec![vault_coin.clone()],
+        })
+        .build()
+        .unwrap();
+    mock.update_perp_params(PerpParamsUpdate::AddOrUpdate {
+        params: default_perp_params(&atom_info.denom),
+    });
+
+    let vault_depositor_account_id = mock.create_credit_account(&vault_depositor).unwrap();
+    mock.update_credit_account(
+        &vault_depositor_account_id,
+        &vault_depositor,
+        vec![Deposit(vault_coin.clone())],
+        &[vault_coin.clone()],
+    )
+    .unwrap();
+    mock.deposit_to_perp_vault(&vault_depositor_account_id, &vault_coin, None).unwrap();
+
+    let account_id = mock.create_credit_account(&user).unwrap();
+    let perp_order = |size: &str| ExecutePerpOrder {
+        denom: atom_info.denom.clone(),
+        order_size: Int128::from_str(size).unwrap(),
+        reduce_only: None,
+        margin: None,
+        acceptable_price: None,
+    };
+
+    let simulation = mock
+        .simulate_actions(&account_id, vec![Deposit(usdc_info.to_coin(10000)), perp_order("100")])
+        .unwrap();
+    assert_eq!(simulation.positions.perps.len(), 1);
+
+    // The second order would be estimated without the position opened by the first one
+    let res = mock.simulate_actions(
+        &account_id,
+        vec![Deposit(usdc_info.to_coin(10000)), perp_order("100"), perp_order("-50")],
+    );
+    let err = ContractError::RepeatedSimulationPerpOrder(atom_info.denom);
+    assert!(res.unwrap_err().to_string().contains(&err.to_string()));
+}
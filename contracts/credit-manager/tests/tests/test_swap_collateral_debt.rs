@@ -0,0 +1,200 @@
+use cosmwasm_std::{Addr, Coin, Decimal, Uint128};
+use mars_credit_manager::error::ContractError;
+use mars_swapper_mock::contract::MOCK_SWAP_RESULT;
+use mars_types::{
+    credit_manager::{
+        Action::{Borrow, Deposit, Lend, SwapCollateral, SwapDebt},
+        ActionAmount, ActionCoin,
+    },
+    swapper::{OsmoRoute, OsmoSwap, SwapperRoute},
+};
+
+use super::helpers::{assert_err, uatom_info, uosmo_info, AccountToFund, MockEnv};
+
+fn osmo_route() -> SwapperRoute {
+    SwapperRoute::Osmo(OsmoRoute {
+        swaps: vec![OsmoSwap {
+            pool_id: 101,
+            to: uosmo_info().denom,
+        }],
+    })
+}
+
+#[test]
+fn swap_collateral_reclaims_lent_funds() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+
+    let user = Addr::unchecked("user");
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone(), atom_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![Coin::new(100u128, atom_info.denom.clone())],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deposit(atom_info.to_coin(100)), Lend(atom_info.to_action_coin(80))],
+        &[atom_info.to_coin(100)],
+    )
+    .unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![SwapCollateral {
+            from: ActionCoin {
+                denom: atom_info.denom.clone(),
+                amount: ActionAmount::AccountBalance,
+            },
+            to: osmo_info.denom.clone(),
+            min_receive: MOCK_SWAP_RESULT,
+            route: Some(osmo_route()),
+        }],
+        &[],
+    )
+    .unwrap();
+
+    let position = mock.query_positions(&account_id);
+    assert!(position.lends.is_empty());
+    assert_eq!(position.deposits, vec![osmo_info.to_coin(MOCK_SWAP_RESULT.u128())]);
+
+    let atom_balance = mock.query_balance(&mock.rover, &atom_info.denom).amount;
+    assert_eq!(atom_balance, Uint128::zero());
+}
+
+#[test]
+fn swap_debt_repays_debt_with_new_borrow() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+
+    let user = Addr::unchecked("user");
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone(), atom_info.clone()])
+        .max_slippage(Decimal::percent(20))
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![Coin::new(1000u128, atom_info.denom.clone())],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deposit(atom_info.to_coin(1000)), Borrow(osmo_info.to_coin(1000))],
+        &[atom_info.to_coin(1000)],
+    )
+    .unwrap();
+
+    let actions = vec![SwapDebt {
+        repay_denom: osmo_info.denom.clone(),
+        borrow_denom: atom_info.denom.clone(),
+        max_borrow: Uint128::new(500),
+        route: Some(osmo_route()),
+    }];
+    let simulation = mock.simulate_actions(&account_id, actions.clone()).unwrap();
+
+    mock.update_credit_account(&account_id, &user, actions, &[]).unwrap();
+
+    // 1000 uosmo debt is worth 250 uatom, grossed up by 20% slippage = 312.5 uatom
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.debts.len(), 1);
+    assert_eq!(position.debts[0].denom, atom_info.denom);
+    assert_eq!(position.debts[0].amount, Uint128::new(313));
+    assert_eq!(simulation.positions.debts, position.debts);
+
+    // The borrowed uatom is swapped, the uosmo balance exceeding the debt remains in the account
+    assert_eq!(
+        position.deposits,
+        vec![atom_info.to_coin(1000), osmo_info.to_coin(MOCK_SWAP_RESULT.u128())]
+    );
+    assert_eq!(simulation.positions.deposits, position.deposits);
+}
+
+#[test]
+fn swap_debt_repays_only_swap_output() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+
+    let user = Addr::unchecked("user");
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone(), atom_info.clone()])
+        .max_slippage(Decimal::percent(20))
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![Coin::new(1000u128, atom_info.denom.clone())],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deposit(atom_info.to_coin(1000)), Borrow(osmo_info.to_coin(2000))],
+        &[atom_info.to_coin(1000)],
+    )
+    .unwrap();
+
+    let actions = vec![SwapDebt {
+        repay_denom: osmo_info.denom.clone(),
+        borrow_denom: atom_info.denom.clone(),
+        max_borrow: Uint128::new(500),
+        route: Some(osmo_route()),
+    }];
+    let simulation = mock.simulate_actions(&account_id, actions.clone()).unwrap();
+
+    mock.update_credit_account(&account_id, &user, actions, &[]).unwrap();
+
+    // The swap output (1337 uosmo) doesn't cover the whole debt
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.debts.len(), 2);
+    let osmo_debt = position.debts.iter().find(|d| d.denom == osmo_info.denom).unwrap();
+    assert_eq!(osmo_debt.amount, Uint128::new(2000) - MOCK_SWAP_RESULT);
+    assert_eq!(simulation.positions.debts, position.debts);
+
+    // The uosmo held before the swap isn't used to repay the debt
+    assert_eq!(position.deposits, vec![atom_info.to_coin(1000), osmo_info.to_coin(2000)]);
+    assert_eq!(simulation.positions.deposits, position.deposits);
+}
+
+#[test]
+fn swap_debt_requires_debt() {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+
+    let user = Addr::unchecked("user");
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone(), atom_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![Coin::new(1000u128, atom_info.denom.clone())],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    let res = mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(atom_info.to_coin(1000)),
+            SwapDebt {
+                repay_denom: osmo_info.denom.clone(),
+                borrow_denom: atom_info.denom.clone(),
+                max_borrow: Uint128::new(500),
+                route: Some(osmo_route()),
+            },
+        ],
+        &[atom_info.to_coin(1000)],
+    );
+
+    assert_err(res, ContractError::NoDebt);
+}
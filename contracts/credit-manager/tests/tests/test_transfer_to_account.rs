@@ -0,0 +1,201 @@
+use cosmwasm_std::{coins, Addr, Uint128};
+use mars_credit_manager::error::ContractError;
+use mars_types::credit_manager::Action::{
+    Borrow, Deposit, Lend, TransferDebtToAccount, TransferLendToAccount, TransferToAccount,
+};
+
+use super::helpers::{assert_err, uatom_info, ujake_info, uosmo_info, AccountToFund, MockEnv};
+
+#[test]
+fn transfer_coins_lends_and_debt_between_accounts() {
+    let osmo_info = uosmo_info();
+    let atom_info = uatom_info();
+    let user = Addr::unchecked("user");
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone(), atom_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: coins(300, osmo_info.denom.clone()),
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+    let recipient_account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(osmo_info.to_coin(300)),
+            Lend(osmo_info.to_action_coin(100)),
+            Borrow(atom_info.to_coin(10)),
+        ],
+        &[osmo_info.to_coin(300)],
+    )
+    .unwrap();
+    let debt_shares = mock.query_positions(&account_id).debts[0].shares;
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            TransferToAccount {
+                recipient_account_id: recipient_account_id.clone(),
+                coin: osmo_info.to_action_coin(100),
+            },
+            TransferLendToAccount {
+                recipient_account_id: recipient_account_id.clone(),
+                coin: osmo_info.to_action_coin_full_balance(),
+            },
+            TransferDebtToAccount {
+                recipient_account_id: recipient_account_id.clone(),
+                coin: atom_info.to_action_coin_full_balance(),
+            },
+        ],
+        &[],
+    )
+    .unwrap();
+
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.deposits, vec![atom_info.to_coin(10), osmo_info.to_coin(100)]);
+    assert!(position.lends.is_empty());
+    assert!(position.debts.is_empty());
+
+    let position = mock.query_positions(&recipient_account_id);
+    assert_eq!(position.deposits, vec![osmo_info.to_coin(100)]);
+    assert_eq!(position.lends, vec![osmo_info.to_coin(100)]);
+    assert_eq!(position.debts.len(), 1);
+    assert_eq!(position.debts[0].amount, Uint128::new(10));
+    assert_eq!(position.debts[0].shares, debt_shares);
+}
+
+#[test]
+fn cannot_transfer_to_account_of_another_owner() {
+    let osmo_info = uosmo_info();
+    let user = Addr::unchecked("user");
+    let another_user = Addr::unchecked("another_user");
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: coins(300, osmo_info.denom.clone()),
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+    let recipient_account_id = mock.create_credit_account(&another_user).unwrap();
+
+    let res = mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(osmo_info.to_coin(300)),
+            TransferToAccount {
+                recipient_account_id: recipient_account_id.clone(),
+                coin: osmo_info.to_action_coin(100),
+            },
+        ],
+        &[osmo_info.to_coin(300)],
+    );
+
+    assert_err(
+        res,
+        ContractError::NotTokenOwner {
+            user: user.to_string(),
+            account_id: recipient_account_id,
+        },
+    );
+}
+
+#[test]
+fn health_of_debt_recipient_is_checked() {
+    let osmo_info = uosmo_info();
+    let atom_info = uatom_info();
+    let user = Addr::unchecked("user");
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone(), atom_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: coins(300, osmo_info.denom.clone()),
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+    let recipient_account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deposit(osmo_info.to_coin(300)), Borrow(atom_info.to_coin(100))],
+        &[osmo_info.to_coin(300)],
+    )
+    .unwrap();
+
+    let res = mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![TransferDebtToAccount {
+            recipient_account_id: recipient_account_id.clone(),
+            coin: atom_info.to_action_coin_full_balance(),
+        }],
+        &[],
+    );
+
+    assert_err(
+        res,
+        ContractError::AboveMaxLTV {
+            account_id: recipient_account_id,
+            max_ltv_health_factor: "0".to_string(),
+        },
+    );
+}
+
+#[test]
+fn hls_rules_of_recipient_are_checked() {
+    let atom_info = uatom_info();
+    let jake_info = ujake_info();
+    let user = Addr::unchecked("user");
+    let mut mock = MockEnv::new()
+        .set_params(&[atom_info.clone(), jake_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![atom_info.to_coin(300), jake_info.to_coin(300)],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+    let hls_account_id = mock.create_hls_account(&user);
+
+    mock.update_credit_account(
+        &hls_account_id,
+        &user,
+        vec![Deposit(atom_info.to_coin(300)), Borrow(atom_info.to_coin(10))],
+        &[atom_info.to_coin(300)],
+    )
+    .unwrap();
+
+    // Receiving a lend which isn't correlated to the debt breaks the HLS rules
+    let res = mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(jake_info.to_coin(300)),
+            Lend(jake_info.to_action_coin(50)),
+            TransferLendToAccount {
+                recipient_account_id: hls_account_id.clone(),
+                coin: jake_info.to_action_coin_full_balance(),
+            },
+        ],
+        &[jake_info.to_coin(300)],
+    );
+
+    assert_err(
+        res,
+        ContractError::HLS {
+            reason: format!(
+                "{} lend is not a correlated asset to debt {}",
+                jake_info.denom, atom_info.denom
+            ),
+        },
+    );
+}
@@ -1,20 +1,25 @@
 use std::str::FromStr;
 
 use anyhow::Error;
-use cosmwasm_std::{Addr, Coin, Decimal, Int128, OverflowError, OverflowOperation, Uint128};
+use cosmwasm_std::{
+    coin, Addr, Coin, Decimal, Int128, OverflowError, OverflowOperation, SignedDecimal, Uint128,
+};
 use cw_multi_test::AppResponse;
 use mars_credit_manager::error::ContractError;
+use mars_mock_oracle::msg::CoinPrice;
 use mars_testing::multitest::helpers::AccountToFund;
 use mars_types::{
     credit_manager::{
         Action::{
-            self, CreateTriggerOrder, DeleteTriggerOrder, Deposit, ExecutePerpOrder, Lend,
-            Liquidate,
+            self, Borrow, CreateTriggerOrder, DeleteTriggerOrder, Deposit, ExecutePerpOrder, Lend,
+            Liquidate, Repay, SwapExactIn, Withdraw, WithdrawToWallet,
         },
         ActionAmount, ActionCoin, Comparison,
-        Condition::{HealthFactor, OraclePrice},
-        LiquidateRequest, TriggerOrder, TriggerOrderResponse,
+        Condition::{BlockTime, FundingRate, HealthFactor, OraclePrice, PerpPositionPnl},
+        CreateTriggerOrderType, KeeperFeeConfig, LiquidateRequest, TriggerOrder,
+        TriggerOrderResponse,
     },
+    oracle::ActionKind,
     params::PerpParamsUpdate,
 };
 use test_case::test_case;
@@ -53,6 +58,8 @@ fn lend_action_whitelisted_in_trigger_orders() {
                         denom: "perp1".to_string(),
                         order_size: Int128::from_str("10").unwrap(),
                         reduce_only: None,
+                        margin: None,
+                        acceptable_price: None,
                     },
                     Lend(ActionCoin {
                         denom: keeper_fee.denom.clone(),
@@ -65,6 +72,9 @@ fn lend_action_whitelisted_in_trigger_orders() {
                     comparison: Comparison::GreaterThan,
                 }],
                 keeper_fee: keeper_fee.clone(),
+                order_type: None,
+                oco_group: None,
+                expires_at: None,
             },
         ],
         &[keeper_fee.clone()],
@@ -107,6 +117,8 @@ fn query_all_trigger_orders() {
                     denom: "perp1".to_string(),
                     order_size,
                     reduce_only: None,
+                    margin: None,
+                    acceptable_price: None,
                 }],
                 conditions: vec![OraclePrice {
                     denom: "perp1".to_string(),
@@ -114,6 +126,9 @@ fn query_all_trigger_orders() {
                     comparison: Comparison::GreaterThan,
                 }],
                 keeper_fee: keeper_fee.clone(),
+                parent_order_id: None,
+                oco_group: None,
+                expires_at: None,
             },
         });
     }
@@ -129,6 +144,9 @@ fn query_all_trigger_orders() {
                     actions: order.order.actions.clone(),
                     conditions: order.order.conditions.clone(),
                     keeper_fee: order.order.keeper_fee.clone(),
+                    order_type: None,
+                    oco_group: None,
+                    expires_at: None,
                 },
             ],
             &[order.order.keeper_fee.clone()],
@@ -175,6 +193,8 @@ fn delete_trigger_order() {
                     denom: "perp1".to_string(),
                     order_size: Int128::from_str("-10").unwrap(),
                     reduce_only: None,
+                    margin: None,
+                    acceptable_price: None,
                 }],
                 conditions: vec![OraclePrice {
                     denom: "perp1".to_string(),
@@ -182,6 +202,9 @@ fn delete_trigger_order() {
                     comparison: Comparison::GreaterThan,
                 }],
                 keeper_fee: keeper_fee.clone(),
+                order_type: None,
+                oco_group: None,
+                expires_at: None,
             },
         ],
         &[keeper_fee.clone()],
@@ -199,6 +222,8 @@ fn delete_trigger_order() {
             denom: "perp1".to_string(),
             order_size: Int128::from_str("-10").unwrap(),
             reduce_only: None,
+            margin: None,
+            acceptable_price: None,
         }]
     );
     assert_eq!(
@@ -244,6 +269,8 @@ fn delete_trigger_order() {
                 denom: uatom_info().denom.to_string(),
                 order_size: Int128::from_str("-10").unwrap(),
                 reduce_only: None,
+                margin: None,
+                acceptable_price: None,
             }],
             conditions: vec![OraclePrice {
                 denom: uatom_info().denom.to_string(),
@@ -251,6 +278,9 @@ fn delete_trigger_order() {
                 comparison: Comparison::GreaterThan,
             }],
             keeper_fee: coin_info("uusdc").to_coin(1),
+            order_type: None,
+            oco_group: None,
+            expires_at: None,
         }
     ],
     None,
@@ -269,6 +299,8 @@ fn delete_trigger_order() {
                 denom: uatom_info().denom.to_string(),
                 order_size: Int128::from_str("-10").unwrap(),
                 reduce_only: None,
+                margin: None,
+                acceptable_price: None,
             }],
             conditions: vec![OraclePrice {
                 denom: uatom_info().denom.to_string(),
@@ -276,6 +308,9 @@ fn delete_trigger_order() {
                 comparison: Comparison::GreaterThan,
             }],
             keeper_fee: coin_info("untrn").to_coin(100000000),
+            order_type: None,
+            oco_group: None,
+            expires_at: None,
         }
     ],
     None,
@@ -295,6 +330,8 @@ fn delete_trigger_order() {
                 denom: uatom_info().denom.to_string(),
                 order_size: Int128::from_str("-10").unwrap(),
                 reduce_only: None,
+                margin: None,
+                acceptable_price: None,
             }],
             conditions: vec![OraclePrice {
                 denom: uatom_info().denom.to_string(),
@@ -302,6 +339,9 @@ fn delete_trigger_order() {
                 comparison: Comparison::GreaterThan,
             }],
             keeper_fee: coin_info("uusdc").to_coin(100000000),
+            order_type: None,
+            oco_group: None,
+            expires_at: None,
         }
     ],
     None,
@@ -324,6 +364,8 @@ fn delete_trigger_order() {
                 denom: uatom_info().denom.to_string(),
                 order_size: Int128::from_str("-10").unwrap(),
                 reduce_only: None,
+                margin: None,
+                acceptable_price: None,
             }],
             conditions: vec![OraclePrice {
                 denom: uatom_info().denom.to_string(),
@@ -331,6 +373,9 @@ fn delete_trigger_order() {
                 comparison: Comparison::GreaterThan,
             }],
             keeper_fee: coin_info("uusdc").to_coin(1000000),
+            order_type: None,
+            oco_group: None,
+            expires_at: None,
         },
     ],
     Some(12347),
@@ -348,6 +393,8 @@ fn delete_trigger_order() {
                 denom: uatom_info().denom.to_string(),
                 order_size: Int128::from_str("-10").unwrap(),
                 reduce_only: None,
+                margin: None,
+                acceptable_price: None,
             }],
             conditions: vec![OraclePrice {
                 denom: uatom_info().denom.to_string(),
@@ -355,6 +402,9 @@ fn delete_trigger_order() {
                 comparison: Comparison::GreaterThan,
             }],
             keeper_fee: coin_info("uusdc").to_coin(1000000),
+            order_type: None,
+            oco_group: None,
+            expires_at: None,
         },
     ],
     Some(1),
@@ -372,6 +422,8 @@ fn delete_trigger_order() {
                 denom: uatom_info().denom.to_string(),
                 order_size: Int128::from_str("-1").unwrap(),
                 reduce_only: None,
+                margin: None,
+                acceptable_price: None,
             }],
             conditions: vec![OraclePrice {
                 denom: uatom_info().denom.to_string(),
@@ -379,6 +431,9 @@ fn delete_trigger_order() {
                 comparison: Comparison::LessThan,
             }],
             keeper_fee: coin_info("uusdc").to_coin(1000000),
+            order_type: None,
+            oco_group: None,
+            expires_at: None,
         },
     ],
     Some(1),
@@ -396,6 +451,8 @@ fn delete_trigger_order() {
                 denom: uatom_info().denom.to_string(),
                 order_size: Int128::from_str("-1").unwrap(),
                 reduce_only: None,
+                margin: None,
+                acceptable_price: None,
             }],
             conditions: vec![
                 OraclePrice {
@@ -409,6 +466,9 @@ fn delete_trigger_order() {
                 }
             ],
             keeper_fee: coin_info("uusdc").to_coin(1000000),
+            order_type: None,
+            oco_group: None,
+            expires_at: None,
         },
     ],
     Some(1),
@@ -425,6 +485,9 @@ fn delete_trigger_order() {
             actions: vec![Deposit(coin_info("uusdc").to_coin(100000000))],
             conditions: vec![],
             keeper_fee: coin_info("uusdc").to_coin(1000000),
+            order_type: None,
+            oco_group: None,
+            expires_at: None,
         },
     ],
     None,
@@ -445,6 +508,9 @@ fn delete_trigger_order() {
             }],
             conditions: vec![],
             keeper_fee: coin_info("uusdc").to_coin(1000000),
+            order_type: None,
+            oco_group: None,
+            expires_at: None,
         },
     ],
     None,
@@ -462,6 +528,8 @@ fn delete_trigger_order() {
                 denom: uatom_info().denom.to_string(),
                 order_size: Int128::from_str("-1").unwrap(),
                 reduce_only: None,
+                margin: None,
+                acceptable_price: None,
             }],
             conditions: vec![
                 OraclePrice {
@@ -475,6 +543,9 @@ fn delete_trigger_order() {
                 }
             ],
             keeper_fee: coin_info("uusdc").to_coin(1000000),
+            order_type: None,
+            oco_group: None,
+            expires_at: None,
         },
     ],
     Some(1),
@@ -483,6 +554,215 @@ fn delete_trigger_order() {
     Some(ContractError::IllegalExecuteTriggerOrder);
     "Error when price condition met and HF not met on execute"
 )]
+#[test_case(
+    coin_info("uusdc").to_coin(1000000),
+    vec![
+        Deposit(coin_info("uusdc").to_coin(100000000)),
+        CreateTriggerOrder {
+            actions: vec![WithdrawToWallet {
+                coin: coin_info("uusdc").to_action_coin(1000),
+                recipient: "keeper".to_string(),
+            }],
+            conditions: vec![],
+            keeper_fee: coin_info("uusdc").to_coin(1000000),
+            order_type: None,
+            oco_group: None,
+            expires_at: None,
+        },
+    ],
+    None,
+    vec![coin_info("uusdc").to_coin(100000000)],
+    Some(ContractError::IllegalTriggerAction),
+    None;
+    "Error when illegal trigger actions used (withdraw to wallet)"
+)]
+#[test_case(
+    coin_info("uusdc").to_coin(1000000),
+    vec![
+        Deposit(coin_info("uusdc").to_coin(100000000)),
+        CreateTriggerOrder {
+            actions: vec![Repay {
+                recipient_account_id: Some("1".to_string()),
+                coin: coin_info("uusdc").to_action_coin(1000),
+            }],
+            conditions: vec![],
+            keeper_fee: coin_info("uusdc").to_coin(1000000),
+            order_type: None,
+            oco_group: None,
+            expires_at: None,
+        },
+    ],
+    None,
+    vec![coin_info("uusdc").to_coin(100000000)],
+    Some(ContractError::IllegalTriggerAction),
+    None;
+    "Error when illegal trigger actions used (repay for another account)"
+)]
+#[test_case(
+    coin_info("uusdc").to_coin(1000000),
+    vec![
+        Deposit(coin_info("uusdc").to_coin(100000000)),
+        CreateTriggerOrder {
+            actions: vec![
+                Lend(coin_info("uusdc").to_action_coin(1000)),
+                Withdraw(coin_info("uusdc").to_action_coin(1000)),
+            ],
+            conditions: vec![OraclePrice {
+                denom: uatom_info().denom.to_string(),
+                price: Decimal::from_str("1.5").unwrap(),
+                comparison: Comparison::LessThan,
+            }],
+            keeper_fee: coin_info("uusdc").to_coin(1000000),
+            order_type: None,
+            oco_group: None,
+            expires_at: None,
+        },
+    ],
+    Some(1),
+    vec![coin_info("uusdc").to_coin(100000000)],
+    None,
+    None;
+    "Succeed when lend and withdraw used on execute"
+)]
+#[test_case(
+    coin_info("uusdc").to_coin(1000000),
+    vec![
+        Deposit(coin_info("uusdc").to_coin(100000000)),
+        CreateTriggerOrder {
+            actions: vec![Lend(coin_info("uusdc").to_action_coin(1000))],
+            conditions: vec![],
+            keeper_fee: coin_info("uusdc").to_coin(1000000),
+            order_type: Some(CreateTriggerOrderType::Child),
+            oco_group: None,
+            expires_at: None,
+        },
+    ],
+    None,
+    vec![coin_info("uusdc").to_coin(100000000)],
+    Some(ContractError::InvalidTriggerOrderRelation { reason: "child order has to follow a parent order".to_string() }),
+    None;
+    "Error when child order created without parent"
+)]
+#[test_case(
+    coin_info("uusdc").to_coin(1000000),
+    vec![
+        Deposit(coin_info("uusdc").to_coin(100000000)),
+        CreateTriggerOrder {
+            actions: vec![Lend(coin_info("uusdc").to_action_coin(1000))],
+            conditions: vec![BlockTime { after: Some(1), before: None }],
+            keeper_fee: coin_info("uusdc").to_coin(1000000),
+            order_type: None,
+            oco_group: None,
+            expires_at: None,
+        },
+    ],
+    Some(1),
+    vec![coin_info("uusdc").to_coin(100000000)],
+    None,
+    None;
+    "Succeed when block time condition met on execute"
+)]
+#[test_case(
+    coin_info("uusdc").to_coin(1000000),
+    vec![
+        Deposit(coin_info("uusdc").to_coin(100000000)),
+        CreateTriggerOrder {
+            actions: vec![Lend(coin_info("uusdc").to_action_coin(1000))],
+            conditions: vec![BlockTime { after: None, before: Some(1) }],
+            keeper_fee: coin_info("uusdc").to_coin(1000000),
+            order_type: None,
+            oco_group: None,
+            expires_at: None,
+        },
+    ],
+    Some(1),
+    vec![coin_info("uusdc").to_coin(100000000)],
+    None,
+    Some(ContractError::IllegalExecuteTriggerOrder);
+    "Error when block time condition not met on execute"
+)]
+#[test_case(
+    coin_info("uusdc").to_coin(1000000),
+    vec![
+        Deposit(coin_info("uusdc").to_coin(100000000)),
+        CreateTriggerOrder {
+            actions: vec![Lend(coin_info("uusdc").to_action_coin(1000))],
+            conditions: vec![FundingRate {
+                denom: uatom_info().denom.to_string(),
+                rate: SignedDecimal::from_str("-0.1").unwrap(),
+                comparison: Comparison::GreaterThan,
+            }],
+            keeper_fee: coin_info("uusdc").to_coin(1000000),
+            order_type: None,
+            oco_group: None,
+            expires_at: None,
+        },
+    ],
+    Some(1),
+    vec![coin_info("uusdc").to_coin(100000000)],
+    None,
+    None;
+    "Succeed when funding rate condition met on execute"
+)]
+#[test_case(
+    coin_info("uusdc").to_coin(1000000),
+    vec![
+        Deposit(coin_info("uusdc").to_coin(100000000)),
+        CreateTriggerOrder {
+            actions: vec![Lend(coin_info("uusdc").to_action_coin(1000))],
+            conditions: vec![PerpPositionPnl {
+                denom: uatom_info().denom.to_string(),
+                threshold: Int128::from_str("-100").unwrap(),
+                comparison: Comparison::GreaterThan,
+            }],
+            keeper_fee: coin_info("uusdc").to_coin(1000000),
+            order_type: None,
+            oco_group: None,
+            expires_at: None,
+        },
+    ],
+    Some(1),
+    vec![coin_info("uusdc").to_coin(100000000)],
+    None,
+    Some(ContractError::IllegalExecuteTriggerOrder);
+    "Error when perp pnl condition used without position on execute"
+)]
+#[test_case(
+    coin_info("uusdc").to_coin(1000000),
+    vec![
+        Deposit(coin_info("uusdc").to_coin(100000000)),
+        ExecutePerpOrder {
+            denom: uatom_info().denom.to_string(),
+            order_size: Int128::from_str("-1").unwrap(),
+            reduce_only: None,
+            margin: None,
+            acceptable_price: None,
+        },
+        CreateTriggerOrder {
+            actions: vec![ExecutePerpOrder {
+                denom: uatom_info().denom.to_string(),
+                order_size: Int128::from_str("1").unwrap(),
+                reduce_only: Some(true),
+                margin: None,
+                acceptable_price: None,
+            }],
+            conditions: vec![PerpPositionPnl {
+                denom: uatom_info().denom.to_string(),
+                threshold: Int128::from_str("1").unwrap(),
+                comparison: Comparison::LessThan,
+            }],
+            keeper_fee: coin_info("uusdc").to_coin(1000000),
+            order_type: None,
+            oco_group: None,
+            expires_at: None,
+        },
+    ],
+    Some(1),
+    vec![coin_info("uusdc").to_coin(100000000)],
+    None,
+    None;
+    "Succeed when perp pnl condition met on execute"
+)]
 fn verify_trigger_orders(
     keeper_fee: Coin,
     actions: Vec<Action>,
@@ -551,6 +831,547 @@ fn verify_trigger_orders(
     }
 }
 
+#[test]
+fn trigger_withdraw_is_sent_to_account_owner() {
+    let usdc_info = coin_info("uusdc");
+    let user = Addr::unchecked("user");
+    let keeper = Addr::unchecked("keeper");
+    let keeper_fee = usdc_info.to_coin(1000000);
+    let deposit = usdc_info.to_coin(100000000);
+
+    let mut mock = MockEnv::new()
+        .set_params(&[usdc_info.clone(), uatom_info()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![deposit.clone()],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(deposit.clone()),
+            CreateTriggerOrder {
+                actions: vec![Withdraw(usdc_info.to_action_coin(10000))],
+                conditions: vec![OraclePrice {
+                    denom: uatom_info().denom,
+                    price: Decimal::from_str("1.5").unwrap(),
+                    comparison: Comparison::LessThan,
+                }],
+                keeper_fee: keeper_fee.clone(),
+                order_type: None,
+                oco_group: None,
+                expires_at: None,
+            },
+        ],
+        &[deposit.clone()],
+    )
+    .unwrap();
+
+    mock.execute_trigger_order(&keeper, &account_id, "1").unwrap();
+
+    // The keeper only receives its fee, the withdrawn funds go to the account owner
+    assert_eq!(mock.query_balance(&keeper, &usdc_info.denom), keeper_fee);
+    assert_eq!(mock.query_balance(&user, &usdc_info.denom).amount, Uint128::new(10000));
+
+    let position = mock.query_positions(&account_id);
+    assert_eq!(
+        position.deposits[0].amount,
+        deposit.amount - keeper_fee.amount - Uint128::new(10000)
+    );
+}
+
+#[test_case(
+    Decimal::percent(99),
+    false;
+    "Succeed when swap output is within max slippage of oracle price"
+)]
+#[test_case(
+    Decimal::percent(10),
+    true;
+    "Error when swap output exceeds max slippage of oracle price"
+)]
+fn trigger_swap_is_bounded_by_oracle_price(max_slippage: Decimal, expect_error: bool) {
+    let atom_info = uatom_info();
+    let osmo_info = uosmo_info();
+    let usdc_info = coin_info("uusdc");
+    let user = Addr::unchecked("user");
+    let keeper = Addr::unchecked("keeper");
+    let keeper_fee = usdc_info.to_coin(1000000);
+
+    let mut mock = MockEnv::new()
+        .max_slippage(max_slippage)
+        .set_params(&[atom_info.clone(), osmo_info.clone(), usdc_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![atom_info.to_coin(10000), keeper_fee.clone()],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    // 10000 uatom are worth 40000 uosmo at oracle prices, the mock swapper returns 1337 uosmo.
+    // The min_receive of the order itself doesn't protect the user.
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(atom_info.to_coin(10000)),
+            Deposit(keeper_fee.clone()),
+            CreateTriggerOrder {
+                actions: vec![SwapExactIn {
+                    coin_in: atom_info.to_action_coin_full_balance(),
+                    denom_out: osmo_info.denom.clone(),
+                    min_receive: Uint128::one(),
+                    route: None,
+                }],
+                conditions: vec![OraclePrice {
+                    denom: atom_info.denom.clone(),
+                    price: Decimal::from_str("1.5").unwrap(),
+                    comparison: Comparison::LessThan,
+                }],
+                keeper_fee: keeper_fee.clone(),
+                order_type: None,
+                oco_group: None,
+                expires_at: None,
+            },
+        ],
+        &[atom_info.to_coin(10000), keeper_fee.clone()],
+    )
+    .unwrap();
+
+    let res = mock.execute_trigger_order(&keeper, &account_id, "1");
+
+    if expect_error {
+        let err = res.unwrap_err();
+        assert!(err.root_cause().to_string().contains("Min amount not reached"));
+        assert_eq!(mock.query_trigger_orders_for_account(account_id, None, None).data.len(), 1);
+    } else {
+        res.unwrap();
+        let position = mock.query_positions(&account_id);
+        assert_eq!(position.deposits.len(), 1);
+        assert_eq!(position.deposits[0].denom, osmo_info.denom);
+        assert_eq!(position.deposits[0].amount, Uint128::new(1337));
+    }
+}
+
+#[test]
+fn one_cancels_other_orders_are_deleted_on_execute() {
+    let usdc_info = coin_info("uusdc");
+    let user = Addr::unchecked("user");
+    let keeper = Addr::unchecked("keeper");
+    let keeper_fee = usdc_info.to_coin(1000000);
+    let deposit = usdc_info.to_coin(100000000);
+
+    let mut mock = MockEnv::new()
+        .set_params(&[usdc_info.clone(), uatom_info()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![deposit.clone()],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    let oco_order = |amount: u128, comparison: Comparison| CreateTriggerOrder {
+        actions: vec![Lend(usdc_info.to_action_coin(amount))],
+        conditions: vec![OraclePrice {
+            denom: uatom_info().denom,
+            price: Decimal::from_str("1.5").unwrap(),
+            comparison,
+        }],
+        keeper_fee: keeper_fee.clone(),
+        order_type: None,
+        oco_group: Some("bracket".to_string()),
+        expires_at: None,
+    };
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(deposit.clone()),
+            oco_order(1000, Comparison::LessThan),
+            oco_order(2000, Comparison::GreaterThan),
+        ],
+        &[deposit.clone()],
+    )
+    .unwrap();
+
+    let orders = mock.query_trigger_orders_for_account(account_id.clone(), None, None);
+    assert_eq!(orders.data.len(), 2);
+    assert_eq!(orders.data[1].order.oco_group, Some("bracket".to_string()));
+
+    mock.execute_trigger_order(&keeper, &account_id, "1").unwrap();
+
+    // The other order of the group is deleted and its keeper fee refunded
+    let orders = mock.query_trigger_orders_for_account(account_id.clone(), None, None);
+    assert_eq!(orders.data.len(), 0);
+    assert_eq!(mock.query_balance(&keeper, &usdc_info.denom), keeper_fee);
+
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.lends[0].amount, Uint128::new(1000));
+    assert_eq!(
+        position.deposits[0].amount,
+        deposit.amount - keeper_fee.amount - Uint128::new(1000)
+    );
+}
+
+#[test]
+fn child_orders_are_only_active_after_parent_is_executed() {
+    let usdc_info = coin_info("uusdc");
+    let user = Addr::unchecked("user");
+    let keeper = Addr::unchecked("keeper");
+    let keeper_fee = usdc_info.to_coin(1000000);
+    let deposit = usdc_info.to_coin(100000000);
+
+    let mut mock = MockEnv::new()
+        .set_params(&[usdc_info.clone(), uatom_info()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![deposit.clone()],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    let order = |amount: u128, order_type: CreateTriggerOrderType| CreateTriggerOrder {
+        actions: vec![Lend(usdc_info.to_action_coin(amount))],
+        conditions: vec![OraclePrice {
+            denom: uatom_info().denom,
+            price: Decimal::from_str("1.5").unwrap(),
+            comparison: Comparison::LessThan,
+        }],
+        keeper_fee: keeper_fee.clone(),
+        order_type: Some(order_type),
+        oco_group: None,
+        expires_at: None,
+    };
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(deposit.clone()),
+            order(1000, CreateTriggerOrderType::Parent),
+            order(100, CreateTriggerOrderType::Child),
+            order(200, CreateTriggerOrderType::Child),
+        ],
+        &[deposit.clone()],
+    )
+    .unwrap();
+
+    let orders = mock.query_trigger_orders_for_account(account_id.clone(), None, None);
+    assert_eq!(orders.data.len(), 3);
+    assert_eq!(orders.data[0].order.parent_order_id, None);
+    assert_eq!(orders.data[1].order.parent_order_id, Some("1".to_string()));
+    assert_eq!(orders.data[2].order.parent_order_id, Some("1".to_string()));
+
+    // Children can't be executed before their parent
+    let res = mock.execute_trigger_order(&keeper, &account_id, "2");
+    check_result_for_exepected_error(
+        res,
+        Some(ContractError::InactiveTriggerOrder {
+            order_id: "2".to_string(),
+            parent_order_id: "1".to_string(),
+        }),
+    );
+
+    mock.execute_trigger_order(&keeper, &account_id, "1").unwrap();
+    let orders = mock.query_trigger_orders_for_account(account_id.clone(), None, None);
+    assert_eq!(orders.data.len(), 2);
+
+    // Executing a child deletes its sibling
+    mock.execute_trigger_order(&keeper, &account_id, "2").unwrap();
+    let orders = mock.query_trigger_orders_for_account(account_id.clone(), None, None);
+    assert_eq!(orders.data.len(), 0);
+
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.lends[0].amount, Uint128::new(1100));
+    assert_eq!(
+        position.deposits[0].amount,
+        deposit.amount - keeper_fee.amount * Uint128::new(2) - Uint128::new(1100)
+    );
+}
+
+#[test]
+fn deleting_parent_order_deletes_children() {
+    let usdc_info = coin_info("uusdc");
+    let user = Addr::unchecked("user");
+    let keeper_fee = usdc_info.to_coin(1000000);
+    let deposit = usdc_info.to_coin(2000000);
+
+    let mut mock = MockEnv::new()
+        .set_params(&[usdc_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![deposit.clone()],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    let order = |order_type: CreateTriggerOrderType| CreateTriggerOrder {
+        actions: vec![Lend(usdc_info.to_action_coin(1))],
+        conditions: vec![],
+        keeper_fee: keeper_fee.clone(),
+        order_type: Some(order_type),
+        oco_group: None,
+        expires_at: None,
+    };
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![
+            Deposit(deposit.clone()),
+            order(CreateTriggerOrderType::Parent),
+            order(CreateTriggerOrderType::Child),
+        ],
+        &[deposit.clone()],
+    )
+    .unwrap();
+
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.deposits.len(), 0);
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![DeleteTriggerOrder {
+            trigger_order_id: "1".to_string(),
+        }],
+        &[],
+    )
+    .unwrap();
+
+    let orders = mock.query_trigger_orders_for_account(account_id.clone(), None, None);
+    assert_eq!(orders.data.len(), 0);
+
+    // Both keeper fees are refunded
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.deposits[0].amount, deposit.amount);
+}
+
+#[test]
+fn child_orders_are_linked_to_the_created_parent_order() {
+    let usdc_info = coin_info("uusdc");
+    let user = Addr::unchecked("user");
+    let keeper_fee = usdc_info.to_coin(1000000);
+    let deposit = usdc_info.to_coin(10000000);
+
+    let mut mock = MockEnv::new()
+        .set_params(&[usdc_info.clone()])
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![usdc_info.to_coin(20000000)],
+        })
+        .build()
+        .unwrap();
+    let account_id_1 = mock.create_credit_account(&user).unwrap();
+    let account_id_2 = mock.create_credit_account(&user).unwrap();
+
+    let order = |order_type: CreateTriggerOrderType| CreateTriggerOrder {
+        actions: vec![Lend(usdc_info.to_action_coin(1))],
+        conditions: vec![],
+        keeper_fee: keeper_fee.clone(),
+        order_type: Some(order_type),
+        oco_group: None,
+        expires_at: None,
+    };
+
+    // Order ids are shared by all accounts
+    mock.update_credit_account(
+        &account_id_1,
+        &user,
+        vec![Deposit(deposit.clone()), order(CreateTriggerOrderType::Default)],
+        &[deposit.clone()],
+    )
+    .unwrap();
+
+    mock.update_credit_account(
+        &account_id_2,
+        &user,
+        vec![
+            Deposit(deposit.clone()),
+            order(CreateTriggerOrderType::Parent),
+            order(CreateTriggerOrderType::Child),
+            order(CreateTriggerOrderType::Parent),
+            order(CreateTriggerOrderType::Child),
+        ],
+        &[deposit.clone()],
+    )
+    .unwrap();
+
+    let orders = mock.query_trigger_orders_for_account(account_id_2.clone(), None, None);
+    let ids = orders
+        .data
+        .iter()
+        .map(|res| (res.order.order_id.as_str(), res.order.parent_order_id.as_deref()))
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![("2", None), ("3", Some("2")), ("4", None), ("5", Some("4"))]);
+}
+
+#[test]
+fn expired_trigger_orders_are_purged() {
+    let usdc_info = coin_info("uusdc");
+    let user = Addr::unchecked("user");
+    let keeper = Addr::unchecked("keeper");
+    let cleaner = Addr::unchecked("cleaner");
+    let keeper_fee = usdc_info.to_coin(1000000);
+    let deposit = usdc_info.to_coin(3000000);
+
+    let mut mock = MockEnv::new()
+        .set_params(&[usdc_info.clone()])
+        .keeper_fee_config(KeeperFeeConfig {
+            min_fee: keeper_fee.clone(),
+            cleanup_reward: Some(Decimal::percent(10)),
+        })
+        .fund_account(AccountToFund {
+            addr: user.clone(),
+            funds: vec![deposit.clone()],
+        })
+        .build()
+        .unwrap();
+    let account_id = mock.create_credit_account(&user).unwrap();
+
+    let now = mock.query_block_time();
+    let order = |expires_at: Option<u64>| CreateTriggerOrder {
+        actions: vec![Lend(usdc_info.to_action_coin(1))],
+        conditions: vec![],
+        keeper_fee: keeper_fee.clone(),
+        order_type: None,
+        oco_group: None,
+        expires_at,
+    };
+
+    let res = mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deposit(deposit.clone()), order(Some(now))],
+        &[deposit.clone()],
+    );
+    check_result_for_exepected_error(
+        res,
+        Some(ContractError::InvalidTriggerOrderExpiry {
+            expires_at: now,
+        }),
+    );
+
+    mock.update_credit_account(
+        &account_id,
+        &user,
+        vec![Deposit(deposit.clone()), order(Some(now + 100)), order(None)],
+        &[deposit.clone()],
+    )
+    .unwrap();
+
+    mock.increment_by_time(100);
+
+    let res = mock.execute_trigger_order(&keeper, &account_id, "1");
+    check_result_for_exepected_error(
+        res,
+        Some(ContractError::TriggerOrderExpired {
+            order_id: "1".to_string(),
+            account_id: account_id.clone(),
+        }),
+    );
+
+    mock.purge_expired_trigger_orders(&cleaner, None).unwrap();
+
+    // Only the order without expiry is left
+    let orders = mock.query_trigger_orders_for_account(account_id.clone(), None, None);
+    assert_eq!(orders.data.len(), 1);
+    assert_eq!(orders.data[0].order.order_id, "2");
+
+    // 10% of the keeper fee is paid as cleanup reward, the rest is refunded to the account
+    assert_eq!(mock.query_balance(&cleaner, &usdc_info.denom).amount, Uint128::new(100000));
+    let position = mock.query_positions(&account_id);
+    assert_eq!(position.deposits[0].amount, Uint128::new(1900000));
+
+    // Nothing left to purge
+    mock.purge_expired_trigger_orders(&cleaner, None).unwrap();
+    assert_eq!(mock.query_balance(&cleaner, &usdc_info.denom).amount, Uint128::new(100000));
+}
+
+#[test]
+fn trigger_orders_are_deleted_on_liquidation() {
+    let osmo_info = uosmo_info();
+    let atom_info = uatom_info();
+    let liquidator = Addr::unchecked("liquidator");
+    let liquidatee = Addr::unchecked("liquidatee");
+    let keeper_fee = osmo_info.to_coin(10);
+
+    let mut mock = MockEnv::new()
+        .set_params(&[osmo_info.clone(), atom_info.clone()])
+        .keeper_fee_config(KeeperFeeConfig {
+            min_fee: keeper_fee.clone(),
+            cleanup_reward: None,
+        })
+        .fund_account(AccountToFund {
+            addr: liquidatee.clone(),
+            funds: vec![coin(3000, osmo_info.denom.clone())],
+        })
+        .fund_account(AccountToFund {
+            addr: liquidator.clone(),
+            funds: vec![coin(3000, atom_info.denom.clone())],
+        })
+        .build()
+        .unwrap();
+    let liquidatee_account_id = mock.create_credit_account(&liquidatee).unwrap();
+
+    mock.update_credit_account(
+        &liquidatee_account_id,
+        &liquidatee,
+        vec![
+            Deposit(osmo_info.to_coin(3000)),
+            Borrow(atom_info.to_coin(1000)),
+            CreateTriggerOrder {
+                actions: vec![Repay {
+                    recipient_account_id: None,
+                    coin: atom_info.to_action_coin(1000),
+                }],
+                conditions: vec![OraclePrice {
+                    denom: atom_info.denom.clone(),
+                    price: Decimal::from_str("100").unwrap(),
+                    comparison: Comparison::GreaterThan,
+                }],
+                keeper_fee: keeper_fee.clone(),
+                order_type: None,
+                oco_group: None,
+                expires_at: None,
+            },
+        ],
+        &[osmo_info.to_coin(3000)],
+    )
+    .unwrap();
+
+    mock.price_change(CoinPrice {
+        pricing: ActionKind::Liquidation,
+        denom: atom_info.denom.clone(),
+        price: Decimal::from_atomics(59u128, 1).unwrap(),
+    });
+
+    let liquidator_account_id = mock.create_credit_account(&liquidator).unwrap();
+    mock.update_credit_account(
+        &liquidator_account_id,
+        &liquidator,
+        vec![
+            Deposit(atom_info.to_coin(100)),
+            Liquidate {
+                liquidatee_account_id: liquidatee_account_id.clone(),
+                debt_coin: atom_info.to_coin(100),
+                request: LiquidateRequest::Deposit(osmo_info.denom.clone()),
+            },
+        ],
+        &[atom_info.to_coin(100)],
+    )
+    .unwrap();
+
+    let orders = mock.query_trigger_orders_for_account(liquidatee_account_id, None, None);
+    assert_eq!(orders.data.len(), 0);
+}
+
 fn check_result_for_exepected_error(
     result: Result<AppResponse, Error>,
     expected_error: Option<ContractError>,
@@ -38,6 +38,7 @@ fn only_owner_can_update_config() {
             perps: None,
             keeper_fee_config: None,
             perps_liquidation_bonus_ratio: None,
+            flash_loan_fee_rate: None,
         },
     );
 
@@ -93,6 +94,7 @@ fn update_config_works_with_full_config() {
     let new_unlocking_max = Uint128::new(321);
     let new_max_slippage = Decimal::percent(12);
     let new_perps_lb_ratio = Decimal::percent(39);
+    let new_flash_loan_fee_rate = Decimal::permille(9);
     let new_swapper = SwapperBase::new("new_swapper".to_string());
     let new_health_contract = HealthContractUnchecked::new("new_health_contract".to_string());
     let new_rewards_collector = "rewards_collector_contract_new".to_string();
@@ -100,6 +102,7 @@ fn update_config_works_with_full_config() {
     let new_perps_contract = PerpsUnchecked::new("new_perps_contract".to_string());
     let keeper_fee_config = KeeperFeeConfig {
         min_fee: coin(100000, "uusdc"),
+        cleanup_reward: None,
     };
 
     mock.update_config(
@@ -119,6 +122,7 @@ fn update_config_works_with_full_config() {
             perps: Some(new_perps_contract.clone()),
             keeper_fee_config: Some(keeper_fee_config.clone()),
             perps_liquidation_bonus_ratio: Some(new_perps_lb_ratio),
+            flash_loan_fee_rate: Some(new_flash_loan_fee_rate),
         },
     )
     .unwrap();
@@ -154,6 +158,9 @@ fn update_config_works_with_full_config() {
         original_config.perps_liquidation_bonus_ratio
     );
 
+    assert_eq!(new_config.flash_loan_fee_rate, new_flash_loan_fee_rate);
+    assert_ne!(new_config.flash_loan_fee_rate, original_config.flash_loan_fee_rate);
+
     assert_eq!(&new_config.swapper, new_swapper.address());
     assert_ne!(new_config.swapper, original_config.swapper);
 
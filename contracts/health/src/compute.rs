@@ -4,7 +4,7 @@ use cosmwasm_std::{Decimal, Deps, StdResult};
 use mars_rover_health_computer::{HealthComputer, PerpsData, VaultsData};
 use mars_types::{
     credit_manager::Positions,
-    health::{HealthResult, HealthState, HealthValuesResponse},
+    health::{HealthResult, HealthState, HealthValuesResponse, IsolatedPerpHealth},
     oracle::ActionKind,
     params::AssetParams,
 };
@@ -20,6 +20,32 @@ pub fn compute_health(
     positions: Positions,
     action: ActionKind,
 ) -> HealthResult<HealthValuesResponse> {
+    let computer = health_computer(deps, q, positions, action, &[])?;
+    Ok(computer.compute_health()?.into())
+}
+
+/// Health of the isolated perp position for `denom`, see `HealthComputer::isolated_perp_health`.
+pub fn compute_isolated_perp_health(
+    deps: Deps,
+    q: HealthQuerier,
+    positions: Positions,
+    action: ActionKind,
+    denom: &str,
+) -> HealthResult<IsolatedPerpHealth> {
+    let computer = health_computer(deps, q, positions, action, &[])?;
+    computer.isolated_perp_health(denom)
+}
+
+/// Queries all necessary data for the positions and builds the `HealthComputer`.
+/// Params and prices are also loaded for `extra_denoms`, which is needed for estimates
+/// involving denoms the account doesn't hold yet.
+pub fn health_computer(
+    deps: Deps,
+    q: HealthQuerier,
+    positions: Positions,
+    action: ActionKind,
+    extra_denoms: &[&str],
+) -> HealthResult<HealthComputer> {
     // Get the denoms that need prices + markets
     let deposit_denoms = positions.deposits.iter().map(|d| &d.denom).collect::<Vec<_>>();
     let debt_denoms = positions.debts.iter().map(|d| &d.denom).collect::<Vec<_>>();
@@ -38,6 +64,7 @@ pub fn compute_health(
 
     // Load the base denom if perps exist
     let base_denom_opt = positions.perps.first().map(|p| p.base_denom.clone());
+    let extra_denoms = extra_denoms.iter().map(|denom| denom.to_string()).collect::<Vec<_>>();
 
     // Collect prices + asset
     let mut asset_params: HashMap<String, AssetParams> = HashMap::new();
@@ -50,6 +77,7 @@ pub fn compute_health(
         .chain(vault_base_token_denoms)
         .chain(staked_lp_denoms)
         .chain(base_denom_opt.iter())
+        .chain(extra_denoms.iter())
         .try_for_each(|denom| -> StdResult<()> {
             let params_opt = q.params.query_asset_params(&deps.querier, denom)?;
             // If the asset is not supported, we skip it (both params and price)
@@ -80,16 +108,14 @@ pub fn compute_health(
         Ok(())
     })?;
 
-    let computer = HealthComputer {
+    Ok(HealthComputer {
         kind: positions.account_kind.clone(),
         positions,
         asset_params,
         oracle_prices,
         vaults_data,
         perps_data,
-    };
-
-    Ok(computer.compute_health()?.into())
+    })
 }
 
 pub fn compute_health_state(
@@ -140,6 +140,7 @@ impl MockEnvBuilder {
                         perps: "n/a".to_string(),
                         keeper_fee_config: Default::default(),
                         perps_liquidation_bonus_ratio: Decimal::percent(60),
+                        flash_loan_fee_rate: Decimal::zero(),
                     },
                 },
                 &[],
@@ -139,6 +139,7 @@ fn adds_vault_base_denoms_to_oracle_and_red_bank() {
             protocol_liquidation_fee: Decimal::percent(2u64),
             deposit_cap: Default::default(),
             close_factor: Decimal::percent(80u64),
+            liquidation_auction: None,
         },
     };
 
@@ -202,6 +203,7 @@ fn whitelisted_coins_work() {
         protocol_liquidation_fee: Decimal::percent(2u64),
         deposit_cap: Default::default(),
         close_factor: Decimal::percent(80u64),
+        liquidation_auction: None,
     };
 
     let update = AddOrUpdate {
@@ -315,6 +317,7 @@ fn vault_whitelist_affects_max_ltv() {
             protocol_liquidation_fee: Decimal::percent(2u64),
             deposit_cap: Default::default(),
             close_factor: Decimal::percent(80u64),
+            liquidation_auction: None,
         },
     };
 
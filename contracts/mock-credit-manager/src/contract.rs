@@ -37,6 +37,9 @@ pub fn execute(
             account_id,
             kind,
         } => set_account_kind_response(deps, account_id, kind),
+        ExecuteMsg::PurgeAccountTriggerOrders {
+            ..
+        } => Ok(Response::new()),
     }
 }
 
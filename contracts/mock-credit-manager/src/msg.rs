@@ -20,4 +20,9 @@ pub enum ExecuteMsg {
         account_id: String,
         kind: AccountKind,
     },
+    /// Sent by the account NFT contract on burn, no-op in the mock
+    PurgeAccountTriggerOrders {
+        account_id: String,
+        refund_recipient: String,
+    },
 }
@@ -6,8 +6,13 @@ use cosmwasm_std::{
 use mars_types::red_bank;
 
 use crate::{
-    execute::{borrow, deposit, init_asset, repay, withdraw},
-    query::{query_collateral, query_collaterals, query_collaterals_v2, query_debt, query_market},
+    execute::{
+        borrow, deposit, init_asset, repay, transfer_collateral_to_credit_account, withdraw,
+    },
+    query::{
+        query_collateral, query_collaterals, query_collaterals_v2, query_debt, query_debts,
+        query_market,
+    },
 };
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -38,8 +43,8 @@ pub fn execute(
             ..
         } => borrow(deps, info, denom, amount),
         red_bank::ExecuteMsg::Repay {
-            ..
-        } => repay(deps, info),
+            on_behalf_of,
+        } => repay(deps, info, on_behalf_of),
         red_bank::ExecuteMsg::Deposit {
             account_id,
             on_behalf_of: _,
@@ -53,6 +58,12 @@ pub fn execute(
         } => {
             withdraw(deps, info, &denom, &amount, account_id, liquidation_related.unwrap_or(false))
         }
+        red_bank::ExecuteMsg::TransferCollateralToCreditAccount {
+            user,
+            account_id,
+            denom,
+            ..
+        } => transfer_collateral_to_credit_account(deps, info, user, account_id, denom),
         _ => unimplemented!("Msg not supported!"),
     }
 }
@@ -67,6 +78,11 @@ pub fn query(deps: Deps, _env: Env, msg: red_bank::QueryMsg) -> StdResult<Binary
             user,
             denom,
         } => to_json_binary(&query_debt(deps, user, denom)?),
+        red_bank::QueryMsg::UserDebts {
+            user,
+            start_after,
+            limit,
+        } => to_json_binary(&query_debts(deps, user, start_after, limit)?),
         red_bank::QueryMsg::UserCollateral {
             user,
             account_id,
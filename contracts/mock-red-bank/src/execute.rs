@@ -59,14 +59,22 @@ pub fn borrow(
     Ok(Response::new().add_message(transfer_msg))
 }
 
-pub fn repay(deps: DepsMut, info: MessageInfo) -> StdResult<Response> {
+pub fn repay(
+    deps: DepsMut,
+    info: MessageInfo,
+    on_behalf_of: Option<String>,
+) -> StdResult<Response> {
     let coin_sent =
         one_coin(&info).map_err(|_| StdError::generic_err("Repay coin reqs not met"))?;
-    let debt_amount = load_debt_amount(deps.storage, &info.sender, &coin_sent.denom)?;
+    let debtor = match on_behalf_of {
+        Some(addr) => deps.api.addr_validate(&addr)?,
+        None => info.sender,
+    };
+    let debt_amount = load_debt_amount(deps.storage, &debtor, &coin_sent.denom)?;
 
     DEBT_AMOUNT.save(
         deps.storage,
-        (info.sender, coin_sent.denom.clone()),
+        (debtor, coin_sent.denom.clone()),
         &debt_amount.checked_sub(coin_sent.amount)?,
     )?;
 
@@ -151,3 +159,49 @@ pub fn withdraw(
         .add_attribute("liquidation_related", liquidation_related.to_string());
     Ok(Response::new().add_event(event).add_message(transfer_msg))
 }
+
+pub fn transfer_collateral_to_credit_account(
+    deps: DepsMut,
+    info: MessageInfo,
+    user: String,
+    account_id: String,
+    denom: String,
+) -> StdResult<Response> {
+    // since this is just a mock, we don't check that the sender is the credit manager
+    let amount = load_collateral_amount(deps.storage, &user, "", &denom)?;
+    if amount.is_zero() {
+        return Err(StdError::generic_err("No collateral to transfer"));
+    }
+
+    COLLATERAL_AMOUNT.remove(deps.storage, (user.clone(), "".to_string(), denom.clone()));
+    COLLATERAL_DENOMS.update(
+        deps.storage,
+        (user, "".to_string()),
+        |denoms_opt| -> StdResult<_> {
+            let mut denoms = denoms_opt.unwrap_or_default();
+            denoms.retain(|s| s != &denom);
+            Ok(denoms)
+        },
+    )?;
+
+    let account_amount =
+        load_collateral_amount(deps.storage, info.sender.as_str(), &account_id, &denom)?;
+    COLLATERAL_AMOUNT.save(
+        deps.storage,
+        (info.sender.to_string(), account_id.clone(), denom.clone()),
+        &account_amount.checked_add(amount)?,
+    )?;
+    COLLATERAL_DENOMS.update(
+        deps.storage,
+        (info.sender.to_string(), account_id),
+        |denoms_opt| -> StdResult<_> {
+            let mut denoms = denoms_opt.unwrap_or_default();
+            if !denoms.contains(&denom) {
+                denoms.push(denom.clone());
+            }
+            Ok(denoms)
+        },
+    )?;
+
+    Ok(Response::new())
+}
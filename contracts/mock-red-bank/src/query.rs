@@ -1,12 +1,13 @@
-use cosmwasm_std::{Deps, StdResult, Uint128};
+use cosmwasm_std::{Deps, Order, StdResult, Uint128};
 use cw_paginate::Metadata;
+use cw_storage_plus::Bound;
 use mars_types::red_bank::{
     Market, PaginatedUserCollateralResponse, UserCollateralResponse, UserDebtResponse,
 };
 
 use crate::{
     helpers::{load_collateral_amount, load_collateral_denoms, load_debt_amount},
-    state::MARKETS,
+    state::{DEBT_AMOUNT, MARKETS},
 };
 
 pub fn query_market(deps: Deps, denom: String) -> StdResult<Market> {
@@ -24,6 +25,31 @@ pub fn query_debt(deps: Deps, user: String, denom: String) -> StdResult<UserDebt
     })
 }
 
+pub fn query_debts(
+    deps: Deps,
+    user: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<UserDebtResponse>> {
+    let user_addr = deps.api.addr_validate(&user)?;
+    let start = start_after.map(Bound::exclusive);
+    DEBT_AMOUNT
+        .prefix(user_addr)
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| !matches!(item, Ok((_, amount)) if amount.is_zero()))
+        .take(limit.unwrap_or(10) as usize)
+        .map(|item| {
+            let (denom, amount) = item?;
+            Ok(UserDebtResponse {
+                denom,
+                amount,
+                amount_scaled: Uint128::zero(),
+                uncollateralized: false,
+            })
+        })
+        .collect()
+}
+
 pub fn query_collateral(
     deps: Deps,
     user: String,
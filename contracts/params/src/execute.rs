@@ -234,6 +234,21 @@ impl<'a> Permission<'a> {
                         reason: "perp param liquidation threshold".to_string()
                     }
                 );
+                // Moving the tier thresholds also changes the liquidation threshold of positions
+                let tier_liquidation_thresholds = |params: &PerpParams| {
+                    params
+                        .margin_tiers
+                        .iter()
+                        .map(|tier| (tier.notional_threshold, tier.liquidation_threshold))
+                        .collect::<Vec<_>>()
+                };
+                ensure_eq!(
+                    tier_liquidation_thresholds(&current_perps_params),
+                    tier_liquidation_thresholds(new_params),
+                    ContractError::RiskManagerUnauthorized {
+                        reason: "perp param margin tier liquidation threshold".to_string()
+                    }
+                );
             } else {
                 return Err(ContractError::RiskManagerUnauthorized {
                     reason: "new perp".to_string(),
@@ -2,13 +2,14 @@ use cosmwasm_std::{Addr, Decimal, DepsMut, Order, Response, StdResult};
 use cw2::{assert_contract_version, set_contract_version};
 use mars_owner::OwnerInit::SetInitialOwner;
 use mars_types::params::{
-    AssetParams, CmSettings, HlsAssetType, HlsParams, LiquidationBonus, MigrateMsg, RedBankSettings,
+    AssetParams, CmSettings, FundingModel, HlsAssetType, HlsParams, LiquidationBonus, MigrateMsg,
+    PerpParams, RedBankSettings,
 };
 
 use crate::{
     contract::{CONTRACT_NAME, CONTRACT_VERSION},
     error::ContractError,
-    state::{ASSET_PARAMS, OWNER, RISK_MANAGER},
+    state::{ASSET_PARAMS, OWNER, PERP_PARAMS, RISK_MANAGER},
 };
 
 const FROM_VERSION: &str = "2.1.0";
@@ -75,6 +76,25 @@ pub mod v2_1_0_state {
     pub type AssetParamsUnchecked = AssetParamsBase<String>;
 
     pub const ASSET_PARAMS: Map<&str, AssetParams> = Map::new("asset_params");
+
+    #[cw_serde]
+    pub struct PerpParams {
+        pub denom: String,
+        pub enabled: bool,
+        pub max_net_oi_value: Uint128,
+        pub max_long_oi_value: Uint128,
+        pub max_short_oi_value: Uint128,
+        pub closing_fee_rate: Decimal,
+        pub opening_fee_rate: Decimal,
+        pub min_position_value: Uint128,
+        pub max_position_value: Option<Uint128>,
+        pub max_loan_to_value: Decimal,
+        pub liquidation_threshold: Decimal,
+        pub max_funding_velocity: Decimal,
+        pub skew_scale: Uint128,
+    }
+
+    pub const PERP_PARAMS: Map<&str, PerpParams> = Map::new("perp_params");
 }
 
 pub fn migrate(deps: DepsMut, msg: MigrateMsg) -> Result<Response, ContractError> {
@@ -107,6 +127,14 @@ pub fn migrate(deps: DepsMut, msg: MigrateMsg) -> Result<Response, ContractError
         )?;
     }
 
+    // Migrate perps
+    let perp_params = v2_1_0_state::PERP_PARAMS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (denom, perp_param) in perp_params.into_iter() {
+        PERP_PARAMS.save(deps.storage, &denom, &from_v2_1_0_to_v2_2_0_perp_param(perp_param))?;
+    }
+
     Ok(Response::new()
         .add_attribute("action", "migrate")
         .add_attribute("from_version", FROM_VERSION)
@@ -139,7 +167,30 @@ fn from_v2_1_0_to_v2_2_0_asset_param(
         },
         protocol_liquidation_fee: value.protocol_liquidation_fee,
         deposit_cap: value.deposit_cap,
-        close_factor, // New field
+        close_factor,              // New field
+        liquidation_auction: None, // New field
+    }
+}
+
+fn from_v2_1_0_to_v2_2_0_perp_param(value: v2_1_0_state::PerpParams) -> PerpParams {
+    PerpParams {
+        denom: value.denom,
+        enabled: value.enabled,
+        max_net_oi_value: value.max_net_oi_value,
+        max_long_oi_value: value.max_long_oi_value,
+        max_short_oi_value: value.max_short_oi_value,
+        closing_fee_rate: value.closing_fee_rate,
+        opening_fee_rate: value.opening_fee_rate,
+        maker_fee_rates: None, // New field, makers pay the same fees as takers
+        min_position_value: value.min_position_value,
+        max_position_value: value.max_position_value,
+        max_loan_to_value: value.max_loan_to_value,
+        liquidation_threshold: value.liquidation_threshold,
+        margin_tiers: vec![], // New field
+        funding_model: FundingModel::Velocity {
+            max_funding_velocity: value.max_funding_velocity,
+        },
+        skew_scale: value.skew_scale,
     }
 }
 
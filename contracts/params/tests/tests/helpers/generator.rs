@@ -2,7 +2,7 @@ use std::str::FromStr;
 
 use cosmwasm_std::{coin, Decimal, Uint128};
 use mars_types::params::{
-    AssetParamsUnchecked, CmSettings, LiquidationBonus, PerpParams, RedBankSettings,
+    AssetParamsUnchecked, CmSettings, FundingModel, LiquidationBonus, PerpParams, RedBankSettings,
     VaultConfigUnchecked,
 };
 
@@ -30,6 +30,7 @@ pub fn default_asset_params(denom: &str) -> AssetParamsUnchecked {
         protocol_liquidation_fee: Decimal::percent(2),
         deposit_cap: Uint128::new(1_000_000_000),
         close_factor: Decimal::percent(80u64),
+        liquidation_auction: None,
     }
 }
 
@@ -53,11 +54,15 @@ pub fn default_perp_params(denom: &str) -> PerpParams {
         max_short_oi_value: Uint128::new(1_000_000_000),
         closing_fee_rate: Decimal::from_str("0.006").unwrap(),
         opening_fee_rate: Decimal::from_str("0.004").unwrap(),
+        maker_fee_rates: None,
         liquidation_threshold: Decimal::from_str("0.85").unwrap(),
+        margin_tiers: vec![],
         max_loan_to_value: Decimal::from_str("0.8").unwrap(),
         max_position_value: None,
         min_position_value: Uint128::zero(),
-        max_funding_velocity: Decimal::from_str("3").unwrap(),
+        funding_model: FundingModel::Velocity {
+            max_funding_velocity: Decimal::from_str("3").unwrap(),
+        },
         skew_scale: Uint128::new(1000000u128),
     }
 }
@@ -4,7 +4,7 @@ use cosmwasm_std::Decimal;
 use mars_params::error::ContractError;
 use mars_types::{
     error::MarsError::Validation,
-    params::{AssetParamsUpdate, HlsAssetType, HlsParamsUnchecked},
+    params::{AssetParamsUpdate, HlsAssetType, HlsParamsUnchecked, LiquidationAuction},
 };
 use mars_utils::error::ValidationError::{InvalidDenom, InvalidParam};
 
@@ -386,3 +386,27 @@ fn close_factor_less_than_or_equal_to_one() {
         })),
     );
 }
+
+#[test]
+fn liquidation_auction_duration_greater_than_zero() {
+    let mut mock = MockEnv::new().build().unwrap();
+    let mut params = default_asset_params("denom_xyz");
+    params.liquidation_auction = Some(LiquidationAuction {
+        duration: 0,
+    });
+
+    let res = mock.update_asset_params(
+        &mock.query_owner(),
+        AssetParamsUpdate::AddOrUpdate {
+            params,
+        },
+    );
+    assert_err(
+        res,
+        ContractError::Mars(Validation(InvalidParam {
+            param_name: "liquidation_auction_duration".to_string(),
+            invalid_value: "0".to_string(),
+            predicate: "> 0".to_string(),
+        })),
+    );
+}
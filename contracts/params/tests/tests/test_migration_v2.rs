@@ -6,11 +6,12 @@ use mars_params::{
     contract::migrate,
     error::ContractError,
     migrations::v2_2_0::v2_1_0_state,
-    state::{ASSET_PARAMS, OWNER, RISK_MANAGER},
+    state::{ASSET_PARAMS, OWNER, PERP_PARAMS, RISK_MANAGER},
 };
 use mars_testing::mock_dependencies;
 use mars_types::params::{
-    AssetParams, CmSettings, HlsAssetType, HlsParams, LiquidationBonus, MigrateMsg, RedBankSettings,
+    AssetParams, CmSettings, FundingModel, HlsAssetType, HlsParams, LiquidationBonus, MigrateMsg,
+    PerpParams, RedBankSettings,
 };
 
 #[test]
@@ -81,6 +82,9 @@ fn successful_migration() {
     v2_1_0_state::ASSET_PARAMS.save(deps.as_mut().storage, "asset_2", &asset_2()).unwrap();
     v2_1_0_state::ASSET_PARAMS.save(deps.as_mut().storage, "asset_1", &asset_1()).unwrap();
 
+    // Initialize the PERP_PARAMS storage items with the old state
+    v2_1_0_state::PERP_PARAMS.save(deps.as_mut().storage, "perp_1", &perp_1()).unwrap();
+
     let res = migrate(
         deps.as_mut(),
         mock_env(),
@@ -116,6 +120,54 @@ fn successful_migration() {
     assert_eq!(asset_params.len(), 2);
     assert_eq!(asset_params.get("asset_1").unwrap(), &expected_asset_1());
     assert_eq!(asset_params.get("asset_2").unwrap(), &expected_asset_2());
+
+    // Check that the PERP_PARAMS storage items have been migrated correctly
+    let perp_params = PERP_PARAMS
+        .range(deps.as_ref().storage, None, None, Order::Ascending)
+        .collect::<StdResult<HashMap<_, _>>>()
+        .unwrap();
+    assert_eq!(perp_params.len(), 1);
+    assert_eq!(perp_params.get("perp_1").unwrap(), &expected_perp_1());
+}
+
+fn perp_1() -> v2_1_0_state::PerpParams {
+    v2_1_0_state::PerpParams {
+        denom: "perp_1".to_string(),
+        enabled: true,
+        max_net_oi_value: Uint128::from(1000u128),
+        max_long_oi_value: Uint128::from(2000u128),
+        max_short_oi_value: Uint128::from(3000u128),
+        closing_fee_rate: Decimal::from_str("0.006").unwrap(),
+        opening_fee_rate: Decimal::from_str("0.004").unwrap(),
+        min_position_value: Uint128::from(10u128),
+        max_position_value: Some(Uint128::from(500u128)),
+        max_loan_to_value: Decimal::from_str("0.8").unwrap(),
+        liquidation_threshold: Decimal::from_str("0.85").unwrap(),
+        max_funding_velocity: Decimal::from_str("3").unwrap(),
+        skew_scale: Uint128::from(1000000u128),
+    }
+}
+
+fn expected_perp_1() -> PerpParams {
+    PerpParams {
+        denom: "perp_1".to_string(),
+        enabled: true,
+        max_net_oi_value: Uint128::from(1000u128),
+        max_long_oi_value: Uint128::from(2000u128),
+        max_short_oi_value: Uint128::from(3000u128),
+        closing_fee_rate: Decimal::from_str("0.006").unwrap(),
+        opening_fee_rate: Decimal::from_str("0.004").unwrap(),
+        maker_fee_rates: None,
+        min_position_value: Uint128::from(10u128),
+        max_position_value: Some(Uint128::from(500u128)),
+        max_loan_to_value: Decimal::from_str("0.8").unwrap(),
+        liquidation_threshold: Decimal::from_str("0.85").unwrap(),
+        margin_tiers: vec![],
+        funding_model: FundingModel::Velocity {
+            max_funding_velocity: Decimal::from_str("3").unwrap(),
+        },
+        skew_scale: Uint128::from(1000000u128),
+    }
 }
 
 fn asset_1() -> v2_1_0_state::AssetParams {
@@ -166,6 +218,7 @@ fn expected_asset_1() -> AssetParams {
         protocol_liquidation_fee: Decimal::from_str("0.05").unwrap(),
         deposit_cap: Uint128::from(1230000u128),
         close_factor: Decimal::from_str("0.9").unwrap(),
+        liquidation_auction: None,
     }
 }
 
@@ -245,5 +298,6 @@ fn expected_asset_2() -> AssetParams {
         protocol_liquidation_fee: Decimal::from_str("0.15").unwrap(),
         deposit_cap: Uint128::from(123u128),
         close_factor: Decimal::from_str("0.9").unwrap(),
+        liquidation_auction: None,
     }
 }
@@ -6,7 +6,7 @@ use cosmwasm_std::{Decimal, Uint128};
 use mars_params::error::ContractError;
 use mars_types::{
     error::MarsError::Validation,
-    params::{PerpParams, PerpParamsUpdate},
+    params::{FundingModel, MakerFeeRates, MarginTier, PerpParams, PerpParamsUpdate},
 };
 use mars_utils::error::ValidationError::InvalidParam;
 
@@ -109,6 +109,103 @@ fn closing_fee_rate_must_be_less_than_one() {
     );
 }
 
+#[test]
+fn maker_fee_rate_must_be_le_taker_fee_rate() {
+    let mut mock = MockEnv::new().build().unwrap();
+    let denom = "btc/perp/usd".to_string();
+    let res = mock.update_perp_params(
+        &mock.query_owner(),
+        PerpParamsUpdate::AddOrUpdate {
+            params: PerpParams {
+                maker_fee_rates: Some(MakerFeeRates {
+                    opening_fee_rate: Decimal::from_str("0.002").unwrap(),
+                    closing_fee_rate: Decimal::from_str("0.007").unwrap(),
+                }),
+                ..default_perp_params(&denom)
+            },
+        },
+    );
+
+    assert_err(
+        res,
+        ContractError::Mars(Validation(InvalidParam {
+            param_name: "maker_closing_fee_rate".to_string(),
+            invalid_value: "0.007".to_string(),
+            predicate: "<= 0.006 (taker fee rate)".to_string(),
+        })),
+    );
+}
+
+#[test]
+fn margin_tier_ltv_must_be_le_previous_tier() {
+    let mut mock = MockEnv::new().build().unwrap();
+    let denom = "btc/perp/usd".to_string();
+    let res = mock.update_perp_params(
+        &mock.query_owner(),
+        PerpParamsUpdate::AddOrUpdate {
+            params: PerpParams {
+                margin_tiers: vec![
+                    MarginTier {
+                        notional_threshold: Uint128::new(1_000_000),
+                        max_loan_to_value: Decimal::from_str("0.7").unwrap(),
+                        liquidation_threshold: Decimal::from_str("0.75").unwrap(),
+                    },
+                    MarginTier {
+                        notional_threshold: Uint128::new(10_000_000),
+                        max_loan_to_value: Decimal::from_str("0.72").unwrap(),
+                        liquidation_threshold: Decimal::from_str("0.75").unwrap(),
+                    },
+                ],
+                ..default_perp_params(&denom)
+            },
+        },
+    );
+
+    assert_err(
+        res,
+        ContractError::Mars(Validation(InvalidParam {
+            param_name: "margin_tier_max_loan_to_value".to_string(),
+            invalid_value: "0.72".to_string(),
+            predicate: "<= 0.7 (previous tier)".to_string(),
+        })),
+    );
+}
+
+#[test]
+fn margin_tier_notional_thresholds_must_be_increasing() {
+    let mut mock = MockEnv::new().build().unwrap();
+    let denom = "btc/perp/usd".to_string();
+    let res = mock.update_perp_params(
+        &mock.query_owner(),
+        PerpParamsUpdate::AddOrUpdate {
+            params: PerpParams {
+                margin_tiers: vec![
+                    MarginTier {
+                        notional_threshold: Uint128::new(10_000_000),
+                        max_loan_to_value: Decimal::from_str("0.7").unwrap(),
+                        liquidation_threshold: Decimal::from_str("0.75").unwrap(),
+                    },
+                    MarginTier {
+                        notional_threshold: Uint128::new(1_000_000),
+                        max_loan_to_value: Decimal::from_str("0.6").unwrap(),
+                        liquidation_threshold: Decimal::from_str("0.65").unwrap(),
+                    },
+                ],
+                ..default_perp_params(&denom)
+            },
+        },
+    );
+
+    assert_err(
+        res,
+        ContractError::Mars(Validation(InvalidParam {
+            param_name: "margin_tier_notional_threshold".to_string(),
+            invalid_value: "1000000".to_string(),
+            predicate: "> 10000000 (previous tier)".to_string(),
+        })),
+    );
+}
+
 #[test]
 fn max_oi_long_must_be_ge_than_max_net_oi() {
     let mut mock = MockEnv::new().build().unwrap();
@@ -208,3 +305,30 @@ fn skew_scale_cannot_be_zero() {
         })),
     );
 }
+
+#[test]
+fn premium_index_funding_clamp_must_be_lt_one() {
+    let mut mock = MockEnv::new().build().unwrap();
+    let denom = "btc/perp/usd".to_string();
+    let res = mock.update_perp_params(
+        &mock.query_owner(),
+        PerpParamsUpdate::AddOrUpdate {
+            params: PerpParams {
+                funding_model: FundingModel::PremiumIndex {
+                    clamp: Decimal::one(),
+                    max_funding_rate: Decimal::percent(10),
+                },
+                ..default_perp_params(&denom)
+            },
+        },
+    );
+
+    assert_err(
+        res,
+        ContractError::Mars(Validation(InvalidParam {
+            param_name: "funding_clamp".to_string(),
+            invalid_value: "1".to_string(),
+            predicate: "< 1".to_string(),
+        })),
+    );
+}
@@ -2,7 +2,7 @@ use std::str::FromStr;
 
 use cosmwasm_std::{Addr, Decimal, Uint128};
 use mars_params::error::ContractError;
-use mars_types::params::{PerpParams, PerpParamsUpdate};
+use mars_types::params::{FundingModel, MarginTier, PerpParams, PerpParamsUpdate};
 
 use super::helpers::{assert_contents_equal, assert_err, default_perp_params, MockEnv};
 
@@ -148,6 +148,63 @@ fn only_owner_can_update_perp_params_liquidation_threshold() {
     .unwrap();
 }
 
+#[test]
+fn only_owner_can_update_perp_params_margin_tier_liquidation_threshold() {
+    let mut mock =
+        MockEnv::new().build_with_risk_manager(Some("risk_manager_123".to_string())).unwrap();
+
+    // Add perp param with a margin tier as owner
+    let mut params = PerpParams {
+        margin_tiers: vec![MarginTier {
+            notional_threshold: Uint128::new(1_000_000),
+            max_loan_to_value: Decimal::from_str("0.7").unwrap(),
+            liquidation_threshold: Decimal::from_str("0.75").unwrap(),
+        }],
+        ..default_perp_params("xyz")
+    };
+    mock.update_perp_params(
+        &mock.query_owner(),
+        PerpParamsUpdate::AddOrUpdate {
+            params: params.clone(),
+        },
+    )
+    .unwrap();
+
+    // Succeed updating the tier max LTV as risk manager
+    params.margin_tiers[0].max_loan_to_value = Decimal::from_str("0.65").unwrap();
+    mock.update_perp_params(
+        &mock.query_risk_manager(),
+        PerpParamsUpdate::AddOrUpdate {
+            params: params.clone(),
+        },
+    )
+    .unwrap();
+
+    // Fail updating as risk manager if changing the tier liq threshold
+    params.margin_tiers[0].liquidation_threshold = Decimal::from_str("0.8").unwrap();
+    let res = mock.update_perp_params(
+        &mock.query_risk_manager(),
+        PerpParamsUpdate::AddOrUpdate {
+            params: params.clone(),
+        },
+    );
+    assert_err(
+        res,
+        ContractError::RiskManagerUnauthorized {
+            reason: "perp param margin tier liquidation threshold".to_string(),
+        },
+    );
+
+    // Succeed updating as owner if changing the tier liq threshold
+    mock.update_perp_params(
+        &mock.query_owner(),
+        PerpParamsUpdate::AddOrUpdate {
+            params: params.clone(),
+        },
+    )
+    .unwrap();
+}
+
 #[test]
 fn initializing_perp_params() {
     let mut mock = MockEnv::new().build().unwrap();
@@ -234,11 +291,15 @@ fn update_existing_perp_params() {
                 max_short_oi_value: Uint128::new(1_321_000_000),
                 closing_fee_rate: Decimal::from_str("0.018").unwrap(),
                 opening_fee_rate: Decimal::from_str("0.016").unwrap(),
+                maker_fee_rates: None,
                 liquidation_threshold: Decimal::from_str("0.85").unwrap(),
+                margin_tiers: vec![],
                 max_loan_to_value: Decimal::from_str("0.8").unwrap(),
                 max_position_value: None,
                 min_position_value: Uint128::zero(),
-                max_funding_velocity: Decimal::from_str("36").unwrap(),
+                funding_model: FundingModel::Velocity {
+                    max_funding_velocity: Decimal::from_str("36").unwrap(),
+                },
                 skew_scale: Uint128::new(7227323000000),
             },
         },
@@ -255,14 +316,19 @@ fn update_existing_perp_params() {
     assert_ne!(perp_params.max_short_oi_value, old_perp_params.max_short_oi_value);
     assert_ne!(perp_params.closing_fee_rate, old_perp_params.closing_fee_rate);
     assert_ne!(perp_params.opening_fee_rate, old_perp_params.opening_fee_rate);
-    assert_ne!(perp_params.max_funding_velocity, old_perp_params.max_funding_velocity);
+    assert_ne!(perp_params.funding_model, old_perp_params.funding_model);
     assert_ne!(perp_params.skew_scale, old_perp_params.skew_scale);
     assert_eq!(perp_params.max_net_oi_value, Uint128::new(888_999_000));
     assert_eq!(perp_params.max_long_oi_value, Uint128::new(1_123_000_000));
     assert_eq!(perp_params.max_short_oi_value, Uint128::new(1_321_000_000));
     assert_eq!(perp_params.closing_fee_rate, Decimal::from_str("0.018").unwrap());
     assert_eq!(perp_params.opening_fee_rate, Decimal::from_str("0.016").unwrap());
-    assert_eq!(perp_params.max_funding_velocity, Decimal::from_str("36").unwrap());
+    assert_eq!(
+        perp_params.funding_model,
+        FundingModel::Velocity {
+            max_funding_velocity: Decimal::from_str("36").unwrap(),
+        }
+    );
     assert_eq!(perp_params.skew_scale, Uint128::new(7227323000000));
 }
 
@@ -4,7 +4,7 @@ use cosmwasm_std::{
 use mars_owner::OwnerInit;
 use mars_types::{
     oracle::ActionKind,
-    perps::{ExecuteMsg, InstantiateMsg, QueryMsg},
+    perps::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg},
 };
 
 use crate::{
@@ -12,13 +12,17 @@ use crate::{
     error::{ContractError, ContractResult},
     initialize::initialize,
     market_management::update_market,
-    position_management::{close_all_positions, execute_order},
+    migrations,
+    position_management::{close_all_positions, close_isolated_position, execute_order},
     query::{
-        query_config, query_market, query_market_accounting, query_market_state, query_markets,
-        query_opening_fee, query_position, query_position_fees, query_positions,
-        query_positions_by_account, query_realized_pnl_by_account_and_market,
+        query_config, query_market, query_market_accounting, query_market_settlement,
+        query_market_state, query_markets, query_opening_fee, query_position, query_position_fees,
+        query_positions, query_positions_by_account, query_realized_pnl_by_account_and_market,
         query_total_accounting, query_vault, query_vault_position,
     },
+    settlement::{
+        delist_market, handle_settlement_request_reply, settle_market, SETTLEMENT_REQUEST_REPLY_ID,
+    },
     state::OWNER,
     update_config::update_config,
     vault::{deposit, unlock, withdraw},
@@ -35,7 +39,11 @@ pub fn instantiate(
     msg: InstantiateMsg,
 ) -> ContractResult<Response> {
     // initialize contract version info
-    cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    cw2::set_contract_version(
+        deps.storage,
+        format!("crates.io:{CONTRACT_NAME}"),
+        CONTRACT_VERSION,
+    )?;
 
     // initialize contract ownership info
     OWNER.initialize(
@@ -74,15 +82,34 @@ pub fn execute(
         ExecuteMsg::CloseAllPositions {
             account_id,
             action,
-        } => {
-            close_all_positions(deps, env, info, account_id, action.unwrap_or(ActionKind::Default))
-        }
+            excluded_denoms,
+        } => close_all_positions(
+            deps,
+            env,
+            info,
+            account_id,
+            action.unwrap_or(ActionKind::Default),
+            excluded_denoms.unwrap_or_default(),
+        ),
+        ExecuteMsg::CloseIsolatedPosition {
+            account_id,
+            denom,
+            action,
+        } => close_isolated_position(
+            deps,
+            env,
+            info,
+            account_id,
+            denom,
+            action.unwrap_or(ActionKind::Default),
+        ),
         ExecuteMsg::ExecuteOrder {
             account_id,
             denom,
             size,
             reduce_only,
-        } => execute_order(deps, env, info, account_id, denom, size, reduce_only),
+            acceptable_price,
+        } => execute_order(deps, env, info, account_id, denom, size, reduce_only, acceptable_price),
         ExecuteMsg::Deleverage {
             account_id,
             denom,
@@ -93,6 +120,13 @@ pub fn execute(
         ExecuteMsg::UpdateConfig {
             updates,
         } => update_config(deps, info.sender, updates),
+        ExecuteMsg::DelistMarket {
+            denom,
+        } => delist_market(deps, env, info.sender, denom),
+        ExecuteMsg::SettleMarket {
+            denom,
+            limit,
+        } => settle_market(deps, env, denom, limit),
     }
 }
 
@@ -100,10 +134,18 @@ pub fn execute(
 pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> ContractResult<Response> {
     match reply.id {
         DELEVERAGE_REQUEST_REPLY_ID => handle_deleverage_request_reply(deps, env, reply),
+        SETTLEMENT_REQUEST_REPLY_ID => handle_settlement_request_reply(deps, env, reply),
         id => Err(ContractError::ReplyIdError(id)),
     }
 }
 
+#[entry_point]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> ContractResult<Response> {
+    match msg {
+        MigrateMsg::V2_2_0ToV2_2_1 {} => migrations::v2_2_1::migrate(deps),
+    }
+}
+
 #[entry_point]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> ContractResult<Binary> {
     match msg {
@@ -183,6 +225,9 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> ContractResult<Binary> {
         QueryMsg::MarketState {
             denom,
         } => to_json_binary(&query_market_state(deps.storage, denom)?),
+        QueryMsg::MarketSettlement {
+            denom,
+        } => to_json_binary(&query_market_settlement(deps.storage, denom)?),
     }
     .map_err(Into::into)
 }
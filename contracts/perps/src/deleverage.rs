@@ -18,11 +18,11 @@ use crate::{
     error::{ContractError, ContractResult},
     market::MarketStateExt,
     position::{PositionExt, PositionModification},
-    position_management::apply_pnl_and_fees,
+    position_management::{apply_pnl_and_fees, write_off_pnl},
     query,
     state::{
-        DeleverageRequestTempStorage, CONFIG, DELEVERAGE_REQUEST_TEMP_STORAGE, MARKET_STATES,
-        POSITIONS, REALIZED_PNL, TOTAL_CASH_FLOW,
+        remove_position, DeleverageRequestTempStorage, CONFIG, DELEVERAGE_REQUEST_TEMP_STORAGE,
+        MARKET_SETTLEMENTS, MARKET_STATES, POSITIONS, REALIZED_PNL, TOTAL_CASH_FLOW,
     },
     utils::{get_oracle_adapter, get_params_adapter, update_position_attributes},
 };
@@ -66,6 +66,13 @@ pub fn deleverage(
         return Err(ContractError::DeleverageDisabled);
     }
 
+    // Positions of delisted markets can only be settled
+    if MARKET_SETTLEMENTS.has(deps.storage, &denom) {
+        return Err(ContractError::MarketDelisted {
+            denom,
+        });
+    }
+
     // Current block time
     let current_time = env.block.time.seconds();
 
@@ -120,8 +127,7 @@ pub fn deleverage(
         initial_skew,
         denom_price,
         base_denom_price,
-        perp_params.opening_fee_rate,
-        perp_params.closing_fee_rate,
+        &perp_params,
         PositionModification::Decrease(position.size),
     )?;
 
@@ -159,7 +165,7 @@ pub fn deleverage(
     )?;
 
     // Save updated states
-    POSITIONS.remove(deps.storage, (&account_id, &denom));
+    remove_position(deps.storage, &account_id, &denom);
     REALIZED_PNL.save(deps.storage, (&account_id, &denom), &realized_pnl)?;
     MARKET_STATES.save(deps.storage, &denom, &ms)?;
     TOTAL_CASH_FLOW.save(deps.storage, &tcf)?;
@@ -192,6 +198,8 @@ pub fn deleverage(
         denom: cfg.base_denom.clone(),
         contract_balance: balance_res.amount.amount.checked_sub(send_amount_from_perps)?, // Subtract the amount send from the contract
         requested_amount: requested_amount_from_cm,
+        account_id: account_id.clone(),
+        market_denom: denom.clone(),
     };
     DELEVERAGE_REQUEST_TEMP_STORAGE.save(deps.storage, &temp_storage)?;
 
@@ -294,10 +302,12 @@ pub fn handle_deleverage_request_reply(
     // Process the reply from the credit manager
     reply.result.into_result().map_err(StdError::generic_err)?;
 
-    // Compare contract balance after deleverage. If the difference is not equal to the requested amount, throw an error.
+    // Compare contract balance after deleverage. If the difference is greater than the requested amount, throw an error.
     // Requested amount is the amount that the credit manager should have sent to the contract.
     // If the requested amount is zero, it means that the contract should not have received any funds (closed position was in profit or break even).
+    // The loss of an isolated position is only paid up to its margin, the rest is bad debt of the vault.
     let temp_storage = DELEVERAGE_REQUEST_TEMP_STORAGE.load(deps.storage)?;
+    let mut bad_debt = Uint128::zero();
     if !temp_storage.requested_amount.is_zero() {
         let balance_res: BalanceResponse =
             deps.querier.query(&QueryRequest::Bank(BankQuery::Balance {
@@ -305,14 +315,23 @@ pub fn handle_deleverage_request_reply(
                 denom: temp_storage.denom,
             }))?;
         let balance_diff = balance_res.amount.amount.checked_sub(temp_storage.contract_balance)?;
-        if balance_diff != temp_storage.requested_amount {
+        if balance_diff > temp_storage.requested_amount {
             return Err(ContractError::InvalidFundsAfterDeleverage {
                 expected: temp_storage.requested_amount,
                 received: balance_diff,
             });
         }
+        bad_debt = temp_storage.requested_amount - balance_diff;
+        write_off_pnl(
+            deps.storage,
+            &temp_storage.account_id,
+            &temp_storage.market_denom,
+            bad_debt.try_into()?,
+        )?;
     }
     DELEVERAGE_REQUEST_TEMP_STORAGE.remove(deps.storage);
 
-    Ok(Response::new().add_attribute("action", "deleverage/handle_reply"))
+    Ok(Response::new()
+        .add_attribute("action", "deleverage/handle_reply")
+        .add_attribute("bad_debt", bad_debt.to_string()))
 }
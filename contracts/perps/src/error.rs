@@ -205,6 +205,25 @@ pub enum ContractError {
     MaxUnlocksReached {
         max_unlocks: u8,
     },
+
+    #[error("Execution price {exec_price} is worse than the acceptable price {acceptable_price}")]
+    UnacceptableExecutionPrice {
+        exec_price: Decimal,
+        acceptable_price: Decimal,
+    },
+
+    #[error("Market {denom} is delisted")]
+    MarketDelisted {
+        denom: String,
+    },
+
+    #[error("Market {denom} is not delisted")]
+    MarketNotDelisted {
+        denom: String,
+    },
+
+    #[error("{0}")]
+    Version(#[from] cw2::VersionError),
 }
 
 pub type ContractResult<T> = Result<T, ContractError>;
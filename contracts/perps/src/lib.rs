@@ -6,9 +6,11 @@ pub mod error;
 pub mod initialize;
 pub mod market;
 pub mod market_management;
+pub mod migrations;
 pub mod position;
 pub mod position_management;
 pub mod query;
+pub mod settlement;
 pub mod state;
 pub mod update_config;
 pub mod utils;
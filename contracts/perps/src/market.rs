@@ -8,14 +8,14 @@ use mars_perps_common::pricing::opening_execution_price;
 use mars_types::{
     adapters::{oracle::Oracle, params::Params},
     oracle::ActionKind,
-    params::PerpParams,
+    params::{FundingModel, PerpParams},
     perps::{Accounting, Funding, MarketState, PnlAmounts, PnlValues, Position},
 };
 
 use crate::{
     accounting::AccountingExt,
     error::{ContractError, ContractResult},
-    state::{MARKET_STATES, TOTAL_CASH_FLOW},
+    state::{MARKET_SETTLEMENTS, MARKET_STATES, TOTAL_CASH_FLOW},
     utils::get_markets_and_base_denom_prices,
 };
 
@@ -44,7 +44,11 @@ pub trait MarketStateExt {
     /// Total size of all outstanding positions
     fn total_size(&self) -> ContractResult<Uint128>;
 
-    /// Returns current funding rate velocity.
+    /// Returns the skew relative to the skew scale, bounded between -1 and 1.
+    /// Should be used _before_ modifying the market skew.
+    fn proportional_skew(&self) -> ContractResult<SignedDecimal>;
+
+    /// Returns current funding rate velocity (zero for models other than the velocity model).
     /// Should be used _before_ modifying the market skew.
     fn current_funding_rate_velocity(&self) -> ContractResult<SignedDecimal>;
 
@@ -55,6 +59,8 @@ pub trait MarketStateExt {
     /// The USDC-denominated funding entrance u(t) calculated _before_ modifying the market skew.
     ///
     /// u(t) = denom_price(t) / usdc_price(t) * (r(t-1) + r(t)) / 2 * (t - t-1) / seconds_in_day
+    ///
+    /// With the premium index model the rate r(t) is used instead of the average rate.
     fn current_funding_entrance_per_unit_in_base_denom(
         &self,
         current_time: u64,
@@ -126,10 +132,12 @@ pub trait MarketStateExt {
     /// Compute the price PnL of all open positions
     fn compute_price_pnl(&self, exit_price: Decimal) -> ContractResult<Int128>;
 
-    /// Compute the closing fees of all open positions
+    /// Compute the closing fees of all open positions.
+    /// Positions on the skewed side are charged the maker rate, even if closing them would overshoot
+    /// the skew.
     fn compute_closing_fee(
         &self,
-        closing_fee_rate: Decimal,
+        perp_params: &PerpParams,
         exit_price: Decimal,
     ) -> ContractResult<Int128>;
 
@@ -150,7 +158,7 @@ pub trait MarketStateExt {
         current_time: u64,
         denom_price: Decimal,
         base_denom_price: Decimal,
-        closing_fee_rate: Decimal,
+        perp_params: &PerpParams,
     ) -> ContractResult<(PnlValues, Funding)>;
 
     /// Computes the accounting data for a given denomination (`denom`).
@@ -161,7 +169,7 @@ pub trait MarketStateExt {
         current_time: u64,
         denom_price: Decimal,
         base_denom_price: Decimal,
-        closing_fee_rate: Decimal,
+        perp_params: &PerpParams,
     ) -> ContractResult<(Accounting, PnlAmounts)>;
 }
 
@@ -180,7 +188,7 @@ impl MarketStateExt for MarketState {
         Ok(self.long_oi.checked_add(self.short_oi)?)
     }
 
-    fn current_funding_rate_velocity(&self) -> ContractResult<SignedDecimal> {
+    fn proportional_skew(&self) -> ContractResult<SignedDecimal> {
         // Avoid a panic due to div by zero
         if self.funding.skew_scale.is_zero() {
             return Ok(SignedDecimal::zero());
@@ -191,19 +199,46 @@ impl MarketStateExt for MarketState {
             self.skew()?,
             Int128::try_from(self.funding.skew_scale)?,
         )?;
-        let p_skew_bounded =
-            p_skew.clamp(SignedDecimal::from_str("-1").unwrap(), SignedDecimal::one());
+        Ok(p_skew.clamp(SignedDecimal::from_str("-1").unwrap(), SignedDecimal::one()))
+    }
+
+    fn current_funding_rate_velocity(&self) -> ContractResult<SignedDecimal> {
+        let FundingModel::Velocity {
+            max_funding_velocity,
+        } = self.funding.funding_model
+        else {
+            return Ok(SignedDecimal::zero());
+        };
 
         let funding_rate_velocity =
-            p_skew_bounded.checked_mul(self.funding.max_funding_velocity.try_into()?)?;
+            self.proportional_skew()?.checked_mul(max_funding_velocity.try_into()?)?;
         Ok(funding_rate_velocity)
     }
 
     fn current_funding_rate(&self, current_time: u64) -> ContractResult<SignedDecimal> {
-        let current_funding_rate = self.funding.last_funding_rate.checked_add(
-            self.current_funding_rate_velocity()?
-                .checked_mul(self.time_elapsed_in_days(current_time).try_into()?)?,
-        )?;
+        let current_funding_rate = match self.funding.funding_model {
+            FundingModel::Velocity {
+                ..
+            } => self.funding.last_funding_rate.checked_add(
+                self.current_funding_rate_velocity()?
+                    .checked_mul(self.time_elapsed_in_days(current_time).try_into()?)?,
+            )?,
+            FundingModel::PremiumIndex {
+                clamp,
+                max_funding_rate,
+            } => {
+                // Premiums within the clamp pay no funding, larger ones are reduced by the clamp
+                let premium_index = self.proportional_skew()?;
+                let clamp = SignedDecimal::try_from(clamp)?;
+                let funding_rate = premium_index.checked_sub(
+                    premium_index.clamp(SignedDecimal::zero().checked_sub(clamp)?, clamp),
+                )?;
+
+                let max_funding_rate = SignedDecimal::try_from(max_funding_rate)?;
+                funding_rate
+                    .clamp(SignedDecimal::zero().checked_sub(max_funding_rate)?, max_funding_rate)
+            }
+        };
 
         // Ensure the funding rate is capped at 4% per hour (96% per day).
         let max_funding_rate_signed = SignedDecimal::try_from(MAX_FUNDING_RATE)?;
@@ -223,11 +258,21 @@ impl MarketStateExt for MarketState {
     ) -> ContractResult<SignedDecimal> {
         let price = denom_price.checked_div(base_denom_price)?;
         let curr_funding_rate = self.current_funding_rate(current_time)?;
-        let avg_funding_rate = self
-            .funding
-            .last_funding_rate
-            .checked_add(curr_funding_rate)?
-            .checked_div(SignedDecimal::from_atomics(2i128, 0)?)?;
+
+        // The velocity model changes the rate linearly since the last update, so the average rate
+        // is used. The premium index rate didn't change since the last update as the skew didn't.
+        let avg_funding_rate = match self.funding.funding_model {
+            FundingModel::Velocity {
+                ..
+            } => self
+                .funding
+                .last_funding_rate
+                .checked_add(curr_funding_rate)?
+                .checked_div(SignedDecimal::from_atomics(2i128, 0)?)?,
+            FundingModel::PremiumIndex {
+                ..
+            } => curr_funding_rate,
+        };
         let res = avg_funding_rate
             .checked_mul(self.time_elapsed_in_days(current_time).try_into()?)?
             .checked_mul(price.try_into()?)?;
@@ -452,41 +497,69 @@ impl MarketStateExt for MarketState {
 
     fn compute_closing_fee(
         &self,
-        closing_fee_rate: Decimal,
+        perp_params: &PerpParams,
         exit_price: Decimal,
     ) -> ContractResult<Int128> {
         let skew = self.skew()?;
-        let total_size = self.total_size()?;
+        let skew_scale = self.funding.skew_scale;
+        let taker_rate = perp_params.closing_fee_rate;
+        let maker_rate = perp_params.maker_closing_fee_rate();
+
+        if maker_rate == taker_rate || skew.is_zero() {
+            return closing_fee_at_rate(
+                taker_rate,
+                exit_price,
+                skew,
+                skew_scale,
+                self.total_abs_multiplied_positions,
+                self.total_size()?,
+            );
+        }
 
-        // Original formula from the doc:
-        // closing_fee_rate * exit_price * (1 / skew_scale * (- skew * total_size + total_abs_multiplied_positions / 2) - total_size)
-        //
-        // If we use as it is we can accumulate rounding errors in:
-        // - 'total_abs_multiplied_positions / 2' will end up as a integer,
-        // - '1 / skew_scale * (- skew * total_size + total_abs_multiplied_positions / 2)' will end up as a integer.
-        //
-        // Let's rewrite it to reduce number of rounding errors:
-        // closing_fee_rate * exit_price * ((total_abs_multiplied_positions - 2 * skew * total_size) / (2 * skew_scale) - total_size)
-        // Introduce following variables:
-        // val_1 = closing_fee_rate * exit_price
-        // val_2 = 2 * skew * total_size
-        // val_3 = total_abs_multiplied_positions - val_2
-        // val_4 = val_3 / (2 * skew_scale)
-        // finally:
-        // val_1 * (val_4 - total_size)
-        let val_1 = closing_fee_rate.checked_mul(exit_price)?;
-        let val_2 =
-            Int256::from(2i128).checked_mul(Int256::from(skew))?.checked_mul(total_size.into())?;
-        let val_3 = self.total_abs_multiplied_positions.checked_sub(val_2)?;
-        let two_times_skew_scale =
-            Int256::from(2i128).checked_mul(self.funding.skew_scale.into())?;
-        // Rounding errors here after rewriting the formula
-        let val_4 =
-            SignedDecimal256::checked_from_ratio(val_3, two_times_skew_scale)?.to_int_floor();
-        let closing_fee = val_4
-            .checked_sub(total_size.into())?
-            .checked_multiply_ratio(val_1.numerator(), val_1.denominator())?;
-        Ok(closing_fee.try_into()?)
+        // Closing positions on the skewed side reduces the skew (maker rate), closing positions
+        // on the other side increases it (taker rate).
+        // This is an approximation of `PerpParams::closing_fee_rate_at`: a position on the skewed
+        // side larger than twice the skew overshoots it and pays the taker rate when closed. The
+        // accumulators can't single out these positions, so they are accounted at the maker rate.
+        // Split the accumulators per side:
+        // long_abs_multiplied_positions = (total_squared_positions + total_abs_multiplied_positions) / 2
+        // short_abs_multiplied_positions = (total_abs_multiplied_positions - total_squared_positions) / 2
+        let total_squared_positions = Int512::from(self.total_squared_positions);
+        let total_abs_multiplied_positions = Int512::from(self.total_abs_multiplied_positions);
+        let two = Int512::from(2i128);
+        let long_abs_multiplied_positions = Int256::try_from(
+            total_squared_positions
+                .checked_add(total_abs_multiplied_positions)?
+                .checked_div(two)?,
+        )?;
+        let short_abs_multiplied_positions = Int256::try_from(
+            total_abs_multiplied_positions
+                .checked_sub(total_squared_positions)?
+                .checked_div(two)?,
+        )?;
+
+        let (long_rate, short_rate) = if skew.is_positive() {
+            (maker_rate, taker_rate)
+        } else {
+            (taker_rate, maker_rate)
+        };
+        let long_closing_fee = closing_fee_at_rate(
+            long_rate,
+            exit_price,
+            skew,
+            skew_scale,
+            long_abs_multiplied_positions,
+            self.long_oi,
+        )?;
+        let short_closing_fee = closing_fee_at_rate(
+            short_rate,
+            exit_price,
+            skew,
+            skew_scale,
+            short_abs_multiplied_positions,
+            self.short_oi,
+        )?;
+        Ok(long_closing_fee.checked_add(short_closing_fee)?)
     }
 
     fn compute_accrued_funding(
@@ -518,10 +591,10 @@ impl MarketStateExt for MarketState {
         current_time: u64,
         denom_price: Decimal,
         base_denom_price: Decimal,
-        closing_fee_rate: Decimal,
+        perp_params: &PerpParams,
     ) -> ContractResult<(PnlValues, Funding)> {
         let price_pnl = self.compute_price_pnl(denom_price)?;
-        let closing_fee = self.compute_closing_fee(closing_fee_rate, denom_price)?;
+        let closing_fee = self.compute_closing_fee(perp_params, denom_price)?;
         let (accrued_funding, curr_funding) =
             self.compute_accrued_funding(current_time, denom_price, base_denom_price)?;
         let pnl_values = PnlValues {
@@ -538,16 +611,55 @@ impl MarketStateExt for MarketState {
         current_time: u64,
         denom_price: Decimal,
         base_denom_price: Decimal,
-        closing_fee_rate: Decimal,
+        perp_params: &PerpParams,
     ) -> ContractResult<(Accounting, PnlAmounts)> {
         let (unrealized_pnl_val, _) =
-            self.compute_pnl(current_time, denom_price, base_denom_price, closing_fee_rate)?;
+            self.compute_pnl(current_time, denom_price, base_denom_price, perp_params)?;
         let unrealized_pnl_amt = PnlAmounts::from_pnl_values(unrealized_pnl_val, base_denom_price)?;
         let acc = Accounting::compute(&self.cash_flow, &unrealized_pnl_amt)?;
         Ok((acc, unrealized_pnl_amt))
     }
 }
 
+/// Compute the closing fees of positions, given the sum of their sizes and of their absolute
+/// multiplied sizes, all closed at the same rate
+fn closing_fee_at_rate(
+    closing_fee_rate: Decimal,
+    exit_price: Decimal,
+    skew: Int128,
+    skew_scale: Uint128,
+    total_abs_multiplied_positions: Int256,
+    total_size: Uint128,
+) -> ContractResult<Int128> {
+    // Original formula from the doc:
+    // closing_fee_rate * exit_price * (1 / skew_scale * (- skew * total_size + total_abs_multiplied_positions / 2) - total_size)
+    //
+    // If we use as it is we can accumulate rounding errors in:
+    // - 'total_abs_multiplied_positions / 2' will end up as a integer,
+    // - '1 / skew_scale * (- skew * total_size + total_abs_multiplied_positions / 2)' will end up as a integer.
+    //
+    // Let's rewrite it to reduce number of rounding errors:
+    // closing_fee_rate * exit_price * ((total_abs_multiplied_positions - 2 * skew * total_size) / (2 * skew_scale) - total_size)
+    // Introduce following variables:
+    // val_1 = closing_fee_rate * exit_price
+    // val_2 = 2 * skew * total_size
+    // val_3 = total_abs_multiplied_positions - val_2
+    // val_4 = val_3 / (2 * skew_scale)
+    // finally:
+    // val_1 * (val_4 - total_size)
+    let val_1 = closing_fee_rate.checked_mul(exit_price)?;
+    let val_2 =
+        Int256::from(2i128).checked_mul(Int256::from(skew))?.checked_mul(total_size.into())?;
+    let val_3 = total_abs_multiplied_positions.checked_sub(val_2)?;
+    let two_times_skew_scale = Int256::from(2i128).checked_mul(skew_scale.into())?;
+    // Rounding errors here after rewriting the formula
+    let val_4 = SignedDecimal256::checked_from_ratio(val_3, two_times_skew_scale)?.to_int_floor();
+    let closing_fee = val_4
+        .checked_sub(total_size.into())?
+        .checked_multiply_ratio(val_1.numerator(), val_1.denominator())?;
+    Ok(closing_fee.try_into()?)
+}
+
 fn decrease_accumulators(
     market_state: &mut MarketState,
     position: &Position,
@@ -646,17 +758,21 @@ pub fn compute_total_pnl(
                 denom: denom.clone(),
             })?;
 
-            // The prices hashmap provider is certain to contain the denom. The oracle is queried
-            // for all the denoms present in MARKET_STATES, so if a price is not available, the
-            // error would have thrown earlier.
-            let denom_price = prices[&denom];
-
-            let (pnl_values, _) = ms.compute_pnl(
-                current_time,
-                denom_price,
-                base_denom_price,
-                perp_params.closing_fee_rate,
-            )?;
+            // Delisted markets are valued at their settlement prices, their funding is frozen.
+            // Otherwise the prices hashmap provider is certain to contain the denom. The oracle is
+            // queried for all the denoms present in MARKET_STATES, so if a price is not available,
+            // the error would have thrown earlier.
+            let (pnl_values, _) = match MARKET_SETTLEMENTS.may_load(deps.storage, &denom)? {
+                Some(settlement) => ms.compute_pnl(
+                    settlement.delisted_at,
+                    settlement.denom_price,
+                    settlement.base_denom_price,
+                    perp_params,
+                )?,
+                None => {
+                    ms.compute_pnl(current_time, prices[&denom], base_denom_price, perp_params)?
+                }
+            };
 
             Ok(PnlValues {
                 price_pnl: acc.price_pnl.checked_add(pnl_values.price_pnl)?,
@@ -698,7 +814,10 @@ pub fn compute_total_accounting_data(
 mod tests {
     use std::str::FromStr;
 
-    use mars_types::perps::{CashFlow, PnlAmounts};
+    use mars_types::{
+        params::MakerFeeRates,
+        perps::{CashFlow, PnlAmounts},
+    };
     use test_case::test_case;
 
     use super::*;
@@ -747,7 +866,9 @@ mod tests {
             long_oi: Uint128::new(300u128),
             short_oi: Uint128::new(150u128),
             funding: Funding {
-                max_funding_velocity: Decimal::from_str("3").unwrap(),
+                funding_model: FundingModel::Velocity {
+                    max_funding_velocity: Decimal::from_str("3").unwrap(),
+                },
                 skew_scale: Uint128::new(1000000u128),
                 ..Default::default()
             },
@@ -763,7 +884,9 @@ mod tests {
             long_oi: Uint128::new(3000000u128),
             short_oi: Uint128::new(150u128),
             funding: Funding {
-                max_funding_velocity: Decimal::from_str("3").unwrap(),
+                funding_model: FundingModel::Velocity {
+                    max_funding_velocity: Decimal::from_str("3").unwrap(),
+                },
                 skew_scale: Uint128::new(1000000u128),
                 ..Default::default()
             },
@@ -779,7 +902,9 @@ mod tests {
             long_oi: Uint128::new(300u128),
             short_oi: Uint128::new(1500000u128),
             funding: Funding {
-                max_funding_velocity: Decimal::from_str("3").unwrap(),
+                funding_model: FundingModel::Velocity {
+                    max_funding_velocity: Decimal::from_str("3").unwrap(),
+                },
                 skew_scale: Uint128::new(1000000u128),
                 ..Default::default()
             },
@@ -814,6 +939,43 @@ mod tests {
         );
     }
 
+    // skew of -12000 and skew scale of 1000000 give a premium index of -0.012
+    #[test_case("0.005", "0.1", "-0.007"; "premium above clamp")]
+    #[test_case("0.02", "0.1", "0"; "premium within clamp")]
+    #[test_case("0.005", "0.005", "-0.005"; "funding rate capped")]
+    fn current_funding_rate_premium_index(clamp: &str, max_funding_rate: &str, expected: &str) {
+        let mut ms = market_state();
+        ms.funding.funding_model = FundingModel::PremiumIndex {
+            clamp: Decimal::from_str(clamp).unwrap(),
+            max_funding_rate: Decimal::from_str(max_funding_rate).unwrap(),
+        };
+        assert_eq!(
+            ms.current_funding_rate(43400).unwrap(),
+            SignedDecimal::from_str(expected).unwrap()
+        );
+        assert_eq!(ms.current_funding_rate_velocity().unwrap(), SignedDecimal::zero());
+    }
+
+    #[test]
+    fn current_funding_entrance_per_unit_in_base_denom_premium_index() {
+        let mut ms = market_state();
+        ms.funding.funding_model = FundingModel::PremiumIndex {
+            clamp: Decimal::from_str("0.005").unwrap(),
+            max_funding_rate: Decimal::from_str("0.1").unwrap(),
+        };
+
+        // The rate is constant since the last update: -0.007 * 0.5 day * 4 (price)
+        assert_eq!(
+            ms.current_funding_entrance_per_unit_in_base_denom(
+                43400,
+                Decimal::from_str("3.6").unwrap(),
+                Decimal::from_str("0.9").unwrap()
+            )
+            .unwrap(),
+            SignedDecimal::from_str("-0.014").unwrap()
+        );
+    }
+
     #[test]
     fn current_funding_accrued_per_unit_in_base_denom() {
         let ms = market_state();
@@ -1070,12 +1232,73 @@ mod tests {
     fn compute_closing_fee() {
         let ms = market_state();
         assert_eq!(
-            ms.compute_closing_fee(Decimal::percent(2), Decimal::from_str("4200").unwrap())
+            ms.compute_closing_fee(&closing_fee_params(None), Decimal::from_str("4200").unwrap())
                 .unwrap(),
             Int128::from_str("-1493940").unwrap()
         );
     }
 
+    #[test]
+    fn compute_closing_fee_with_maker_rate() {
+        let ms = MarketState {
+            long_oi: Uint128::new(3000u128),
+            short_oi: Uint128::new(15000u128),
+            total_squared_positions: Uint256::from(234000000u128),
+            total_abs_multiplied_positions: Int256::from(-216000000i128),
+            ..market_state()
+        };
+        let exit_price = Decimal::from_str("4200").unwrap();
+
+        // Without maker rate all positions close at the taker rate
+        assert_eq!(
+            ms.compute_closing_fee(&closing_fee_params(None), exit_price).unwrap(),
+            Int128::from_str("-1502928").unwrap()
+        );
+
+        // Shorts reduce the (negative) skew when closing, so they pay the maker rate
+        let maker_fee_rates = MakerFeeRates {
+            opening_fee_rate: Decimal::percent(1),
+            closing_fee_rate: Decimal::percent(1),
+        };
+        assert_eq!(
+            ms.compute_closing_fee(&closing_fee_params(Some(maker_fee_rates)), exit_price).unwrap(),
+            Int128::from_str("-875826").unwrap()
+        );
+    }
+
+    #[test]
+    fn compute_closing_fee_with_overshooting_position() {
+        // Skew is 2, closing the long of 10 overshoots it
+        let ms = MarketState {
+            long_oi: Uint128::new(10u128),
+            short_oi: Uint128::new(8u128),
+            total_squared_positions: Uint256::from(164u128),
+            total_abs_multiplied_positions: Int256::from(36i128),
+            ..market_state()
+        };
+        let exit_price = Decimal::from_str("4200").unwrap();
+        let maker_fee_rates = MakerFeeRates {
+            opening_fee_rate: Decimal::percent(1),
+            closing_fee_rate: Decimal::percent(1),
+        };
+        let perp_params = closing_fee_params(Some(maker_fee_rates));
+
+        // Both positions pay the taker rate when closed
+        let skew = ms.skew().unwrap();
+        assert_eq!(perp_params.closing_fee_rate_at(skew, Int128::new(10)), Decimal::percent(2));
+        assert_eq!(perp_params.closing_fee_rate_at(skew, Int128::new(-8)), Decimal::percent(2));
+        assert_eq!(
+            ms.compute_closing_fee(&closing_fee_params(None), exit_price).unwrap(),
+            Int128::from_str("-1596").unwrap()
+        );
+
+        // The long is accounted at the maker rate
+        assert_eq!(
+            ms.compute_closing_fee(&perp_params, exit_price).unwrap(),
+            Int128::from_str("-1176").unwrap()
+        );
+    }
+
     #[test]
     fn compute_accrued_funding() {
         let ms = market_state();
@@ -1109,7 +1332,7 @@ mod tests {
                 43400,
                 Decimal::from_str("4200").unwrap(),
                 Decimal::from_str("0.8").unwrap(),
-                Decimal::percent(2),
+                &closing_fee_params(None),
             )
             .unwrap();
 
@@ -1133,13 +1356,23 @@ mod tests {
         )
     }
 
+    fn closing_fee_params(maker_fee_rates: Option<MakerFeeRates>) -> PerpParams {
+        PerpParams {
+            closing_fee_rate: Decimal::percent(2),
+            maker_fee_rates,
+            ..Default::default()
+        }
+    }
+
     fn market_state() -> MarketState {
         MarketState {
             enabled: true,
             long_oi: Uint128::new(3000u128),
             short_oi: Uint128::new(15000u128),
             funding: Funding {
-                max_funding_velocity: Decimal::from_str("3").unwrap(),
+                funding_model: FundingModel::Velocity {
+                    max_funding_velocity: Decimal::from_str("3").unwrap(),
+                },
                 skew_scale: Uint128::new(1000000u128),
                 last_funding_rate: SignedDecimal::from_str("-0.025").unwrap(),
                 last_funding_accrued_per_unit_in_base_denom: SignedDecimal::from_str("-12.5")
@@ -10,7 +10,7 @@ use mars_types::{
 use crate::{
     error::{ContractError, ContractResult},
     market::MarketStateExt,
-    state::{CONFIG, MARKET_STATES},
+    state::{CONFIG, MARKET_SETTLEMENTS, MARKET_STATES},
     utils::get_oracle_adapter,
 };
 
@@ -30,6 +30,13 @@ pub fn update_market(
     // Ensure that the sender is authorized to update the parameters
     assert_is_authorized(&deps, &sender, &cfg.address_provider)?;
 
+    // A delisted market can't be updated until it is settled and removed
+    if MARKET_SETTLEMENTS.has(deps.storage, &params.denom) {
+        return Err(ContractError::MarketDelisted {
+            denom: params.denom,
+        });
+    }
+
     // Try to load the existing state for the given market
     let market_state_opt = MARKET_STATES.may_load(deps.storage, &params.denom)?;
 
@@ -50,7 +57,7 @@ pub fn update_market(
         .add_attribute("action", "update_market")
         .add_attribute("denom", params.denom)
         .add_attribute("enabled", params.enabled.to_string())
-        .add_attribute("max_funding_velocity", params.max_funding_velocity.to_string())
+        .add_attribute("funding_model", params.funding_model.to_string())
         .add_attribute("skew_scale", params.skew_scale.to_string()))
 }
 
@@ -74,7 +81,7 @@ fn initialize_market_state(params: &PerpParams, current_time: u64) -> MarketStat
     MarketState {
         enabled: params.enabled,
         funding: Funding {
-            max_funding_velocity: params.max_funding_velocity,
+            funding_model: params.funding_model.clone(),
             skew_scale: params.skew_scale,
             ..Default::default()
         },
@@ -110,7 +117,7 @@ fn update_market_state(
     }
 
     // Update the funding parameters and enable/disable the market
-    market_state.funding.max_funding_velocity = params.max_funding_velocity;
+    market_state.funding.funding_model = params.funding_model.clone();
     market_state.funding.skew_scale = params.skew_scale;
     market_state.enabled = params.enabled;
     market_state.last_updated = current_time;
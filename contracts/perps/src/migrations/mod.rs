@@ -0,0 +1 @@
+pub mod v2_2_1;
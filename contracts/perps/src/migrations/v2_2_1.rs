@@ -0,0 +1,99 @@
+use cosmwasm_std::{DepsMut, Order, Response, StdResult};
+use cw2::{assert_contract_version, set_contract_version};
+use mars_types::{
+    params::FundingModel,
+    perps::{Funding, MarketState},
+};
+
+use crate::{
+    contract::{CONTRACT_NAME, CONTRACT_VERSION},
+    error::ContractResult,
+    state::{MARKET_POSITIONS, MARKET_STATES, POSITIONS},
+};
+
+const FROM_VERSION: &str = "2.2.0";
+
+/// Copy paste of the market state structs from before the funding models were introduced.
+pub mod v2_2_0_state {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{Decimal, Int128, Int256, SignedDecimal, Uint128, Uint256};
+    use cw_storage_plus::Map;
+    use mars_types::perps::CashFlow;
+
+    #[cw_serde]
+    pub struct Funding {
+        pub max_funding_velocity: Decimal,
+        pub skew_scale: Uint128,
+        pub last_funding_rate: SignedDecimal,
+        pub last_funding_accrued_per_unit_in_base_denom: SignedDecimal,
+    }
+
+    #[cw_serde]
+    pub struct MarketState {
+        pub enabled: bool,
+        pub long_oi: Uint128,
+        pub short_oi: Uint128,
+        pub total_entry_cost: Int128,
+        pub total_entry_funding: Int128,
+        pub total_squared_positions: Uint256,
+        pub total_abs_multiplied_positions: Int256,
+        pub cash_flow: CashFlow,
+        pub funding: Funding,
+        pub last_updated: u64,
+    }
+
+    pub const MARKET_STATES: Map<&str, MarketState> = Map::new("markets");
+}
+
+pub fn migrate(deps: DepsMut) -> ContractResult<Response> {
+    // make sure we're migrating the correct contract and from the correct version.
+    // Contracts instantiated at 2.2.0 stored the name without the `crates.io:` prefix.
+    assert_contract_version(deps.storage, CONTRACT_NAME, FROM_VERSION)?;
+
+    // Existing markets keep funding with the velocity model
+    let market_states = v2_2_0_state::MARKET_STATES
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (denom, ms) in market_states {
+        MARKET_STATES.save(deps.storage, &denom, &from_v2_2_0_market_state(ms))?;
+    }
+
+    // Index the open positions by market
+    let position_keys = POSITIONS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (account_id, denom) in position_keys {
+        MARKET_POSITIONS.save(deps.storage, (&denom, &account_id), &())?;
+    }
+
+    set_contract_version(deps.storage, format!("crates.io:{CONTRACT_NAME}"), CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", FROM_VERSION)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+fn from_v2_2_0_market_state(value: v2_2_0_state::MarketState) -> MarketState {
+    MarketState {
+        enabled: value.enabled,
+        long_oi: value.long_oi,
+        short_oi: value.short_oi,
+        total_entry_cost: value.total_entry_cost,
+        total_entry_funding: value.total_entry_funding,
+        total_squared_positions: value.total_squared_positions,
+        total_abs_multiplied_positions: value.total_abs_multiplied_positions,
+        cash_flow: value.cash_flow,
+        funding: Funding {
+            funding_model: FundingModel::Velocity {
+                max_funding_velocity: value.funding.max_funding_velocity,
+            },
+            skew_scale: value.funding.skew_scale,
+            last_funding_rate: value.funding.last_funding_rate,
+            last_funding_accrued_per_unit_in_base_denom: value
+                .funding
+                .last_funding_accrued_per_unit_in_base_denom,
+        },
+        last_updated: value.last_updated,
+    }
+}
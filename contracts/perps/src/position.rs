@@ -2,7 +2,10 @@ use std::cmp::Ordering;
 
 use cosmwasm_std::{Decimal, Fraction, Int128, SignedDecimal, Uint128};
 use mars_perps_common::pricing::{closing_execution_price, opening_execution_price};
-use mars_types::perps::{Funding, PnlAmounts, Position};
+use mars_types::{
+    params::PerpParams,
+    perps::{Funding, PnlAmounts, Position},
+};
 
 use crate::error::{ContractError, ContractResult};
 
@@ -14,8 +17,7 @@ pub trait PositionExt {
         skew: Int128,
         denom_price: Decimal,
         base_denom_price: Decimal,
-        opening_fee_rate: Decimal,
-        closing_fee_rate: Decimal,
+        perp_params: &PerpParams,
         modification: PositionModification,
     ) -> ContractResult<PnlAmounts>;
 }
@@ -27,8 +29,7 @@ impl PositionExt for Position {
         skew: Int128,
         denom_price: Decimal,
         base_denom_price: Decimal,
-        opening_fee_rate: Decimal,
-        closing_fee_rate: Decimal,
+        perp_params: &PerpParams,
         modification: PositionModification,
     ) -> ContractResult<PnlAmounts> {
         let exit_exec_price =
@@ -55,8 +56,7 @@ impl PositionExt for Position {
         // - opening fee if we are increasing size
         // - closing fee if we are reducing size
         let fees = modification.compute_fees(
-            opening_fee_rate,
-            closing_fee_rate,
+            perp_params,
             denom_price,
             base_denom_price,
             skew,
@@ -142,10 +142,11 @@ impl PositionModification {
     /// - For `Increase`: calculates the opening fee.
     /// - For `Decrease`: calculates the closing fee.
     /// - For `Flip`: calculates both the closing fee for the old size and the opening fee for the new size.
+    ///
+    /// Modifications reducing the skew pay the maker fee rates, the others the taker fee rates.
     pub fn compute_fees(
         &self,
-        opening_fee_rate: Decimal,
-        closing_fee_rate: Decimal,
+        perp_params: &PerpParams,
         denom_price: Decimal,
         base_denom_price: Decimal,
         skew: Int128,
@@ -159,6 +160,7 @@ impl PositionModification {
             PositionModification::Increase(size) => {
                 let denom_exec_price =
                     opening_execution_price(skew, skew_scale, *size, denom_price)?;
+                let opening_fee_rate = perp_params.opening_fee_rate_at(skew, *size);
                 let opening_fee =
                     compute_fee(opening_fee_rate, *size, denom_exec_price, base_denom_price)?;
                 let fees = PositionModificationFees {
@@ -174,6 +176,7 @@ impl PositionModification {
             PositionModification::Decrease(size) => {
                 let denom_exec_price =
                     closing_execution_price(skew, skew_scale, *size, denom_price)?;
+                let closing_fee_rate = perp_params.closing_fee_rate_at(skew, *size);
                 let closing_fee =
                     compute_fee(closing_fee_rate, *size, denom_exec_price, base_denom_price)?;
                 let fees = PositionModificationFees {
@@ -195,6 +198,7 @@ impl PositionModification {
                 // Closing the old_size
                 let closing_exec_price =
                     closing_execution_price(skew, skew_scale, *old_size, denom_price)?;
+                let closing_fee_rate = perp_params.closing_fee_rate_at(skew, *old_size);
                 let closing_fee =
                     compute_fee(closing_fee_rate, *old_size, closing_exec_price, base_denom_price)?;
 
@@ -204,6 +208,7 @@ impl PositionModification {
                 // Calculate opening fee for the new_size
                 let opening_exec_price =
                     opening_execution_price(new_skew, skew_scale, *new_size, denom_price)?;
+                let opening_fee_rate = perp_params.opening_fee_rate_at(new_skew, *new_size);
                 let opening_fee =
                     compute_fee(opening_fee_rate, *new_size, opening_exec_price, base_denom_price)?;
 
@@ -264,7 +269,7 @@ mod tests {
     use std::str::FromStr;
 
     use cosmwasm_std::Uint128;
-    use mars_types::perps::PnlAmounts;
+    use mars_types::{params::MakerFeeRates, perps::PnlAmounts};
     use test_case::test_case;
 
     use super::*;
@@ -406,8 +411,10 @@ mod tests {
                 Int128::from_str("280").unwrap(),
                 current_price,
                 Decimal::from_str("0.8").unwrap(),
-                Decimal::zero(),
-                closing_fee,
+                &PerpParams {
+                    closing_fee_rate: closing_fee,
+                    ..Default::default()
+                },
                 PositionModification::Decrease(position.size),
             )
             .unwrap();
@@ -450,8 +457,11 @@ mod tests {
     ) {
         let fees = position
             .compute_fees(
-                Decimal::from_str("0.003").unwrap(),
-                Decimal::from_str("0.005").unwrap(),
+                &PerpParams {
+                    opening_fee_rate: Decimal::from_str("0.003").unwrap(),
+                    closing_fee_rate: Decimal::from_str("0.005").unwrap(),
+                    ..Default::default()
+                },
                 current_price,
                 base_denom_price,
                 Int128::from_str("280").unwrap(),
@@ -461,4 +471,46 @@ mod tests {
         assert_eq!(fees.opening_fee, expected_fees.0);
         assert_eq!(fees.closing_fee, expected_fees.1);
     }
+
+    #[test_case(
+        PositionModification::Increase(Int128::from_str("100").unwrap()),
+        (Int128::from_str("-1501").unwrap(), Int128::zero());
+        "increase skew - taker"
+    )]
+    #[test_case(
+        PositionModification::Increase(Int128::from_str("-100").unwrap()),
+        (Int128::from_str("-501").unwrap(), Int128::zero());
+        "reduce skew by opening - maker"
+    )]
+    #[test_case(
+        PositionModification::Decrease(Int128::from_str("45").unwrap()),
+        (Int128::zero(), Int128::from_str("-451").unwrap());
+        "reduce skew by closing - maker"
+    )]
+    #[test_case(
+        PositionModification::Flip(Int128::from_str("-50").unwrap(),Int128::from_str("35").unwrap()),
+        (Int128::from_str("-251").unwrap(), Int128::from_str("-351").unwrap());
+        "reduce skew by flipping - maker"
+    )]
+    fn computing_maker_taker_fees(position: PositionModification, expected_fees: (Int128, Int128)) {
+        let fees = position
+            .compute_fees(
+                &PerpParams {
+                    opening_fee_rate: Decimal::from_str("0.003").unwrap(),
+                    closing_fee_rate: Decimal::from_str("0.005").unwrap(),
+                    maker_fee_rates: Some(MakerFeeRates {
+                        opening_fee_rate: Decimal::from_str("0.001").unwrap(),
+                        closing_fee_rate: Decimal::from_str("0.002").unwrap(),
+                    }),
+                    ..Default::default()
+                },
+                Decimal::from_str("4000").unwrap(),
+                Decimal::from_str("0.8").unwrap(),
+                Int128::from_str("280").unwrap(),
+                Uint128::new(1000000u128),
+            )
+            .unwrap();
+        assert_eq!(fees.opening_fee, expected_fees.0);
+        assert_eq!(fees.closing_fee, expected_fees.1);
+    }
 }
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use cosmwasm_std::{
     coins, ensure_eq, Addr, Attribute, BankMsg, Coin, CosmosMsg, Decimal, DepsMut, Env, Int128,
-    MessageInfo, Order, Response, StdError, Uint128,
+    MessageInfo, Order, Response, StdError, Storage, Uint128,
 };
 use cw_utils::may_pay;
 use mars_perps_common::pricing::opening_execution_price;
@@ -18,10 +18,13 @@ use crate::{
     error::{ContractError, ContractResult},
     market::MarketStateExt,
     position::{calculate_new_size, PositionExt, PositionModification},
-    state::{CONFIG, MARKET_STATES, POSITIONS, REALIZED_PNL, TOTAL_CASH_FLOW},
+    state::{
+        remove_position, save_position, CONFIG, MARKET_SETTLEMENTS, MARKET_STATES, POSITIONS,
+        REALIZED_PNL, TOTAL_CASH_FLOW,
+    },
     utils::{
-        ensure_max_position, ensure_min_position, get_oracle_adapter, get_params_adapter,
-        update_position_attributes,
+        ensure_acceptable_price, ensure_max_position, ensure_min_position, get_oracle_adapter,
+        get_params_adapter, update_position_attributes,
     },
 };
 
@@ -29,6 +32,7 @@ use crate::{
 ///
 /// Depending on whether a position exists and the reduce_only flag, this function either opens a new
 /// position, modifies an existing one, or returns an error if the operation is illegal.
+#[allow(clippy::too_many_arguments)]
 pub fn execute_order(
     deps: DepsMut,
     env: Env,
@@ -37,7 +41,15 @@ pub fn execute_order(
     denom: String,
     size: Int128,
     reduce_only: Option<bool>,
+    acceptable_price: Option<Decimal>,
 ) -> ContractResult<Response> {
+    // Positions of delisted markets can only be settled
+    if MARKET_SETTLEMENTS.has(deps.storage, &denom) {
+        return Err(ContractError::MarketDelisted {
+            denom,
+        });
+    }
+
     let position = POSITIONS.may_load(deps.storage, (&account_id, &denom))?;
     let reduce_only_checked = reduce_only.unwrap_or(false);
 
@@ -45,10 +57,19 @@ pub fn execute_order(
         None if reduce_only_checked => Err(ContractError::IllegalPositionModification {
             reason: "Cannot open position if reduce_only = true".to_string(),
         }),
-        None => open_position(deps, env, info, account_id, denom, size),
+        None => open_position(deps, env, info, account_id, denom, size, acceptable_price),
         Some(position) => {
             let new_size = calculate_new_size(position.size, size, reduce_only_checked)?;
-            modify_position(deps, env, info, position, account_id, denom, new_size)
+            modify_position(
+                deps,
+                env,
+                info,
+                position,
+                account_id,
+                denom,
+                new_size,
+                acceptable_price,
+            )
         }
     }
 }
@@ -64,6 +85,7 @@ fn open_position(
     account_id: String,
     denom: String,
     size: Int128,
+    acceptable_price: Option<Decimal>,
 ) -> ContractResult<Response> {
     let cfg = CONFIG.load(deps.storage)?;
 
@@ -139,8 +161,7 @@ fn open_position(
     ensure_max_position(position_value, &perp_params)?;
 
     let fees = PositionModification::Increase(size).compute_fees(
-        perp_params.opening_fee_rate,
-        perp_params.closing_fee_rate,
+        &perp_params,
         denom_price,
         base_denom_price,
         ms.skew()?,
@@ -164,6 +185,15 @@ fn open_position(
     // Skew _before_ modification
     let initial_skew = ms.skew()?;
 
+    // The execution price can't be worse than the one accepted by the user
+    ensure_acceptable_price(
+        initial_skew,
+        ms.funding.skew_scale,
+        size,
+        denom_price,
+        acceptable_price,
+    )?;
+
     // Update the denom's accumulators.
     // Funding rates and index is updated to the current block time (using old size).
     ms.open_position(env.block.time.seconds(), size, denom_price, base_denom_price)?;
@@ -206,9 +236,10 @@ fn open_position(
     MARKET_STATES.save(deps.storage, &denom, &ms)?;
 
     // Save the user's new position with updated funding
-    POSITIONS.save(
+    save_position(
         deps.storage,
-        (&account_id, &denom),
+        &account_id,
+        &denom,
         &Position {
             size,
             entry_price: denom_price,
@@ -239,6 +270,7 @@ fn open_position(
 /// This function adjusts the position size based on the provided new size and performs necessary updates
 /// to the position state, including PnL realization, funding rates, and accumulator updates. The function
 /// ensures that the position modifications adhere to the market parameters and the denom's current state.
+#[allow(clippy::too_many_arguments)]
 fn modify_position(
     deps: DepsMut,
     env: Env,
@@ -247,6 +279,7 @@ fn modify_position(
     account_id: String,
     denom: String,
     new_size: Int128,
+    acceptable_price: Option<Decimal>,
 ) -> ContractResult<Response> {
     // Load the contract's configuration
     let cfg = CONFIG.load(deps.storage)?;
@@ -294,6 +327,15 @@ fn modify_position(
     // skew _before_ modification
     let initial_skew = ms.skew()?;
 
+    // The execution price can't be worse than the one accepted by the user
+    ensure_acceptable_price(
+        initial_skew,
+        ms.funding.skew_scale,
+        new_size.checked_sub(entry_size)?,
+        denom_price,
+        acceptable_price,
+    )?;
+
     // Determine the type of modification to the position based on the new size
     let modification = if new_size.is_zero() {
         // Close the position
@@ -334,8 +376,7 @@ fn modify_position(
         initial_skew,
         denom_price,
         base_denom_price,
-        perp_params.opening_fee_rate,
-        perp_params.closing_fee_rate,
+        &perp_params,
         modification,
     )?;
 
@@ -380,7 +421,7 @@ fn modify_position(
     // Modify or delete the position state based on the new size
     let method = if new_size.is_zero() {
         // Delete the position if the new size is zero
-        POSITIONS.remove(deps.storage, (&account_id, &denom));
+        remove_position(deps.storage, &account_id, &denom);
 
         "close_position"
     } else {
@@ -391,9 +432,10 @@ fn modify_position(
         let entry_exec_price =
             opening_execution_price(initial_skew, ms.funding.skew_scale, new_size, denom_price)?;
 
-        POSITIONS.save(
+        save_position(
             deps.storage,
-            (&account_id, &denom),
+            &account_id,
+            &denom,
             &Position {
                 size: new_size,
                 entry_price: denom_price,
@@ -427,6 +469,7 @@ pub fn close_all_positions(
     info: MessageInfo,
     account_id: String,
     action: ActionKind,
+    excluded_denoms: Vec<String>,
 ) -> ContractResult<Response> {
     let cfg = CONFIG.load(deps.storage)?;
 
@@ -451,12 +494,15 @@ pub fn close_all_positions(
     let oracle = get_oracle_adapter(&addresses[&MarsAddressType::Oracle]);
     let params = get_params_adapter(&addresses[&MarsAddressType::Params]);
 
-    // Read all positions for the account
+    // Read all positions for the account, except the excluded ones
     let account_positions: Vec<_> = {
         // Collect all positions for the account to avoid problems with mutable/immutable borrows in the same scope
         POSITIONS
             .prefix(&account_id)
             .range(deps.storage, None, None, Order::Ascending)
+            .filter(|item| {
+                item.as_ref().map_or(true, |(denom, _)| !excluded_denoms.contains(denom))
+            })
             .map(|item| {
                 let (denom, position) = item?;
                 Ok((denom, position))
@@ -486,15 +532,26 @@ pub fn close_all_positions(
         // Params for the given market
         let perp_params = params.query_perp_params(&deps.querier, &denom)?;
 
-        // Prices
-        let denom_price = oracle.query_price(&deps.querier, &denom, action.clone())?.price;
+        // Prices. Positions of delisted markets are settled at the settlement prices, with the
+        // funding frozen at the delisting.
+        let (denom_price, base_denom_price, current_time) = match MARKET_SETTLEMENTS
+            .may_load(deps.storage, &denom)?
+        {
+            Some(settlement) => {
+                (settlement.denom_price, settlement.base_denom_price, settlement.delisted_at)
+            }
+            None => {
+                let denom_price = oracle.query_price(&deps.querier, &denom, action.clone())?.price;
+                (denom_price, base_denom_price, env.block.time.seconds())
+            }
+        };
 
         // skew _before_ modification
         let initial_skew = ms.skew()?;
 
         // Update the denom's accumulators.
         // Funding rates and index is updated to the current block time (using old size).
-        ms.close_position(env.block.time.seconds(), denom_price, base_denom_price, &position)?;
+        ms.close_position(current_time, denom_price, base_denom_price, &position)?;
 
         // Compute the position's unrealized PnL
         let pnl_amounts = position.compute_pnl(
@@ -502,8 +559,7 @@ pub fn close_all_positions(
             initial_skew,
             denom_price,
             base_denom_price,
-            perp_params.opening_fee_rate,
-            perp_params.closing_fee_rate,
+            &perp_params,
             PositionModification::Decrease(position.size),
         )?;
 
@@ -533,7 +589,7 @@ pub fn close_all_positions(
         )?;
 
         // Remove the position
-        POSITIONS.remove(deps.storage, (&account_id, &denom));
+        remove_position(deps.storage, &account_id, &denom);
 
         // Save updated states
         REALIZED_PNL.save(deps.storage, (&account_id, &denom), &realized_pnl)?;
@@ -561,6 +617,139 @@ pub fn close_all_positions(
         .add_attributes(attrs))
 }
 
+/// Closes a single position of an account, whose loss is paid up to the funds sent by the credit
+/// manager (the margin of an isolated position).
+///
+/// If the price gapped past the liquidation price of the position, the loss exceeds the funds.
+/// The unpaid loss is bad debt: it is removed from the price PnL realized by the account, so that
+/// the cash flow of the vault only includes what was actually paid.
+pub fn close_isolated_position(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    account_id: String,
+    denom: String,
+    action: ActionKind,
+) -> ContractResult<Response> {
+    let cfg = CONFIG.load(deps.storage)?;
+
+    let addresses = query_contract_addrs(
+        deps.as_ref(),
+        &cfg.address_provider,
+        vec![
+            MarsAddressType::CreditManager,
+            MarsAddressType::Oracle,
+            MarsAddressType::Params,
+            MarsAddressType::RewardsCollector,
+        ],
+    )?;
+
+    // Only the credit manager contract can adjust positions
+    ensure_eq!(
+        info.sender,
+        addresses[&MarsAddressType::CreditManager],
+        ContractError::SenderIsNotCreditManager
+    );
+
+    if MARKET_SETTLEMENTS.has(deps.storage, &denom) {
+        return Err(ContractError::MarketDelisted {
+            denom,
+        });
+    }
+
+    let position = POSITIONS.may_load(deps.storage, (&account_id, &denom))?.ok_or_else(|| {
+        ContractError::PositionNotFound {
+            account_id: account_id.clone(),
+            denom: denom.clone(),
+        }
+    })?;
+
+    let oracle = get_oracle_adapter(&addresses[&MarsAddressType::Oracle]);
+    let params = get_params_adapter(&addresses[&MarsAddressType::Params]);
+
+    let paid_amount = may_pay(&info, &cfg.base_denom)?;
+
+    let denom_price = oracle.query_price(&deps.querier, &denom, action.clone())?.price;
+    let base_denom_price = oracle.query_price(&deps.querier, &cfg.base_denom, action)?.price;
+    let perp_params = params.query_perp_params(&deps.querier, &denom)?;
+
+    let mut realized_pnl =
+        REALIZED_PNL.may_load(deps.storage, (&account_id, &denom))?.unwrap_or_default();
+    let mut ms = MARKET_STATES.load(deps.storage, &denom)?;
+    let mut tcf = TOTAL_CASH_FLOW.may_load(deps.storage)?.unwrap_or_default();
+
+    // skew _before_ modification
+    let initial_skew = ms.skew()?;
+
+    ms.close_position(env.block.time.seconds(), denom_price, base_denom_price, &position)?;
+
+    let mut pnl_amounts = position.compute_pnl(
+        &ms.funding,
+        initial_skew,
+        denom_price,
+        base_denom_price,
+        &perp_params,
+        PositionModification::Decrease(position.size),
+    )?;
+
+    let mut attrs = vec![];
+    update_position_attributes(
+        &mut attrs,
+        &denom,
+        &position,
+        Int128::zero(),
+        denom_price,
+        initial_skew,
+        ms.funding.last_funding_accrued_per_unit_in_base_denom,
+        &pnl_amounts,
+    );
+
+    // The loss is capped at the paid amount, the rest is bad debt of the vault
+    let bad_debt = match pnl_amounts.to_coins(&cfg.base_denom).pnl {
+        PnL::Loss(loss) if loss.amount > paid_amount => loss.amount - paid_amount,
+        _ => Uint128::zero(),
+    };
+    if !bad_debt.is_zero() {
+        let bad_debt = Int128::try_from(bad_debt)?;
+        pnl_amounts.price_pnl = pnl_amounts.price_pnl.checked_add(bad_debt)?;
+        pnl_amounts.pnl = pnl_amounts.pnl.checked_add(bad_debt)?;
+    }
+
+    let mut msgs = vec![];
+    apply_pnl_and_fees(
+        &cfg,
+        &addresses[&MarsAddressType::RewardsCollector],
+        &mut ms,
+        &mut tcf,
+        &mut realized_pnl,
+        &pnl_amounts,
+        &mut attrs,
+        &mut msgs,
+    )?;
+
+    let pnl = pnl_amounts.to_coins(&cfg.base_denom).pnl;
+    apply_payment_to_cm_if_needed(
+        &cfg,
+        &addresses[&MarsAddressType::CreditManager],
+        &mut msgs,
+        paid_amount,
+        &pnl,
+    )?;
+
+    remove_position(deps.storage, &account_id, &denom);
+    REALIZED_PNL.save(deps.storage, (&account_id, &denom), &realized_pnl)?;
+    MARKET_STATES.save(deps.storage, &denom, &ms)?;
+    TOTAL_CASH_FLOW.save(deps.storage, &tcf)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "close_isolated_position")
+        .add_attribute("account_id", account_id)
+        .add_attribute("realized_pnl_change", pnl_amounts.pnl.to_string())
+        .add_attribute("bad_debt", bad_debt.to_string())
+        .add_attributes(attrs))
+}
+
 /// Adjusts the position size with validation and determines the type of position modification.
 ///
 /// This function takes the new position size and validates it against the current position size,
@@ -684,6 +873,42 @@ pub fn apply_pnl_and_fees(
     Ok(())
 }
 
+/// Writes off the part of a realized PnL the credit manager couldn't settle. A positive amount is
+/// an unpaid loss (bad debt of the vault), a negative amount a profit which couldn't be credited
+/// to the account. It is taken into the price PnL of the account and of the cash flows, the market
+/// cash flow is only updated if the market wasn't removed in the meantime.
+pub fn write_off_pnl(
+    storage: &mut dyn Storage,
+    account_id: &str,
+    denom: &str,
+    amount: Int128,
+) -> ContractResult<()> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+
+    let written_off = PnlAmounts {
+        price_pnl: amount,
+        pnl: amount,
+        ..Default::default()
+    };
+
+    let mut realized_pnl = REALIZED_PNL.may_load(storage, (account_id, denom))?.unwrap_or_default();
+    realized_pnl.add(&written_off)?;
+    REALIZED_PNL.save(storage, (account_id, denom), &realized_pnl)?;
+
+    if let Some(mut ms) = MARKET_STATES.may_load(storage, denom)? {
+        ms.cash_flow.add(&written_off, Uint128::zero())?;
+        MARKET_STATES.save(storage, denom, &ms)?;
+    }
+
+    let mut tcf = TOTAL_CASH_FLOW.may_load(storage)?.unwrap_or_default();
+    tcf.add(&written_off, Uint128::zero())?;
+    TOTAL_CASH_FLOW.save(storage, &tcf)?;
+
+    Ok(())
+}
+
 /// Applies payments to the credit manager if necessary based on the PnL and paid amount.
 fn apply_payment_to_cm_if_needed(
     cfg: &Config<Addr>,
@@ -12,9 +12,10 @@ use mars_types::{
     oracle::ActionKind,
     params::PerpParams,
     perps::{
-        AccountingResponse, Config, MarketResponse, MarketState, MarketStateResponse, PerpPosition,
-        PnlAmounts, PositionFeesResponse, PositionResponse, PositionsByAccountResponse, TradingFee,
-        VaultDeposit, VaultPositionResponse, VaultResponse, VaultUnlock,
+        AccountingResponse, Config, MarketResponse, MarketSettlement, MarketState,
+        MarketStateResponse, PerpPosition, PnlAmounts, PositionFeesResponse, PositionResponse,
+        PositionsByAccountResponse, TradingFee, VaultDeposit, VaultPositionResponse, VaultResponse,
+        VaultUnlock,
     },
 };
 
@@ -24,7 +25,7 @@ use crate::{
     market::{compute_total_accounting_data, MarketStateExt},
     position::{PositionExt, PositionModification},
     state::{
-        CONFIG, DEPOSIT_SHARES, MARKET_STATES, POSITIONS, REALIZED_PNL,
+        CONFIG, DEPOSIT_SHARES, MARKET_SETTLEMENTS, MARKET_STATES, POSITIONS, REALIZED_PNL,
         TOTAL_UNLOCKING_OR_UNLOCKED_SHARES, UNLOCKS, VAULT_STATE,
     },
     utils::{create_user_id_key, get_oracle_adapter, get_params_adapter},
@@ -311,8 +312,7 @@ pub fn query_position(
         ms.skew()?,
         denom_price,
         base_denom_price,
-        perp_params.opening_fee_rate,
-        perp_params.closing_fee_rate,
+        &perp_params,
         modification,
     )?;
 
@@ -331,6 +331,7 @@ pub fn query_position(
             current_exec_price: exit_exec_price,
             unrealized_pnl: pnl_amounts,
             realized_pnl: position.realized_pnl,
+            isolated_margin: None,
         }),
     })
 }
@@ -399,8 +400,7 @@ pub fn query_positions(
                 skew,
                 current_price,
                 base_denom_price,
-                perp_params.opening_fee_rate,
-                perp_params.closing_fee_rate,
+                &perp_params,
                 PositionModification::Decrease(position.size),
             )?;
 
@@ -419,6 +419,7 @@ pub fn query_positions(
                     current_exec_price: exit_exec_price,
                     unrealized_pnl: pnl_amounts,
                     realized_pnl: position.realized_pnl,
+                    isolated_margin: None,
                 }),
             })
         })
@@ -457,17 +458,31 @@ pub fn query_positions_by_account(
             let (denom, position) = item?;
             let perp_params = params.query_perp_params(&deps.querier, &denom)?;
 
-            let base_denom_price = if let Some(price) = base_denom_price {
-                price
-            } else {
-                let price =
-                    oracle.query_price(&deps.querier, &cfg.base_denom, action.clone())?.price;
-                base_denom_price = Some(price);
-                price
+            // Positions of delisted markets are valued as they will be settled
+            let (denom_price, base_denom_price, current_time) = match MARKET_SETTLEMENTS
+                .may_load(deps.storage, &denom)?
+            {
+                Some(settlement) => {
+                    (settlement.denom_price, settlement.base_denom_price, settlement.delisted_at)
+                }
+                None => {
+                    let base_denom_price = if let Some(price) = base_denom_price {
+                        price
+                    } else {
+                        let price = oracle
+                            .query_price(&deps.querier, &cfg.base_denom, action.clone())?
+                            .price;
+                        base_denom_price = Some(price);
+                        price
+                    };
+
+                    let denom_price =
+                        oracle.query_price(&deps.querier, &denom, action.clone())?.price;
+
+                    (denom_price, base_denom_price, current_time)
+                }
             };
 
-            let denom_price = oracle.query_price(&deps.querier, &denom, action.clone())?.price;
-
             let ms = MARKET_STATES.load(deps.storage, &denom)?;
             let curr_funding = ms.current_funding(current_time, denom_price, base_denom_price)?;
 
@@ -476,8 +491,7 @@ pub fn query_positions_by_account(
                 ms.skew()?,
                 denom_price,
                 base_denom_price,
-                perp_params.opening_fee_rate,
-                perp_params.closing_fee_rate,
+                &perp_params,
                 PositionModification::Decrease(position.size),
             )?;
 
@@ -498,6 +512,7 @@ pub fn query_positions_by_account(
                 current_exec_price: exit_exec_price,
                 unrealized_pnl: pnl_amounts,
                 realized_pnl: position.realized_pnl,
+                isolated_margin: None,
             })
         })
         .collect::<ContractResult<Vec<_>>>()?;
@@ -545,12 +560,8 @@ pub fn query_market_accounting(
         oracle.query_price(&deps.querier, &cfg.base_denom, ActionKind::Default)?.price;
 
     let ms = MARKET_STATES.load(deps.storage, denom)?;
-    let (accounting, unrealized_pnl) = ms.compute_accounting_data(
-        current_time,
-        denom_price,
-        base_denom_price,
-        perp_params.closing_fee_rate,
-    )?;
+    let (accounting, unrealized_pnl) =
+        ms.compute_accounting_data(current_time, denom_price, base_denom_price, &perp_params)?;
 
     Ok(AccountingResponse {
         accounting,
@@ -610,17 +621,17 @@ pub fn query_opening_fee(deps: Deps, denom: &str, size: Int128) -> ContractResul
     let denom_price = oracle.query_price(&deps.querier, denom, ActionKind::Default)?.price;
     let perp_params = params.query_perp_params(&deps.querier, denom)?;
 
+    let skew = ms.skew()?;
     let fees = PositionModification::Increase(size).compute_fees(
-        perp_params.opening_fee_rate,
-        perp_params.closing_fee_rate,
+        &perp_params,
         denom_price,
         base_denom_price,
-        ms.skew()?,
+        skew,
         perp_params.skew_scale,
     )?;
 
     Ok(TradingFee {
-        rate: perp_params.opening_fee_rate,
+        rate: perp_params.opening_fee_rate_at(skew, size),
         fee: coin(fees.opening_fee.unsigned_abs().u128(), cfg.base_denom),
     })
 }
@@ -688,8 +699,7 @@ pub fn query_position_fees(
         }
     };
     let fees = modification.compute_fees(
-        perp_params.opening_fee_rate,
-        perp_params.closing_fee_rate,
+        &perp_params,
         denom_price,
         base_denom_price,
         skew,
@@ -713,3 +723,11 @@ pub fn query_market_state(store: &dyn Storage, denom: String) -> StdResult<Marke
         market_state: ms,
     })
 }
+
+/// Retrieves the settlement of a delisted market, `None` if the market isn't delisted.
+pub fn query_market_settlement(
+    store: &dyn Storage,
+    denom: String,
+) -> StdResult<Option<MarketSettlement>> {
+    MARKET_SETTLEMENTS.may_load(store, &denom)
+}
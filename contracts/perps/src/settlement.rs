@@ -0,0 +1,303 @@
+use cosmwasm_std::{
+    coins, to_json_binary, Addr, BalanceResponse, BankQuery, CosmosMsg, DepsMut, Env, Int128,
+    Order, QueryRequest, Reply, Response, StdResult, SubMsg, SubMsgResult, Uint128, WasmMsg,
+};
+use mars_types::{
+    address_provider::{
+        helpers::{query_contract_addr, query_contract_addrs},
+        MarsAddressType,
+    },
+    credit_manager::ExecuteMsg,
+    oracle::ActionKind,
+    perps::MarketSettlement,
+};
+
+use crate::{
+    error::{ContractError, ContractResult},
+    market::MarketStateExt,
+    position::{PositionExt, PositionModification},
+    position_management::{apply_pnl_and_fees, write_off_pnl},
+    state::{
+        remove_position, SettlementRequestsTempStorage, CONFIG, MARKET_POSITIONS,
+        MARKET_SETTLEMENTS, MARKET_STATES, OWNER, POSITIONS, REALIZED_PNL,
+        SETTLEMENT_REQUESTS_TEMP_STORAGE, TOTAL_CASH_FLOW,
+    },
+    utils::{get_oracle_adapter, get_params_adapter},
+};
+
+pub const SETTLEMENT_REQUEST_REPLY_ID: u64 = 10_002;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// Delists a market so that it can be retired.
+///
+/// Trading is stopped and the current oracle prices are kept as the settlement prices of the
+/// market. Funding is accrued up to the delisting and frozen afterwards, so that every position
+/// is settled with the same prices and funding no matter when `SettleMarket` is called.
+pub fn delist_market(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    denom: String,
+) -> ContractResult<Response> {
+    OWNER.assert_owner(deps.storage, &sender)?;
+
+    if MARKET_SETTLEMENTS.has(deps.storage, &denom) {
+        return Err(ContractError::MarketDelisted {
+            denom,
+        });
+    }
+
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut ms = MARKET_STATES.load(deps.storage, &denom)?;
+
+    // Current block time
+    let current_time = env.block.time.seconds();
+
+    let oracle_addr =
+        query_contract_addr(deps.as_ref(), &cfg.address_provider, MarsAddressType::Oracle)?;
+    let oracle = get_oracle_adapter(&oracle_addr);
+
+    // Snapshot the prices the positions are settled at
+    let denom_price = oracle.query_price(&deps.querier, &denom, ActionKind::Default)?.price;
+    let base_denom_price =
+        oracle.query_price(&deps.querier, &cfg.base_denom, ActionKind::Default)?.price;
+
+    // Accrue funding up to the delisting, it isn't updated anymore
+    ms.funding = ms.current_funding(current_time, denom_price, base_denom_price)?;
+    ms.last_updated = current_time;
+    ms.enabled = false;
+    MARKET_STATES.save(deps.storage, &denom, &ms)?;
+
+    let settlement = MarketSettlement {
+        denom_price,
+        base_denom_price,
+        delisted_at: current_time,
+    };
+    MARKET_SETTLEMENTS.save(deps.storage, &denom, &settlement)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "delist_market")
+        .add_attribute("denom", denom)
+        .add_attribute("denom_price", denom_price.to_string())
+        .add_attribute("base_denom_price", base_denom_price.to_string())
+        .add_attribute("delisted_at", current_time.to_string()))
+}
+
+/// Settles up to `limit` open positions of a delisted market.
+///
+/// Positions are closed at the settlement prices and funding of the market (fees are charged as
+/// for any other close). The PnL of each account is paid through the credit manager, the same way
+/// as after a deleverage. Each account is updated in its own submessage, the funds sent back for
+/// losses are verified in the reply. The PnL the credit manager can't settle (e.g. the loss of an
+/// isolated position exceeding its margin, or an account which can't pay its loss) is written off,
+/// so that a single account can't block the settlement of the market. Once the last position is
+/// settled, the market is removed.
+pub fn settle_market(
+    deps: DepsMut,
+    env: Env,
+    denom: String,
+    limit: Option<u32>,
+) -> ContractResult<Response> {
+    let settlement = MARKET_SETTLEMENTS.may_load(deps.storage, &denom)?.ok_or_else(|| {
+        ContractError::MarketNotDelisted {
+            denom: denom.clone(),
+        }
+    })?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let cfg = CONFIG.load(deps.storage)?;
+
+    // Settled positions are removed from the market index, hence the next batch starts from the
+    // beginning again
+    let positions = MARKET_POSITIONS
+        .prefix(&denom)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .take(limit)
+        .map(|account_id| {
+            let account_id = account_id?;
+            let position = POSITIONS.load(deps.storage, (&account_id, &denom))?;
+            Ok((account_id, position))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    // The last batch settles all remaining positions
+    let fully_settled = positions.len() < limit;
+
+    let addresses = query_contract_addrs(
+        deps.as_ref(),
+        &cfg.address_provider,
+        vec![
+            MarsAddressType::CreditManager,
+            MarsAddressType::Params,
+            MarsAddressType::RewardsCollector,
+        ],
+    )?;
+
+    let mut ms = MARKET_STATES.load(deps.storage, &denom)?;
+    let mut tcf = TOTAL_CASH_FLOW.may_load(deps.storage)?.unwrap_or_default();
+
+    let mut attrs = vec![];
+    let mut msgs = vec![];
+    let mut cm_msgs = vec![];
+    let mut requests = vec![];
+    if !positions.is_empty() {
+        let params = get_params_adapter(&addresses[&MarsAddressType::Params]);
+        let perp_params = params.query_perp_params(&deps.querier, &denom)?;
+
+        for (account_id, position) in positions {
+            let mut realized_pnl =
+                REALIZED_PNL.may_load(deps.storage, (&account_id, &denom))?.unwrap_or_default();
+
+            // Close the position at the settlement prices. Funding was frozen at the delisting.
+            let initial_skew = ms.skew()?;
+            ms.close_position(
+                settlement.delisted_at,
+                settlement.denom_price,
+                settlement.base_denom_price,
+                &position,
+            )?;
+
+            let pnl_amounts = position.compute_pnl(
+                &ms.funding,
+                initial_skew,
+                settlement.denom_price,
+                settlement.base_denom_price,
+                &perp_params,
+                PositionModification::Decrease(position.size),
+            )?;
+
+            apply_pnl_and_fees(
+                &cfg,
+                &addresses[&MarsAddressType::RewardsCollector],
+                &mut ms,
+                &mut tcf,
+                &mut realized_pnl,
+                &pnl_amounts,
+                &mut attrs,
+                &mut msgs,
+            )?;
+
+            remove_position(deps.storage, &account_id, &denom);
+            REALIZED_PNL.save(deps.storage, (&account_id, &denom), &realized_pnl)?;
+
+            // Profits are sent along, losses are requested from the credit manager
+            let pnl = pnl_amounts.to_coins(&cfg.base_denom).pnl;
+            let signed_uint_pnl = pnl.to_signed_uint()?;
+            let funds = if signed_uint_pnl > Int128::zero() {
+                coins(signed_uint_pnl.unsigned_abs().u128(), cfg.base_denom.clone())
+            } else {
+                vec![]
+            };
+
+            attrs.push(("settled_account_id", account_id.clone()).into());
+            attrs.push(("settled_pnl", pnl.to_string()).into());
+
+            let msg = CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: addresses[&MarsAddressType::CreditManager].to_string(),
+                msg: to_json_binary(&ExecuteMsg::UpdateBalanceAfterDeleverage {
+                    account_id: account_id.clone(),
+                    pnl,
+                })?,
+                funds,
+            });
+            cm_msgs.push(SubMsg::reply_always(msg, SETTLEMENT_REQUEST_REPLY_ID));
+            requests.push((account_id, signed_uint_pnl));
+        }
+    }
+
+    TOTAL_CASH_FLOW.save(deps.storage, &tcf)?;
+    if fully_settled {
+        MARKET_STATES.remove(deps.storage, &denom);
+        MARKET_SETTLEMENTS.remove(deps.storage, &denom);
+    } else {
+        MARKET_STATES.save(deps.storage, &denom, &ms)?;
+    }
+
+    // The funds sent back by the credit manager are verified after each account is updated, before
+    // the protocol fees leave the contract
+    if !requests.is_empty() {
+        let balance_res: BalanceResponse =
+            deps.querier.query(&QueryRequest::Bank(BankQuery::Balance {
+                address: env.contract.address.to_string(),
+                denom: cfg.base_denom.clone(),
+            }))?;
+        let temp_storage = SettlementRequestsTempStorage {
+            denom: cfg.base_denom.clone(),
+            market_denom: denom.clone(),
+            contract_balance: balance_res.amount.amount,
+            requests,
+        };
+        SETTLEMENT_REQUESTS_TEMP_STORAGE.save(deps.storage, &temp_storage)?;
+    }
+
+    Ok(Response::new()
+        .add_submessages(cm_msgs)
+        .add_messages(msgs)
+        .add_attribute("action", "settle_market")
+        .add_attribute("denom", denom)
+        .add_attribute("fully_settled", fully_settled.to_string())
+        .add_attributes(attrs))
+}
+
+/// Handles the reply from the credit manager after updating the balance of a settled account.
+///
+/// The funds received are compared with the loss requested from the account. Whatever the credit
+/// manager couldn't settle is written off: the part of the loss it didn't pay, or the whole PnL if
+/// the update failed (its funds are returned to the contract in that case).
+pub fn handle_settlement_request_reply(
+    deps: DepsMut,
+    env: Env,
+    reply: Reply,
+) -> ContractResult<Response> {
+    let mut temp_storage = SETTLEMENT_REQUESTS_TEMP_STORAGE.load(deps.storage)?;
+    if temp_storage.requests.is_empty() {
+        return Err(ContractError::ReplyIdError(reply.id));
+    }
+    let (account_id, pnl) = temp_storage.requests.remove(0);
+
+    let balance_res: BalanceResponse =
+        deps.querier.query(&QueryRequest::Bank(BankQuery::Balance {
+            address: env.contract.address.to_string(),
+            denom: temp_storage.denom.clone(),
+        }))?;
+    let balance = balance_res.amount.amount;
+
+    let mut response = Response::new().add_attribute("action", "settle_market/handle_reply");
+    let written_off_pnl = match reply.result {
+        SubMsgResult::Ok(_) => {
+            // Profits are sent along the message, losses are sent back by the credit manager
+            let (sent_amount, requested_amount) = if pnl > Int128::zero() {
+                (pnl.unsigned_abs(), Uint128::zero())
+            } else {
+                (Uint128::zero(), pnl.unsigned_abs())
+            };
+            let received_amount =
+                balance.checked_add(sent_amount)?.checked_sub(temp_storage.contract_balance)?;
+            if received_amount > requested_amount {
+                return Err(ContractError::InvalidFundsAfterDeleverage {
+                    expected: requested_amount,
+                    received: received_amount,
+                });
+            }
+            Int128::try_from(requested_amount - received_amount)?
+        }
+        SubMsgResult::Err(err) => {
+            response = response.add_attribute("error", err);
+            Int128::zero().checked_sub(pnl)?
+        }
+    };
+    write_off_pnl(deps.storage, &account_id, &temp_storage.market_denom, written_off_pnl)?;
+
+    temp_storage.contract_balance = balance;
+    if temp_storage.requests.is_empty() {
+        SETTLEMENT_REQUESTS_TEMP_STORAGE.remove(deps.storage);
+    } else {
+        SETTLEMENT_REQUESTS_TEMP_STORAGE.save(deps.storage, &temp_storage)?;
+    }
+
+    Ok(response
+        .add_attribute("account_id", account_id)
+        .add_attribute("written_off_pnl", written_off_pnl.to_string()))
+}
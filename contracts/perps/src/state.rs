@@ -1,10 +1,13 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, StdError, StdResult, Storage, Uint128};
+use cosmwasm_std::{Addr, Int128, StdError, StdResult, Storage, Uint128};
 use cw_storage_plus::{Item, Map};
 use mars_owner::Owner;
 use mars_types::{
     keys::UserIdKey,
-    perps::{CashFlow, Config, MarketState, PnlAmounts, Position, UnlockState, VaultState},
+    perps::{
+        CashFlow, Config, MarketSettlement, MarketState, PnlAmounts, Position, UnlockState,
+        VaultState,
+    },
 };
 
 #[cw_serde]
@@ -17,6 +20,27 @@ pub struct DeleverageRequestTempStorage {
 
     /// Requested amount of the denom from Credit Manager contract (to cover PnL loss)
     pub requested_amount: Uint128,
+
+    /// Account of the deleveraged position
+    pub account_id: String,
+
+    /// Market of the deleveraged position
+    pub market_denom: String,
+}
+
+#[cw_serde]
+pub struct SettlementRequestsTempStorage {
+    /// Denom of the PnL paid to or requested from Credit Manager contract
+    pub denom: String,
+
+    /// Market being settled
+    pub market_denom: String,
+
+    /// Contract balance before the next account is updated by Credit Manager contract
+    pub contract_balance: Uint128,
+
+    /// Settled accounts with their PnL, in the order they are updated by Credit Manager contract
+    pub requests: Vec<(String, Int128)>,
 }
 
 pub const OWNER: Owner = Owner::new("owner");
@@ -28,6 +52,9 @@ pub const VAULT_STATE: Item<VaultState> = Item::new("vault");
 // denom => market state
 pub const MARKET_STATES: Map<&str, MarketState> = Map::new("markets");
 
+// denom => settlement of a delisted market
+pub const MARKET_SETTLEMENTS: Map<&str, MarketSettlement> = Map::new("market_settlements");
+
 // (user, account id) => shares
 pub const DEPOSIT_SHARES: Map<&UserIdKey, Uint128> = Map::new("deposit_shares");
 
@@ -37,6 +64,9 @@ pub const UNLOCKS: Map<&UserIdKey, Vec<UnlockState>> = Map::new("unlocks");
 // (account_id, denom) => position
 pub const POSITIONS: Map<(&str, &str), Position> = Map::new("positions");
 
+// (denom, account_id) => (), index of the positions of a market
+pub const MARKET_POSITIONS: Map<(&str, &str), ()> = Map::new("market_positions");
+
 // (account_id, denom) => realized PnL amounts
 pub const REALIZED_PNL: Map<(&str, &str), PnlAmounts> = Map::new("realized_pnls");
 
@@ -50,10 +80,30 @@ pub const TOTAL_CASH_FLOW: Item<CashFlow> = Item::new("total_cf");
 pub const DELEVERAGE_REQUEST_TEMP_STORAGE: Item<DeleverageRequestTempStorage> =
     Item::new("deleverage_req_temp_var");
 
+pub const SETTLEMENT_REQUESTS_TEMP_STORAGE: Item<SettlementRequestsTempStorage> =
+    Item::new("settlement_reqs_temp_var");
+
 // Total unlocking shares across all users
 pub const TOTAL_UNLOCKING_OR_UNLOCKED_SHARES: Item<Uint128> =
     Item::new("total_unlocking_or_unlocked_shares");
 
+/// Save the position of an account, indexing it by the denom of its market
+pub fn save_position(
+    store: &mut dyn Storage,
+    account_id: &str,
+    denom: &str,
+    position: &Position,
+) -> StdResult<()> {
+    POSITIONS.save(store, (account_id, denom), position)?;
+    MARKET_POSITIONS.save(store, (denom, account_id), &())
+}
+
+/// Remove the position of an account along with its market index entry
+pub fn remove_position(store: &mut dyn Storage, account_id: &str, denom: &str) {
+    POSITIONS.remove(store, (account_id, denom));
+    MARKET_POSITIONS.remove(store, (denom, account_id));
+}
+
 /// Increase the deposit shares of a depositor by the given amount.
 /// Return the updated deposit shares.
 pub fn increase_deposit_shares(
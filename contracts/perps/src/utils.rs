@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use cosmwasm_std::{
     Addr, Attribute, Decimal, Deps, Int128, Order, SignedDecimal, StdResult, Uint128,
 };
+use mars_perps_common::pricing::opening_execution_price;
 use mars_types::{
     adapters::{
         oracle::{Oracle, OracleBase},
@@ -16,7 +17,7 @@ use mars_types::{
 
 use crate::{
     error::{ContractError, ContractResult},
-    state::MARKET_STATES,
+    state::{MARKET_SETTLEMENTS, MARKET_STATES},
 };
 
 pub fn ensure_min_position(
@@ -48,6 +49,39 @@ pub fn ensure_max_position(
     Ok(())
 }
 
+/// Ensures the execution price of an order (the oracle price adjusted for the order's impact on
+/// the skew) is not worse than the acceptable price, if the order has one
+pub fn ensure_acceptable_price(
+    skew: Int128,
+    skew_scale: Uint128,
+    order_size: Int128,
+    denom_price: Decimal,
+    acceptable_price: Option<Decimal>,
+) -> ContractResult<()> {
+    let Some(acceptable_price) = acceptable_price else {
+        return Ok(());
+    };
+    if order_size.is_zero() {
+        return Ok(());
+    }
+
+    let exec_price = opening_execution_price(skew, skew_scale, order_size, denom_price)?;
+
+    // Buying above or selling below the acceptable price is not allowed
+    let worse = if order_size.is_negative() {
+        exec_price < acceptable_price
+    } else {
+        exec_price > acceptable_price
+    };
+    if worse {
+        return Err(ContractError::UnacceptableExecutionPrice {
+            exec_price,
+            acceptable_price,
+        });
+    }
+    Ok(())
+}
+
 pub fn create_user_id_key(
     user_addr: &Addr,
     account_id: Option<String>,
@@ -64,8 +98,12 @@ pub fn get_markets_and_base_denom_prices(
     base_denom: &str,
     action: ActionKind,
 ) -> StdResult<HashMap<String, Decimal>> {
+    // Delisted markets are valued at their settlement prices
     let mut denoms = MARKET_STATES
         .keys(deps.storage, None, None, Order::Ascending)
+        .filter(|denom| {
+            denom.as_ref().map_or(true, |denom| !MARKET_SETTLEMENTS.has(deps.storage, denom))
+        })
         .collect::<StdResult<Vec<_>>>()?;
 
     if !denoms.contains(&base_denom.to_string()) {
@@ -214,6 +214,28 @@ impl MockEnv {
         size: Int128,
         reduce_only: Option<bool>,
         funds: &[Coin],
+    ) -> AnyResult<AppResponse> {
+        self.execute_perp_order_with_acceptable_price(
+            sender,
+            account_id,
+            denom,
+            size,
+            reduce_only,
+            None,
+            funds,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute_perp_order_with_acceptable_price(
+        &mut self,
+        sender: &Addr,
+        account_id: &str,
+        denom: &str,
+        size: Int128,
+        reduce_only: Option<bool>,
+        acceptable_price: Option<Decimal>,
+        funds: &[Coin],
     ) -> AnyResult<AppResponse> {
         self.app.execute_contract(
             sender.clone(),
@@ -223,6 +245,7 @@ impl MockEnv {
                 denom: denom.to_string(),
                 size,
                 reduce_only,
+                acceptable_price,
             },
             funds,
         )
@@ -240,6 +263,7 @@ impl MockEnv {
             &perps::ExecuteMsg::CloseAllPositions {
                 account_id: account_id.to_string(),
                 action: None,
+                excluded_denoms: None,
             },
             funds,
         )
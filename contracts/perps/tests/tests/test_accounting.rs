@@ -4,7 +4,7 @@ use cosmwasm_std::{coin, Coin, Decimal, Int128, Uint128};
 use cw_paginate::MAX_LIMIT;
 use mars_perps::error::ContractError;
 use mars_types::{
-    params::{PerpParams, PerpParamsUpdate},
+    params::{FundingModel, PerpParams, PerpParamsUpdate},
     perps::{Accounting, Balance, CashFlow, PerpPosition, PnL, VaultResponse},
 };
 use test_case::test_case;
@@ -43,7 +43,9 @@ fn accounting() {
             params: PerpParams {
                 closing_fee_rate: Decimal::percent(1),
                 opening_fee_rate: Decimal::percent(2),
-                max_funding_velocity: Decimal::from_str("32").unwrap(),
+                funding_model: FundingModel::Velocity {
+                    max_funding_velocity: Decimal::from_str("32").unwrap(),
+                },
                 ..default_perp_params("uosmo")
             },
         },
@@ -54,7 +56,9 @@ fn accounting() {
             params: PerpParams {
                 closing_fee_rate: Decimal::percent(1),
                 opening_fee_rate: Decimal::percent(2),
-                max_funding_velocity: Decimal::from_str("30").unwrap(),
+                funding_model: FundingModel::Velocity {
+                    max_funding_velocity: Decimal::from_str("30").unwrap(),
+                },
                 ..default_perp_params("uatom")
             },
         },
@@ -438,11 +442,15 @@ fn accounting_works_up_to_oi_limits(
                 max_short_oi_value,
                 closing_fee_rate: Decimal::from_str("0.00075").unwrap(),
                 opening_fee_rate: Decimal::from_str("0.00075").unwrap(),
+                maker_fee_rates: None,
                 liquidation_threshold: Decimal::from_str("0.91").unwrap(),
+                margin_tiers: vec![],
                 max_loan_to_value: Decimal::from_str("0.90").unwrap(),
                 max_position_value: None,
                 min_position_value: Uint128::zero(),
-                max_funding_velocity: Decimal::from_str("36").unwrap(),
+                funding_model: FundingModel::Velocity {
+                    max_funding_velocity: Decimal::from_str("36").unwrap(),
+                },
                 skew_scale: Uint128::new(1186268000000000000000000u128),
             },
         },
@@ -4,7 +4,7 @@ use cosmwasm_std::{coin, Addr, Decimal, Int128, SignedDecimal, Uint128};
 use mars_perps::{error::ContractError, market::SECONDS_IN_DAY};
 use mars_types::{
     error::MarsError,
-    params::{EmergencyUpdate, PerpParams, PerpParamsUpdate, PerpsEmergencyUpdate},
+    params::{EmergencyUpdate, FundingModel, PerpParams, PerpParamsUpdate, PerpsEmergencyUpdate},
     perps::{Funding, MarketResponse, MarketState, MarketStateResponse},
 };
 
@@ -28,7 +28,9 @@ fn initialize_market() {
     mock.update_market(
         &params_addr,
         PerpParams {
-            max_funding_velocity: Decimal::from_str("3").unwrap(),
+            funding_model: FundingModel::Velocity {
+                max_funding_velocity: Decimal::from_str("3").unwrap(),
+            },
             skew_scale: Uint128::new(1000000u128),
             ..default_perp_params("perp/osmo/usd")
         },
@@ -44,7 +46,9 @@ fn initialize_market() {
             market_state: MarketState {
                 enabled: true,
                 funding: Funding {
-                    max_funding_velocity: Decimal::from_str("3").unwrap(),
+                    funding_model: FundingModel::Velocity {
+                        max_funding_velocity: Decimal::from_str("3").unwrap(),
+                    },
                     skew_scale: Uint128::new(1000000u128),
                     last_funding_rate: SignedDecimal::zero(),
                     last_funding_accrued_per_unit_in_base_denom: SignedDecimal::zero()
@@ -69,7 +73,9 @@ fn update_market() {
     mock.update_market(
         &params_addr,
         PerpParams {
-            max_funding_velocity: Decimal::from_str("389").unwrap(),
+            funding_model: FundingModel::Velocity {
+                max_funding_velocity: Decimal::from_str("389").unwrap(),
+            },
             skew_scale: Uint128::new(1234000u128),
             ..default_perp_params("perp/osmo/usd")
         },
@@ -86,7 +92,9 @@ fn update_market() {
             market_state: MarketState {
                 enabled: true,
                 funding: Funding {
-                    max_funding_velocity: Decimal::from_str("389").unwrap(),
+                    funding_model: FundingModel::Velocity {
+                        max_funding_velocity: Decimal::from_str("389").unwrap(),
+                    },
                     skew_scale: Uint128::new(1234000u128),
                     last_funding_rate: SignedDecimal::zero(),
                     last_funding_accrued_per_unit_in_base_denom: SignedDecimal::zero()
@@ -101,7 +109,9 @@ fn update_market() {
         &params_addr,
         PerpParams {
             enabled: false,
-            max_funding_velocity: Decimal::from_str("36").unwrap(),
+            funding_model: FundingModel::Velocity {
+                max_funding_velocity: Decimal::from_str("36").unwrap(),
+            },
             skew_scale: Uint128::new(8976543u128),
             ..default_perp_params("perp/osmo/usd")
         },
@@ -116,7 +126,9 @@ fn update_market() {
             market_state: MarketState {
                 enabled: false,
                 funding: Funding {
-                    max_funding_velocity: Decimal::from_str("36").unwrap(),
+                    funding_model: FundingModel::Velocity {
+                        max_funding_velocity: Decimal::from_str("36").unwrap(),
+                    },
                     skew_scale: Uint128::new(8976543u128),
                     last_funding_rate: SignedDecimal::zero(),
                     last_funding_accrued_per_unit_in_base_denom: SignedDecimal::zero()
@@ -168,7 +180,9 @@ fn paginate_markets() {
         &params_addr,
         PerpParams {
             enabled: false,
-            max_funding_velocity: Decimal::from_str("389").unwrap(),
+            funding_model: FundingModel::Velocity {
+                max_funding_velocity: Decimal::from_str("389").unwrap(),
+            },
             skew_scale: Uint128::new(1234000u128),
             ..default_perp_params("perp/osmo/usd")
         },
@@ -179,7 +193,9 @@ fn paginate_markets() {
         &params_addr,
         PerpParams {
             enabled: true,
-            max_funding_velocity: Decimal::from_str("100").unwrap(),
+            funding_model: FundingModel::Velocity {
+                max_funding_velocity: Decimal::from_str("100").unwrap(),
+            },
             skew_scale: Uint128::new(23400u128),
             ..default_perp_params("perp/ntrn/usd")
         },
@@ -235,7 +251,9 @@ fn funding_change_accordingly_to_market_state_modification() {
         &owner,
         PerpParamsUpdate::AddOrUpdate {
             params: PerpParams {
-                max_funding_velocity: Decimal::from_str("30").unwrap(),
+                funding_model: FundingModel::Velocity {
+                    max_funding_velocity: Decimal::from_str("30").unwrap(),
+                },
                 skew_scale: Uint128::new(1000000u128),
                 ..default_perp_params("ueth")
             },
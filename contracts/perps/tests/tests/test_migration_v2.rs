@@ -0,0 +1,132 @@
+use cosmwasm_std::{
+    attr, testing::mock_env, Decimal, Event, Int128, Int256, Order, SignedDecimal, StdResult,
+    Uint128, Uint256,
+};
+use cw2::{ContractVersion, VersionError};
+use mars_perps::{
+    contract::migrate,
+    error::ContractError,
+    migrations::v2_2_1::v2_2_0_state,
+    state::{MARKET_POSITIONS, MARKET_STATES, POSITIONS},
+};
+use mars_testing::mock_dependencies;
+use mars_types::{
+    params::FundingModel,
+    perps::{CashFlow, MigrateMsg, PnlAmounts, Position},
+};
+
+#[test]
+fn wrong_contract_name() {
+    let mut deps = mock_dependencies(&[]);
+    cw2::set_contract_version(deps.as_mut().storage, "contract_xyz", "2.2.0").unwrap();
+
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg::V2_2_0ToV2_2_1 {}).unwrap_err();
+
+    assert_eq!(
+        err,
+        ContractError::Version(VersionError::WrongContract {
+            expected: "mars-perps".to_string(),
+            found: "contract_xyz".to_string()
+        })
+    );
+}
+
+#[test]
+fn wrong_contract_version() {
+    let mut deps = mock_dependencies(&[]);
+    cw2::set_contract_version(deps.as_mut().storage, "mars-perps", "2.1.0").unwrap();
+
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg::V2_2_0ToV2_2_1 {}).unwrap_err();
+
+    assert_eq!(
+        err,
+        ContractError::Version(VersionError::WrongVersion {
+            expected: "2.2.0".to_string(),
+            found: "2.1.0".to_string()
+        })
+    );
+}
+
+#[test]
+fn successful_migration() {
+    let mut deps = mock_dependencies(&[]);
+    cw2::set_contract_version(deps.as_mut().storage, "mars-perps", "2.2.0").unwrap();
+
+    let max_funding_velocity = Decimal::percent(3);
+    v2_2_0_state::MARKET_STATES
+        .save(deps.as_mut().storage, "uatom", &market_state(max_funding_velocity))
+        .unwrap();
+    for (account_id, denom) in [("1", "uatom"), ("1", "uosmo"), ("2", "uatom")] {
+        POSITIONS.save(deps.as_mut().storage, (account_id, denom), &position()).unwrap();
+    }
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg::V2_2_0ToV2_2_1 {}).unwrap();
+
+    assert_eq!(res.messages, vec![]);
+    assert_eq!(res.events, vec![] as Vec<Event>);
+    assert!(res.data.is_none());
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "migrate"), attr("from_version", "2.2.0"), attr("to_version", "2.2.1")]
+    );
+
+    let new_contract_version = ContractVersion {
+        contract: "crates.io:mars-perps".to_string(),
+        version: "2.2.1".to_string(),
+    };
+    assert_eq!(cw2::get_contract_version(deps.as_ref().storage).unwrap(), new_contract_version);
+
+    // Existing markets keep funding with the velocity model
+    let ms = MARKET_STATES.load(deps.as_ref().storage, "uatom").unwrap();
+    assert_eq!(
+        ms.funding.funding_model,
+        FundingModel::Velocity {
+            max_funding_velocity
+        }
+    );
+
+    // Positions are indexed by market
+    let atom_accounts = MARKET_POSITIONS
+        .prefix("uatom")
+        .keys(deps.as_ref().storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(atom_accounts, vec!["1".to_string(), "2".to_string()]);
+    let osmo_accounts = MARKET_POSITIONS
+        .prefix("uosmo")
+        .keys(deps.as_ref().storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(osmo_accounts, vec!["1".to_string()]);
+}
+
+fn market_state(max_funding_velocity: Decimal) -> v2_2_0_state::MarketState {
+    v2_2_0_state::MarketState {
+        enabled: true,
+        long_oi: Uint128::new(200),
+        short_oi: Uint128::new(100),
+        total_entry_cost: Int128::zero(),
+        total_entry_funding: Int128::zero(),
+        total_squared_positions: Uint256::zero(),
+        total_abs_multiplied_positions: Int256::zero(),
+        cash_flow: CashFlow::default(),
+        funding: v2_2_0_state::Funding {
+            max_funding_velocity,
+            skew_scale: Uint128::new(1_000_000),
+            last_funding_rate: SignedDecimal::zero(),
+            last_funding_accrued_per_unit_in_base_denom: SignedDecimal::zero(),
+        },
+        last_updated: 100,
+    }
+}
+
+fn position() -> Position {
+    Position {
+        size: Int128::new(100),
+        entry_price: Decimal::one(),
+        entry_exec_price: Decimal::one(),
+        entry_accrued_funding_per_unit_in_base_denom: SignedDecimal::zero(),
+        initial_skew: Int128::zero(),
+        realized_pnl: PnlAmounts::default(),
+    }
+}
@@ -4,7 +4,7 @@ use cosmwasm_std::{coin, coins, Addr, Decimal, Int128, SignedDecimal, Uint128};
 use mars_perps::{accounting::BalanceExt, error::ContractError};
 use mars_types::{
     oracle::ActionKind,
-    params::{PerpParams, PerpParamsUpdate},
+    params::{MakerFeeRates, PerpParams, PerpParamsUpdate},
     perps::{Accounting, Balance, CashFlow, PnL, PnlAmounts, PositionFeesResponse},
 };
 use test_case::test_case;
@@ -593,6 +593,101 @@ fn increased_position_cannot_be_too_big() {
     );
 }
 
+#[test]
+fn order_aborted_if_execution_price_worse_than_acceptable() {
+    let mut mock = MockEnv::new().build().unwrap();
+
+    let owner = mock.owner.clone();
+    let credit_manager = mock.credit_manager.clone();
+    let user = "jake";
+
+    mock.fund_accounts(&[&credit_manager], 1_000_000_000_000_000u128, &["uosmo", "uatom", "uusdc"]);
+
+    // set prices
+    mock.set_price(&owner, "uusdc", Decimal::from_str("1").unwrap()).unwrap();
+    mock.set_price(&owner, "uatom", Decimal::from_str("10").unwrap()).unwrap();
+
+    mock.deposit_to_vault(
+        &credit_manager,
+        Some(user),
+        None,
+        &[coin(1_000_000_000_000u128, "uusdc")],
+    )
+    .unwrap();
+
+    // init denoms
+    mock.update_perp_params(
+        &owner,
+        PerpParamsUpdate::AddOrUpdate {
+            params: default_perp_params("uatom"),
+        },
+    );
+
+    // buying 1000 with no skew and skew scale of 1000000:
+    // exec price = 10 * (1 + (0 + 0.001) / 2) = 10.005
+    let size = Int128::from_str("1000").unwrap();
+    let res = mock.execute_perp_order_with_acceptable_price(
+        &credit_manager,
+        "2",
+        "uatom",
+        size,
+        None,
+        Some(Decimal::from_str("10.004").unwrap()),
+        &[],
+    );
+    assert_err(
+        res,
+        ContractError::UnacceptableExecutionPrice {
+            exec_price: Decimal::from_str("10.005").unwrap(),
+            acceptable_price: Decimal::from_str("10.004").unwrap(),
+        },
+    );
+
+    mock.execute_perp_order_with_acceptable_price(
+        &credit_manager,
+        "2",
+        "uatom",
+        size,
+        None,
+        Some(Decimal::from_str("10.005").unwrap()),
+        &[],
+    )
+    .unwrap();
+
+    // selling 1000 back with skew of 1000:
+    // exec price = 10 * (1 + (0.001 + 0) / 2) = 10.005
+    let res = mock.execute_perp_order_with_acceptable_price(
+        &credit_manager,
+        "2",
+        "uatom",
+        Int128::zero() - size,
+        Some(true),
+        Some(Decimal::from_str("10.006").unwrap()),
+        &[],
+    );
+    assert_err(
+        res,
+        ContractError::UnacceptableExecutionPrice {
+            exec_price: Decimal::from_str("10.005").unwrap(),
+            acceptable_price: Decimal::from_str("10.006").unwrap(),
+        },
+    );
+
+    mock.execute_perp_order_with_acceptable_price(
+        &credit_manager,
+        "2",
+        "uatom",
+        Int128::zero() - size,
+        Some(true),
+        Some(Decimal::from_str("10.005").unwrap()),
+        &[],
+    )
+    .unwrap();
+
+    let position = mock.query_position("2", "uatom");
+    assert!(position.position.is_none());
+}
+
 #[test_case(
     Uint128::new(2009),
     Uint128::new(6029),
@@ -2043,6 +2138,63 @@ fn query_position_fees(
     assert_eq!(position_fees, expected_fees);
 }
 
+#[test]
+fn opening_fee_depends_on_skew() {
+    let mut mock = MockEnv::new().build().unwrap();
+
+    let owner = mock.owner.clone();
+    let credit_manager = mock.credit_manager.clone();
+    let user = "jake";
+
+    // set prices
+    mock.set_price(&owner, "uusdc", Decimal::from_str("0.9").unwrap()).unwrap();
+    mock.set_price(&owner, "uosmo", Decimal::from_str("1.25").unwrap()).unwrap();
+
+    // credit manager is calling the perps contract, so we need to fund it (funds will be used for closing losing position)
+    mock.fund_accounts(&[&credit_manager], 1_000_000_000_000_000u128, &["uosmo", "uusdc"]);
+
+    // deposit some big number of uusdc to vault
+    mock.deposit_to_vault(
+        &credit_manager,
+        Some(user),
+        None,
+        &[coin(1_000_000_000_000u128, "uusdc")],
+    )
+    .unwrap();
+
+    // init denoms
+    mock.update_perp_params(
+        &owner,
+        PerpParamsUpdate::AddOrUpdate {
+            params: PerpParams {
+                opening_fee_rate: Decimal::from_str("0.004").unwrap(),
+                closing_fee_rate: Decimal::from_str("0.006").unwrap(),
+                maker_fee_rates: Some(MakerFeeRates {
+                    opening_fee_rate: Decimal::from_str("0.001").unwrap(),
+                    closing_fee_rate: Decimal::from_str("0.002").unwrap(),
+                }),
+                ..default_perp_params("uosmo")
+            },
+        },
+    );
+
+    // no skew, the order increases it and pays the taker fee
+    let size = Int128::from_str("10000").unwrap();
+    let opening_fee = mock.query_opening_fee("uosmo", size);
+    assert_eq!(opening_fee.rate, Decimal::from_str("0.004").unwrap());
+    mock.execute_perp_order(&credit_manager, "2", "uosmo", size, None, &[opening_fee.fee]).unwrap();
+
+    // long skew is increased by a long order
+    let opening_fee = mock.query_opening_fee("uosmo", Int128::from_str("1000").unwrap());
+    assert_eq!(opening_fee.rate, Decimal::from_str("0.004").unwrap());
+    assert_eq!(opening_fee.fee, coin(6u128, "uusdc"));
+
+    // long skew is reduced by a short order
+    let opening_fee = mock.query_opening_fee("uosmo", Int128::from_str("-1000").unwrap());
+    assert_eq!(opening_fee.rate, Decimal::from_str("0.001").unwrap());
+    assert_eq!(opening_fee.fee, coin(2u128, "uusdc"));
+}
+
 #[test]
 fn random_user_cannot_close_all_positions() {
     let mut mock = MockEnv::new().build().unwrap();
@@ -1,7 +1,7 @@
 use std::str::FromStr;
 
 use cosmwasm_std::{coin, Coin, Decimal, Int128, Uint128};
-use mars_types::params::{PerpParams, PerpParamsUpdate};
+use mars_types::params::{FundingModel, PerpParams, PerpParamsUpdate};
 use test_case::test_case;
 
 use crate::tests::helpers::{default_perp_params, MockEnv};
@@ -49,7 +49,9 @@ fn protocol_fee_sent_to_rewards_collector(
             params: PerpParams {
                 closing_fee_rate,
                 opening_fee_rate,
-                max_funding_velocity: Decimal::from_str("32").unwrap(),
+                funding_model: FundingModel::Velocity {
+                    max_funding_velocity: Decimal::from_str("32").unwrap(),
+                },
                 skew_scale: Uint128::new(1000000u128),
                 ..default_perp_params("uosmo")
             },
@@ -151,7 +153,9 @@ fn close_all_positions_applies_fees() {
             params: PerpParams {
                 closing_fee_rate,
                 opening_fee_rate,
-                max_funding_velocity: Decimal::from_str("32").unwrap(),
+                funding_model: FundingModel::Velocity {
+                    max_funding_velocity: Decimal::from_str("32").unwrap(),
+                },
                 skew_scale: Uint128::new(1000000u128),
                 ..default_perp_params(denom_1)
             },
@@ -163,7 +167,9 @@ fn close_all_positions_applies_fees() {
             params: PerpParams {
                 closing_fee_rate,
                 opening_fee_rate,
-                max_funding_velocity: Decimal::from_str("32").unwrap(),
+                funding_model: FundingModel::Velocity {
+                    max_funding_velocity: Decimal::from_str("32").unwrap(),
+                },
                 skew_scale: Uint128::new(1000000u128),
                 ..default_perp_params(denom_2)
             },
@@ -2,7 +2,7 @@ use std::str::FromStr;
 
 use cosmwasm_std::{coin, Decimal, Int128, SignedDecimal, Uint128};
 use mars_types::{
-    params::{PerpParams, PerpParamsUpdate},
+    params::{FundingModel, PerpParams, PerpParamsUpdate},
     perps::MarketResponse,
 };
 
@@ -30,7 +30,9 @@ fn query_market() {
         &owner,
         PerpParamsUpdate::AddOrUpdate {
             params: PerpParams {
-                max_funding_velocity: initial_funding_velocity,
+                funding_model: FundingModel::Velocity {
+                    max_funding_velocity: initial_funding_velocity,
+                },
                 skew_scale: initial_skew_scale,
                 ..default_perp_params(denom1)
             },
@@ -132,7 +134,9 @@ fn query_markets() {
         &owner,
         PerpParamsUpdate::AddOrUpdate {
             params: PerpParams {
-                max_funding_velocity: initial_funding_velocity1,
+                funding_model: FundingModel::Velocity {
+                    max_funding_velocity: initial_funding_velocity1,
+                },
                 skew_scale: initial_skew_scale1,
                 ..default_perp_params(denom1)
             },
@@ -142,7 +146,9 @@ fn query_markets() {
         &owner,
         PerpParamsUpdate::AddOrUpdate {
             params: PerpParams {
-                max_funding_velocity: initial_funding_velocity2,
+                funding_model: FundingModel::Velocity {
+                    max_funding_velocity: initial_funding_velocity2,
+                },
                 skew_scale: initial_skew_scale2,
                 ..default_perp_params(denom2)
             },
@@ -152,7 +158,9 @@ fn query_markets() {
         &owner,
         PerpParamsUpdate::AddOrUpdate {
             params: PerpParams {
-                max_funding_velocity: initial_funding_velocity3,
+                funding_model: FundingModel::Velocity {
+                    max_funding_velocity: initial_funding_velocity3,
+                },
                 skew_scale: initial_skew_scale3,
                 ..default_perp_params(denom3)
             },
@@ -261,7 +269,9 @@ fn query_positions() {
         &owner,
         PerpParamsUpdate::AddOrUpdate {
             params: PerpParams {
-                max_funding_velocity: Decimal::from_str("3").unwrap(),
+                funding_model: FundingModel::Velocity {
+                    max_funding_velocity: Decimal::from_str("3").unwrap(),
+                },
                 skew_scale: Uint128::new(1000000u128),
                 ..default_perp_params("uatom")
             },
@@ -271,7 +281,9 @@ fn query_positions() {
         &owner,
         PerpParamsUpdate::AddOrUpdate {
             params: PerpParams {
-                max_funding_velocity: Decimal::from_str("3").unwrap(),
+                funding_model: FundingModel::Velocity {
+                    max_funding_velocity: Decimal::from_str("3").unwrap(),
+                },
                 skew_scale: Uint128::new(1200000u128),
                 ..default_perp_params("utia")
             },
@@ -10,7 +10,7 @@ use crate::{
     health::assert_below_max_ltv_after_borrow,
     helpers::query_asset_params,
     interest_rates::{apply_accumulated_interests, update_interest_rates},
-    state::{CONFIG, MARKETS},
+    state::{CONFIG, LIQUIDATABLE_SINCE, MARKETS},
     user::User,
 };
 
@@ -89,6 +89,9 @@ pub fn borrow(
         )? {
             return Err(ContractError::BorrowAmountExceedsGivenCollateral {});
         }
+
+        // A user below its max LTV is healthy, so its liquidation bonus auction ends
+        LIQUIDATABLE_SINCE.remove(deps.storage, borrower.address());
     } else {
         uncollateralized_debt = true;
     }
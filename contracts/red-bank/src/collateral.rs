@@ -1,14 +1,17 @@
-use cosmwasm_std::{DepsMut, Env, MessageInfo, Response};
+use cosmwasm_std::{DepsMut, Env, MessageInfo, Response, Uint128};
+use mars_interest_rate::{get_scaled_liquidity_amount, get_underlying_liquidity_amount};
 use mars_types::{
     self,
     address_provider::{self, MarsAddressType},
+    error::MarsError,
     keys::{UserId, UserIdKey},
 };
 
 use crate::{
     error::ContractError,
-    health::get_health_and_positions,
-    state::{COLLATERALS, CONFIG},
+    health::{assert_below_liq_threshold_after_withdraw, get_health_and_positions},
+    interest_rates::{apply_accumulated_interests, update_interest_rates},
+    state::{COLLATERALS, CONFIG, MARKETS},
     user::User,
 };
 
@@ -72,3 +75,134 @@ pub fn update_asset_collateral_status(
         .add_attribute("denom", denom)
         .add_attribute("enable", enable.to_string()))
 }
+
+/// Move collateral of a user to a credit account of the credit manager.
+/// The coins stay in the Red Bank, only the ownership of the collateral shares changes.
+pub fn transfer_collateral_to_credit_account(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    user: String,
+    account_id: String,
+    denom: String,
+    amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let addresses = address_provider::helpers::query_contract_addrs(
+        deps.as_ref(),
+        &config.address_provider,
+        vec![
+            MarsAddressType::Oracle,
+            MarsAddressType::Incentives,
+            MarsAddressType::RewardsCollector,
+            MarsAddressType::Params,
+            MarsAddressType::CreditManager,
+        ],
+    )?;
+    let rewards_collector_addr = &addresses[&MarsAddressType::RewardsCollector];
+    let incentives_addr = &addresses[&MarsAddressType::Incentives];
+    let oracle_addr = &addresses[&MarsAddressType::Oracle];
+    let params_addr = &addresses[&MarsAddressType::Params];
+    let credit_manager_addr = &addresses[&MarsAddressType::CreditManager];
+
+    if info.sender != credit_manager_addr {
+        return Err(ContractError::Mars(MarsError::Unauthorized {}));
+    }
+
+    let user_addr = deps.api.addr_validate(&user)?;
+    let user = User(&user_addr);
+    let credit_manager = User(credit_manager_addr);
+
+    let mut market = MARKETS.load(deps.storage, &denom)?;
+
+    let collateral = user.collateral(deps.storage, &denom, "").map_err(|_| {
+        ContractError::UserNoCollateralBalance {
+            user: user.into(),
+            denom: denom.clone(),
+        }
+    })?;
+
+    let mut response = Response::new();
+
+    // update indexes so that the amount is converted to shares at the current index
+    response = apply_accumulated_interests(
+        deps.storage,
+        &env,
+        &mut market,
+        rewards_collector_addr,
+        incentives_addr,
+        response,
+    )?;
+
+    let user_balance_before = get_underlying_liquidity_amount(
+        collateral.amount_scaled,
+        &market,
+        env.block.time.seconds(),
+    )?;
+
+    let (transfer_amount, transfer_amount_scaled) = match amount {
+        Some(amount) if amount.is_zero() || amount > user_balance_before => {
+            return Err(ContractError::InvalidWithdrawAmount {
+                denom,
+            });
+        }
+        Some(amount) => {
+            let balance_scaled_after = get_scaled_liquidity_amount(
+                user_balance_before - amount,
+                &market,
+                env.block.time.seconds(),
+            )?;
+            (amount, collateral.amount_scaled.checked_sub(balance_scaled_after)?)
+        }
+        // If no amount is specified, the full balance is transferred
+        None => (user_balance_before, collateral.amount_scaled),
+    };
+
+    // the user's remaining debt (if any) has to stay covered by the remaining collateral
+    if collateral.enabled
+        && user.is_borrowing(deps.storage)
+        && !assert_below_liq_threshold_after_withdraw(
+            &deps.as_ref(),
+            &env,
+            user.address(),
+            "",
+            oracle_addr,
+            params_addr,
+            &denom,
+            transfer_amount,
+            false,
+        )?
+    {
+        return Err(ContractError::InvalidHealthFactorAfterWithdraw {});
+    }
+
+    response = user.decrease_collateral(
+        deps.storage,
+        &market,
+        transfer_amount_scaled,
+        incentives_addr,
+        response,
+        None,
+    )?;
+    response = credit_manager.increase_collateral(
+        deps.storage,
+        &market,
+        transfer_amount_scaled,
+        incentives_addr,
+        response,
+        Some(account_id.clone()),
+    )?;
+
+    // total collateral of the market doesn't change, but the rates are updated with the new index
+    response = update_interest_rates(&env, &mut market, response)?;
+    MARKETS.save(deps.storage, &denom, &market)?;
+
+    Ok(response
+        .add_attribute("action", "transfer_collateral_to_credit_account")
+        .add_attribute("user", user)
+        .add_attribute("account_id", account_id)
+        .add_attribute("denom", denom)
+        .add_attribute("amount", transfer_amount)
+        .add_attribute("amount_scaled", transfer_amount_scaled))
+}
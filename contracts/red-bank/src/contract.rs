@@ -107,6 +107,17 @@ pub fn execute(
                 recipient,
             )
         }
+        ExecuteMsg::BatchLiquidate {
+            liquidations,
+            recipient,
+        } => liquidate::batch_liquidate(deps, env, info, liquidations, recipient),
+        ExecuteMsg::FlagLiquidatable {
+            user,
+        } => {
+            cw_utils::nonpayable(&info)?;
+            let user_addr = deps.api.addr_validate(&user)?;
+            liquidate::flag_liquidatable(deps, env, user_addr)
+        }
         ExecuteMsg::UpdateAssetCollateralStatus {
             denom,
             enable,
@@ -114,6 +125,17 @@ pub fn execute(
             cw_utils::nonpayable(&info)?;
             collateral::update_asset_collateral_status(deps, env, info, denom, enable)
         }
+        ExecuteMsg::TransferCollateralToCreditAccount {
+            user,
+            account_id,
+            denom,
+            amount,
+        } => {
+            cw_utils::nonpayable(&info)?;
+            collateral::transfer_collateral_to_credit_account(
+                deps, env, info, user, account_id, denom, amount,
+            )
+        }
     }
 }
 
@@ -212,6 +234,10 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractErro
             let user_addr = deps.api.addr_validate(&user)?;
             to_json_binary(&query::query_user_position(deps, env, user_addr, account_id, true)?)
         }
+        QueryMsg::LiquidatableUsers {
+            start_after,
+            limit,
+        } => to_json_binary(&query::query_liquidatable_users(deps, env, start_after, limit)?),
         QueryMsg::ScaledLiquidityAmount {
             denom,
             amount,
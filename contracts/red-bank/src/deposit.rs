@@ -9,6 +9,7 @@ use crate::{
     error::ContractError,
     helpers::{query_asset_params, query_total_deposit},
     interest_rates::{apply_accumulated_interests, update_interest_rates},
+    liquidate::end_auction_if_healthy,
     state::{CONFIG, MARKETS},
     user::User,
 };
@@ -113,6 +114,9 @@ pub fn deposit(
 
     MARKETS.save(deps.storage, &denom, &market)?;
 
+    // The liquidation bonus auction ends once the user is healthy again
+    end_auction_if_healthy(deps, &env, user.address())?;
+
     Ok(response
         .add_attribute("action", "deposit")
         .add_attribute("sender", &info.sender)
@@ -159,4 +159,7 @@ pub enum ContractError {
 
     #[error("Cannot liquidate credit manager (use credit-manager contract liquidate function)")]
     CannotLiquidateCreditManager {},
+
+    #[error("Funds sent don't match the debt coins of the liquidations")]
+    InvalidBatchLiquidationFunds {},
 }
@@ -1,4 +1,7 @@
-use cosmwasm_std::{Addr, Decimal, DepsMut, Env, MessageInfo, Response, Uint128};
+use cosmwasm_std::{
+    Addr, BankMsg, Coins, Decimal, DepsMut, Env, Event, MessageInfo, Response, StdResult, Storage,
+    Uint128,
+};
 use mars_interest_rate::{
     get_scaled_debt_amount, get_scaled_liquidity_amount, get_underlying_debt_amount,
     get_underlying_liquidity_amount,
@@ -7,15 +10,16 @@ use mars_liquidation::liquidation::calculate_liquidation_amounts;
 use mars_types::{
     address_provider::{self, MarsAddressType},
     keys::{UserId, UserIdKey},
+    red_bank::BatchLiquidation,
 };
 use mars_utils::helpers::{build_send_asset_msg, option_string_to_addr};
 
 use crate::{
     error::ContractError,
-    health::get_health_and_positions,
+    health::{compute_position_health, get_health_and_positions},
     helpers::query_asset_params,
     interest_rates::{apply_accumulated_interests, update_interest_rates},
-    state::{COLLATERALS, CONFIG, DEBTS, MARKETS},
+    state::{COLLATERALS, CONFIG, DEBTS, LIQUIDATABLE_SINCE, MARKETS},
     user::User,
 };
 
@@ -91,7 +95,7 @@ pub fn liquidate(
     let collateral_market = MARKETS.load(deps.storage, &collateral_denom)?;
 
     // 2. Compute health factor
-    let (health, assets_positions) = get_health_and_positions(
+    let (health, mut assets_positions) = get_health_and_positions(
         &deps.as_ref(),
         &env,
         &liquidatee_addr,
@@ -148,6 +152,7 @@ pub fn liquidate(
         &debt_params,
         &health.try_into()?,
         Decimal::zero(), // doesn't matter for RB liquidation
+        liquidatable_for(deps.storage, &env, &liquidatee_addr)?,
     )?;
     let protocol_fee = collateral_amount_to_liquidate - collateral_amount_received_by_liquidator;
 
@@ -207,6 +212,20 @@ pub fn liquidate(
 
     liquidatee.decrease_debt(deps.storage, &debt_denom, debt_amount_scaled_delta)?;
 
+    // The liquidation bonus auction ends once the user is healthy again
+    if LIQUIDATABLE_SINCE.has(deps.storage, &liquidatee_addr) {
+        if let Some(position) = assets_positions.get_mut(&collateral_denom) {
+            position.collateral_amount =
+                position.collateral_amount.checked_sub(collateral_amount_to_liquidate)?;
+        }
+        if let Some(position) = assets_positions.get_mut(&debt_denom) {
+            position.debt_amount = user_debt_amount_after;
+        }
+        if !compute_position_health(&assets_positions)?.is_liquidatable() {
+            LIQUIDATABLE_SINCE.remove(deps.storage, &liquidatee_addr);
+        }
+    }
+
     let market_debt_total_scaled_after =
         debt_market.debt_total_scaled.checked_sub(debt_amount_scaled_delta)?;
 
@@ -248,3 +267,170 @@ pub fn liquidate(
         .add_attribute("protocol_fee_amount", protocol_fee)
         .add_attribute("protocol_fee_amount_scaled", protocol_fee_scaled))
 }
+
+/// Execute several loan liquidations at once. Users which can't be liquidated anymore (healthy,
+/// without debt or without the requested collateral, e.g. because they were liquidated first by
+/// someone else) are skipped and their debt coin is refunded to the liquidator.
+pub fn batch_liquidate(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    liquidations: Vec<BatchLiquidation>,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    // The coins sent must exactly cover the debt coins of the liquidations
+    let mut debt_coins = Coins::default();
+    for liquidation in &liquidations {
+        debt_coins.add(liquidation.debt_coin.clone())?;
+    }
+    let mut sent_coins = Coins::default();
+    for coin in &info.funds {
+        sent_coins.add(coin.clone())?;
+    }
+    if debt_coins != sent_coins {
+        return Err(ContractError::InvalidBatchLiquidationFunds {});
+    }
+
+    let mut response = Response::new().add_attribute("action", "batch_liquidate");
+    let mut refund_coins = Coins::default();
+    for (idx, liquidation) in liquidations.into_iter().enumerate() {
+        let liquidatee_addr = deps.api.addr_validate(&liquidation.user)?;
+        let liquidation_info = MessageInfo {
+            sender: info.sender.clone(),
+            funds: vec![liquidation.debt_coin.clone()],
+        };
+        let result = match liquidate(
+            deps.branch(),
+            env.clone(),
+            liquidation_info,
+            liquidation.collateral_denom,
+            liquidation.debt_coin.denom.clone(),
+            liquidatee_addr,
+            liquidation.debt_coin.amount,
+            recipient.clone(),
+        ) {
+            Ok(res) => {
+                response = response
+                    .add_submessages(res.messages)
+                    .add_events(res.events)
+                    .add_event(Event::new("liquidate").add_attributes(res.attributes));
+                "liquidated"
+            }
+            Err(ContractError::CannotLiquidateHealthyPosition {})
+            | Err(ContractError::CannotLiquidateWhenNoDebtBalance {})
+            | Err(ContractError::CannotLiquidateWhenNoCollateralBalance {}) => {
+                refund_coins.add(liquidation.debt_coin)?;
+                "skipped"
+            }
+            Err(err) => return Err(err),
+        };
+        response = response
+            .add_attribute(format!("liquidation_{idx}"), format!("{}:{result}", liquidation.user));
+    }
+
+    if !refund_coins.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: refund_coins.into_vec(),
+        });
+    }
+
+    Ok(response)
+}
+
+/// Flag a liquidatable user, starting the auction of its liquidation bonus. Users already flagged
+/// keep their auction start. The flag of a user which is healthy again is removed.
+///
+/// The flag is also removed when the user deposits, repays, borrows or withdraws while healthy.
+/// A user recovering through price moves alone keeps its flag until then, or until it is flagged
+/// again while healthy.
+pub fn flag_liquidatable(
+    deps: DepsMut,
+    env: Env,
+    user_addr: Addr,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let addresses = address_provider::helpers::query_contract_addrs(
+        deps.as_ref(),
+        &config.address_provider,
+        vec![MarsAddressType::Oracle, MarsAddressType::Params],
+    )?;
+    let oracle_addr = &addresses[&MarsAddressType::Oracle];
+    let params_addr = &addresses[&MarsAddressType::Params];
+
+    let (health, _) = get_health_and_positions(
+        &deps.as_ref(),
+        &env,
+        &user_addr,
+        "",
+        oracle_addr,
+        params_addr,
+        true,
+    )?;
+
+    let liquidatable_since = if health.is_liquidatable() {
+        let since = LIQUIDATABLE_SINCE
+            .may_load(deps.storage, &user_addr)?
+            .unwrap_or_else(|| env.block.time.seconds());
+        LIQUIDATABLE_SINCE.save(deps.storage, &user_addr, &since)?;
+        since.to_string()
+    } else {
+        LIQUIDATABLE_SINCE.remove(deps.storage, &user_addr);
+        "none".to_string()
+    };
+
+    Ok(Response::new()
+        .add_attribute("action", "flag_liquidatable")
+        .add_attribute("user", user_addr)
+        .add_attribute("liquidatable_since", liquidatable_since))
+}
+
+/// Ends the liquidation bonus auction of a flagged user once it is healthy again, e.g. after a
+/// deposit or a repayment. A user whose health can't be computed (e.g. a missing price) stays
+/// flagged, so that the deposit or repayment doesn't depend on the oracle.
+pub fn end_auction_if_healthy(
+    deps: DepsMut,
+    env: &Env,
+    user_addr: &Addr,
+) -> Result<(), ContractError> {
+    if !LIQUIDATABLE_SINCE.has(deps.storage, user_addr) {
+        return Ok(());
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+
+    let addresses = address_provider::helpers::query_contract_addrs(
+        deps.as_ref(),
+        &config.address_provider,
+        vec![MarsAddressType::Oracle, MarsAddressType::Params],
+    )?;
+    let oracle_addr = &addresses[&MarsAddressType::Oracle];
+    let params_addr = &addresses[&MarsAddressType::Params];
+
+    let liquidatable = get_health_and_positions(
+        &deps.as_ref(),
+        env,
+        user_addr,
+        "",
+        oracle_addr,
+        params_addr,
+        true,
+    )
+    .map_or(true, |(health, _)| health.is_liquidatable());
+    if !liquidatable {
+        LIQUIDATABLE_SINCE.remove(deps.storage, user_addr);
+    }
+
+    Ok(())
+}
+
+/// Seconds since the user was flagged as liquidatable, `None` if not flagged
+pub fn liquidatable_for(
+    storage: &dyn Storage,
+    env: &Env,
+    user_addr: &Addr,
+) -> StdResult<Option<u64>> {
+    let since = LIQUIDATABLE_SINCE.may_load(storage, user_addr)?;
+    Ok(since.map(|since| env.block.time.seconds().saturating_sub(since)))
+}
@@ -1,9 +1,10 @@
-use cosmwasm_std::{DepsMut, Response};
+use cosmwasm_std::{DepsMut, Order, Response, StdResult};
 use cw2::{assert_contract_version, set_contract_version};
 
 use crate::{
     contract::{CONTRACT_NAME, CONTRACT_VERSION},
     error::ContractError,
+    state::{DEBTORS, DEBTS},
 };
 
 const FROM_VERSION: &str = "2.1.0";
@@ -12,6 +13,13 @@ pub fn migrate(deps: DepsMut) -> Result<Response, ContractError> {
     // make sure we're migrating the correct contract and from the correct version
     assert_contract_version(deps.storage, &format!("crates.io:{CONTRACT_NAME}"), FROM_VERSION)?;
 
+    // Index the users with debt
+    let debt_keys =
+        DEBTS.keys(deps.storage, None, None, Order::Ascending).collect::<StdResult<Vec<_>>>()?;
+    for (user, _) in debt_keys {
+        DEBTORS.save(deps.storage, &user, &())?;
+    }
+
     set_contract_version(deps.storage, format!("crates.io:{CONTRACT_NAME}"), CONTRACT_VERSION)?;
 
     Ok(Response::new()
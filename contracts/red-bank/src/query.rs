@@ -1,24 +1,30 @@
-use cosmwasm_std::{Addr, BlockInfo, Decimal, Deps, Env, Order, StdResult, Uint128};
+use std::collections::HashMap;
+
+use cosmwasm_std::{coin, Addr, BlockInfo, Decimal, Deps, Env, Order, StdResult, Uint128};
 use cw_paginate::{paginate_map_query, paginate_prefix_query, PaginationResponse};
 use cw_storage_plus::Bound;
+use mars_health::health::Health;
 use mars_interest_rate::{
     get_scaled_debt_amount, get_scaled_liquidity_amount, get_underlying_debt_amount,
     get_underlying_liquidity_amount,
 };
+use mars_liquidation::liquidation::{calculate_liquidation_amounts, HealthData};
 use mars_types::{
     address_provider::{self, MarsAddressType},
     keys::{UserId, UserIdKey},
     red_bank::{
-        Collateral, ConfigResponse, Debt, Market, MarketV2Response,
-        PaginatedUserCollateralResponse, UserCollateralResponse, UserDebtResponse,
-        UserHealthStatus, UserPositionResponse,
+        Collateral, ConfigResponse, Debt, LiquidatableUser, LiquidatableUsersResponse,
+        LiquidationCandidate, Market, MarketV2Response, PaginatedUserCollateralResponse, Position,
+        UserCollateralResponse, UserDebtResponse, UserHealthStatus, UserPositionResponse,
     },
 };
 
 use crate::{
     error::{ContractError, ContractResult},
     health,
-    state::{COLLATERALS, CONFIG, DEBTS, MARKETS, OWNER},
+    helpers::query_asset_params,
+    liquidate::liquidatable_for,
+    state::{COLLATERALS, CONFIG, DEBTORS, DEBTS, MARKETS, OWNER},
 };
 
 const DEFAULT_LIMIT: u32 = 10;
@@ -319,7 +325,153 @@ pub fn query_user_position(
     )?;
     let health = health::compute_position_health(&positions)?;
 
-    let health_status = if let (Some(max_ltv_hf), Some(liq_threshold_hf)) =
+    Ok(UserPositionResponse {
+        total_enabled_collateral: health.total_collateral_value,
+        total_collateralized_debt: health.total_debt_value,
+        weighted_max_ltv_collateral: health.max_ltv_adjusted_collateral,
+        weighted_liquidation_threshold_collateral: health.liquidation_threshold_adjusted_collateral,
+        health_status: health_status(&health),
+    })
+}
+
+pub fn query_liquidatable_users(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<LiquidatableUsersResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let addresses = address_provider::helpers::query_contract_addrs(
+        deps,
+        &config.address_provider,
+        vec![MarsAddressType::Oracle, MarsAddressType::Params, MarsAddressType::CreditManager],
+    )?;
+    let oracle_addr = &addresses[&MarsAddressType::Oracle];
+    let params_addr = &addresses[&MarsAddressType::Params];
+    let credit_manager_addr = &addresses[&MarsAddressType::CreditManager];
+
+    let start_after = start_after.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let user_addrs = DEBTORS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = if user_addrs.len() == limit {
+        user_addrs.last().map(|addr| addr.to_string())
+    } else {
+        None
+    };
+
+    let mut users = vec![];
+    let mut failed_users = vec![];
+    for user_addr in user_addrs {
+        // Credit Manager debt is liquidated through the Credit Manager accounts
+        if user_addr == credit_manager_addr {
+            continue;
+        }
+
+        // A user whose health can't be computed doesn't prevent checking the others
+        match liquidatable_user(deps, &env, &user_addr, oracle_addr, params_addr) {
+            Ok(Some(user)) => users.push(user),
+            Ok(None) => {}
+            Err(_) => failed_users.push(user_addr.to_string()),
+        }
+    }
+
+    Ok(LiquidatableUsersResponse {
+        users,
+        failed_users,
+        next_start_after,
+    })
+}
+
+fn liquidatable_user(
+    deps: Deps,
+    env: &Env,
+    user_addr: &Addr,
+    oracle_addr: &Addr,
+    params_addr: &Addr,
+) -> Result<Option<LiquidatableUser>, ContractError> {
+    let (health, positions) = health::get_health_and_positions(
+        &deps,
+        env,
+        user_addr,
+        "",
+        oracle_addr,
+        params_addr,
+        true,
+    )?;
+    if !health.is_liquidatable() {
+        return Ok(None);
+    }
+
+    let health_status = health_status(&health);
+    let liquidatable_for = liquidatable_for(deps.storage, env, user_addr)?;
+    let liquidation = best_liquidation(deps, params_addr, health, &positions, liquidatable_for)?;
+    Ok(Some(LiquidatableUser {
+        user: user_addr.to_string(),
+        health_status,
+        liquidation,
+    }))
+}
+
+/// Find the collateral / debt pair repaying the most debt (by value) when liquidated
+fn best_liquidation(
+    deps: Deps,
+    params_addr: &Addr,
+    health: Health,
+    positions: &HashMap<String, Position>,
+    liquidatable_for: Option<u64>,
+) -> Result<Option<LiquidationCandidate>, ContractError> {
+    let health_data: HealthData = health.try_into()?;
+
+    let mut best: Option<(Uint128, LiquidationCandidate)> = None;
+    for debt in positions.values().filter(|p| !p.debt_amount.is_zero()) {
+        let debt_params = query_asset_params(&deps.querier, params_addr, &debt.denom)?;
+
+        for collateral in positions.values().filter(|p| !p.collateral_amount.is_zero()) {
+            let collateral_params =
+                query_asset_params(&deps.querier, params_addr, &collateral.denom)?;
+
+            // Pairs which can't be liquidated (e.g. amounts too small) are ignored
+            let Ok((debt_amount, _, _)) = calculate_liquidation_amounts(
+                collateral.collateral_amount,
+                collateral.asset_price,
+                &collateral_params,
+                debt.debt_amount,
+                debt.debt_amount,
+                debt.asset_price,
+                &debt_params,
+                &health_data,
+                Decimal::zero(), // doesn't matter for RB liquidation
+                liquidatable_for,
+            ) else {
+                continue;
+            };
+
+            let debt_value = debt_amount.checked_mul_floor(debt.asset_price)?;
+            if debt_value.is_zero() || best.as_ref().is_some_and(|(value, _)| debt_value <= *value)
+            {
+                continue;
+            }
+            best = Some((
+                debt_value,
+                LiquidationCandidate {
+                    collateral_denom: collateral.denom.clone(),
+                    debt_coin: coin(debt_amount.u128(), &debt.denom),
+                },
+            ));
+        }
+    }
+
+    Ok(best.map(|(_, candidate)| candidate))
+}
+
+fn health_status(health: &Health) -> UserHealthStatus {
+    if let (Some(max_ltv_hf), Some(liq_threshold_hf)) =
         (health.max_ltv_health_factor, health.liquidation_health_factor)
     {
         UserHealthStatus::Borrowing {
@@ -328,13 +480,5 @@ pub fn query_user_position(
         }
     } else {
         UserHealthStatus::NotBorrowing
-    };
-
-    Ok(UserPositionResponse {
-        total_enabled_collateral: health.total_collateral_value,
-        total_collateralized_debt: health.total_debt_value,
-        weighted_max_ltv_collateral: health.max_ltv_adjusted_collateral,
-        weighted_liquidation_threshold_collateral: health.liquidation_threshold_adjusted_collateral,
-        health_status,
-    })
+    }
 }
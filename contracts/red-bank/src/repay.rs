@@ -6,6 +6,7 @@ use mars_utils::helpers::build_send_asset_msg;
 use crate::{
     error::ContractError,
     interest_rates::{apply_accumulated_interests, update_interest_rates},
+    liquidate::end_auction_if_healthy,
     state::{CONFIG, DEBTS, MARKETS},
     user::User,
 };
@@ -91,6 +92,9 @@ pub fn repay(
     response = update_interest_rates(&env, &mut market, response)?;
     MARKETS.save(deps.storage, &denom, &market)?;
 
+    // The liquidation bonus auction ends once the user is healthy again
+    end_auction_if_healthy(deps, &env, user.address())?;
+
     Ok(response
         .add_attribute("action", "repay")
         .add_attribute("sender", &info.sender)
@@ -11,3 +11,9 @@ pub const CONFIG: Item<Config<Addr>> = Item::new("config");
 pub const MARKETS: Map<&str, Market> = Map::new("markets");
 pub const COLLATERALS: Map<(&UserIdKey, &str), Collateral> = Map::new("colls");
 pub const DEBTS: Map<(&Addr, &str), Debt> = Map::new("debts");
+
+/// Users with debt, i.e. the users which can become liquidatable
+pub const DEBTORS: Map<&Addr, ()> = Map::new("debtors");
+
+/// Users flagged as liquidatable, start (timestamp in seconds) of their liquidation bonus auction
+pub const LIQUIDATABLE_SINCE: Map<&Addr, u64> = Map::new("liquidatable_since");
@@ -8,7 +8,7 @@ use mars_types::{
     red_bank::{Collateral, Debt, Market},
 };
 
-use crate::state::{COLLATERALS, DEBTS};
+use crate::state::{COLLATERALS, DEBTORS, DEBTS};
 
 /// A helper class providing an intuitive API for managing user positions in the contract store.
 ///
@@ -220,6 +220,7 @@ impl<'a> User<'a> {
                 }),
             }
         })?;
+        DEBTORS.save(store, self.0, &())?;
         Ok(())
     }
 
@@ -239,6 +240,9 @@ impl<'a> User<'a> {
 
         if debt.amount_scaled.is_zero() {
             DEBTS.remove(store, (self.0, denom));
+            if !self.is_borrowing(store) {
+                DEBTORS.remove(store, self.0);
+            }
         } else {
             DEBTS.save(store, (self.0, denom), &debt)?;
         }
@@ -8,7 +8,7 @@ use crate::{
     health::assert_below_liq_threshold_after_withdraw,
     helpers::query_asset_params,
     interest_rates::{apply_accumulated_interests, update_interest_rates},
-    state::{CONFIG, MARKETS},
+    state::{CONFIG, LIQUIDATABLE_SINCE, MARKETS},
     user::User,
 };
 
@@ -95,9 +95,8 @@ pub fn withdraw(
 
     // if asset is used as collateral and user is borrowing we need to validate health factor after withdraw,
     // otherwise no reasons to block the withdraw
-    if collateral.enabled
-        && withdrawer.is_borrowing(deps.storage)
-        && !assert_below_liq_threshold_after_withdraw(
+    if collateral.enabled && withdrawer.is_borrowing(deps.storage) {
+        if !assert_below_liq_threshold_after_withdraw(
             &deps.as_ref(),
             &env,
             withdrawer.address(),
@@ -107,9 +106,12 @@ pub fn withdraw(
             &denom,
             withdraw_amount,
             liquidation_related,
-        )?
-    {
-        return Err(ContractError::InvalidHealthFactorAfterWithdraw {});
+        )? {
+            return Err(ContractError::InvalidHealthFactorAfterWithdraw {});
+        }
+
+        // A user below its liquidation threshold is healthy, so its liquidation bonus auction ends
+        LIQUIDATABLE_SINCE.remove(deps.storage, withdrawer.address());
     }
 
     let mut response = Response::new();
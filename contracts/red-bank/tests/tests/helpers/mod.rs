@@ -145,6 +145,7 @@ pub fn th_default_asset_params() -> AssetParams {
         protocol_liquidation_fee: Decimal::percent(2u64),
         deposit_cap: Uint128::MAX,
         close_factor: Decimal::percent(80u64),
+        liquidation_auction: None,
     }
 }
 
@@ -1,9 +1,12 @@
 use cosmwasm_std::{coin, Addr, Decimal};
+use mars_red_bank::error::ContractError;
 use mars_testing::integration::{
     helpers::{osmo_asset_params, usdc_asset_params},
     mock_env::MockEnvBuilder,
 };
-use mars_types::red_bank::UserHealthStatus;
+use mars_types::{error::MarsError, red_bank::UserHealthStatus};
+
+use super::helpers::assert_err;
 
 #[test]
 fn deposit_and_withdraw_for_credit_account_works() {
@@ -108,3 +111,85 @@ fn deposit_and_withdraw_for_credit_account_works() {
     assert!(cm_position.total_collateralized_debt.is_zero());
     assert_eq!(cm_position.health_status, UserHealthStatus::NotBorrowing);
 }
+
+#[test]
+fn transfer_collateral_to_credit_account_works() {
+    let owner = Addr::unchecked("owner");
+    let mut mock_env = MockEnvBuilder::new(None, owner.clone()).build();
+
+    let red_bank = mock_env.red_bank.clone();
+    let params = mock_env.params.clone();
+    let oracle = mock_env.oracle.clone();
+
+    let funded_amt = 1_000_000_000_000u128;
+    let provider = Addr::unchecked("provider"); // provides collateral to be borrowed by others
+    let user = Addr::unchecked("user");
+    let credit_manager = mock_env.credit_manager.clone();
+    let account_id = "111".to_string();
+
+    // setup red-bank
+    let (market_params, asset_params) = osmo_asset_params();
+    red_bank.init_asset(&mut mock_env, &asset_params.denom, market_params);
+    params.init_params(&mut mock_env, asset_params);
+    let (market_params, asset_params) = usdc_asset_params();
+    red_bank.init_asset(&mut mock_env, &asset_params.denom, market_params);
+    params.init_params(&mut mock_env, asset_params);
+
+    // setup oracle
+    oracle.set_price_source_fixed(&mut mock_env, "uosmo", Decimal::one());
+    oracle.set_price_source_fixed(&mut mock_env, "uusdc", Decimal::one());
+
+    // fund accounts
+    mock_env.fund_accounts(&[&provider, &user], funded_amt, &["uosmo", "uusdc"]);
+
+    red_bank.deposit(&mut mock_env, &provider, coin(1000000000, "uusdc")).unwrap();
+    red_bank.deposit(&mut mock_env, &user, coin(1000000, "uosmo")).unwrap();
+    red_bank.borrow(&mut mock_env, &user, "uusdc", 500000).unwrap();
+
+    // only the credit manager can transfer collateral
+    let res = red_bank.transfer_collateral_to_credit_account(
+        &mut mock_env,
+        &user,
+        &user,
+        &account_id,
+        "uosmo",
+        None,
+    );
+    assert_err(res, ContractError::Mars(MarsError::Unauthorized {}));
+
+    // the remaining collateral has to cover the debt of the user
+    let res = red_bank.transfer_collateral_to_credit_account(
+        &mut mock_env,
+        &credit_manager,
+        &user,
+        &account_id,
+        "uosmo",
+        None,
+    );
+    assert_err(res, ContractError::InvalidHealthFactorAfterWithdraw {});
+
+    red_bank.repay(&mut mock_env, &user, coin(500000, "uusdc")).unwrap();
+    red_bank
+        .transfer_collateral_to_credit_account(
+            &mut mock_env,
+            &credit_manager,
+            &user,
+            &account_id,
+            "uosmo",
+            None,
+        )
+        .unwrap();
+
+    let user_collaterals = red_bank.query_user_collaterals(&mut mock_env, &user);
+    assert!(user_collaterals.is_empty());
+    let cm_collaterals = red_bank.query_user_collaterals_with_acc_id(
+        &mut mock_env,
+        &credit_manager,
+        Some(account_id),
+    );
+    assert_eq!(cm_collaterals.get("uosmo").unwrap().amount.u128(), 1000000);
+
+    // coins didn't leave the red bank
+    let market = red_bank.query_market_v2(&mut mock_env, "uosmo");
+    assert_eq!(market.collateral_total_amount.u128(), 1000000);
+}
@@ -5,6 +5,7 @@ use cosmwasm_std::{
     testing::{mock_dependencies, mock_env, mock_info},
     to_json_binary, Addr, Decimal, SubMsg, Uint128, WasmMsg,
 };
+use cw_multi_test::AppResponse;
 use cw_utils::PaymentError;
 use mars_red_bank::{contract::execute, error::ContractError};
 use mars_testing::{
@@ -15,9 +16,9 @@ use mars_types::{
     address_provider::MarsAddressType,
     incentives,
     incentives::IncentiveKind,
-    params::{AssetParams, CmSettings, LiquidationBonus, RedBankSettings},
+    params::{AssetParams, CmSettings, LiquidationAuction, LiquidationBonus, RedBankSettings},
     red_bank::{
-        ExecuteMsg, InitOrUpdateAssetParams, InterestRateModel, Market, QueryMsg,
+        BatchLiquidation, ExecuteMsg, InitOrUpdateAssetParams, InterestRateModel, Market, QueryMsg,
         UserCollateralResponse, UserDebtResponse,
     },
 };
@@ -1073,6 +1074,317 @@ fn expected_messages(
     ]
 }
 
+#[test]
+fn batch_liquidation_skips_users_not_liquidatable() {
+    let mut mock_env = MockEnvBuilder::new(None, Addr::unchecked("owner"))
+        .target_health_factor(Decimal::from_ratio(12u128, 10u128))
+        .build();
+
+    let red_bank = mock_env.red_bank.clone();
+    let oracle = mock_env.oracle.clone();
+
+    let (funded_amt, provider, liquidatee, liquidator) = setup_env(&mut mock_env);
+
+    // change price to be able to liquidate
+    oracle.set_price_source_fixed(&mut mock_env, "uosmo", Decimal::from_ratio(3u128, 1u128));
+    oracle.set_price_source_fixed(&mut mock_env, "uusdc", Decimal::from_ratio(85u128, 10u128));
+
+    // coins sent have to match the debt coins
+    let liquidations = vec![
+        BatchLiquidation {
+            user: liquidatee.to_string(),
+            collateral_denom: "uosmo".to_string(),
+            debt_coin: coin(2400, "uusdc"),
+        },
+        BatchLiquidation {
+            user: provider.to_string(),
+            collateral_denom: "uusdc".to_string(),
+            debt_coin: coin(100, "uusdc"),
+        },
+    ];
+    let error_res = red_bank.batch_liquidate(
+        &mut mock_env,
+        &liquidator,
+        liquidations.clone(),
+        &[coin(2400, "uusdc")],
+    );
+    assert_err(error_res, ContractError::InvalidBatchLiquidationFunds {});
+
+    // provider has no debt, its liquidation is skipped
+    let res = red_bank
+        .batch_liquidate(&mut mock_env, &liquidator, liquidations, &[coin(2500, "uusdc")])
+        .unwrap();
+    let result = |key: &str| {
+        res.events
+            .iter()
+            .flat_map(|e| &e.attributes)
+            .find(|a| a.key == key)
+            .map(|a| a.value.clone())
+            .unwrap()
+    };
+    assert_eq!(result("liquidation_0"), format!("{liquidatee}:liquidated"));
+    assert_eq!(result("liquidation_1"), format!("{provider}:skipped"));
+
+    // same result as a single liquidation (see `max_debt_repayed`)
+    let liquidatee_debts = red_bank.query_user_debts(&mut mock_env, &liquidatee);
+    assert_eq!(liquidatee_debts.get("uusdc").unwrap().amount.u128(), 600);
+    let liquidator_collaterals = red_bank.query_user_collaterals(&mut mock_env, &liquidator);
+    assert_eq!(liquidator_collaterals.get("uosmo").unwrap().amount.u128(), 7264);
+
+    // debt coin of the skipped liquidation is refunded
+    let provider_collaterals = red_bank.query_user_collaterals(&mut mock_env, &provider);
+    assert_eq!(provider_collaterals.get("uusdc").unwrap().amount.u128(), 1000000);
+    let usdc_liquidator_balance = mock_env.query_balance(&liquidator, "uusdc").unwrap();
+    assert_eq!(usdc_liquidator_balance.amount.u128(), funded_amt - 2400);
+}
+
+#[test]
+fn batch_liquidation_skips_users_without_requested_collateral() {
+    let mut mock_env = MockEnvBuilder::new(None, Addr::unchecked("owner"))
+        .target_health_factor(Decimal::from_ratio(12u128, 10u128))
+        .build();
+
+    let red_bank = mock_env.red_bank.clone();
+    let oracle = mock_env.oracle.clone();
+
+    let (funded_amt, _, liquidatee, liquidator) = setup_env(&mut mock_env);
+
+    // change price to be able to liquidate
+    oracle.set_price_source_fixed(&mut mock_env, "uosmo", Decimal::from_ratio(3u128, 1u128));
+    oracle.set_price_source_fixed(&mut mock_env, "uusdc", Decimal::from_ratio(85u128, 10u128));
+
+    // liquidatee has no untrn collateral, its second liquidation is skipped
+    let liquidations = vec![
+        BatchLiquidation {
+            user: liquidatee.to_string(),
+            collateral_denom: "uosmo".to_string(),
+            debt_coin: coin(2400, "uusdc"),
+        },
+        BatchLiquidation {
+            user: liquidatee.to_string(),
+            collateral_denom: "untrn".to_string(),
+            debt_coin: coin(100, "uusdc"),
+        },
+    ];
+    let res = red_bank
+        .batch_liquidate(&mut mock_env, &liquidator, liquidations, &[coin(2500, "uusdc")])
+        .unwrap();
+    assert_eq!(attribute(&res, "liquidation_0"), format!("{liquidatee}:liquidated"));
+    assert_eq!(attribute(&res, "liquidation_1"), format!("{liquidatee}:skipped"));
+
+    // debt coin of the skipped liquidation is refunded
+    let liquidatee_debts = red_bank.query_user_debts(&mut mock_env, &liquidatee);
+    assert_eq!(liquidatee_debts.get("uusdc").unwrap().amount.u128(), 600);
+    let usdc_liquidator_balance = mock_env.query_balance(&liquidator, "uusdc").unwrap();
+    assert_eq!(usdc_liquidator_balance.amount.u128(), funded_amt - 2400);
+}
+
+#[test]
+fn liquidatable_users_query_returns_liquidation_candidate() {
+    let mut mock_env = MockEnvBuilder::new(None, Addr::unchecked("owner"))
+        .target_health_factor(Decimal::from_ratio(12u128, 10u128))
+        .build();
+
+    let red_bank = mock_env.red_bank.clone();
+    let oracle = mock_env.oracle.clone();
+
+    let (funded_amt, _, liquidatee, liquidator) = setup_env(&mut mock_env);
+
+    // provider has no debt and liquidatee is healthy
+    let res = red_bank.query_liquidatable_users(&mut mock_env, None, None);
+    assert!(res.users.is_empty());
+    assert_eq!(res.next_start_after, None);
+
+    // change price to be able to liquidate
+    oracle.set_price_source_fixed(&mut mock_env, "uosmo", Decimal::from_ratio(3u128, 1u128));
+    oracle.set_price_source_fixed(&mut mock_env, "uusdc", Decimal::from_ratio(85u128, 10u128));
+
+    let res = red_bank.query_liquidatable_users(&mut mock_env, None, None);
+    assert_eq!(res.users.len(), 1);
+    let user = res.users[0].clone();
+    assert_eq!(user.user, liquidatee.to_string());
+    let candidate = user.liquidation.unwrap();
+    let debt_denom = candidate.debt_coin.denom.clone();
+
+    let debt_before = red_bank.query_user_debts(&mut mock_env, &liquidatee)[&debt_denom].amount;
+    red_bank
+        .liquidate(
+            &mut mock_env,
+            &liquidator,
+            &liquidatee,
+            &candidate.collateral_denom,
+            &[candidate.debt_coin.clone()],
+        )
+        .unwrap();
+
+    // the whole candidate debt is repaid, nothing is refunded
+    let debt_after = red_bank.query_user_debts(&mut mock_env, &liquidatee)[&debt_denom].amount;
+    assert!(debt_after < debt_before);
+    let liquidator_balance = mock_env.query_balance(&liquidator, &debt_denom).unwrap();
+    assert_eq!(liquidator_balance.amount.u128(), funded_amt - candidate.debt_coin.amount.u128());
+}
+
+#[test]
+fn liquidatable_users_query_reports_users_without_price() {
+    let mut mock_env = MockEnvBuilder::new(None, Addr::unchecked("owner"))
+        .target_health_factor(Decimal::from_ratio(12u128, 10u128))
+        .build();
+
+    let red_bank = mock_env.red_bank.clone();
+    let params = mock_env.params.clone();
+    let oracle = mock_env.oracle.clone();
+
+    let (_, _, liquidatee, _) = setup_env(&mut mock_env);
+
+    // ustars has no price source
+    let (market_params, asset_params) = default_asset_params_with(
+        "ustars",
+        Decimal::percent(50),
+        Decimal::percent(55),
+        Decimal::percent(80),
+    );
+    red_bank.init_asset(&mut mock_env, &asset_params.denom, market_params);
+    params.init_params(&mut mock_env, asset_params);
+
+    let unpriced = Addr::unchecked("unpriced");
+    mock_env.fund_accounts(&[&unpriced], 1_000_000u128, &["uosmo", "ustars"]);
+    red_bank.deposit(&mut mock_env, &unpriced, coin(10000, "uosmo")).unwrap();
+    red_bank.borrow(&mut mock_env, &unpriced, "uusdc", 1000).unwrap();
+    red_bank.deposit(&mut mock_env, &unpriced, coin(10000, "ustars")).unwrap();
+
+    // change price to be able to liquidate
+    oracle.set_price_source_fixed(&mut mock_env, "uosmo", Decimal::from_ratio(3u128, 1u128));
+    oracle.set_price_source_fixed(&mut mock_env, "uusdc", Decimal::from_ratio(85u128, 10u128));
+
+    // the user without price doesn't prevent returning the liquidatable one
+    let res = red_bank.query_liquidatable_users(&mut mock_env, None, None);
+    assert_eq!(res.users.len(), 1);
+    assert_eq!(res.users[0].user, liquidatee.to_string());
+    assert_eq!(res.failed_users, vec![unpriced.to_string()]);
+}
+
+#[test]
+fn auctioned_liquidation_bonus_grows_with_time_since_flagged() {
+    let mut mock_env = MockEnvBuilder::new(None, Addr::unchecked("owner"))
+        .target_health_factor(Decimal::from_ratio(12u128, 10u128))
+        .build();
+
+    let red_bank = mock_env.red_bank.clone();
+    let params = mock_env.params.clone();
+    let oracle = mock_env.oracle.clone();
+
+    let (_, _, liquidatee, liquidator) = setup_env(&mut mock_env);
+
+    // LB of uosmo goes from min_lb to max_lb over 1000 seconds
+    let mut osmo_params = params.query_params(&mut mock_env, "uosmo");
+    osmo_params.liquidation_auction = Some(LiquidationAuction {
+        duration: 1000,
+    });
+    params.init_params(&mut mock_env, osmo_params);
+
+    // flagging a healthy user doesn't start the auction
+    let res = red_bank.flag_liquidatable(&mut mock_env, &liquidator, &liquidatee).unwrap();
+    assert_eq!(attribute(&res, "liquidatable_since"), "none");
+
+    // change price to be able to liquidate
+    oracle.set_price_source_fixed(&mut mock_env, "uosmo", Decimal::from_ratio(3u128, 1u128));
+    oracle.set_price_source_fixed(&mut mock_env, "uusdc", Decimal::from_ratio(85u128, 10u128));
+
+    let res = red_bank.flag_liquidatable(&mut mock_env, &liquidator, &liquidatee).unwrap();
+    assert_eq!(
+        attribute(&res, "liquidatable_since"),
+        mock_env.app.block_info().time.seconds().to_string()
+    );
+
+    // same debt repaid by each liquidation, the collateral received grows with the LB
+    let liquidate = |mock_env: &mut MockEnv| {
+        red_bank
+            .liquidate(mock_env, &liquidator, &liquidatee, "uosmo", &[coin(100, "uusdc")])
+            .unwrap();
+        let collaterals = red_bank.query_user_collaterals(mock_env, &liquidator);
+        collaterals.get("uosmo").unwrap().amount
+    };
+
+    let received_at_start = liquidate(&mut mock_env);
+
+    mock_env.increment_by_time(500);
+    let received_at_half = liquidate(&mut mock_env) - received_at_start;
+    assert!(received_at_half > received_at_start);
+
+    mock_env.increment_by_time(1000);
+    let received_at_end = liquidate(&mut mock_env) - received_at_start - received_at_half;
+    assert!(received_at_end > received_at_half);
+}
+
+#[test]
+fn flag_of_recovered_user_is_removed_on_deposit() {
+    let mut mock_env = MockEnvBuilder::new(None, Addr::unchecked("owner"))
+        .target_health_factor(Decimal::from_ratio(12u128, 10u128))
+        .build();
+
+    let red_bank = mock_env.red_bank.clone();
+    let oracle = mock_env.oracle.clone();
+
+    let (_, _, liquidatee, liquidator) = setup_env(&mut mock_env);
+
+    // change price to be able to liquidate
+    oracle.set_price_source_fixed(&mut mock_env, "uosmo", Decimal::from_ratio(3u128, 1u128));
+    oracle.set_price_source_fixed(&mut mock_env, "uusdc", Decimal::from_ratio(85u128, 10u128));
+    red_bank.flag_liquidatable(&mut mock_env, &liquidator, &liquidatee).unwrap();
+
+    // the user recovers through a price move and deposits
+    oracle.set_price_source_fixed(&mut mock_env, "uusdc", Decimal::one());
+    red_bank.deposit(&mut mock_env, &liquidatee, coin(100, "uosmo")).unwrap();
+
+    // once liquidatable again, the auction starts over
+    mock_env.increment_by_time(1000);
+    oracle.set_price_source_fixed(&mut mock_env, "uusdc", Decimal::from_ratio(85u128, 10u128));
+    let res = red_bank.flag_liquidatable(&mut mock_env, &liquidator, &liquidatee).unwrap();
+    assert_eq!(
+        attribute(&res, "liquidatable_since"),
+        mock_env.app.block_info().time.seconds().to_string()
+    );
+}
+
+#[test]
+fn flag_of_recovered_user_is_removed_on_borrow() {
+    let mut mock_env = MockEnvBuilder::new(None, Addr::unchecked("owner"))
+        .target_health_factor(Decimal::from_ratio(12u128, 10u128))
+        .build();
+
+    let red_bank = mock_env.red_bank.clone();
+    let oracle = mock_env.oracle.clone();
+
+    let (_, _, liquidatee, liquidator) = setup_env(&mut mock_env);
+
+    // change price to be able to liquidate
+    oracle.set_price_source_fixed(&mut mock_env, "uosmo", Decimal::from_ratio(3u128, 1u128));
+    oracle.set_price_source_fixed(&mut mock_env, "uusdc", Decimal::from_ratio(85u128, 10u128));
+    red_bank.flag_liquidatable(&mut mock_env, &liquidator, &liquidatee).unwrap();
+
+    // the user recovers through a price move and borrows
+    oracle.set_price_source_fixed(&mut mock_env, "uusdc", Decimal::one());
+    red_bank.borrow(&mut mock_env, &liquidatee, "uusdc", 10).unwrap();
+
+    // once liquidatable again, the auction starts over instead of resuming the stale flag
+    mock_env.increment_by_time(1000);
+    oracle.set_price_source_fixed(&mut mock_env, "uusdc", Decimal::from_ratio(85u128, 10u128));
+    let res = red_bank.flag_liquidatable(&mut mock_env, &liquidator, &liquidatee).unwrap();
+    assert_eq!(
+        attribute(&res, "liquidatable_since"),
+        mock_env.app.block_info().time.seconds().to_string()
+    );
+}
+
+fn attribute(res: &AppResponse, key: &str) -> String {
+    res.events
+        .iter()
+        .flat_map(|e| &e.attributes)
+        .find(|a| a.key == key)
+        .map(|a| a.value.clone())
+        .unwrap()
+}
+
 fn setup_env(mock_env: &mut MockEnv) -> (u128, Addr, Addr, Addr) {
     setup_env_with_usdc_cf(mock_env, Decimal::percent(80))
 }
@@ -1243,6 +1555,7 @@ fn _default_asset_params_with(
         protocol_liquidation_fee: Decimal::percent(2),
         deposit_cap: Uint128::MAX,
         close_factor,
+        liquidation_auction: None,
     };
     (market_params, asset_params)
 }
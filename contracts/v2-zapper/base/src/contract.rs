@@ -88,6 +88,9 @@ where
             QueryMsg::EstimateWithdrawLiquidity {
                 coin_in,
             } => Self::query_estimate_withdraw_liquidity(deps, env, coin_in),
+            QueryMsg::IsLpToken {
+                denom,
+            } => to_json_binary(&P::get_pool_for_lp_token(deps, &denom).is_ok()),
         }
     }
 
@@ -77,6 +77,9 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> ContractResult<Binary> {
         QueryMsg::EstimateWithdrawLiquidity {
             coin_in,
         } => to_json_binary(&estimate_withdraw_liquidity(deps.storage, &coin_in)?),
+        QueryMsg::IsLpToken {
+            denom,
+        } => to_json_binary(&COIN_CONFIG.has(deps.storage, &denom)),
     };
     res.map_err(Into::into)
 }
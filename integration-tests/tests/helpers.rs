@@ -48,6 +48,7 @@ pub fn default_asset_params(denom: &str) -> (InitOrUpdateAssetParams, AssetParam
         protocol_liquidation_fee: Decimal::percent(2u64),
         deposit_cap: Uint128::MAX,
         close_factor: Decimal::percent(80u64),
+        liquidation_auction: None,
     };
     (market_params, asset_params)
 }
@@ -85,6 +86,7 @@ pub fn default_asset_params_with(
         protocol_liquidation_fee: Decimal::percent(2u64),
         deposit_cap: Uint128::MAX,
         close_factor: Decimal::percent(80u64),
+        liquidation_auction: None,
     };
     (market_params, asset_params)
 }
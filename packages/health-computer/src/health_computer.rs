@@ -16,8 +16,9 @@ use mars_types::{
         HealthError::{
             DenomNotPresent, MissingAmount, MissingAssetParams, MissingHLSParams,
             MissingPerpParams, MissingPrice, MissingVaultConfig, MissingVaultValues,
+            NotIsolatedPerp,
         },
-        HealthResult, LiquidationPriceKind, SwapKind,
+        HealthResult, IsolatedPerpHealth, LiquidationPriceKind, SwapKind,
     },
     params::{AssetParams, CmSettings, HlsAssetType, VaultConfig},
     perps::{PerpPosition, PnL},
@@ -70,8 +71,7 @@ impl HealthComputer {
         } = self.total_collateral_value()?;
 
         let spot_debt_value = self.debt_value()?;
-        let (perp_hf_values, perp_pnl_values) =
-            self.perp_hf_values_and_pnl(&self.positions.perps)?;
+        let (perp_hf_values, perp_pnl_values) = self.perp_hf_values_and_pnl(&self.cross_perps())?;
         let ltv_numerator =
             max_ltv_adjusted_collateral.checked_add(perp_hf_values.max_ltv_numerator)?;
         let ltv_denominator = spot_debt_value.checked_add(perp_hf_values.max_ltv_denominator)?;
@@ -168,7 +168,7 @@ impl HealthComputer {
                     max_ltv_denominator: perp_denominator,
                     max_ltv_numerator: perp_numerator,
                     ..
-                } = self.perp_hf_values_and_pnl(&self.positions.perps)?.0;
+                } = self.perp_hf_values_and_pnl(&self.cross_perps())?.0;
 
                 let one = Uint128::one();
                 let numerator = total_max_ltv_adjusted_value.checked_add(perp_numerator)?;
@@ -236,7 +236,7 @@ impl HealthComputer {
             max_ltv_denominator: perp_denominator,
             max_ltv_numerator: perp_numerator,
             ..
-        } = self.perp_hf_values_and_pnl(&self.positions.perps)?.0;
+        } = self.perp_hf_values_and_pnl(&self.cross_perps())?.0;
 
         let one = Uint128::one();
         let numerator = total_max_ltv_adjusted_value.checked_add(perp_numerator)?;
@@ -373,7 +373,7 @@ impl HealthComputer {
             max_ltv_denominator: perp_denominator,
             max_ltv_numerator: perp_numerator,
             ..
-        } = self.perp_hf_values_and_pnl(&self.positions.perps)?.0;
+        } = self.perp_hf_values_and_pnl(&self.cross_perps())?.0;
 
         let params = self
             .asset_params
@@ -575,10 +575,8 @@ impl HealthComputer {
         let perp_params =
             self.perps_data.params.get(denom).ok_or(MissingPerpParams(denom.to_string()))?;
         let closing_fee_rate = perp_params.closing_fee_rate;
-        let opening_fee_rate = perp_params.opening_fee_rate;
         let skew_scale = perp_params.skew_scale;
         let ltv_base_denom = self.get_coin_max_ltv(base_denom)?;
-        let ltv_p = perp_params.max_loan_to_value;
 
         // The max position change amount afforded by the open interest caps, in the given direction
         let max_oi_change_amount = calculate_remaining_oi_amount(
@@ -592,6 +590,9 @@ impl HealthComputer {
         // Current skew
         let k = Int128::try_from(long_oi_amount)?.checked_sub(short_oi_amount.try_into()?)?;
 
+        // Orders in the given direction pay the maker opening fee if they reduce the skew
+        let opening_fee_rate = perp_params.opening_fee_rate_at(k, direction.sign());
+
         let (
             // Current unrealized funding
             f_amount,
@@ -626,8 +627,9 @@ impl HealthComputer {
         }
 
         let p_ex = closing_execution_price(k, skew_scale, q_old, perp_oracle_price)?;
-        let closing_fee_value =
-            q_old.unsigned_abs().checked_mul_floor(p_ex.checked_mul(closing_fee_rate)?)?;
+        let closing_fee_value = q_old
+            .unsigned_abs()
+            .checked_mul_floor(p_ex.checked_mul(perp_params.closing_fee_rate_at(k, q_old))?)?;
 
         // Indicator functions
         let (i, i_prim) = if (q_old.is_negative() && direction == &Direction::Long)
@@ -658,19 +660,6 @@ impl HealthComputer {
         let (base_denom_collateral_value, rwa_value, debt_value) =
             self.account_composition(base_denom, denom, base_denom_price)?;
 
-        // z = LTVp - closing fee - opening fee - 1
-        let z = ltv_p.bd() - closing_fee_rate.bd() - opening_fee_rate.bd() - BigDecimal::one();
-
-        // a = - z * (price_oracle / (2 * skew_scale)) (SHORT)
-        // a = z * (price_oracle / (2 * skew_scale)) (LONG)
-        let two_times_skew_scale = BigDecimal::from(2u128) * skew_scale.bd();
-        let a = direction.sign().bd() * z.clone() * perp_oracle_price.bd() / two_times_skew_scale;
-
-        // b = z * price_oracle * (1 + (k - q_old) / skew_scale)
-        let b = z
-            * perp_oracle_price.bd()
-            * (BigDecimal::one() + (k.bd() - q_old.bd()) / skew_scale.bd());
-
         // c = y + i * opening_fee_rate * |q_old| * price_oracle * (1 + (k - q_old / 2) / skew_scale)
         // y = RWA - debt + c_big_max * LTV_base_denom - c_big_min
         // c_big_max = max(0, c_big)
@@ -687,14 +676,49 @@ impl HealthComputer {
             * (BigDecimal::one()
                 + (k.bd() - q_old.bd() / BigDecimal::from(2u128)) / skew_scale.bd());
 
-        // d = b^2 - 4ac
-        let d = b.square() - BigDecimal::from(4u128) * a.clone() * c;
+        // Max position size if the whole position uses the given LTVp
+        let q_max_amount_for_ltv = |ltv_p: Decimal| -> HealthResult<Int128> {
+            // z = LTVp - closing fee - opening fee - 1
+            let z = ltv_p.bd() - closing_fee_rate.bd() - opening_fee_rate.bd() - BigDecimal::one();
+
+            // a = - z * (price_oracle / (2 * skew_scale)) (SHORT)
+            // a = z * (price_oracle / (2 * skew_scale)) (LONG)
+            let two_times_skew_scale = BigDecimal::from(2u128) * skew_scale.bd();
+            let a =
+                direction.sign().bd() * z.clone() * perp_oracle_price.bd() / two_times_skew_scale;
+
+            // b = z * price_oracle * (1 + (k - q_old) / skew_scale)
+            let b = z
+                * perp_oracle_price.bd()
+                * (BigDecimal::one() + (k.bd() - q_old.bd()) / skew_scale.bd());
+
+            // d = b^2 - 4ac
+            let d = b.square() - BigDecimal::from(4u128) * a.clone() * c.clone();
+
+            // q_max = - (b + sqrt(d)) / (2 * a)
+            let q_max_amount =
+                -(b + d.sqrt().unwrap_or(BigDecimal::zero())) / (BigDecimal::from(2u128) * a);
+            let q_max_amount = q_max_amount.with_scale_round(0, RoundingMode::Down);
+            Ok(Int128::from_str(q_max_amount.to_string().as_str())?)
+        };
+
+        // Positions reaching a margin tier use its (lower) LTV. If the size afforded with the LTV
+        // of a tier doesn't reach the tier, the position is capped just below the tier.
+        let mut q_max_amount = q_max_amount_for_ltv(perp_params.max_loan_to_value)?;
+        for margin_tier in perp_params.margin_tiers.iter() {
+            let tier_amount: Int128 =
+                margin_tier.notional_threshold.checked_div_ceil(perp_oracle_price)?.try_into()?;
+            if q_max_amount < tier_amount {
+                break;
+            }
 
-        // q_max = - (b + sqrt(d)) / (2 * a)
-        let q_max_amount =
-            -(b + d.sqrt().unwrap_or(BigDecimal::zero())) / (BigDecimal::from(2u128) * a);
-        let q_max_amount = q_max_amount.with_scale_round(0, RoundingMode::Down);
-        let mut q_max_amount = Int128::from_str(q_max_amount.to_string().as_str())?;
+            let tier_q_max_amount = q_max_amount_for_ltv(margin_tier.max_loan_to_value)?;
+            if tier_q_max_amount < tier_amount {
+                q_max_amount = tier_amount.checked_sub(Int128::one())?;
+                break;
+            }
+            q_max_amount = tier_q_max_amount;
+        }
 
         // If we are increasing the position, we need to adjust the max oi to include the current position.
         // For example:
@@ -761,7 +785,7 @@ impl HealthComputer {
             base_denom_lends.first().map_or(Uint128::zero(), |l| l.amount);
 
         let filtered_perps: Vec<_> =
-            self.positions.perps.iter().filter(|x| x.denom != denom).cloned().collect();
+            self.cross_perps().into_iter().filter(|x| x.denom != denom).collect();
 
         // (named c_usdc in docs + sheet)
         // Refers to the value of collateral the user has in the base_denom (e.g usdc)
@@ -805,6 +829,60 @@ impl HealthComputer {
         Ok((base_denom_collateral_value, other_collateral_value, debt_value))
     }
 
+    /// Health of an isolated perp position. The margin is weighted like a deposit of the base denom
+    /// and the position contributes the same numerator and denominator as in the account health factor.
+    pub fn isolated_perp_health(&self, denom: &str) -> HealthResult<IsolatedPerpHealth> {
+        let position = self
+            .positions
+            .perps
+            .iter()
+            .find(|x| x.denom == denom)
+            .ok_or(DenomNotPresent(denom.to_string()))?;
+        let margin = position.isolated_margin.ok_or(NotIsolatedPerp(denom.to_string()))?;
+
+        let base_denom = &position.base_denom;
+        let margin_value = margin.checked_mul_floor(self.get_price(base_denom)?)?;
+        let perp_hf_values = self.perp_health_factor_values(position)?;
+
+        let max_ltv_numerator = margin_value
+            .checked_mul_floor(self.get_coin_max_ltv(base_denom)?)?
+            .checked_add(perp_hf_values.max_ltv_numerator)?;
+        let liq_ltv_numerator = margin_value
+            .checked_mul_floor(self.get_coin_liq_ltv(base_denom)?)?
+            .checked_add(perp_hf_values.liq_ltv_numerator)?;
+
+        let (max_ltv_health_factor, liquidation_health_factor) =
+            if perp_hf_values.max_ltv_denominator.is_zero()
+                || perp_hf_values.liq_ltv_denominator.is_zero()
+            {
+                (None, None)
+            } else {
+                (
+                    Some(Decimal::checked_from_ratio(
+                        max_ltv_numerator,
+                        perp_hf_values.max_ltv_denominator,
+                    )?),
+                    Some(Decimal::checked_from_ratio(
+                        liq_ltv_numerator,
+                        perp_hf_values.liq_ltv_denominator,
+                    )?),
+                )
+            };
+
+        Ok(IsolatedPerpHealth {
+            denom: denom.to_string(),
+            margin_value,
+            max_ltv_health_factor,
+            liquidation_health_factor,
+        })
+    }
+
+    /// Perp positions backed by the whole account. Isolated positions only count towards their
+    /// own health (see `isolated_perp_health`).
+    fn cross_perps(&self) -> Vec<PerpPosition> {
+        self.positions.perps.iter().filter(|x| x.isolated_margin.is_none()).cloned().collect()
+    }
+
     fn perp_hf_values_and_pnl(
         &self,
         perps: &[PerpPosition],
@@ -879,8 +957,8 @@ impl HealthComputer {
         // Borrow and liquidation ltv maximums for the perp and the funding denom
         // It was agreed to change LTV in the formula from usdc to perp ltv, as it should be more
         // conservative (when we make usdc LTV greater than or equal to any perp LTV)
-        let checked_max_ltv = self.get_perp_max_ltv(denom)?;
-        let checked_liq_ltv = self.get_perp_liq_ltv(denom)?;
+        let checked_max_ltv = self.get_perp_max_ltv(position)?;
+        let checked_liq_ltv = self.get_perp_liq_ltv(position)?;
         let (funding_min, funding_max) = self.get_min_and_max_funding_amounts(position)?;
         let funding_min_value = funding_min.checked_mul_floor(base_denom_price)?;
         let funding_max_value_ltv =
@@ -1190,7 +1268,9 @@ impl HealthComputer {
         )?)
     }
 
-    fn get_perp_max_ltv(&self, denom: &str) -> HealthResult<Decimal> {
+    /// Max LTV of the perp position, depending on the margin tier of its notional value
+    fn get_perp_max_ltv(&self, position: &PerpPosition) -> HealthResult<Decimal> {
+        let denom = &position.denom;
         let params =
             self.perps_data.params.get(denom).ok_or(MissingPerpParams(denom.to_string()))?;
 
@@ -1198,10 +1278,15 @@ impl HealthComputer {
             return Ok(Decimal::zero());
         }
 
-        Ok(params.max_loan_to_value)
+        let position_value =
+            position.size.unsigned_abs().checked_mul_floor(position.current_price)?;
+        Ok(params.max_loan_to_value_for(position_value))
     }
 
-    fn get_perp_liq_ltv(&self, denom: &str) -> HealthResult<Decimal> {
+    /// Liquidation threshold of the perp position, depending on the margin tier of its notional
+    /// value
+    fn get_perp_liq_ltv(&self, position: &PerpPosition) -> HealthResult<Decimal> {
+        let denom = &position.denom;
         let params =
             self.perps_data.params.get(denom).ok_or(MissingPerpParams(denom.to_string()))?;
 
@@ -1209,7 +1294,9 @@ impl HealthComputer {
             return Ok(Decimal::zero());
         }
 
-        Ok(params.liquidation_threshold)
+        let position_value =
+            position.size.unsigned_abs().checked_mul_floor(position.current_price)?;
+        Ok(params.liquidation_threshold_for(position_value))
     }
 
     fn get_coin_max_ltv(&self, denom: &str) -> HealthResult<Decimal> {
@@ -1322,7 +1409,7 @@ impl HealthComputer {
         let debt_value = self.debt_value()?;
         let current_price = self.get_price(denom)?;
         let collateral_ltv_value = self.total_collateral_value()?.liq_ltv_adjusted_collateral;
-        let (perps_hf_values, _) = self.perp_hf_values_and_pnl(&self.positions.perps)?;
+        let (perps_hf_values, _) = self.perp_hf_values_and_pnl(&self.cross_perps())?;
 
         // When debt and liq_ltv_denominator are zero, there is no debt, so also no
         // liquidation price
@@ -1419,7 +1506,7 @@ impl HealthComputer {
                     .ok_or(MissingPerpParams(denom.to_string()))?
                     .closing_fee_rate;
 
-                let perp_ltv = self.get_perp_liq_ltv(denom)?;
+                let perp_ltv = self.get_perp_liq_ltv(perp_position)?;
                 let current_perp_price = perp_position.current_exec_price;
 
                 match perp_position.size.is_negative() {
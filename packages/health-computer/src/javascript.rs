@@ -1,5 +1,6 @@
 use mars_types::health::{
-    BorrowTarget, HealthValuesResponse, LiquidationPriceKind, Number, SwapKind, Uint,
+    BorrowTarget, HealthValuesResponse, IsolatedPerpHealth, LiquidationPriceKind, Number, SwapKind,
+    Uint,
 };
 use wasm_bindgen::prelude::*;
 
@@ -15,6 +16,11 @@ pub fn compute_health_js(c: HealthComputer) -> HealthValuesResponse {
     c.compute_health().unwrap().into()
 }
 
+#[wasm_bindgen]
+pub fn isolated_perp_health_js(c: HealthComputer, denom: String) -> IsolatedPerpHealth {
+    c.isolated_perp_health(&denom).unwrap()
+}
+
 #[wasm_bindgen]
 pub fn max_withdraw_estimate_js(c: HealthComputer, withdraw_denom: String) -> String {
     c.max_withdraw_amount_estimate(&withdraw_denom).unwrap().to_string()
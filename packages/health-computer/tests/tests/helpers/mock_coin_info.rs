@@ -41,6 +41,7 @@ pub fn umars_info() -> CoinInfo {
             protocol_liquidation_fee: Decimal::percent(2u64),
             deposit_cap: Default::default(),
             close_factor: Decimal::percent(80u64),
+            liquidation_auction: None,
         },
     }
 }
@@ -73,6 +74,7 @@ pub fn udai_info() -> CoinInfo {
             protocol_liquidation_fee: Decimal::percent(2u64),
             deposit_cap: Default::default(),
             close_factor: Decimal::percent(80u64),
+            liquidation_auction: None,
         },
     }
 }
@@ -105,6 +107,7 @@ pub fn uluna_info() -> CoinInfo {
             protocol_liquidation_fee: Decimal::percent(2u64),
             deposit_cap: Default::default(),
             close_factor: Decimal::percent(80u64),
+            liquidation_auction: None,
         },
     }
 }
@@ -149,6 +152,7 @@ pub fn ustars_info() -> CoinInfo {
             protocol_liquidation_fee: Decimal::percent(2u64),
             deposit_cap: Default::default(),
             close_factor: Decimal::percent(80u64),
+            liquidation_auction: None,
         },
     }
 }
@@ -181,6 +185,7 @@ pub fn ujuno_info() -> CoinInfo {
             protocol_liquidation_fee: Decimal::percent(2u64),
             deposit_cap: Default::default(),
             close_factor: Decimal::percent(80u64),
+            liquidation_auction: None,
         },
     }
 }
@@ -219,6 +224,7 @@ pub fn uatom_info() -> CoinInfo {
             protocol_liquidation_fee: Decimal::percent(2u64),
             deposit_cap: Default::default(),
             close_factor: Decimal::percent(80u64),
+            liquidation_auction: None,
         },
     }
 }
@@ -257,6 +263,7 @@ pub fn uusdc_info() -> CoinInfo {
             protocol_liquidation_fee: Decimal::percent(2u64),
             deposit_cap: Default::default(),
             close_factor: Decimal::percent(80u64),
+            liquidation_auction: None,
         },
     }
 }
@@ -293,6 +300,7 @@ pub fn create_coin_info(
             protocol_liquidation_fee: Decimal::percent(2u64),
             deposit_cap: Default::default(),
             close_factor: Decimal::percent(80u64),
+            liquidation_auction: None,
         },
     }
 }
@@ -1,7 +1,10 @@
 use std::str::FromStr;
 
 use cosmwasm_std::{Decimal, SignedDecimal, Uint128};
-use mars_types::{params::PerpParams, perps::Funding};
+use mars_types::{
+    params::{FundingModel, PerpParams},
+    perps::Funding,
+};
 
 pub struct PerpInfo {
     pub denom: String,
@@ -18,11 +21,15 @@ pub fn create_default_perp_info() -> PerpParams {
         max_short_oi_value: Uint128::new(800),
         closing_fee_rate: Decimal::percent(5),
         opening_fee_rate: Decimal::percent(5),
+        maker_fee_rates: None,
         min_position_value: Uint128::new(10),
         max_position_value: None,
         max_loan_to_value: Decimal::percent(75),
         liquidation_threshold: Decimal::percent(78),
-        max_funding_velocity: Decimal::from_str("36").unwrap(),
+        margin_tiers: vec![],
+        funding_model: FundingModel::Velocity {
+            max_funding_velocity: Decimal::from_str("36").unwrap(),
+        },
         skew_scale: Uint128::new(1_000_000_000_000_000u128),
     }
 }
@@ -42,11 +49,15 @@ pub fn create_perp_info(
             max_short_oi_value: Uint128::new(800),
             closing_fee_rate: Decimal::from_str("0.0002").unwrap(),
             opening_fee_rate: Decimal::percent(5),
+            maker_fee_rates: None,
             min_position_value: Uint128::new(10),
             max_position_value: None,
             max_loan_to_value: max_ltv,
             liquidation_threshold,
-            max_funding_velocity: Decimal::from_str("36").unwrap(),
+            margin_tiers: vec![],
+            funding_model: FundingModel::Velocity {
+                max_funding_velocity: Decimal::from_str("36").unwrap(),
+            },
             skew_scale: Uint128::new(1_000_000_000_000_000u128),
         },
         denom,
@@ -85,7 +96,9 @@ pub fn create_default_funding() -> Funding {
     Funding {
         skew_scale: Uint128::new(1_000_000_000_000_000u128),
         last_funding_rate: SignedDecimal::from_str("1.0").unwrap(),
-        max_funding_velocity: Decimal::percent(0),
+        funding_model: FundingModel::Velocity {
+            max_funding_velocity: Decimal::percent(0),
+        },
         last_funding_accrued_per_unit_in_base_denom: SignedDecimal::from_str("3").unwrap(),
     }
 }
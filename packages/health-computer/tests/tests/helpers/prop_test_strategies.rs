@@ -12,8 +12,8 @@ use mars_types::{
     credit_manager::{DebtAmount, Positions},
     health::AccountKind,
     params::{
-        AssetParams, CmSettings, HlsAssetType, HlsParams, LiquidationBonus, PerpParams,
-        RedBankSettings, VaultConfig,
+        AssetParams, CmSettings, FundingModel, HlsAssetType, HlsParams, LiquidationBonus,
+        PerpParams, RedBankSettings, VaultConfig,
     },
     perps::{Funding, PerpPosition, PnlAmounts, Position},
 };
@@ -136,6 +136,7 @@ fn random_coin_info() -> impl Strategy<Value = AssetParams> {
                 protocol_liquidation_fee: Default::default(),
                 deposit_cap: Default::default(),
                 close_factor,
+                liquidation_auction: None,
             }
         },
     )
@@ -432,7 +433,9 @@ fn random_perps(perp_denoms_data: PerpsData) -> impl Strategy<Value = Vec<PerpPo
                         rate.checked_div(Decimal::from_str("1000").unwrap()).unwrap();
 
                     let funding = Funding {
-                        max_funding_velocity: Decimal::from_str("3").unwrap(),
+                        funding_model: FundingModel::Velocity {
+                            max_funding_velocity: Decimal::from_str("3").unwrap(),
+                        },
                         skew_scale,
                         last_funding_rate: rate.try_into().unwrap(),
                         last_funding_accrued_per_unit_in_base_denom:
@@ -447,8 +450,11 @@ fn random_perps(perp_denoms_data: PerpsData) -> impl Strategy<Value = Vec<PerpPo
                             current_skew,
                             market_price,
                             base_denom_price,
-                            opening_fee_rate,
-                            closing_fee_rate,
+                            &PerpParams {
+                                opening_fee_rate,
+                                closing_fee_rate,
+                                ..Default::default()
+                            },
                             PositionModification::Decrease(position.size),
                         )
                         .unwrap();
@@ -463,6 +469,7 @@ fn random_perps(perp_denoms_data: PerpsData) -> impl Strategy<Value = Vec<PerpPo
                         current_exec_price: market_price,
                         unrealized_pnl: pnl_amounts,
                         realized_pnl: PnlAmounts::default(),
+                        isolated_margin: None,
                     }
                 },
             ),
@@ -9,7 +9,7 @@ use mars_types::{
     },
     credit_manager::{DebtAmount, Positions},
     health::AccountKind,
-    params::VaultConfig,
+    params::{MarginTier, VaultConfig},
     perps::{PerpPosition, PnlAmounts},
 };
 
@@ -1376,6 +1376,7 @@ fn long_one_negative_pnl_perp_no_spot_debt() {
                     ..Default::default()
                 },
                 realized_pnl: PnlAmounts::default(),
+                isolated_margin: None,
             }],
         },
         oracle_prices,
@@ -1402,6 +1403,86 @@ fn long_one_negative_pnl_perp_no_spot_debt() {
     assert!(health.is_liquidatable());
 }
 
+/// An isolated position is left out of the account health and only backed by its margin.
+/// With the margin of `long_one_negative_pnl_perp_no_spot_debt` deposits, the isolated health
+/// factors match the account health factors of that scenario.
+#[test]
+fn isolated_perp_is_backed_by_its_margin_only() {
+    let uusd = uusdc_info();
+    let entry_price = Decimal::from_str("100").unwrap();
+    let current_price = Decimal::from_str("92").unwrap();
+    let max_ltv = Decimal::from_str("0.9").unwrap();
+    let liquidation_threshold = Decimal::from_str("0.95").unwrap();
+    let size = Int128::from_str("10000000").unwrap();
+    let btcperp =
+        create_perp_info("btc/usd/perp".to_string(), current_price, max_ltv, liquidation_threshold);
+
+    let asset_params = HashMap::from([(uusd.denom.clone(), uusd.params.clone())]);
+
+    let oracle_prices =
+        HashMap::from([(uusd.denom.clone(), uusd.price), (btcperp.denom.clone(), btcperp.price)]);
+
+    let vaults_data = Default::default();
+    let unrealized_funding_accrued = Int128::from_str("-25210000").unwrap();
+    let perps_data = PerpsData {
+        params: HashMap::from([(btcperp.denom.clone(), btcperp.perp_params.clone())]),
+    };
+    let h = HealthComputer {
+        kind: AccountKind::Default,
+        positions: Positions {
+            account_id: "123".to_string(),
+            account_kind: AccountKind::Default,
+
+            deposits: vec![coin(1000, &uusd.denom)],
+            debts: vec![],
+            lends: vec![],
+            staked_astro_lps: vec![],
+            vaults: vec![],
+            perps: vec![PerpPosition {
+                denom: btcperp.denom.clone(),
+                base_denom: uusd.denom,
+                current_price,
+                entry_price,
+                entry_exec_price: entry_price,
+                current_exec_price: current_price,
+                size,
+                unrealized_pnl: PnlAmounts {
+                    accrued_funding: unrealized_funding_accrued,
+                    pnl: Int128::from_str("-24790000").unwrap(),
+                    ..Default::default()
+                },
+                realized_pnl: PnlAmounts::default(),
+                isolated_margin: Some(Uint128::new(152000000)),
+            }],
+        },
+        oracle_prices,
+        asset_params,
+        vaults_data,
+        perps_data,
+    };
+
+    let health = h.compute_health().unwrap();
+    assert_eq!(health.total_collateral_value, Uint128::new(1000));
+    assert_eq!(health.total_debt_value, Uint128::new(0));
+    assert_eq!(health.perps_pnl_loss, Uint128::new(0));
+    assert_eq!(health.max_ltv_health_factor, None);
+    assert_eq!(health.liquidation_health_factor, None);
+    assert!(health.has_perps);
+
+    let isolated = h.isolated_perp_health(&btcperp.denom).unwrap();
+    assert_eq!(isolated.margin_value, Uint128::new(152000000));
+    assert_eq!(
+        isolated.max_ltv_health_factor,
+        Some(Decimal::from_str("0.940896011548853405").unwrap())
+    );
+    assert_eq!(
+        isolated.liquidation_health_factor,
+        Some(Decimal::from_str("0.993177983047375659").unwrap())
+    );
+    assert!(isolated.is_above_max_ltv());
+    assert!(isolated.is_liquidatable());
+}
+
 // DOC: Health Factor positive - longs
 #[test]
 fn long_one_positive_pnl_perp_no_spot_debt() {
@@ -1449,6 +1530,7 @@ fn long_one_positive_pnl_perp_no_spot_debt() {
                     ..Default::default()
                 },
                 realized_pnl: PnlAmounts::default(),
+                isolated_margin: None,
             }],
         },
         asset_params,
@@ -1469,6 +1551,75 @@ fn long_one_positive_pnl_perp_no_spot_debt() {
     assert!(!health.is_liquidatable());
 }
 
+#[test]
+fn long_perp_uses_ltvs_of_its_margin_tier() {
+    let uusd = uusdc_info();
+    let entry_price = Decimal::from_str("100").unwrap();
+    let current_price = Decimal::from_str("104").unwrap();
+    let max_ltv = Decimal::from_str("0.9").unwrap();
+    let liquidation_threshold = Decimal::from_str("0.95").unwrap();
+    let size = Int128::from_str("10000000").unwrap();
+    let mut btcperp =
+        create_perp_info("btc/usd/perp".to_string(), current_price, max_ltv, liquidation_threshold);
+    // The position notional value (1040000000) reaches the tier
+    btcperp.perp_params.margin_tiers = vec![MarginTier {
+        notional_threshold: Uint128::new(1000000000),
+        max_loan_to_value: Decimal::from_str("0.8").unwrap(),
+        liquidation_threshold: Decimal::from_str("0.85").unwrap(),
+    }];
+
+    let asset_params = HashMap::from([(uusd.denom.clone(), uusd.params.clone())]);
+
+    let oracle_prices =
+        HashMap::from([(uusd.denom.clone(), uusd.price), (btcperp.denom.clone(), btcperp.price)]);
+
+    let perps_data = PerpsData {
+        params: HashMap::from([(btcperp.denom.clone(), btcperp.perp_params.clone())]),
+    };
+    let vaults_data = Default::default();
+    let unrealized_funding_accrued = Int128::from_str("15210000").unwrap();
+    let h = HealthComputer {
+        kind: AccountKind::Default,
+        positions: Positions {
+            account_id: "123".to_string(),
+            account_kind: AccountKind::Default,
+
+            deposits: vec![coin(152000000, &uusd.denom)],
+            debts: vec![],
+            lends: vec![],
+            vaults: vec![],
+            staked_astro_lps: vec![],
+            perps: vec![PerpPosition {
+                denom: btcperp.denom,
+                base_denom: uusd.denom,
+                current_price,
+                entry_price,
+                entry_exec_price: entry_price,
+                current_exec_price: current_price,
+                size,
+                unrealized_pnl: PnlAmounts {
+                    accrued_funding: unrealized_funding_accrued,
+                    pnl: Int128::from_str("-24790000").unwrap(),
+                    ..Default::default()
+                },
+                realized_pnl: PnlAmounts::default(),
+                isolated_margin: None,
+            }],
+        },
+        asset_params,
+        oracle_prices,
+        vaults_data,
+        perps_data,
+    };
+
+    // Same position as in `long_one_positive_pnl_perp_no_spot_debt`, with lower health factors
+    let health = h.compute_health().unwrap();
+    assert_eq!(health.max_ltv_health_factor, Some(Decimal::from_str("0.98076").unwrap()));
+    assert_eq!(health.liquidation_health_factor, Some(Decimal::from_str("1.0411205").unwrap()));
+    assert!(health.is_above_max_ltv());
+    assert!(!health.is_liquidatable());
+}
+
 // DOC - Health Factor Underwater (short)
 #[test]
 fn one_short_negative_pnl_perp_no_spot_debt() {
@@ -1517,6 +1668,7 @@ fn one_short_negative_pnl_perp_no_spot_debt() {
                     ..Default::default()
                 },
                 realized_pnl: PnlAmounts::default(),
+                isolated_margin: None,
             }],
         },
         oracle_prices,
@@ -1625,6 +1777,7 @@ fn one_short_negative_pnl_perp_vault_collateral_no_spot_debt() {
                     ..Default::default()
                 },
                 realized_pnl: PnlAmounts::default(),
+                isolated_margin: None,
             }],
         },
         oracle_prices,
@@ -1700,6 +1853,7 @@ fn one_short_positive_pnl_perp_no_spot_debt() {
                     ..Default::default()
                 },
                 realized_pnl: PnlAmounts::default(),
+                isolated_margin: None,
             }],
         },
         oracle_prices,
@@ -1798,6 +1952,7 @@ fn perps_one_short_negative_pnl_one_long_negative_pnl_no_spot_debt() {
                         ..Default::default()
                     },
                     realized_pnl: PnlAmounts::default(),
+                    isolated_margin: None,
                 },
                 PerpPosition {
                     denom: ethperp.denom,
@@ -1814,6 +1969,7 @@ fn perps_one_short_negative_pnl_one_long_negative_pnl_no_spot_debt() {
                         ..Default::default()
                     },
                     realized_pnl: PnlAmounts::default(),
+                    isolated_margin: None,
                 },
             ],
         },
@@ -1913,6 +2069,7 @@ fn perps_one_short_negative_pnl_one_long_positive_pnl_no_spot_debt() {
                         ..Default::default()
                     },
                     realized_pnl: PnlAmounts::default(),
+                    isolated_margin: None,
                 },
                 PerpPosition {
                     denom: ethperp.denom,
@@ -1929,6 +2086,7 @@ fn perps_one_short_negative_pnl_one_long_positive_pnl_no_spot_debt() {
                         ..Default::default()
                     },
                     realized_pnl: PnlAmounts::default(),
+                    isolated_margin: None,
                 },
             ],
         },
@@ -2015,6 +2173,7 @@ fn perp_short_delta_neutral_with_btc_collateral() {
                     ..Default::default()
                 },
                 realized_pnl: PnlAmounts::default(),
+                isolated_margin: None,
             }],
         },
         oracle_prices,
@@ -2105,6 +2264,7 @@ fn spot_short_delta_neutral_with_leverage() {
                     ..Default::default()
                 },
                 realized_pnl: PnlAmounts::default(),
+                isolated_margin: None,
             }],
         },
         oracle_prices,
@@ -2242,6 +2402,7 @@ fn perps_two_short_positive_pnl_one_long_negative_pnl_with_spot_debt() {
                         ..Default::default()
                     },
                     realized_pnl: PnlAmounts::default(),
+                    isolated_margin: None,
                 },
                 PerpPosition {
                     denom: ethperp.denom,
@@ -2257,6 +2418,7 @@ fn perps_two_short_positive_pnl_one_long_negative_pnl_with_spot_debt() {
                         ..Default::default()
                     },
                     realized_pnl: PnlAmounts::default(),
+                    isolated_margin: None,
                 },
                 PerpPosition {
                     denom: atomperp.denom,
@@ -2272,6 +2434,7 @@ fn perps_two_short_positive_pnl_one_long_negative_pnl_with_spot_debt() {
                         ..Default::default()
                     },
                     realized_pnl: PnlAmounts::default(),
+                    isolated_margin: None,
                 },
             ],
         },
@@ -2346,6 +2509,7 @@ fn single_perp_funding_greater_than_pnl() {
                     ..Default::default()
                 },
                 realized_pnl: PnlAmounts::default(),
+                isolated_margin: None,
             }],
         },
         oracle_prices,
@@ -2440,6 +2604,7 @@ fn same_denomination_for_values() {
                     ..Default::default()
                 },
                 realized_pnl: PnlAmounts::default(),
+                isolated_margin: None,
             }],
         },
         oracle_prices: oracle_prices.clone(),
@@ -2497,6 +2662,7 @@ fn same_denomination_for_values() {
                     ..Default::default()
                 },
                 realized_pnl: PnlAmounts::default(),
+                isolated_margin: None,
             }],
         },
         oracle_prices,
@@ -260,6 +260,7 @@ fn liquidation_price_asset() {
                         pnl: Int128::from_str("-127700000").unwrap(),
                     },
                     realized_pnl: PnlAmounts::default(),
+                    isolated_margin: None,
                 },
                 PerpPosition {
                     denom: "udydx".to_string(),
@@ -277,6 +278,7 @@ fn liquidation_price_asset() {
                         pnl: Int128::from_str("123760000").unwrap(),
                     },
                     realized_pnl: PnlAmounts::default(),
+                    isolated_margin: None,
                 },
             ],
         },
@@ -6,7 +6,7 @@ use mars_rover_health_computer::{HealthComputer, PerpsData};
 use mars_types::{
     credit_manager::{DebtAmount, Positions},
     health::AccountKind,
-    params::{AssetParams, PerpParams},
+    params::{AssetParams, MarginTier, PerpParams},
     perps::{PerpPosition, Position},
 };
 use test_case::test_case;
@@ -23,6 +23,25 @@ use crate::tests::helpers::{create_coin_info, create_default_funding, create_def
     "500000000";
     "No existing perp position"
 )]
+#[test_case(
+    "999999",
+    "-999999",
+    vec![],
+    Some(PerpParams {
+        max_long_oi_value: Uint128::new(6000000000000),
+        max_short_oi_value: Uint128::new(6000000000000),
+        max_net_oi_value: Uint128::new(40000000000000),
+        margin_tiers: vec![MarginTier {
+            notional_threshold: Uint128::new(2000000000), // divided by price 2000, tier from size 1000000
+            max_loan_to_value: Decimal::from_str("0.1").unwrap(),
+            liquidation_threshold: Decimal::from_str("0.2").unwrap(),
+        }],
+        ..produce_eth_perp_params()
+    }),
+    "100000000",
+    "500000000";
+    "Max size capped below a margin tier which can't be afforded"
+)]
 #[test_case(
     "1338102",
     "-500000",
@@ -254,8 +273,7 @@ fn asserting_health_factor(
                             skew,
                             current_eth_perp_price,
                             base_denom_price,
-                            eth_perp_params.opening_fee_rate,
-                            eth_perp_params.closing_fee_rate,
+                            &eth_perp_params,
                             position::PositionModification::Decrease(size),
                         )
                         .unwrap();
@@ -270,6 +288,7 @@ fn asserting_health_factor(
                         entry_price: Decimal::from_str("2000").unwrap(),
                         realized_pnl: Default::default(),
                         unrealized_pnl: pnl_amounts,
+                        isolated_margin: None,
                     }
                 })
                 .collect(),
@@ -152,6 +152,7 @@ fn mock_setup() -> MarsMockQuerier {
             protocol_liquidation_fee: Decimal::zero(),
             deposit_cap: Default::default(),
             close_factor: Decimal::percent(80u64),
+            liquidation_auction: None,
         },
     );
     let atom_market = Market {
@@ -184,6 +185,7 @@ fn mock_setup() -> MarsMockQuerier {
             protocol_liquidation_fee: Decimal::zero(),
             deposit_cap: Default::default(),
             close_factor: Decimal::percent(80u64),
+            liquidation_auction: None,
         },
     );
 
@@ -46,6 +46,7 @@ fn health_success_from_coins() {
             protocol_liquidation_fee: Decimal::zero(),
             deposit_cap: Uint128::MAX,
             close_factor: Decimal::percent(80u64),
+            liquidation_auction: None,
         },
     );
     let atom_market = Market {
@@ -78,6 +79,7 @@ fn health_success_from_coins() {
             protocol_liquidation_fee: Decimal::zero(),
             deposit_cap: Uint128::MAX,
             close_factor: Decimal::percent(80u64),
+            liquidation_auction: None,
         },
     );
 
@@ -149,6 +151,7 @@ fn health_error_from_coins() {
             protocol_liquidation_fee: Decimal::zero(),
             deposit_cap: Default::default(),
             close_factor: Decimal::percent(80u64),
+            liquidation_auction: None,
         },
     );
 
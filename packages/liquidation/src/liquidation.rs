@@ -98,6 +98,9 @@ fn prepare_hf_and_cr(
 /// requested_collateral_liq_th - Liquidation threshold of requested collateral
 /// LB                          - Liquidation Bonus
 ///
+/// `liquidatable_for` is the time (in seconds) since the account was flagged as liquidatable, used
+/// if the LB of the collateral is auctioned (see `LiquidationAuction`).
+///
 /// PLF (Protocol Liqudiation Fee) is charged as a % of the LB.
 /// For example, if we define the PLF as 10%, then the PLF would be deducted from the LB, so upon a liquidation:
 /// - The liquidator receives 90% of the LB.
@@ -113,6 +116,7 @@ pub fn calculate_liquidation_amounts(
     debt_params: &AssetParams,
     health: &HealthData,
     perps_lb_ratio: Decimal,
+    liquidatable_for: Option<u64>,
 ) -> Result<(Uint128, Uint128, Uint128), LiquidationError> {
     let user_collateral_value = collateral_amount.checked_mul_floor(collateral_price)?;
 
@@ -120,6 +124,7 @@ pub fn calculate_liquidation_amounts(
         health.liquidation_health_factor,
         health.collateralization_ratio,
         collateral_params,
+        liquidatable_for,
     )?;
 
     // maximum debt being closed at once is restricted by the fixed close factor
@@ -212,6 +217,45 @@ pub fn calculate_liquidation_amounts(
     ))
 }
 
+/// Calculates the collateral amounts of a partial perp liquidation (a perp position of the account
+/// is reduced instead of repaying debt). The bonus is the perps liquidation bonus applied to the
+/// PnL loss realized by the liquidation:
+/// perps_lb_value = pnl_loss_value * perps_lb_ratio * LB
+/// It is capped by the collateral available and the PLF is charged on it like on a debt liquidation.
+/// Returns -> (Collateral amount to liquidate, Collateral amount received by liquidator)
+pub fn calculate_perp_liquidation_amounts(
+    collateral_amount: Uint128,
+    collateral_price: Decimal,
+    collateral_params: &AssetParams,
+    pnl_loss_value: Uint128,
+    health: &HealthData,
+    perps_lb_ratio: Decimal,
+    liquidatable_for: Option<u64>,
+) -> Result<(Uint128, Uint128), LiquidationError> {
+    let liquidation_bonus = calculate_liquidation_bonus(
+        health.liquidation_health_factor,
+        health.collateralization_ratio,
+        collateral_params,
+        liquidatable_for,
+    )?;
+
+    let perps_lb_adjusted = perps_lb_ratio.checked_mul(liquidation_bonus)?;
+    let perps_lb_value = pnl_loss_value.checked_mul_floor(perps_lb_adjusted)?;
+    let collateral_amount_to_liquidate =
+        min(perps_lb_value.checked_div_floor(collateral_price)?, collateral_amount);
+
+    // Use ceiling in favour of protocol
+    let lb_value = collateral_amount_to_liquidate.checked_mul_floor(collateral_price)?;
+    let protocol_fee_value =
+        lb_value.checked_mul_ceil(collateral_params.protocol_liquidation_fee)?;
+    let protocol_fee_amount = protocol_fee_value.checked_div_floor(collateral_price)?;
+
+    let collateral_amount_received_by_liquidator =
+        collateral_amount_to_liquidate.checked_sub(protocol_fee_amount)?;
+
+    Ok((collateral_amount_to_liquidate, collateral_amount_received_by_liquidator))
+}
+
 /// The LB will depend on the Health Factor and a couple other parameters as follows:
 /// Liquidation Bonus = min(
 ///     starting_lb + (slope * (1 - HF)),
@@ -221,11 +265,18 @@ pub fn calculate_liquidation_amounts(
 ///     )
 /// )
 /// `CR` is the Collateralization Ratio of the position calculated as `CR = Total Assets / Total Debt`.
+///
+/// If the LB of the collateral is auctioned, `starting_lb + (slope * (1 - HF))` is replaced by
+/// `min_lb + (max_lb - min_lb) * min(elapsed, duration) / duration`, `elapsed` being the time since
+/// the account was flagged as liquidatable (zero if not flagged).
 fn calculate_liquidation_bonus(
     liquidation_health_factor: Decimal,
     collateralization_ratio: Decimal,
     collateral_params: &AssetParams,
+    liquidatable_for: Option<u64>,
 ) -> Result<Decimal, LiquidationError> {
+    let lb_params = &collateral_params.liquidation_bonus;
+
     // (CR - 1) can't be negative
     let collateralization_ratio_adjusted = if collateralization_ratio > Decimal::one() {
         collateralization_ratio - Decimal::one()
@@ -233,17 +284,21 @@ fn calculate_liquidation_bonus(
         Decimal::zero()
     };
 
-    let max_lb_adjusted = max(
-        min(collateralization_ratio_adjusted, collateral_params.liquidation_bonus.max_lb),
-        collateral_params.liquidation_bonus.min_lb,
-    );
-
-    let calculated_bonus = collateral_params.liquidation_bonus.starting_lb.checked_add(
-        collateral_params
-            .liquidation_bonus
-            .slope
-            .checked_mul(Decimal::one() - liquidation_health_factor)?,
-    )?;
+    let max_lb_adjusted =
+        max(min(collateralization_ratio_adjusted, lb_params.max_lb), lb_params.min_lb);
+
+    let calculated_bonus = match collateral_params.liquidation_auction.as_ref() {
+        Some(auction) => {
+            let elapsed = min(liquidatable_for.unwrap_or_default(), auction.duration);
+            lb_params.min_lb.checked_add(
+                (lb_params.max_lb - lb_params.min_lb)
+                    .checked_mul(Decimal::from_ratio(elapsed, auction.duration))?,
+            )?
+        }
+        None => lb_params.starting_lb.checked_add(
+            lb_params.slope.checked_mul(Decimal::one() - liquidation_health_factor)?,
+        )?,
+    };
 
     let liquidation_bonus = min(calculated_bonus, max_lb_adjusted);
 
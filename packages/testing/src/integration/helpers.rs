@@ -51,6 +51,7 @@ pub fn default_asset_params_with(
         protocol_liquidation_fee: Decimal::percent(2),
         deposit_cap: Uint128::MAX,
         close_factor: Decimal::percent(80),
+        liquidation_auction: None,
     };
     (market_params, asset_params)
 }
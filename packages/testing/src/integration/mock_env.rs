@@ -20,8 +20,8 @@ use mars_types::{
     },
     params::{AssetParams, AssetParamsUpdate, TotalDepositResponse},
     red_bank::{
-        self, CreateOrUpdateConfig, InitOrUpdateAssetParams, Market, MarketV2Response,
-        UserCollateralResponse, UserDebtResponse, UserPositionResponse,
+        self, CreateOrUpdateConfig, InitOrUpdateAssetParams, LiquidatableUsersResponse, Market,
+        MarketV2Response, UserCollateralResponse, UserDebtResponse, UserPositionResponse,
     },
     rewards_collector,
 };
@@ -538,6 +538,28 @@ impl RedBank {
         )
     }
 
+    pub fn transfer_collateral_to_credit_account(
+        &self,
+        env: &mut MockEnv,
+        sender: &Addr,
+        user: &Addr,
+        account_id: &str,
+        denom: &str,
+        amount: Option<Uint128>,
+    ) -> AnyResult<AppResponse> {
+        env.app.execute_contract(
+            sender.clone(),
+            self.contract_addr.clone(),
+            &red_bank::ExecuteMsg::TransferCollateralToCreditAccount {
+                user: user.to_string(),
+                account_id: account_id.to_string(),
+                denom: denom.to_string(),
+                amount,
+            },
+            &[],
+        )
+    }
+
     pub fn liquidate(
         &self,
         env: &mut MockEnv,
@@ -577,6 +599,40 @@ impl RedBank {
         )
     }
 
+    pub fn batch_liquidate(
+        &self,
+        env: &mut MockEnv,
+        liquidator: &Addr,
+        liquidations: Vec<red_bank::BatchLiquidation>,
+        send_funds: &[Coin],
+    ) -> AnyResult<AppResponse> {
+        env.app.execute_contract(
+            liquidator.clone(),
+            self.contract_addr.clone(),
+            &red_bank::ExecuteMsg::BatchLiquidate {
+                liquidations,
+                recipient: None,
+            },
+            send_funds,
+        )
+    }
+
+    pub fn flag_liquidatable(
+        &self,
+        env: &mut MockEnv,
+        sender: &Addr,
+        user: &Addr,
+    ) -> AnyResult<AppResponse> {
+        env.app.execute_contract(
+            sender.clone(),
+            self.contract_addr.clone(),
+            &red_bank::ExecuteMsg::FlagLiquidatable {
+                user: user.to_string(),
+            },
+            &[],
+        )
+    }
+
     pub fn query_market(&self, env: &mut MockEnv, denom: &str) -> Market {
         env.app
             .wrap()
@@ -730,6 +786,24 @@ impl RedBank {
             .unwrap()
     }
 
+    pub fn query_liquidatable_users(
+        &self,
+        env: &mut MockEnv,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> LiquidatableUsersResponse {
+        env.app
+            .wrap()
+            .query_wasm_smart(
+                self.contract_addr.clone(),
+                &red_bank::QueryMsg::LiquidatableUsers {
+                    start_after,
+                    limit,
+                },
+            )
+            .unwrap()
+    }
+
     pub fn query_scaled_liquidity_amount(&self, env: &mut MockEnv, coin: Coin) -> Uint128 {
         env.app
             .wrap()
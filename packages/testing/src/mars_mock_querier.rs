@@ -11,7 +11,7 @@ use mars_oracle_wasm::slinky::CurrencyPairExt;
 use mars_types::{
     address_provider, incentives, oracle,
     params::AssetParams,
-    perps::{VaultPositionResponse, VaultResponse},
+    perps::{PositionResponse, VaultPositionResponse, VaultResponse},
     red_bank,
 };
 use neutron_sdk::bindings::{
@@ -301,6 +301,11 @@ impl MarsMockQuerier {
         self.perps_querier.vault = vault_res;
     }
 
+    /// Positions are expected to be set in ascending (account id, denom) order
+    pub fn set_perp_position(&mut self, position: PositionResponse) {
+        self.perps_querier.positions.push(position);
+    }
+
     fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
         match &request {
             QueryRequest::Wasm(WasmQuery::Smart {
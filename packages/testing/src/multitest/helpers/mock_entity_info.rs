@@ -2,7 +2,9 @@ use std::str::FromStr;
 
 use cosmwasm_std::{coin, Decimal, Uint128};
 use cw_utils::Duration;
-use mars_types::params::{HlsAssetType, HlsParamsUnchecked, LiquidationBonus, PerpParams};
+use mars_types::params::{
+    FundingModel, HlsAssetType, HlsParamsUnchecked, LiquidationBonus, PerpParams,
+};
 
 use super::{CoinInfo, VaultTestInfo};
 
@@ -220,11 +222,15 @@ pub fn default_perp_params(denom: &str) -> PerpParams {
         max_short_oi_value: Uint128::new(1_000_000_000_000),
         closing_fee_rate: Decimal::from_str("0.01").unwrap(),
         opening_fee_rate: Decimal::from_str("0.01").unwrap(),
+        maker_fee_rates: None,
         liquidation_threshold: Decimal::from_str("0.90").unwrap(),
+        margin_tiers: vec![],
         max_loan_to_value: Decimal::from_str("0.82").unwrap(),
         max_position_value: None,
         min_position_value: Uint128::zero(),
-        max_funding_velocity: Decimal::from_str("3").unwrap(),
+        funding_model: FundingModel::Velocity {
+            max_funding_velocity: Decimal::from_str("3").unwrap(),
+        },
         skew_scale: Uint128::new(1000000u128),
     }
 }
@@ -39,11 +39,12 @@ use mars_types::{
     },
     address_provider::{self, MarsAddressType},
     credit_manager::{
-        Account, Action, CallbackMsg, CoinBalanceResponseItem, ConfigResponse, ConfigUpdates,
-        DebtShares, ExecuteMsg, InstantiateMsg, KeeperFeeConfig, Positions,
+        Account, AccountSettings, Action, CallbackMsg, CoinBalanceResponseItem, ConfigResponse,
+        ConfigUpdates, DebtShares, ExecuteMsg, InstantiateMsg, KeeperFeeConfig,
+        LiquidatableAccountsResponse, OperatorGrantResponse, OperatorPermissions, Positions,
         QueryMsg::{self, EstimateProvideLiquidity, VaultPositionValue},
-        SharesResponseItem, TriggerOrderResponse, VaultBinding, VaultPositionResponseItem,
-        VaultUtilizationResponse,
+        RecurringOrderResponse, SharesResponseItem, SimulateActionsResponse, TriggerOrderResponse,
+        VaultBinding, VaultPositionResponseItem, VaultUtilizationResponse,
     },
     health::{
         AccountKind, ExecuteMsg::UpdateConfig, HealthValuesResponse,
@@ -64,8 +65,8 @@ use mars_types::{
         VaultConfig, VaultConfigUnchecked, VaultConfigUpdate,
     },
     perps::{
-        self, Config, InstantiateMsg as PerpsInstantiateMsg, PnL, PositionResponse, TradingFee,
-        VaultPositionResponse, VaultResponse,
+        self, Config, InstantiateMsg as PerpsInstantiateMsg, MarketSettlement, PnL,
+        PositionResponse, TradingFee, VaultPositionResponse, VaultResponse,
     },
     red_bank::{
         self, InitOrUpdateAssetParams, InterestRateModel,
@@ -130,6 +131,7 @@ pub struct MockEnvBuilder {
     pub withdraw_enabled: Option<bool>,
     pub keeper_fee_config: Option<KeeperFeeConfig>,
     pub perps_liquidation_bonus_ratio: Option<Decimal>,
+    pub flash_loan_fee_rate: Option<Decimal>,
 }
 
 #[allow(clippy::new_ret_no_self)]
@@ -161,6 +163,7 @@ impl MockEnv {
             withdraw_enabled: None,
             keeper_fee_config: None,
             perps_liquidation_bonus_ratio: None,
+            flash_loan_fee_rate: None,
         }
     }
 
@@ -285,6 +288,129 @@ impl MockEnv {
         )
     }
 
+    pub fn execute_recurring_order(
+        &mut self,
+        sender: &Addr,
+        account_id: &str,
+        order_id: &str,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            sender.clone(),
+            self.rover.clone(),
+            &ExecuteMsg::ExecuteRecurringOrder {
+                account_id: account_id.to_string(),
+                order_id: order_id.to_string(),
+            },
+            &[],
+        )
+    }
+
+    pub fn deposit_to_red_bank(&mut self, sender: &Addr, coin: &Coin) -> AnyResult<AppResponse> {
+        let config = self.query_config();
+        self.app.execute_contract(
+            sender.clone(),
+            Addr::unchecked(config.red_bank),
+            &red_bank::ExecuteMsg::Deposit {
+                account_id: None,
+                on_behalf_of: None,
+            },
+            &[coin.clone()],
+        )
+    }
+
+    pub fn borrow_from_red_bank(&mut self, sender: &Addr, coin: &Coin) -> AnyResult<AppResponse> {
+        let config = self.query_config();
+        self.app.execute_contract(
+            sender.clone(),
+            Addr::unchecked(config.red_bank),
+            &red_bank::ExecuteMsg::Borrow {
+                denom: coin.denom.clone(),
+                amount: coin.amount,
+                recipient: None,
+            },
+            &[],
+        )
+    }
+
+    pub fn purge_expired_trigger_orders(
+        &mut self,
+        sender: &Addr,
+        limit: Option<u32>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            sender.clone(),
+            self.rover.clone(),
+            &ExecuteMsg::PurgeExpiredTriggerOrders {
+                limit,
+            },
+            &[],
+        )
+    }
+
+    pub fn grant_operator(
+        &mut self,
+        sender: &Addr,
+        account_id: &str,
+        operator: &Addr,
+        permissions: OperatorPermissions,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            sender.clone(),
+            self.rover.clone(),
+            &ExecuteMsg::GrantOperator {
+                account_id: account_id.to_string(),
+                operator: operator.to_string(),
+                permissions,
+            },
+            &[],
+        )
+    }
+
+    pub fn revoke_operator(
+        &mut self,
+        sender: &Addr,
+        account_id: &str,
+        operator: &Addr,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            sender.clone(),
+            self.rover.clone(),
+            &ExecuteMsg::RevokeOperator {
+                account_id: account_id.to_string(),
+                operator: operator.to_string(),
+            },
+            &[],
+        )
+    }
+
+    pub fn update_account_settings(
+        &mut self,
+        sender: &Addr,
+        account_id: &str,
+        settings: AccountSettings,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            sender.clone(),
+            self.rover.clone(),
+            &ExecuteMsg::UpdateAccountSettings {
+                account_id: account_id.to_string(),
+                settings,
+            },
+            &[],
+        )
+    }
+
+    pub fn flag_liquidatable(&mut self, sender: &Addr, account_id: &str) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            sender.clone(),
+            self.rover.clone(),
+            &ExecuteMsg::FlagLiquidatable {
+                account_id: account_id.to_string(),
+            },
+            &[],
+        )
+    }
+
     pub fn update_config(
         &mut self,
         sender: &Addr,
@@ -552,6 +678,34 @@ impl MockEnv {
         )
     }
 
+    pub fn delist_perp_market(&mut self, sender: &Addr, denom: &str) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            sender.clone(),
+            self.perps.address().clone(),
+            &perps::ExecuteMsg::DelistMarket {
+                denom: denom.to_string(),
+            },
+            &[],
+        )
+    }
+
+    pub fn settle_perp_market(
+        &mut self,
+        sender: &Addr,
+        denom: &str,
+        limit: Option<u32>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            sender.clone(),
+            self.perps.address().clone(),
+            &perps::ExecuteMsg::SettleMarket {
+                denom: denom.to_string(),
+                limit,
+            },
+            &[],
+        )
+    }
+
     //--------------------------------------------------------------------------------------------------
     // Queries
     //--------------------------------------------------------------------------------------------------
@@ -574,6 +728,20 @@ impl MockEnv {
         )
     }
 
+    pub fn simulate_actions(
+        &self,
+        account_id: &str,
+        actions: Vec<Action>,
+    ) -> Result<SimulateActionsResponse, StdError> {
+        self.app.wrap().query_wasm_smart(
+            self.rover.clone(),
+            &QueryMsg::SimulateActions {
+                account_id: account_id.to_string(),
+                actions,
+            },
+        )
+    }
+
     pub fn query_health(&self, account_id: &str, action: ActionKind) -> HealthValuesResponse {
         self.app
             .wrap()
@@ -870,6 +1038,20 @@ impl MockEnv {
             .unwrap()
     }
 
+    pub fn query_red_bank_user_debt(&self, user: &Addr, denom: &str) -> UserDebtResponse {
+        let config = self.query_config();
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                config.red_bank,
+                &UserDebt {
+                    user: user.to_string(),
+                    denom: denom.into(),
+                },
+            )
+            .unwrap()
+    }
+
     pub fn query_red_bank_collateral(
         &self,
         account_id: &str,
@@ -966,6 +1148,25 @@ impl MockEnv {
             .unwrap()
     }
 
+    pub fn query_recurring_orders_for_account(
+        &self,
+        account_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> PaginationResponse<RecurringOrderResponse> {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.rover.clone(),
+                &QueryMsg::AllAccountRecurringOrders {
+                    account_id,
+                    start_after,
+                    limit,
+                },
+            )
+            .unwrap()
+    }
+
     pub fn query_all_trigger_orders(
         &self,
         start_after: Option<(String, String)>,
@@ -983,6 +1184,56 @@ impl MockEnv {
             .unwrap()
     }
 
+    pub fn query_account_operators(
+        &self,
+        account_id: &str,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Vec<OperatorGrantResponse> {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.rover.clone(),
+                &QueryMsg::AccountOperators {
+                    account_id: account_id.to_string(),
+                    start_after,
+                    limit,
+                },
+            )
+            .unwrap()
+    }
+
+    pub fn query_account_settings(&self, account_id: &str) -> AccountSettings {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.rover.clone(),
+                &QueryMsg::AccountSettings {
+                    account_id: account_id.to_string(),
+                },
+            )
+            .unwrap()
+    }
+
+    pub fn query_liquidatable_accounts(
+        &self,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        action: ActionKind,
+    ) -> LiquidatableAccountsResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.rover.clone(),
+                &QueryMsg::LiquidatableAccounts {
+                    start_after,
+                    limit,
+                    action,
+                },
+            )
+            .unwrap()
+    }
+
     pub fn query_swap_estimate(
         &self,
         coin_in: &Coin,
@@ -1089,6 +1340,18 @@ impl MockEnv {
             .unwrap()
     }
 
+    pub fn query_perp_market_settlement(&self, denom: &str) -> Option<MarketSettlement> {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.perps.address(),
+                &perps::QueryMsg::MarketSettlement {
+                    denom: denom.to_string(),
+                },
+            )
+            .unwrap()
+    }
+
     pub fn query_perp_opening_fee(&self, denom: &str, size: Int128) -> TradingFee {
         self.app
             .wrap()
@@ -1305,6 +1568,7 @@ impl MockEnvBuilder {
         let max_unlocking_positions = self.get_max_unlocking_positions();
         let max_slippage = self.get_max_slippage();
         let perps_liquidation_bonus_ratio = self.get_perps_liquidation_ratio();
+        let flash_loan_fee_rate = self.flash_loan_fee_rate.unwrap_or_default();
 
         let oracle = self.get_oracle().into();
         let zapper = self.deploy_zapper(&oracle)?.into();
@@ -1333,6 +1597,7 @@ impl MockEnvBuilder {
                     incentives,
                     keeper_fee_config,
                     perps_liquidation_bonus_ratio,
+                    flash_loan_fee_rate,
                 },
                 &[],
                 "mock-rover-contract",
@@ -1801,6 +2066,7 @@ impl MockEnvBuilder {
     fn get_keeper_fee_config(&self) -> KeeperFeeConfig {
         self.keeper_fee_config.clone().unwrap_or(KeeperFeeConfig {
             min_fee: coin(1000000, "uusdc"),
+            cleanup_reward: None,
         })
     }
 
@@ -1921,6 +2187,11 @@ impl MockEnvBuilder {
         self
     }
 
+    pub fn flash_loan_fee_rate(mut self, rate: Decimal) -> Self {
+        self.flash_loan_fee_rate = Some(rate);
+        self
+    }
+
     pub fn evil_vault(mut self, credit_account: &str) -> Self {
         self.evil_vault = Some(credit_account.to_string());
         self
@@ -89,6 +89,7 @@ impl From<CoinInfo> for AssetParamsUnchecked {
             protocol_liquidation_fee: c.protocol_liquidation_fee,
             deposit_cap: Uint128::MAX,
             close_factor: c.close_factor,
+            liquidation_auction: None,
         }
     }
 }
@@ -1,12 +1,13 @@
 use std::collections::HashMap;
 
 use cosmwasm_std::{to_json_binary, Binary, ContractResult, QuerierResult};
-use mars_types::perps::{QueryMsg, VaultPositionResponse, VaultResponse};
+use mars_types::perps::{PositionResponse, QueryMsg, VaultPositionResponse, VaultResponse};
 
 #[derive(Default)]
 pub struct PerpsQuerier {
     pub vault: VaultResponse,
     pub vault_positions: HashMap<String, VaultPositionResponse>,
+    pub positions: Vec<PositionResponse>,
 }
 
 impl PerpsQuerier {
@@ -24,6 +25,25 @@ impl PerpsQuerier {
             QueryMsg::Vault {
                 action: _,
             } => to_json_binary(&self.vault).into(),
+            QueryMsg::Positions {
+                start_after,
+                limit,
+            } => {
+                let positions = self
+                    .positions
+                    .iter()
+                    .filter(|res| {
+                        let key = (
+                            res.account_id.clone(),
+                            res.position.as_ref().map(|p| p.denom.clone()).unwrap_or_default(),
+                        );
+                        start_after.as_ref().map_or(true, |start_after| key > *start_after)
+                    })
+                    .take(limit.unwrap_or(10) as usize)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                to_json_binary(&positions).into()
+            }
             _ => Err("[mock]: Unsupported perps query".to_string()).into(),
         };
 
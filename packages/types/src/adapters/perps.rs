@@ -1,12 +1,13 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    to_json_binary, Addr, Api, Coin, CosmosMsg, Int128, QuerierWrapper, StdResult, Uint128, WasmMsg,
+    to_json_binary, Addr, Api, Coin, CosmosMsg, Decimal, Int128, QuerierWrapper, StdResult,
+    Uint128, WasmMsg,
 };
 
 use crate::{
     oracle::ActionKind,
     perps::{
-        Config, ExecuteMsg, MarketResponse, PerpPosition, PositionResponse,
+        Config, ExecuteMsg, MarketResponse, PerpPosition, PositionFeesResponse, PositionResponse,
         PositionsByAccountResponse, QueryMsg, TradingFee, VaultPositionResponse,
     },
 };
@@ -95,6 +96,7 @@ impl Perps {
         denom: impl Into<String>,
         size: Int128,
         reduce_only: Option<bool>,
+        acceptable_price: Option<Decimal>,
         funds: Vec<Coin>,
     ) -> StdResult<CosmosMsg> {
         Ok(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -104,23 +106,49 @@ impl Perps {
                 denom: denom.into(),
                 size,
                 reduce_only,
+                acceptable_price,
             })?,
             funds,
         }))
     }
 
-    /// Generate message for closing all perp positions
+    /// Generate message for closing all perp positions except the ones of `excluded_denoms`
     pub fn close_all_msg(
         &self,
         account_id: impl Into<String>,
         funds: Vec<Coin>,
         action: ActionKind,
+        excluded_denoms: Vec<String>,
     ) -> StdResult<CosmosMsg> {
         Ok(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: self.address().into(),
             msg: to_json_binary(&ExecuteMsg::CloseAllPositions {
                 account_id: account_id.into(),
                 action: Some(action),
+                excluded_denoms: if excluded_denoms.is_empty() {
+                    None
+                } else {
+                    Some(excluded_denoms)
+                },
+            })?,
+            funds,
+        }))
+    }
+
+    /// Generate message for closing an isolated perp position, the loss is paid up to `funds`
+    pub fn close_isolated_position_msg(
+        &self,
+        account_id: impl Into<String>,
+        denom: impl Into<String>,
+        funds: Vec<Coin>,
+        action: ActionKind,
+    ) -> StdResult<CosmosMsg> {
+        Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: self.address().into(),
+            msg: to_json_binary(&ExecuteMsg::CloseIsolatedPosition {
+                account_id: account_id.into(),
+                denom: denom.into(),
+                action: Some(action),
             })?,
             funds,
         }))
@@ -146,6 +174,21 @@ impl Perps {
         Ok(res.position)
     }
 
+    pub fn query_positions(
+        &self,
+        querier: &QuerierWrapper,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<PositionResponse>> {
+        querier.query_wasm_smart(
+            self.address(),
+            &QueryMsg::Positions {
+                start_after,
+                limit,
+            },
+        )
+    }
+
     pub fn query_positions_by_account(
         &self,
         querier: &QuerierWrapper,
@@ -178,6 +221,24 @@ impl Perps {
         Ok(res)
     }
 
+    pub fn query_position_fees(
+        &self,
+        querier: &QuerierWrapper,
+        account_id: impl Into<String>,
+        denom: impl Into<String>,
+        new_size: Int128,
+    ) -> StdResult<PositionFeesResponse> {
+        let res: PositionFeesResponse = querier.query_wasm_smart(
+            self.address(),
+            &QueryMsg::PositionFees {
+                account_id: account_id.into(),
+                denom: denom.into(),
+                new_size,
+            },
+        )?;
+        Ok(res)
+    }
+
     pub fn query_perp_market_state(
         &self,
         querier: &QuerierWrapper,
@@ -70,6 +70,36 @@ impl RedBank {
         }))
     }
 
+    /// Generate message for repaying a specified amount of coin on behalf of a Red Bank user
+    pub fn repay_on_behalf_of_msg(&self, coin: &Coin, on_behalf_of: &str) -> StdResult<CosmosMsg> {
+        Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: self.addr.to_string(),
+            msg: to_json_binary(&red_bank::ExecuteMsg::Repay {
+                on_behalf_of: Some(on_behalf_of.to_string()),
+            })?,
+            funds: vec![coin.clone()],
+        }))
+    }
+
+    /// Generate message for moving the whole collateral of a Red Bank user to a credit account
+    pub fn transfer_collateral_msg(
+        &self,
+        user: &str,
+        account_id: &str,
+        denom: &str,
+    ) -> StdResult<CosmosMsg> {
+        Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: self.addr.to_string(),
+            msg: to_json_binary(&red_bank::ExecuteMsg::TransferCollateralToCreditAccount {
+                user: user.to_string(),
+                account_id: account_id.to_string(),
+                denom: denom.to_string(),
+                amount: None,
+            })?,
+            funds: vec![],
+        }))
+    }
+
     /// Generate message for lending a specified amount of coin
     pub fn lend_msg(&self, coin: &Coin, account_id: &str) -> StdResult<CosmosMsg> {
         Ok(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -160,6 +190,64 @@ impl RedBank {
         }))
     }
 
+    /// Query all collaterals of a Red Bank user (deposited outside of the credit manager)
+    pub fn query_user_collaterals(
+        &self,
+        querier: &QuerierWrapper,
+        user: &str,
+    ) -> StdResult<Vec<Coin>> {
+        let mut start_after = Option::<String>::None;
+        let mut has_more = true;
+        let mut all_collaterals = Vec::new();
+        while has_more {
+            let response: red_bank::PaginatedUserCollateralResponse =
+                querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                    contract_addr: self.addr.to_string(),
+                    msg: to_json_binary(&red_bank::QueryMsg::UserCollateralsV2 {
+                        user: user.to_string(),
+                        account_id: None,
+                        start_after,
+                        limit: None,
+                    })?,
+                }))?;
+            for item in response.data {
+                all_collaterals.push(Coin {
+                    denom: item.denom,
+                    amount: item.amount,
+                });
+            }
+            start_after = all_collaterals.last().map(|item| item.denom.clone());
+            has_more = response.metadata.has_more;
+        }
+        Ok(all_collaterals)
+    }
+
+    /// Query all debts of a Red Bank user (borrowed outside of the credit manager)
+    pub fn query_user_debts(&self, querier: &QuerierWrapper, user: &str) -> StdResult<Vec<Coin>> {
+        let mut start_after = Option::<String>::None;
+        let mut all_debts = Vec::new();
+        loop {
+            let response: Vec<red_bank::UserDebtResponse> =
+                querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                    contract_addr: self.addr.to_string(),
+                    msg: to_json_binary(&red_bank::QueryMsg::UserDebts {
+                        user: user.to_string(),
+                        start_after,
+                        limit: None,
+                    })?,
+                }))?;
+            let Some(last) = response.last() else {
+                break;
+            };
+            start_after = Some(last.denom.clone());
+            all_debts.extend(response.into_iter().map(|item| Coin {
+                denom: item.denom,
+                amount: item.amount,
+            }));
+        }
+        Ok(all_debts)
+    }
+
     pub fn query_debt(&self, querier: &QuerierWrapper, denom: &str) -> StdResult<Uint128> {
         let response: red_bank::UserDebtResponse =
             querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
@@ -1,9 +1,9 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    to_json_binary, Addr, Api, Coin, CosmosMsg, Empty, StdResult, Uint128, WasmMsg,
+    to_json_binary, Addr, Api, Coin, CosmosMsg, Empty, QuerierWrapper, StdResult, Uint128, WasmMsg,
 };
 
-use crate::swapper::{ExecuteMsg, SwapperRoute};
+use crate::swapper::{EstimateExactInSwapResponse, ExecuteMsg, QueryMsg, SwapperRoute};
 
 #[cw_serde]
 pub struct SwapperBase<T>(T);
@@ -53,6 +53,25 @@ impl Swapper {
             funds: vec![coin_in.clone()],
         }))
     }
+
+    /// Estimate the amount received when swapping an exact amount in
+    pub fn estimate_exact_in_swap(
+        &self,
+        querier: &QuerierWrapper,
+        coin_in: &Coin,
+        denom_out: &str,
+        route: Option<SwapperRoute>,
+    ) -> StdResult<Uint128> {
+        let res: EstimateExactInSwapResponse = querier.query_wasm_smart(
+            self.address().to_string(),
+            &QueryMsg::EstimateExactInSwap {
+                coin_in: coin_in.clone(),
+                denom_out: denom_out.to_string(),
+                route,
+            },
+        )?;
+        Ok(res.amount)
+    }
 }
 
 #[cfg(test)]
@@ -62,6 +62,15 @@ impl Zapper {
         )
     }
 
+    pub fn is_lp_token(&self, querier: &QuerierWrapper, denom: &str) -> StdResult<bool> {
+        querier.query_wasm_smart(
+            self.address().to_string(),
+            &QueryMsg::IsLpToken {
+                denom: denom.to_string(),
+            },
+        )
+    }
+
     pub fn provide_liquidity_msg(
         &self,
         coins_in: &[Coin],
@@ -2,7 +2,8 @@ use std::collections::BTreeMap;
 
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    to_json_binary, Addr, Coin, CosmosMsg, Decimal, Int128, StdResult, Uint128, WasmMsg,
+    to_json_binary, Addr, Coin, CosmosMsg, Decimal, Int128, SignedDecimal, StdResult, Uint128,
+    WasmMsg,
 };
 use mars_owner::OwnerUpdate;
 
@@ -11,6 +12,7 @@ use crate::{
     account_nft::NftConfigUpdates,
     adapters::vault::{Vault, VaultPositionType, VaultUnchecked},
     health::{AccountKind, HealthState, HealthValuesResponse},
+    oracle::ActionKind,
     perps::PnL,
     swapper::SwapperRoute,
 };
@@ -40,6 +42,52 @@ pub enum ExecuteMsg {
         trigger_order_id: String,
     },
 
+    /// Deletes up to `limit` expired trigger orders (with their children). Permissionless.
+    /// Keeper fees are refunded to the accounts, minus the cleanup reward (see `KeeperFeeConfig`)
+    /// which is paid to the sender.
+    PurgeExpiredTriggerOrders {
+        limit: Option<u32>,
+    },
+
+    /// Deletes all trigger orders of a burned account, sending the keeper fees to `refund_recipient`.
+    /// Only callable by the account NFT contract.
+    PurgeAccountTriggerOrders {
+        account_id: String,
+        refund_recipient: String,
+    },
+
+    /// Executes the next period of a recurring order. Permissionless, the caller receives the
+    /// keeper fee of the execution. The swap is bounded by the max slippage against the oracle price.
+    ExecuteRecurringOrder {
+        account_id: String,
+        order_id: String,
+    },
+
+    /// Grants `operator` a time-limited permission to update the account with a restricted set of
+    /// actions. Only callable by the account owner. Replaces any existing grant for the operator.
+    GrantOperator {
+        account_id: String,
+        operator: String,
+        permissions: OperatorPermissions,
+    },
+    /// Revokes a previously granted operator permission. Only callable by the account owner.
+    RevokeOperator {
+        account_id: String,
+        operator: String,
+    },
+
+    /// Replaces the settings of the account. Only callable by the account owner.
+    UpdateAccountSettings {
+        account_id: String,
+        settings: AccountSettings,
+    },
+
+    /// Starts the liquidation bonus auction of a liquidatable account (see `LiquidationAuction`).
+    /// Permissionless. If the account is healthy, its previous flag is removed instead.
+    FlagLiquidatable {
+        account_id: String,
+    },
+
     //--------------------------------------------------------------------------------------------------
     // Privileged messages
     //--------------------------------------------------------------------------------------------------
@@ -62,7 +110,9 @@ pub enum ExecuteMsg {
     /// Updates the account balance based on the specified PnL for the given account.
     /// Depending on the PnL type:
     /// - Profit: increases the account balance.
-    /// - Loss: decreases the account balance and borrows from the red-bank if necessary.
+    /// - Loss: decreases the account balance and borrows from the red-bank if necessary. The loss
+    ///   of an isolated position is only paid from its margin, the perps contract takes the rest as
+    ///   bad debt.
     /// - Break-even: no action is taken.
     /// If the total PnL results in a loss, the corresponding amount of coins must be sent to the perps contract.
     UpdateBalanceAfterDeleverage {
@@ -71,6 +121,41 @@ pub enum ExecuteMsg {
     },
 }
 
+/// Per-account preferences set by the account owner
+#[cw_serde]
+#[derive(Default)]
+pub struct AccountSettings {
+    /// Lend every idle coin balance to the Red Bank at the end of each account update.
+    /// Lent coins spent by a later update are reclaimed before its actions are executed.
+    pub auto_lend: bool,
+    /// How perp losses exceeding the base denom balance and lends are covered
+    pub perp_settlement: PerpSettlementPolicy,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub enum PerpSettlementPolicy {
+    /// Borrow the shortfall from the Red Bank
+    #[default]
+    Borrow,
+    /// Swap the coin balances of the listed denoms, in order, into the base denom before
+    /// borrowing what is still missing. Swaps are bounded by the oracle price and the max slippage.
+    SwapFrom(Vec<String>),
+}
+
+/// Permissions granted by an account owner to a delegated operator (e.g. a trading bot)
+#[cw_serde]
+pub struct OperatorPermissions {
+    /// Action types the operator is allowed to dispatch. `Withdraw` and `WithdrawToWallet` can never be granted.
+    /// Swaps of the operator are bounded by the oracle price and the max slippage.
+    pub allowed_actions: Vec<ActionType>,
+    /// Optional per-denom allowances. Every action drawing a limited denom from the account
+    /// balance consumes the allowance. Denoms not listed are not limited.
+    pub spend_limits: Vec<Coin>,
+    /// Timestamp (in seconds) after which the grant is no longer valid
+    pub expires_at: u64,
+}
+
 #[cw_serde]
 pub enum ActionAmount {
     Exact(Uint128),
@@ -136,6 +221,43 @@ pub enum LiquidateRequest<T> {
     /// Pay back debt of a liquidatable credit manager account for a via liquidating an Astro LP position.
     /// LP shares are transfered from the liquidatable to the liquidator.
     StakedAstroLp(String),
+    /// Close a liquidatable isolated perp position (see `Action::ExecutePerpOrder`).
+    /// Only the margin of the position is used: a loss exceeding the margin is bad debt of the
+    /// perps vault. The liquidator receives `perps_liquidation_bonus_ratio * LB` of the PnL loss
+    /// from the margin left (minus the protocol liquidation fee) and the rest is returned to the
+    /// account. `debt_coin` is ignored.
+    IsolatedPerp(String),
+    /// Reduce a perp position of a liquidatable account by `size` (absolute, capped at the close
+    /// factor of the base denom) instead of closing all of its perp positions. The liquidator
+    /// receives a bonus of `perps_liquidation_bonus_ratio * LB` of the PnL loss realized for the
+    /// closed size, paid in the base denom of the perps market if the account holds any, from its
+    /// other collateral otherwise. `debt_coin` is ignored and the rest of the account is left
+    /// untouched.
+    Perp {
+        denom: String,
+        size: Uint128,
+    },
+}
+
+/// A liquidation of `Action::BatchLiquidate`, same parameters as `Action::Liquidate`
+#[cw_serde]
+pub struct BatchLiquidation {
+    pub liquidatee_account_id: String,
+    pub debt_coin: Coin,
+    pub request: LiquidateRequest<VaultUnchecked>,
+}
+
+/// Relation of a new trigger order with the other trigger orders of the account
+#[cw_serde]
+pub enum CreateTriggerOrderType {
+    /// Independent order
+    Default,
+    /// Order whose children only become active once it has been executed.
+    /// Deleting the parent order also deletes its children.
+    Parent,
+    /// Child of the last parent order created in the same list of actions.
+    /// Children of the same parent are one-cancels-other (e.g. a take-profit/stop-loss pair).
+    Child,
 }
 
 #[cw_serde]
@@ -145,7 +267,7 @@ pub enum Comparison {
 }
 
 impl Comparison {
-    pub fn is_met(&self, lhs: Decimal, rhs: Decimal) -> bool {
+    pub fn is_met<T: PartialOrd>(&self, lhs: T, rhs: T) -> bool {
         match self {
             Comparison::GreaterThan => lhs > rhs,
             Comparison::LessThan => lhs < rhs,
@@ -176,6 +298,35 @@ pub enum Condition {
         threshold: Decimal,
         comparison: Comparison,
     },
+    /// If the block time (in seconds) is after `after` and before `before`, the condition is met.
+    /// Either bound can be omitted.
+    BlockTime {
+        after: Option<u64>,
+        before: Option<u64>,
+    },
+    /// If the unrealized PnL (denominated in the perps base denom) of the account's perp position
+    /// is above or below the specified threshold, depending on the comparison, the condition is met.
+    /// The condition is never met if the account has no position in the market.
+    PerpPositionPnl {
+        denom: String,
+        threshold: Int128,
+        comparison: Comparison,
+    },
+    /// If the current funding rate of the perp market is above or below the specified rate,
+    /// depending on the comparison, the condition is met.
+    FundingRate {
+        denom: String,
+        rate: SignedDecimal,
+        comparison: Comparison,
+    },
+    /// If the share price of the managed vault is above or below the specified price,
+    /// depending on the comparison, the condition is met.
+    /// The condition is never met if the vault has no shares minted.
+    VaultSharePrice {
+        vault_address: String,
+        price: Decimal,
+        comparison: Comparison,
+    },
 }
 
 /// The list of actions that users can perform on their positions
@@ -227,17 +378,31 @@ pub enum Action {
     /// Note that size is signed
     ///     - to increase short or reduce long, use a negative value
     ///     - to reduce short or increase long, use a positive value
+    /// Passing a `margin` (in the perps base denom) makes the position isolated: the margin is
+    /// taken from the account balance and only backs this position, which gets its own health
+    /// check and can be liquidated without touching the rest of the account. Margin can be added
+    /// to an isolated position later on, but a cross margin position can't become isolated.
+    /// With an `acceptable_price` the order fails if its skew adjusted execution price is above it
+    /// when buying (positive size) or below it when selling (negative size).
     ExecutePerpOrder {
         denom: String,
         order_size: Int128,
         reduce_only: Option<bool>,
+        margin: Option<Uint128>,
+        acceptable_price: Option<Decimal>,
     },
 
-    /// Dispatch orders to be triggered under specified conditions
+    /// Dispatch orders to be triggered under specified conditions.
+    /// Orders sharing the same `oco_group` are one-cancels-other: executing one of them deletes
+    /// the others (refunding their keeper fees). See `CreateTriggerOrderType` for parent/child orders.
+    /// Orders can't be executed from `expires_at` (block time in seconds) onwards.
     CreateTriggerOrder {
         actions: Vec<Action>,
         conditions: Vec<Condition>,
         keeper_fee: Coin,
+        order_type: Option<CreateTriggerOrderType>,
+        oco_group: Option<String>,
+        expires_at: Option<u64>,
     },
 
     DeleteTriggerOrder {
@@ -274,6 +439,12 @@ pub enum Action {
         /// Position details to be liquidated
         request: LiquidateRequest<VaultUnchecked>,
     },
+    /// Liquidate several accounts at once (see `Liquidate`). Liquidatees which aren't liquidatable
+    /// anymore are skipped instead of failing the whole batch, the result of each liquidation is
+    /// returned in the `liquidation_{index}` attributes. A liquidatee can only appear once.
+    BatchLiquidate {
+        liquidations: Vec<BatchLiquidation>,
+    },
     /// Perform a swapper with an exact-in amount. Requires slippage allowance %.
     /// If `coin_in.amount: AccountBalance`, the accounts entire balance of `coin_in.denom` will be used.
     SwapExactIn {
@@ -310,6 +481,254 @@ pub enum Action {
     },
     /// Refunds all coin balances back to user wallet
     RefundAllCoinBalances {},
+    /// Borrow coin from the Red Bank for the duration of the nested actions.
+    /// After the nested actions, the borrowed amount plus the flash loan fee has to be available
+    /// in the account balance, it's repaid to the Red Bank and the fee is sent to the rewards-collector.
+    FlashLoan {
+        coin: Coin,
+        actions: Vec<Action>,
+    },
+    /// Swap collateral of one denom into another.
+    /// If the coin balance of `from.denom` is not enough, the remainder is reclaimed from the Red Bank.
+    /// If `from.amount: AccountBalance`, the entire balance and lent amount of `from.denom` is swapped.
+    SwapCollateral {
+        from: ActionCoin,
+        to: String,
+        min_receive: Uint128,
+        route: Option<SwapperRoute>,
+    },
+    /// Refinance the debt of `repay_denom` with a debt of `borrow_denom`.
+    /// Borrows `borrow_denom` (up to `max_borrow`, capped at the oracle value of the debt including
+    /// max slippage), swaps it into `repay_denom` and repays the debt with the account balance.
+    /// Any amount exceeding the debt stays in the account balance.
+    SwapDebt {
+        repay_denom: String,
+        borrow_denom: String,
+        max_borrow: Uint128,
+        route: Option<SwapperRoute>,
+    },
+    /// Borrow `debt_denom` and swap it into `collateral_denom` until the account LTV
+    /// (total debt value / total collateral value) reaches `target_ltv`.
+    /// The borrowed amount is capped at the max amount that keeps the account healthy.
+    /// Slippage allowance (%) can't exceed the max slippage set in the config.
+    Leverage {
+        collateral_denom: String,
+        debt_denom: String,
+        target_ltv: Decimal,
+        slippage: Decimal,
+    },
+    /// Swap `collateral_denom` into `debt_denom` and repay the debt until the account LTV
+    /// drops to `target_ltv` (or the debt of `debt_denom` is fully repaid).
+    /// Slippage allowance (%) can't exceed the max slippage set in the config.
+    Deleverage {
+        collateral_denom: String,
+        debt_denom: String,
+        target_ltv: Decimal,
+        slippage: Decimal,
+    },
+    /// Transfer coin balance to another credit account owned by the same wallet.
+    /// If `coin.amount: AccountBalance`, the entire balance of `coin.denom` is transferred.
+    TransferToAccount {
+        recipient_account_id: String,
+        coin: ActionCoin,
+    },
+    /// Move coins lent to the Red Bank to another credit account owned by the same wallet.
+    /// If `coin.amount: AccountBalance`, the entire lent amount of `coin.denom` is moved.
+    TransferLendToAccount {
+        recipient_account_id: String,
+        coin: ActionCoin,
+    },
+    /// Move debt to another credit account owned by the same wallet. The health of the recipient
+    /// is checked as well. If `coin.amount: AccountBalance`, the entire debt of `coin.denom` is moved.
+    TransferDebtToAccount {
+        recipient_account_id: String,
+        coin: ActionCoin,
+    },
+    /// Swap `coin_in` into `denom_out` every `interval` seconds, `periods` times, executed by keepers.
+    /// The first period can be executed right away. Periods missed by the keepers are skipped,
+    /// the next execution stays on the schedule. The keeper fee is paid per execution,
+    /// the fees of all periods are deducted from the account upfront.
+    /// If `lend_output` is set, the account balance of `denom_out` is lent after each swap.
+    CreateRecurringOrder {
+        coin_in: Coin,
+        denom_out: String,
+        interval: u64,
+        periods: u32,
+        lend_output: bool,
+        keeper_fee: Coin,
+    },
+    /// Delete a recurring order, refunding the keeper fees of the remaining periods
+    DeleteRecurringOrder {
+        order_id: String,
+    },
+    /// Unwind every position of the account and send the remaining coin balances to `recipient`.
+    /// Perps are closed, vault and Astro LP positions are exited, lends are reclaimed and
+    /// collateral is swapped into the debt denoms as needed to repay all debts.
+    /// Fails if a position can't be closed (e.g. vault shares which are still locked).
+    /// Slippage allowance (%) of the swaps and liquidity withdrawals can't exceed the max slippage
+    /// set in the config.
+    CloseAccount {
+        recipient: String,
+        slippage: Decimal,
+    },
+    /// Take over the Red Bank position of the sender: its debts are borrowed by the account and
+    /// repaid on behalf of the sender, its collaterals become lends of the account.
+    /// The health of the account is only checked once the whole position has been imported.
+    ImportRedBankPosition {},
+}
+
+/// Type of an `Action`, without its parameters
+#[cw_serde]
+#[derive(Copy, Eq, Hash, PartialOrd, Ord, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum ActionType {
+    Deposit,
+    Withdraw,
+    WithdrawToWallet,
+    Borrow,
+    Lend,
+    Reclaim,
+    ClaimRewards,
+    Repay,
+    DepositToPerpVault,
+    UnlockFromPerpVault,
+    WithdrawFromPerpVault,
+    ExecutePerpOrder,
+    CreateTriggerOrder,
+    DeleteTriggerOrder,
+    EnterVault,
+    ExitVault,
+    RequestVaultUnlock,
+    ExitVaultUnlocked,
+    Liquidate,
+    BatchLiquidate,
+    SwapExactIn,
+    ProvideLiquidity,
+    WithdrawLiquidity,
+    StakeAstroLp,
+    UnstakeAstroLp,
+    ClaimAstroLpRewards,
+    RefundAllCoinBalances,
+    FlashLoan,
+    SwapCollateral,
+    SwapDebt,
+    Leverage,
+    Deleverage,
+    TransferToAccount,
+    TransferLendToAccount,
+    TransferDebtToAccount,
+    CreateRecurringOrder,
+    DeleteRecurringOrder,
+    CloseAccount,
+    ImportRedBankPosition,
+}
+
+impl Action {
+    pub fn action_type(&self) -> ActionType {
+        match self {
+            Action::Deposit(..) => ActionType::Deposit,
+            Action::Withdraw(..) => ActionType::Withdraw,
+            Action::WithdrawToWallet {
+                ..
+            } => ActionType::WithdrawToWallet,
+            Action::Borrow(..) => ActionType::Borrow,
+            Action::Lend(..) => ActionType::Lend,
+            Action::Reclaim(..) => ActionType::Reclaim,
+            Action::ClaimRewards {} => ActionType::ClaimRewards,
+            Action::Repay {
+                ..
+            } => ActionType::Repay,
+            Action::DepositToPerpVault {
+                ..
+            } => ActionType::DepositToPerpVault,
+            Action::UnlockFromPerpVault {
+                ..
+            } => ActionType::UnlockFromPerpVault,
+            Action::WithdrawFromPerpVault {
+                ..
+            } => ActionType::WithdrawFromPerpVault,
+            Action::ExecutePerpOrder {
+                ..
+            } => ActionType::ExecutePerpOrder,
+            Action::CreateTriggerOrder {
+                ..
+            } => ActionType::CreateTriggerOrder,
+            Action::DeleteTriggerOrder {
+                ..
+            } => ActionType::DeleteTriggerOrder,
+            Action::EnterVault {
+                ..
+            } => ActionType::EnterVault,
+            Action::ExitVault {
+                ..
+            } => ActionType::ExitVault,
+            Action::RequestVaultUnlock {
+                ..
+            } => ActionType::RequestVaultUnlock,
+            Action::ExitVaultUnlocked {
+                ..
+            } => ActionType::ExitVaultUnlocked,
+            Action::Liquidate {
+                ..
+            } => ActionType::Liquidate,
+            Action::BatchLiquidate {
+                ..
+            } => ActionType::BatchLiquidate,
+            Action::SwapExactIn {
+                ..
+            } => ActionType::SwapExactIn,
+            Action::ProvideLiquidity {
+                ..
+            } => ActionType::ProvideLiquidity,
+            Action::WithdrawLiquidity {
+                ..
+            } => ActionType::WithdrawLiquidity,
+            Action::StakeAstroLp {
+                ..
+            } => ActionType::StakeAstroLp,
+            Action::UnstakeAstroLp {
+                ..
+            } => ActionType::UnstakeAstroLp,
+            Action::ClaimAstroLpRewards {
+                ..
+            } => ActionType::ClaimAstroLpRewards,
+            Action::RefundAllCoinBalances {} => ActionType::RefundAllCoinBalances,
+            Action::FlashLoan {
+                ..
+            } => ActionType::FlashLoan,
+            Action::SwapCollateral {
+                ..
+            } => ActionType::SwapCollateral,
+            Action::SwapDebt {
+                ..
+            } => ActionType::SwapDebt,
+            Action::Leverage {
+                ..
+            } => ActionType::Leverage,
+            Action::Deleverage {
+                ..
+            } => ActionType::Deleverage,
+            Action::TransferToAccount {
+                ..
+            } => ActionType::TransferToAccount,
+            Action::TransferLendToAccount {
+                ..
+            } => ActionType::TransferLendToAccount,
+            Action::TransferDebtToAccount {
+                ..
+            } => ActionType::TransferDebtToAccount,
+            Action::CreateRecurringOrder {
+                ..
+            } => ActionType::CreateRecurringOrder,
+            Action::DeleteRecurringOrder {
+                ..
+            } => ActionType::DeleteRecurringOrder,
+            Action::CloseAccount {
+                ..
+            } => ActionType::CloseAccount,
+            Action::ImportRedBankPosition {} => ActionType::ImportRedBankPosition,
+        }
+    }
 }
 
 /// Internal actions made by the contract with pre-validated inputs
@@ -393,12 +812,20 @@ pub enum CallbackMsg {
         actions: Vec<Action>,
         conditions: Vec<Condition>,
         keeper_fee: Coin,
+        /// Id of the parent order, created by a previous callback of the same dispatch
+        parent_order_id: Option<String>,
+        oco_group: Option<String>,
+        expires_at: Option<u64>,
     },
     // Deletes an accounts trigger order
     DeleteTriggerOrder {
         account_id: String,
         trigger_order_id: String,
     },
+    /// Deletes all trigger orders of an account, refunding the keeper fees to the account
+    DeleteAllTriggerOrders {
+        account_id: String,
+    },
     /// Adds coin to a vault strategy
     EnterVault {
         account_id: String,
@@ -425,6 +852,24 @@ pub enum CallbackMsg {
         denom: String,
         size: Int128,
         reduce_only: Option<bool>,
+        margin: Option<Uint128>,
+        acceptable_price: Option<Decimal>,
+    },
+    /// Adds the account to the liquidation index if it has debt or perp positions, removes it
+    /// otherwise
+    UpdateLiquidationIndex {
+        account_id: String,
+    },
+    /// Asserts the isolated perp position for the denom (if any) is not above its max LTV
+    AssertIsolatedPerpHealth {
+        account_id: String,
+        denom: String,
+    },
+    /// Closes a liquidatable isolated perp position using its margin only
+    LiquidateIsolatedPerp {
+        liquidator_account_id: String,
+        liquidatee_account_id: String,
+        denom: String,
     },
     /// Requests unlocking of shares for a vault with a lock period
     RequestVaultUnlock {
@@ -438,7 +883,7 @@ pub enum CallbackMsg {
         vault: Vault,
         position_id: u64,
     },
-    /// Close all perp positions before liquidation
+    /// Close all cross margin perp positions before liquidation, isolated ones are left open
     CloseAllPerps {
         account_id: String,
     },
@@ -458,6 +903,9 @@ pub enum CallbackMsg {
         denom_out: String,
         min_receive: Uint128,
         route: Option<SwapperRoute>,
+        /// If set, `min_receive` is raised to the oracle value of `coin_in` (in `denom_out`)
+        /// reduced by this slippage.
+        oracle_slippage: Option<Decimal>,
     },
     /// Used to update the coin balance of account after an async action
     UpdateCoinBalance {
@@ -522,6 +970,129 @@ pub enum CallbackMsg {
     /// At the end of the execution of dispatched actions, this callback removes the guard
     /// and allows subsequent dispatches.
     RemoveReentrancyGuard {},
+    /// Repay a flash loan taken earlier in the same dispatch and collect the flash loan fee
+    RepayFlashLoan {
+        account_id: String,
+        coin: Coin,
+    },
+    /// Reclaim the missing amount of collateral from the Red Bank (if needed) and swap it
+    SwapCollateral {
+        account_id: String,
+        from: ActionCoin,
+        to: String,
+        min_receive: Uint128,
+        route: Option<SwapperRoute>,
+        oracle_slippage: Option<Decimal>,
+    },
+    /// Borrow, swap and repay in order to exchange one debt for another
+    SwapDebt {
+        account_id: String,
+        repay_denom: String,
+        borrow_denom: String,
+        max_borrow: Uint128,
+        route: Option<SwapperRoute>,
+    },
+    /// Borrow and swap into collateral up to the target LTV
+    Leverage {
+        account_id: String,
+        collateral_denom: String,
+        debt_denom: String,
+        target_ltv: Decimal,
+        slippage: Decimal,
+    },
+    /// Swap collateral and repay debt down to the target LTV
+    Deleverage {
+        account_id: String,
+        collateral_denom: String,
+        debt_denom: String,
+        target_ltv: Decimal,
+        slippage: Decimal,
+    },
+    /// Emit the LTV and leverage the account ended up with
+    ReportLeverage {
+        account_id: String,
+    },
+    /// Move coin balance from one account to another
+    TransferToAccount {
+        account_id: String,
+        recipient_account_id: String,
+        coin: ActionCoin,
+    },
+    /// Reclaim lent coins of one account and lend them for another
+    TransferLendToAccount {
+        account_id: String,
+        recipient_account_id: String,
+        coin: ActionCoin,
+    },
+    /// Move debt shares from one account to another
+    TransferDebtToAccount {
+        account_id: String,
+        recipient_account_id: String,
+        coin: ActionCoin,
+    },
+    /// Creates a recurring order for an account
+    CreateRecurringOrder {
+        account_id: String,
+        coin_in: Coin,
+        denom_out: String,
+        interval: u64,
+        periods: u32,
+        lend_output: bool,
+        keeper_fee: Coin,
+    },
+    /// Deletes a recurring order of an account
+    DeleteRecurringOrder {
+        account_id: String,
+        order_id: String,
+    },
+    /// Closes the perps, vault, Astro LP and lend positions of the account
+    /// followed by `RepayAllDebts` and `FinalizeCloseAccount`
+    CloseAccount {
+        account_id: String,
+        recipient: Addr,
+        slippage: Decimal,
+    },
+    /// Swaps collateral into the debt denoms where the coin balance doesn't cover the debt
+    /// and repays all debts of the account
+    RepayAllDebts {
+        account_id: String,
+        slippage: Decimal,
+    },
+    /// Asserts that no debt is left and sends all coin balances to `recipient`
+    FinalizeCloseAccount {
+        account_id: String,
+        recipient: Addr,
+    },
+    /// Moves the Red Bank debts and collaterals of `user` to the account
+    ImportRedBankPosition {
+        account_id: String,
+        user: Addr,
+    },
+    /// Lends the idle coin balances of an account with auto-lend enabled
+    AutoLend {
+        account_id: String,
+    },
+    /// Lends the coins of `denom` received by the account (e.g. the output of a swap) since its
+    /// coin balance was `prev_balance`
+    LendReceived {
+        account_id: String,
+        denom: String,
+        prev_balance: Uint128,
+    },
+    /// Repays the debt of `denom` with the coins received by the account (e.g. the output of a
+    /// swap) since its coin balance was `prev_balance`
+    RepayReceived {
+        account_id: String,
+        denom: String,
+        prev_balance: Uint128,
+    },
+    /// Pays the rest of a perp loss once collateral has been swapped into the base denom,
+    /// borrowing what is still missing
+    SettlePerpLoss {
+        account_id: String,
+        coin: Coin,
+        action: Option<ActionKind>,
+    },
 }
 
 impl CallbackMsg {
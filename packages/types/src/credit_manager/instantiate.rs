@@ -44,6 +44,9 @@ pub struct InstantiateMsg {
     /// PnL loss as follows:
     /// `bonus applied to liquidation = perps_liquidation_bonus_ratio * original LB * PnL loss`
     pub perps_liquidation_bonus_ratio: Decimal,
+    /// Fee charged on flash loans, as a fraction of the borrowed amount.
+    /// It's paid to the rewards-collector account.
+    pub flash_loan_fee_rate: Decimal,
 }
 
 /// Used when you want to update fields on Instantiate config
@@ -65,4 +68,5 @@ pub struct ConfigUpdates {
     pub perps: Option<PerpsUnchecked>,
     pub keeper_fee_config: Option<KeeperFeeConfig>,
     pub perps_liquidation_bonus_ratio: Option<Decimal>,
+    pub flash_loan_fee_rate: Option<Decimal>,
 }
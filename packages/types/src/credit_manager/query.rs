@@ -2,13 +2,13 @@ use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{Coin, Decimal, Uint128};
 use mars_owner::OwnerResponse;
 
-use super::{Action, Condition};
+use super::{AccountSettings, Action, Condition, LiquidateRequest, OperatorPermissions};
 use crate::{
     adapters::{
         rewards_collector::RewardsCollector,
         vault::{Vault, VaultPosition, VaultUnchecked},
     },
-    health::AccountKind,
+    health::{AccountKind, HealthValuesResponse},
     oracle::ActionKind,
     perps::PerpPosition,
     traits::Coins,
@@ -107,6 +107,19 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Return all recurring orders.
+    #[returns(cw_paginate::PaginationResponse<RecurringOrderResponse>)]
+    AllRecurringOrders {
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    /// Return all recurring orders for an account.
+    #[returns(cw_paginate::PaginationResponse<RecurringOrderResponse>)]
+    AllAccountRecurringOrders {
+        account_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 
     /// Enumerate all vault bindings; start_after accepts account_id
     #[returns(Vec<VaultBinding>)]
@@ -114,6 +127,64 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+
+    /// Enumerate the active (not expired) operator grants of an account; start_after accepts operator address
+    #[returns(Vec<OperatorGrantResponse>)]
+    AccountOperators {
+        account_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    #[returns(AccountSettings)]
+    AccountSettings {
+        account_id: String,
+    },
+
+    /// Dry-run the actions on an account without executing them.
+    /// Swaps, liquidity provision and perp orders are modeled with the estimates of the
+    /// respective contracts, hence the result may slightly differ from the actual execution.
+    /// Each perp position can only be modified by one of the actions.
+    #[returns(SimulateActionsResponse)]
+    SimulateActions {
+        account_id: String,
+        actions: Vec<Action>,
+    },
+
+    /// Liquidatable accounts among the accounts with debt or perp positions, with the liquidation
+    /// repaying the most debt. Pagination is done over all the accounts with debt or perp positions
+    /// (see `LiquidatableAccountsResponse::next_start_after`).
+    #[returns(LiquidatableAccountsResponse)]
+    LiquidatableAccounts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        action: ActionKind,
+    },
+}
+
+#[cw_serde]
+pub struct LiquidatableAccountsResponse {
+    pub accounts: Vec<LiquidatableAccount>,
+    /// Accounts whose health couldn't be computed (e.g. a price is missing), they are skipped
+    pub failed_account_ids: Vec<String>,
+    /// Last account checked, to be used as `start_after` of the next page.
+    /// `None` once all the accounts have been checked.
+    pub next_start_after: Option<String>,
+}
+
+#[cw_serde]
+pub struct LiquidatableAccount {
+    pub account_id: String,
+    pub health: HealthValuesResponse,
+    /// Liquidation repaying the most debt, `None` if no deposit, lend or perp position of the
+    /// account can be liquidated
+    pub liquidation: Option<LiquidationCandidate>,
+}
+
+#[cw_serde]
+pub struct LiquidationCandidate {
+    pub debt_coin: Coin,
+    pub request: LiquidateRequest<VaultUnchecked>,
 }
 
 #[cw_serde]
@@ -161,6 +232,9 @@ pub struct DebtAmount {
 #[derive(Default)]
 pub struct KeeperFeeConfig {
     pub min_fee: Coin,
+    /// Share of the keeper fee of a purged expired trigger order paid to the caller of
+    /// `PurgeExpiredTriggerOrders`. The rest is refunded to the account.
+    pub cleanup_reward: Option<Decimal>,
 }
 
 #[cw_serde]
@@ -169,6 +243,12 @@ pub struct TriggerOrder {
     pub actions: Vec<Action>,
     pub conditions: Vec<Condition>,
     pub keeper_fee: Coin,
+    /// The order can only be executed once its parent order has been executed
+    pub parent_order_id: Option<String>,
+    /// Executing the order deletes the other orders of the account in the same group
+    pub oco_group: Option<String>,
+    /// Block time (in seconds) from which the order can't be executed anymore
+    pub expires_at: Option<u64>,
 }
 
 impl Coins for Vec<DebtAmount> {
@@ -206,6 +286,29 @@ pub struct TriggerOrderResponse {
     pub order: TriggerOrder,
 }
 
+#[cw_serde]
+pub struct RecurringOrder {
+    pub order_id: String,
+    /// Coin swapped in each period
+    pub coin_in: Coin,
+    pub denom_out: String,
+    /// Seconds between two executions
+    pub interval: u64,
+    pub periods_remaining: u32,
+    /// Block time (in seconds) from which the next period can be executed
+    pub next_execution: u64,
+    /// Lend the account balance of `denom_out` after each swap
+    pub lend_output: bool,
+    /// Keeper fee paid per execution
+    pub keeper_fee: Coin,
+}
+
+#[cw_serde]
+pub struct RecurringOrderResponse {
+    pub account_id: String,
+    pub order: RecurringOrder,
+}
+
 #[cw_serde]
 pub struct VaultWithBalance {
     pub vault: Vault,
@@ -229,6 +332,7 @@ pub struct ConfigResponse {
     pub rewards_collector: Option<RewardsCollector>,
     pub keeper_fee_config: KeeperFeeConfig,
     pub perps_liquidation_bonus_ratio: Decimal,
+    pub flash_loan_fee_rate: Decimal,
 }
 
 #[cw_serde]
@@ -237,8 +341,33 @@ pub struct Account {
     pub kind: AccountKind,
 }
 
+/// Operator permissions as stored in the contract
+#[cw_serde]
+pub struct OperatorGrant {
+    /// Account owner at the time of the grant. The grant is void once the account NFT changes hands.
+    pub granter: String,
+    pub permissions: OperatorPermissions,
+}
+
+#[cw_serde]
+pub struct OperatorGrantResponse {
+    pub account_id: String,
+    pub operator: String,
+    pub grant: OperatorGrant,
+}
+
 #[cw_serde]
 pub struct VaultBinding {
     pub account_id: String,
     pub vault_address: String,
 }
+
+#[cw_serde]
+pub struct SimulateActionsResponse {
+    /// Positions of the account after applying the actions
+    pub positions: Positions,
+    /// Health of the resulting positions
+    pub health: HealthValuesResponse,
+    /// Coins which would be sent out of the account (withdrawals and refunds)
+    pub coins_out: Vec<Coin>,
+}
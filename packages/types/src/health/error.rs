@@ -83,4 +83,7 @@ pub enum HealthError {
 
     #[error("{0}")]
     Version(#[from] VersionError),
+
+    #[error("{0} perp position is not isolated")]
+    NotIsolatedPerp(String),
 }
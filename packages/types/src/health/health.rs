@@ -64,6 +64,31 @@ impl Health {
     }
 }
 
+/// Health of an isolated perp position. The position and its margin are valued as if they were
+/// an account of their own, the other positions of the account are not taken into account.
+#[cw_serde]
+#[cfg_attr(feature = "javascript", derive(Tsify))]
+#[cfg_attr(feature = "javascript", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct IsolatedPerpHealth {
+    pub denom: String,
+    /// Value of the margin earmarked to the position
+    pub margin_value: Uint128,
+    pub max_ltv_health_factor: Option<Decimal>,
+    pub liquidation_health_factor: Option<Decimal>,
+}
+
+impl IsolatedPerpHealth {
+    #[inline]
+    pub fn is_liquidatable(&self) -> bool {
+        is_below_one(&self.liquidation_health_factor)
+    }
+
+    #[inline]
+    pub fn is_above_max_ltv(&self) -> bool {
+        is_below_one(&self.max_ltv_health_factor)
+    }
+}
+
 pub fn is_below_one(health_factor: &Option<Decimal>) -> bool {
     health_factor.map_or(false, |hf| hf < Decimal::one())
 }
@@ -1,6 +1,8 @@
 use cosmwasm_std::{Decimal, Uint128};
 use mars_utils::error::ValidationError;
 
+use super::MarginTier;
+
 pub(super) fn assert_lqt_gt_max_ltv(
     max_ltv: Decimal,
     liq_threshold: Decimal,
@@ -29,6 +31,52 @@ pub(super) fn assert_hls_lqt_gt_max_ltv(
     Ok(())
 }
 
+/// Margin tiers must be sorted by notional threshold, with LTVs lower than or equal to the ones of
+/// the previous tier (or the base LTVs for the first one)
+pub(super) fn assert_margin_tiers(
+    max_ltv: Decimal,
+    liq_threshold: Decimal,
+    margin_tiers: &[MarginTier],
+) -> Result<(), ValidationError> {
+    let mut prev_notional_threshold = Uint128::zero();
+    let mut prev_max_ltv = max_ltv;
+    let mut prev_liq_threshold = liq_threshold;
+    for margin_tier in margin_tiers {
+        if margin_tier.notional_threshold <= prev_notional_threshold {
+            return Err(ValidationError::InvalidParam {
+                param_name: "margin_tier_notional_threshold".to_string(),
+                invalid_value: margin_tier.notional_threshold.to_string(),
+                predicate: format!("> {} (previous tier)", prev_notional_threshold),
+            });
+        }
+        if margin_tier.max_loan_to_value > prev_max_ltv {
+            return Err(ValidationError::InvalidParam {
+                param_name: "margin_tier_max_loan_to_value".to_string(),
+                invalid_value: margin_tier.max_loan_to_value.to_string(),
+                predicate: format!("<= {} (previous tier)", prev_max_ltv),
+            });
+        }
+        if margin_tier.liquidation_threshold > prev_liq_threshold {
+            return Err(ValidationError::InvalidParam {
+                param_name: "margin_tier_liquidation_threshold".to_string(),
+                invalid_value: margin_tier.liquidation_threshold.to_string(),
+                predicate: format!("<= {} (previous tier)", prev_liq_threshold),
+            });
+        }
+        if margin_tier.liquidation_threshold <= margin_tier.max_loan_to_value {
+            return Err(ValidationError::InvalidParam {
+                param_name: "margin_tier_liquidation_threshold".to_string(),
+                invalid_value: margin_tier.liquidation_threshold.to_string(),
+                predicate: format!("> {} (max LTV)", margin_tier.max_loan_to_value),
+            });
+        }
+        prev_notional_threshold = margin_tier.notional_threshold;
+        prev_max_ltv = margin_tier.max_loan_to_value;
+        prev_liq_threshold = margin_tier.liquidation_threshold;
+    }
+    Ok(())
+}
+
 pub(super) fn assert_max_net_oi_le_max_oi_long(
     max_long_oi_value: Uint128,
     max_net_oi_value: Uint128,
@@ -75,6 +123,21 @@ pub(super) fn assert_max_size_gt_min(
     Ok(())
 }
 
+pub(super) fn assert_maker_fee_rate_le_taker(
+    maker_fee_rate: Decimal,
+    taker_fee_rate: Decimal,
+    param_name: &str,
+) -> Result<(), ValidationError> {
+    if maker_fee_rate > taker_fee_rate {
+        return Err(ValidationError::InvalidParam {
+            param_name: param_name.to_string(),
+            invalid_value: maker_fee_rate.to_string(),
+            predicate: format!("<= {} (taker fee rate)", taker_fee_rate),
+        });
+    }
+    Ok(())
+}
+
 pub(super) fn assert_skew_scale(skew_scale: Uint128) -> Result<(), ValidationError> {
     if skew_scale.is_zero() {
         return Err(ValidationError::InvalidParam {
@@ -2,7 +2,9 @@ use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{Addr, Api, Decimal, Uint128};
 use mars_utils::{
     error::ValidationError,
-    helpers::{decimal_param_le_one, decimal_param_lt_one, validate_native_denom},
+    helpers::{
+        decimal_param_le_one, decimal_param_lt_one, integer_param_gt_zero, validate_native_denom,
+    },
 };
 
 use super::{
@@ -63,6 +65,28 @@ impl LiquidationBonus {
     }
 }
 
+/// Dutch auction of the LB. Once an account is flagged as liquidatable, the LB grows linearly from
+/// `min_lb` to `max_lb` over `duration` instead of depending on the Health Factor:
+/// Liquidation Bonus = min(
+///     min_lb + (max_lb - min_lb) * min(elapsed, duration) / duration,
+///     max(
+///         min(CR - 1, max_lb),
+///         min_lb
+///     )
+/// )
+/// `elapsed` is the time since the account was flagged, accounts not flagged yet get `min_lb`.
+#[cw_serde]
+pub struct LiquidationAuction {
+    /// Seconds it takes for the LB to reach `max_lb`
+    pub duration: u64,
+}
+
+impl LiquidationAuction {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        integer_param_gt_zero(self.duration, "liquidation_auction_duration")
+    }
+}
+
 #[cw_serde]
 pub struct AssetParamsBase<T> {
     pub denom: String,
@@ -74,6 +98,8 @@ pub struct AssetParamsBase<T> {
     pub protocol_liquidation_fee: Decimal,
     pub deposit_cap: Uint128,
     pub close_factor: Decimal,
+    /// If set, the LB of this collateral is auctioned instead of depending on the Health Factor
+    pub liquidation_auction: Option<LiquidationAuction>,
 }
 
 pub type AssetParams = AssetParamsBase<Addr>;
@@ -95,6 +121,7 @@ impl From<AssetParams> for AssetParamsUnchecked {
             protocol_liquidation_fee: p.protocol_liquidation_fee,
             deposit_cap: p.deposit_cap,
             close_factor: p.close_factor,
+            liquidation_auction: p.liquidation_auction,
         }
     }
 }
@@ -110,6 +137,9 @@ impl AssetParamsUnchecked {
         decimal_param_le_one(self.close_factor, "close_factor")?;
 
         self.liquidation_bonus.validate()?;
+        if let Some(auction) = self.liquidation_auction.as_ref() {
+            auction.validate()?;
+        }
         decimal_param_lt_one(self.protocol_liquidation_fee, "protocol_liquidation_fee")?;
 
         if let Some(hls) = self.credit_manager.hls.as_ref() {
@@ -134,6 +164,7 @@ impl AssetParamsUnchecked {
             protocol_liquidation_fee: self.protocol_liquidation_fee,
             deposit_cap: self.deposit_cap,
             close_factor: self.close_factor,
+            liquidation_auction: self.liquidation_auction.clone(),
         })
     }
 }
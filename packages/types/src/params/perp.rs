@@ -1,13 +1,76 @@
+use std::fmt;
+
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_std::{Decimal, Int128, Uint128};
 use mars_utils::helpers::{decimal_param_le_one, decimal_param_lt_one};
 
 use super::assertions::{
-    assert_lqt_gt_max_ltv, assert_max_net_oi_le_max_oi_long, assert_max_net_oi_le_max_oi_short,
-    assert_max_size_gt_min, assert_skew_scale,
+    assert_lqt_gt_max_ltv, assert_maker_fee_rate_le_taker, assert_margin_tiers,
+    assert_max_net_oi_le_max_oi_long, assert_max_net_oi_le_max_oi_short, assert_max_size_gt_min,
+    assert_skew_scale,
 };
 use crate::error::MarsError;
 
+/// Model used to compute the funding rate of a perp market
+#[cw_serde]
+pub enum FundingModel {
+    /// The funding rate drifts over time, at a velocity proportional to the skew
+    Velocity {
+        /// Determines the maximum rate at which funding can be adjusted
+        max_funding_velocity: Decimal,
+    },
+    /// The funding rate follows the premium index (skew / skew_scale) of the market. Premiums
+    /// within `clamp` (in both directions) pay no funding, larger premiums are reduced by the
+    /// `clamp`. The resulting 24-hour funding rate is capped at `max_funding_rate` (in both
+    /// directions).
+    PremiumIndex {
+        clamp: Decimal,
+        max_funding_rate: Decimal,
+    },
+}
+
+impl Default for FundingModel {
+    fn default() -> Self {
+        FundingModel::Velocity {
+            max_funding_velocity: Decimal::zero(),
+        }
+    }
+}
+
+impl fmt::Display for FundingModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FundingModel::Velocity {
+                max_funding_velocity,
+            } => write!(f, "velocity:{max_funding_velocity}"),
+            FundingModel::PremiumIndex {
+                clamp,
+                max_funding_rate,
+            } => write!(f, "premium_index:{clamp}:{max_funding_rate}"),
+        }
+    }
+}
+
+/// Fees paid by perp orders reducing the market skew (as a percent)
+#[cw_serde]
+pub struct MakerFeeRates {
+    /// The fee paid to open (or increase) a position
+    pub opening_fee_rate: Decimal,
+    /// The fee paid to close (or reduce) a position
+    pub closing_fee_rate: Decimal,
+}
+
+/// Margin requirements of perp positions with a notional value at or above the threshold
+#[cw_serde]
+pub struct MarginTier {
+    /// The minimum notional value of a position in the tier (in oracle uusd denomination)
+    pub notional_threshold: Uint128,
+    /// Max loan to position value for positions in the tier
+    pub max_loan_to_value: Decimal,
+    /// LTV at which positions in the tier become liquidatable
+    pub liquidation_threshold: Decimal,
+}
+
 #[cw_serde]
 #[derive(Default)]
 pub struct PerpParams {
@@ -25,6 +88,10 @@ pub struct PerpParams {
     pub closing_fee_rate: Decimal,
     /// The fee paid by the user to open a position (as a percent)
     pub opening_fee_rate: Decimal,
+    /// Lower fees paid by orders reducing the market skew (makers). Orders increasing the skew
+    /// (takers) pay the `opening_fee_rate` and `closing_fee_rate`. If not set, makers pay the
+    /// same fees as takers.
+    pub maker_fee_rates: Option<MakerFeeRates>,
     /// The minimum value of a position (in oracle uusd denomination)
     pub min_position_value: Uint128,
     /// The maximum value of a position (in oracle uusd denomination)
@@ -33,8 +100,11 @@ pub struct PerpParams {
     pub max_loan_to_value: Decimal,
     /// LTV at which a position becomes liquidatable
     pub liquidation_threshold: Decimal,
-    /// Determines the maximum rate at which funding can be adjusted
-    pub max_funding_velocity: Decimal,
+    /// Lower LTVs for large positions, sorted by notional threshold. Positions below the first
+    /// threshold use `max_loan_to_value` and `liquidation_threshold`.
+    pub margin_tiers: Vec<MarginTier>,
+    /// Model used to compute the funding rate of the market
+    pub funding_model: FundingModel,
     /// Determines the funding rate for a given level of skew.
     /// The lower the skew_scale the higher the funding rate.
     pub skew_scale: Uint128,
@@ -44,12 +114,37 @@ impl PerpParams {
     pub fn check(&self) -> Result<PerpParams, MarsError> {
         decimal_param_le_one(self.liquidation_threshold, "liquidation_threshold")?;
         assert_lqt_gt_max_ltv(self.max_loan_to_value, self.liquidation_threshold)?;
+        assert_margin_tiers(
+            self.max_loan_to_value,
+            self.liquidation_threshold,
+            &self.margin_tiers,
+        )?;
         decimal_param_lt_one(self.opening_fee_rate, "opening_fee_rate")?;
         decimal_param_lt_one(self.closing_fee_rate, "closing_fee_rate")?;
+        if let Some(maker_fee_rates) = &self.maker_fee_rates {
+            assert_maker_fee_rate_le_taker(
+                maker_fee_rates.opening_fee_rate,
+                self.opening_fee_rate,
+                "maker_opening_fee_rate",
+            )?;
+            assert_maker_fee_rate_le_taker(
+                maker_fee_rates.closing_fee_rate,
+                self.closing_fee_rate,
+                "maker_closing_fee_rate",
+            )?;
+        }
         assert_max_net_oi_le_max_oi_long(self.max_long_oi_value, self.max_net_oi_value)?;
         assert_max_net_oi_le_max_oi_short(self.max_short_oi_value, self.max_net_oi_value)?;
         assert_max_size_gt_min(self.max_position_value, self.min_position_value)?;
         assert_skew_scale(self.skew_scale)?;
+        if let FundingModel::PremiumIndex {
+            clamp,
+            max_funding_rate,
+        } = &self.funding_model
+        {
+            decimal_param_lt_one(*clamp, "funding_clamp")?;
+            decimal_param_le_one(*max_funding_rate, "max_funding_rate")?;
+        }
 
         Ok(PerpParams {
             denom: self.denom.clone(),
@@ -59,12 +154,69 @@ impl PerpParams {
             max_short_oi_value: self.max_short_oi_value,
             closing_fee_rate: self.closing_fee_rate,
             opening_fee_rate: self.opening_fee_rate,
+            maker_fee_rates: self.maker_fee_rates.clone(),
             min_position_value: self.min_position_value,
             max_position_value: self.max_position_value,
             max_loan_to_value: self.max_loan_to_value,
             liquidation_threshold: self.liquidation_threshold,
-            max_funding_velocity: self.max_funding_velocity,
+            margin_tiers: self.margin_tiers.clone(),
+            funding_model: self.funding_model.clone(),
             skew_scale: self.skew_scale,
         })
     }
+
+    /// Max LTV of a position with the given notional value (in oracle uusd denomination)
+    pub fn max_loan_to_value_for(&self, position_value: Uint128) -> Decimal {
+        self.margin_tier(position_value)
+            .map_or(self.max_loan_to_value, |margin_tier| margin_tier.max_loan_to_value)
+    }
+
+    /// Liquidation threshold of a position with the given notional value (in oracle uusd
+    /// denomination)
+    pub fn liquidation_threshold_for(&self, position_value: Uint128) -> Decimal {
+        self.margin_tier(position_value)
+            .map_or(self.liquidation_threshold, |margin_tier| margin_tier.liquidation_threshold)
+    }
+
+    /// Highest margin tier reached by the notional value, `None` if below the first tier
+    fn margin_tier(&self, position_value: Uint128) -> Option<&MarginTier> {
+        self.margin_tiers
+            .iter()
+            .rev()
+            .find(|margin_tier| position_value >= margin_tier.notional_threshold)
+    }
+
+    /// Fee rate paid to open `size` at the market `skew`: the maker rate if the skew is reduced,
+    /// the taker rate otherwise
+    pub fn opening_fee_rate_at(&self, skew: Int128, size: Int128) -> Decimal {
+        match &self.maker_fee_rates {
+            Some(maker_fee_rates) if reduces_skew(skew, skew.checked_add(size).ok()) => {
+                maker_fee_rates.opening_fee_rate
+            }
+            _ => self.opening_fee_rate,
+        }
+    }
+
+    /// Fee rate paid to close `size` at the market `skew`: the maker rate if the skew is reduced,
+    /// the taker rate otherwise
+    pub fn closing_fee_rate_at(&self, skew: Int128, size: Int128) -> Decimal {
+        match &self.maker_fee_rates {
+            Some(maker_fee_rates) if reduces_skew(skew, skew.checked_sub(size).ok()) => {
+                maker_fee_rates.closing_fee_rate
+            }
+            _ => self.closing_fee_rate,
+        }
+    }
+
+    /// Fee rate paid to close positions reducing the market skew
+    pub fn maker_closing_fee_rate(&self) -> Decimal {
+        self.maker_fee_rates
+            .as_ref()
+            .map_or(self.closing_fee_rate, |maker_fee_rates| maker_fee_rates.closing_fee_rate)
+    }
+}
+
+/// Whether the new skew is closer to zero than the current one
+fn reduces_skew(skew: Int128, new_skew: Option<Int128>) -> bool {
+    new_skew.is_some_and(|new_skew| new_skew.unsigned_abs() < skew.unsigned_abs())
 }
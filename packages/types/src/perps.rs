@@ -10,7 +10,11 @@ use cosmwasm_std::{
 use mars_owner::OwnerUpdate;
 use thiserror::Error;
 
-use crate::{error::MarsError, oracle::ActionKind, params::PerpParams};
+use crate::{
+    error::MarsError,
+    oracle::ActionKind,
+    params::{FundingModel, PerpParams},
+};
 
 // ------------------------------- message types -------------------------------
 
@@ -226,6 +230,19 @@ pub struct MarketState {
     pub last_updated: u64,
 }
 
+/// Final prices of a delisted market. Its open positions are settled at them.
+#[cw_serde]
+pub struct MarketSettlement {
+    /// Price of the market's denom when it was delisted
+    pub denom_price: Decimal,
+
+    /// Price of the base denom when the market was delisted
+    pub base_denom_price: Decimal,
+
+    /// Time of the delisting. Funding doesn't accrue afterwards.
+    pub delisted_at: u64,
+}
+
 /// Funding parameters for a single denom.
 ///
 /// The role of funding rates is generally to balance long and short demand.
@@ -233,8 +250,8 @@ pub struct MarketState {
 /// If the funding rate is positive, long position holders will pay the funding rate to those holding short positions, and vice versa.
 #[cw_serde]
 pub struct Funding {
-    /// Determines the maximum rate at which funding can be adjusted
-    pub max_funding_velocity: Decimal,
+    /// Model used to compute the funding rate
+    pub funding_model: FundingModel,
 
     /// Determines the funding rate for a given level of skew.
     /// The lower the skew_scale the higher the funding rate.
@@ -250,7 +267,7 @@ pub struct Funding {
 impl Default for Funding {
     fn default() -> Self {
         Funding {
-            max_funding_velocity: Decimal::zero(),
+            funding_model: FundingModel::default(),
             skew_scale: Uint128::one(),
             last_funding_rate: SignedDecimal::zero(),
             last_funding_accrued_per_unit_in_base_denom: SignedDecimal::zero(),
@@ -374,6 +391,9 @@ pub struct PerpPosition {
     pub current_exec_price: Decimal,
     pub unrealized_pnl: PnlAmounts,
     pub realized_pnl: PnlAmounts,
+    /// Margin (in the base denom) earmarked to an isolated position. Always `None` in the
+    /// responses of the perps contract, the credit manager fills it in for isolated positions.
+    pub isolated_margin: Option<Uint128>,
 }
 
 /// The profit-and-loss of a perp position, denominated in the base currency.
@@ -586,6 +606,11 @@ pub enum ExecuteMsg {
         // Reduce Only enforces a position size cannot increase in absolute terms, ensuring a position will never flip
         // from long to short or vice versa
         reduce_only: Option<bool>,
+
+        // Worst acceptable execution price (skew adjusted) for the order. The order is aborted if
+        // the execution price is above it when buying (positive size) or below it when selling
+        // (negative size)
+        acceptable_price: Option<Decimal>,
     },
 
     /// Close all perp positions. Use this to liquidate a user's credit account.
@@ -595,6 +620,21 @@ pub enum ExecuteMsg {
     CloseAllPositions {
         account_id: String,
         action: Option<ActionKind>,
+
+        /// Markets whose positions are left open (e.g. isolated margin positions, which are not
+        /// backed by the rest of the account)
+        excluded_denoms: Option<Vec<String>>,
+    },
+
+    /// Close a single perp position. The loss of the position is paid up to the funds sent by
+    /// the credit manager (the margin of an isolated position), the unpaid part of the loss is
+    /// bad debt borne by the vault.
+    ///
+    /// Only callable by Rover credit manager.
+    CloseIsolatedPosition {
+        account_id: String,
+        denom: String,
+        action: Option<ActionKind>,
     },
 
     /// Deleveraging a vault by closing a position for an account.
@@ -617,6 +657,28 @@ pub enum ExecuteMsg {
     UpdateConfig {
         updates: ConfigUpdates,
     },
+
+    /// Delist a market. Trading is stopped and the current oracle prices are kept as the
+    /// settlement prices of its open positions.
+    ///
+    /// Only callable by the owner.
+    DelistMarket {
+        denom: String,
+    },
+
+    /// Settle open positions of a delisted market at its settlement prices, paying their PnL
+    /// through the credit manager. The market is removed once all positions are settled.
+    ///
+    /// Callable by anyone. Large markets can be settled in batches of `limit` positions.
+    SettleMarket {
+        denom: String,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub enum MigrateMsg {
+    V2_2_0ToV2_2_1 {},
 }
 
 #[cw_serde]
@@ -723,6 +785,12 @@ pub enum QueryMsg {
         denom: String,
         new_size: Int128,
     },
+
+    /// Query the settlement of a delisted market, `None` if the market isn't delisted.
+    #[returns(Option<MarketSettlement>)]
+    MarketSettlement {
+        denom: String,
+    },
 }
 
 #[cw_serde]
@@ -1,5 +1,5 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_std::{Coin, Decimal, Uint128};
 use mars_owner::OwnerUpdate;
 
 use crate::red_bank::InterestRateModel;
@@ -95,6 +95,27 @@ pub enum ExecuteMsg {
         recipient: Option<String>,
     },
 
+    /// Liquidate several under-collateralized loans at once (see `Liquidate`). The coins sent must
+    /// match the debt coins of the liquidations.
+    ///
+    /// Users which aren't liquidatable anymore (healthy, without debt or without the requested
+    /// collateral) are skipped instead of failing the whole batch and their debt coin is refunded.
+    /// The result of each liquidation is returned in the `liquidation_{index}` attributes.
+    BatchLiquidate {
+        liquidations: Vec<BatchLiquidation>,
+        /// The address for receiving underlying collateral
+        recipient: Option<String>,
+    },
+
+    /// Starts the liquidation bonus auction of a liquidatable user (see `LiquidationAuction`).
+    /// Permissionless. If the user is healthy, its previous flag is removed instead. Users
+    /// recovering through price moves keep their flag until they deposit, repay, borrow or withdraw,
+    /// or until they are flagged again while healthy.
+    FlagLiquidatable {
+        /// The address of the borrower to flag
+        user: String,
+    },
+
     /// Update (enable / disable) asset as collateral for the caller
     UpdateAssetCollateralStatus {
         /// Asset to update status for
@@ -102,6 +123,30 @@ pub enum ExecuteMsg {
         /// Option to enable (true) / disable (false) asset as collateral
         enable: bool,
     },
+
+    /// Move collateral of a user to a credit account (Rover) without withdrawing it.
+    /// Only callable by the credit manager, which is responsible for verifying that the user
+    /// authorized the transfer.
+    TransferCollateralToCreditAccount {
+        /// The address of the user whose collateral is transferred
+        user: String,
+        /// Credit account id (Rover) receiving the collateral
+        account_id: String,
+        /// Asset to transfer
+        denom: String,
+        /// Amount to be transferred. If None is specified, the full amount will be transferred.
+        amount: Option<Uint128>,
+    },
+}
+
+#[cw_serde]
+pub struct BatchLiquidation {
+    /// The address of the borrower getting liquidated
+    pub user: String,
+    /// Denom of the collateral asset, which liquidator gets from the borrower
+    pub collateral_denom: String,
+    /// Debt coin to repay
+    pub debt_coin: Coin,
 }
 
 #[cw_serde]
@@ -217,6 +262,15 @@ pub enum QueryMsg {
         account_id: Option<String>,
     },
 
+    /// Liquidatable users among the users with debt (with liquidation pricing), with the
+    /// liquidation repaying the most debt. Pagination is done over all the users with debt
+    /// (see `LiquidatableUsersResponse::next_start_after`).
+    #[returns(crate::red_bank::LiquidatableUsersResponse)]
+    LiquidatableUsers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
     /// Get liquidity scaled amount for a given underlying asset amount.
     /// (i.e: how much scaled collateral is added if the given amount is deposited)
     #[returns(Uint128)]
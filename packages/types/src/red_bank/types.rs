@@ -1,5 +1,5 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_std::{Coin, Decimal, Uint128};
 use cw_paginate::PaginationResponse;
 
 use crate::red_bank::Market;
@@ -107,6 +107,30 @@ pub struct UserPositionResponse {
     pub health_status: UserHealthStatus,
 }
 
+#[cw_serde]
+pub struct LiquidatableUser {
+    pub user: String,
+    pub health_status: UserHealthStatus,
+    /// Liquidation repaying the most debt (by value), `None` if no collateral can be liquidated
+    pub liquidation: Option<LiquidationCandidate>,
+}
+
+#[cw_serde]
+pub struct LiquidationCandidate {
+    pub collateral_denom: String,
+    pub debt_coin: Coin,
+}
+
+#[cw_serde]
+pub struct LiquidatableUsersResponse {
+    pub users: Vec<LiquidatableUser>,
+    /// Users whose health couldn't be computed (e.g. a price is missing), they are skipped
+    pub failed_users: Vec<String>,
+    /// Last user checked, to be used as `start_after` of the next page.
+    /// `None` once all the users have been checked.
+    pub next_start_after: Option<String>,
+}
+
 #[cw_serde]
 pub struct MarketV2Response {
     pub collateral_total_amount: Uint128,
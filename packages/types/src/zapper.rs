@@ -48,4 +48,9 @@ pub enum QueryMsg {
     EstimateWithdrawLiquidity {
         coin_in: Coin,
     },
+    /// Whether the denom is the LP token of a pool supported by the zapper
+    #[returns(bool)]
+    IsLpToken {
+        denom: String,
+    },
 }